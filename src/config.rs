@@ -1,5 +1,6 @@
 use crate::Cli;
-use crate::monitor::{DatabaseConfig, IoTConfig, BackupConfig};
+use crate::monitor::{DatabaseConfig, IoTConfig, BackupConfig, ChipConfig, FanControlConfig, PressureConfig};
+use crate::monitor::hwmon::parse_sensors_conf;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -23,6 +24,12 @@ pub struct AdvancedMonitoringConfig {
     pub iot: IoTConfigWrapper,
     pub backup: BackupConfigWrapper,
     pub apm: APMConfig,
+    #[serde(default)]
+    pub sensors: SensorsConfigWrapper,
+    #[serde(default)]
+    pub fan_control: FanControlConfig,
+    #[serde(default)]
+    pub pressure: PressureConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +87,16 @@ pub struct APMConfig {
     pub anomaly_detection_enabled: bool,
 }
 
+/// Label, compute and ignore statements for hwmon chips, inline or from
+/// sensors3.conf-format files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SensorsConfigWrapper {
+    #[serde(default)]
+    pub sensors_conf_files: Vec<String>,
+    #[serde(default)]
+    pub chips: Vec<ChipConfig>,
+}
+
 impl Config {
     pub fn new(cli: Cli) -> Result<Self> {
         let mut config = Self::load_from_file().unwrap_or_default();
@@ -133,6 +150,9 @@ impl Default for AdvancedMonitoringConfig {
             iot: IoTConfigWrapper::default(),
             backup: BackupConfigWrapper::default(),
             apm: APMConfig::default(),
+            sensors: SensorsConfigWrapper::default(),
+            fan_control: FanControlConfig::default(),
+            pressure: PressureConfig::default(),
         }
     }
 }
@@ -251,6 +271,19 @@ impl Config {
         }
     }
 
+    /// Statements from the sensors3.conf files first, then the inline chips,
+    /// so inline ones win where both match.
+    pub fn get_sensor_chip_config(&self) -> Result<Vec<ChipConfig>> {
+        let mut chips = Vec::new();
+        for path in &self.advanced_monitoring.sensors.sensors_conf_files {
+            let content = fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+            chips.extend(parse_sensors_conf(&content).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?);
+        }
+        chips.extend(self.advanced_monitoring.sensors.chips.iter().cloned());
+        Ok(chips)
+    }
+
     pub fn get_backup_config(&self) -> BackupConfig {
         BackupConfig {
             monitor_interval: Duration::from_secs(self.advanced_monitoring.backup.monitor_interval_seconds),
@@ -293,6 +326,7 @@ mod tests {
             export: Some("json".to_string()),
             threshold_cpu: 90.0,
             threshold_memory: 85.0,
+            fan_watchdog: false,
         };
 
         let config = Config::new(cli).expect("Failed to create config");
@@ -26,4 +26,8 @@ pub struct Cli {
 
     #[arg(long = "threshold-memory", default_value = "80")]
     pub threshold_memory: f32,
+
+    /// Runs the fan control watchdog; started by seer itself.
+    #[arg(long = "fan-watchdog", hide = true)]
+    pub fan_watchdog: bool,
 }
\ No newline at end of file
@@ -13,11 +13,14 @@ use std::io;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
-use seer::{Cli, config::Config, monitor::SystemMonitor, ui::App};
+use seer::{Cli, config::Config, monitor::{BlocklistFormat, ContainerAction, ContainerInfo, ServiceAction, SystemMonitor}, ui::App};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    if cli.fan_watchdog {
+        return seer::monitor::fan_control::run_watchdog();
+    }
     let config = Config::new(cli)?;
     
     enable_raw_mode()?;
@@ -28,6 +31,17 @@ async fn main() -> Result<()> {
 
     let mut app = App::new(config.clone());
     let mut system_monitor = SystemMonitor::new();
+    if let Err(e) = config.get_sensor_chip_config()
+        .and_then(|chips| system_monitor.hardware_sensor_monitor.set_chip_config(chips))
+    {
+        app.set_error_message(Some(format!("Sensor configuration: {}", e)));
+    }
+    if let Err(e) = system_monitor.thermal_monitor.fan_control()
+        .configure(config.advanced_monitoring.fan_control.clone())
+    {
+        app.set_error_message(Some(format!("Fan control: {}", e)));
+    }
+    system_monitor.pressure_monitor.configure(config.advanced_monitoring.pressure.clone());
     
     let refresh_duration = Duration::from_secs(config.refresh_rate);
     let mut last_update = Instant::now();
@@ -42,6 +56,9 @@ async fn main() -> Result<()> {
     )?;
     terminal.show_cursor()?;
 
+    if let Err(err) = system_monitor.thermal_monitor.fan_control().deactivate() {
+        println!("Fan control: {:?}", err);
+    }
     if let Err(err) = result {
         println!("{:?}", err);
     }
@@ -79,6 +96,132 @@ async fn run_app(
                     app.set_error_message(None);
                     continue;
                 }
+
+                if app.pending_container_action.is_some() {
+                    match key.code {
+                        KeyCode::Char('y') => app.confirm_container_action(system_monitor),
+                        _ => app.cancel_container_action(),
+                    }
+                    continue;
+                }
+
+                if app.pending_service_action.is_some() {
+                    match key.code {
+                        KeyCode::Char('y') => app.confirm_service_action(system_monitor),
+                        _ => app.cancel_service_action(),
+                    }
+                    continue;
+                }
+
+                if matches!(app.current_view, seer::ui::AppView::SessionView) {
+                    let handled = match key.code {
+                        KeyCode::Up => { app.previous_session(); true }
+                        KeyCode::Down => { app.next_session(); true }
+                        KeyCode::Char('a') => { app.toggle_all_login_history(); true }
+                        KeyCode::Char('b') => { app.write_ssh_blocklist(BlocklistFormat::Nftables, system_monitor); true }
+                        KeyCode::Char('i') => { app.write_ssh_blocklist(BlocklistFormat::Iptables, system_monitor); true }
+                        _ => false,
+                    };
+
+                    if handled {
+                        continue;
+                    }
+                }
+
+                if matches!(app.current_view, seer::ui::AppView::ThermalView) {
+                    let handled = match key.code {
+                        KeyCode::Up => { app.previous_fan_output(); true }
+                        KeyCode::Down => { app.next_fan_output(); true }
+                        KeyCode::Char('c') => { app.toggle_fan_control(system_monitor); true }
+                        KeyCode::Char('+') => { app.adjust_fan_output(system_monitor, 1); true }
+                        KeyCode::Char('-') => { app.adjust_fan_output(system_monitor, -1); true }
+                        KeyCode::Char('a') => { app.set_fan_output_automatic(system_monitor); true }
+                        _ => false,
+                    };
+
+                    if handled {
+                        continue;
+                    }
+                }
+
+                if matches!(app.current_view, seer::ui::AppView::ProcessDetails) {
+                    let handled = match key.code {
+                        KeyCode::Up => { app.previous_thread(); true }
+                        KeyCode::Down => { app.next_thread(); true }
+                        KeyCode::Char('s') => { app.cycle_thread_sort(); true }
+                        KeyCode::Char('+') => { app.adjust_thread_nice(1); true }
+                        KeyCode::Char('-') => { app.adjust_thread_nice(-1); true }
+                        KeyCode::Char('a') => { app.toggle_thread_pinning(); true }
+                        KeyCode::Char('c') => { app.edit_thread_affinity(); true }
+                        _ => false,
+                    };
+
+                    if handled {
+                        continue;
+                    }
+                }
+
+                if matches!(app.current_view, seer::ui::AppView::ProcessAffinity) {
+                    let handled = match key.code {
+                        KeyCode::Left => { app.move_affinity_cursor(-1, false); true }
+                        KeyCode::Right => { app.move_affinity_cursor(1, false); true }
+                        KeyCode::Up => { app.move_affinity_cursor(-1, true); true }
+                        KeyCode::Down => { app.move_affinity_cursor(1, true); true }
+                        KeyCode::Char(' ') => { app.toggle_affinity_cpu(); true }
+                        KeyCode::Char('a') => { app.select_all_affinity_cpus(); true }
+                        KeyCode::Enter => { app.apply_affinity(); true }
+                        KeyCode::Char('g') => { app.cycle_affinity_target(); true }
+                        _ => false,
+                    };
+
+                    if handled {
+                        continue;
+                    }
+                }
+
+                if matches!(app.current_view, seer::ui::AppView::ServiceView) {
+                    let handled = match key.code {
+                        KeyCode::Up => { app.previous_service(); true }
+                        KeyCode::Down => { app.next_service(); true }
+                        KeyCode::Char('s') => { app.request_service_action(ServiceAction::Start); true }
+                        KeyCode::Char('o') => { app.request_service_action(ServiceAction::Stop); true }
+                        KeyCode::Char('b') => { app.request_service_action(ServiceAction::Restart); true }
+                        KeyCode::Char('d') => { app.toggle_service_dependencies(); true }
+                        KeyCode::Char('c') => { app.toggle_service_slices(); true }
+                        KeyCode::Char('l') => { app.toggle_service_journal(system_monitor); true }
+                        _ => false,
+                    };
+
+                    if handled {
+                        continue;
+                    }
+                }
+
+                if matches!(app.current_view, seer::ui::AppView::ContainerView) {
+                    let handled = match key.code {
+                        KeyCode::Up => { app.previous_container(); true }
+                        KeyCode::Down => { app.next_container(); true }
+                        KeyCode::Char('s') => { app.request_container_action(ContainerAction::Start); true }
+                        KeyCode::Char('o') => { app.request_container_action(ContainerAction::Stop); true }
+                        KeyCode::Char('b') => { app.request_container_action(ContainerAction::Restart); true }
+                        KeyCode::Char('u') => { app.request_container_action(ContainerAction::Pause); true }
+                        KeyCode::Char('k') => { app.request_container_action(ContainerAction::Kill); true }
+                        KeyCode::Delete => { app.request_container_action(ContainerAction::Remove); true }
+                        KeyCode::Char('l') => { app.toggle_container_logs(system_monitor); true }
+                        KeyCode::Char('x') => { app.request_container_exec(); true }
+                        _ => false,
+                    };
+
+                    if let Some(container) = app.pending_container_exec.take() {
+                        if let Err(e) = run_container_shell(terminal, system_monitor, &container) {
+                            app.set_error_message(Some(format!("Shell failed: {}", e)));
+                        }
+                    }
+
+                    if handled {
+                        continue;
+                    }
+                }
                 
                 match key.code {
                     KeyCode::Char('q') => return Ok(()),
@@ -113,7 +256,9 @@ async fn run_app(
                     KeyCode::Char('L') => app.toggle_log_monitor_view(),
                     KeyCode::Char('F') => app.toggle_filesystem_monitor_view(),
                     KeyCode::Char('C') => app.toggle_container_view(),
+                    KeyCode::Char('K') => app.toggle_pod_view(),
                     KeyCode::Char('V') => app.toggle_service_view(),
+                    KeyCode::Char('Z') => app.toggle_scheduled_jobs_view(),
                     KeyCode::Char('X') => app.toggle_session_view(),
                     KeyCode::Char('W') => app.toggle_hardware_sensor_view(),
                     // Advanced Monitoring Domains
@@ -154,6 +299,7 @@ async fn run_app(
                     KeyCode::Char('m') => app.sort_by_memory(),
                     KeyCode::Char('1') => app.sort_by_pid(),
                     KeyCode::Char('2') => app.sort_by_name(),
+                    KeyCode::Char('3') => app.sort_by_pss(),
                     KeyCode::Char('k') => app.kill_selected_process()?,
                     KeyCode::Char('e') => {
                         if let Err(e) = app.export_current_data("json") {
@@ -186,4 +332,22 @@ async fn run_app(
 
         sleep(Duration::from_millis(16)).await;
     }
-}
\ No newline at end of file
+}
+
+/// Hands the terminal over to an interactive shell inside the container and
+/// restores the TUI once the shell exits. Raw mode stays on so keystrokes,
+/// including Ctrl-C, reach the container untouched.
+fn run_container_shell(
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    system_monitor: &mut SystemMonitor,
+    container: &ContainerInfo,
+) -> Result<()> {
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    let (columns, rows) = crossterm::terminal::size()?;
+
+    let result = system_monitor.container_monitor.exec_shell(container, columns, rows);
+
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableMouseCapture)?;
+    terminal.clear()?;
+    result
+}
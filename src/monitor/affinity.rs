@@ -1,5 +1,10 @@
 use anyhow::Result;
 use std::fs;
+#[cfg(target_os = "linux")]
+use nix::{sched::{sched_getaffinity, sched_setaffinity, CpuSet}, unistd::Pid};
+use super::cgroups::{systemd_unit_from_cgroup_path, CgroupReader};
+use super::systemd::SystemdClient;
+use super::topology::CpuTopology;
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ProcessAffinity {
@@ -19,6 +24,17 @@ impl ProcessAffinity {
         }
     }
 
+    pub fn from_cpus(pid: u32, cpus: &[usize], cpu_count: usize) -> Self {
+        let cpu_mask: Vec<bool> = (0..cpu_count).map(|cpu| cpus.contains(&cpu)).collect();
+        let allowed_cpus = (0..cpu_count).filter(|&cpu| cpu_mask[cpu]).collect();
+        Self {
+            pid,
+            cpu_mask,
+            allowed_cpus,
+            cpu_count,
+        }
+    }
+
     pub fn from_mask_string(pid: u32, mask_str: &str, cpu_count: usize) -> Result<Self> {
         let mut cpu_mask = vec![false; cpu_count];
         let mut allowed_cpus = Vec::new();
@@ -72,28 +88,30 @@ impl ProcessAffinity {
 pub struct AffinityManager;
 
 impl AffinityManager {
-    /// Get current CPU affinity for a process (Linux only)
+    /// Number of CPUs the kernel was configured with, including offline
+    /// ones. `num_cpus::get` is limited by seer's own affinity mask.
+    pub fn cpu_count() -> usize {
+        // SAFETY: sysconf only reads a configuration value
+        let configured = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+        if configured > 0 {
+            configured as usize
+        } else {
+            num_cpus::get()
+        }
+    }
+
+    /// Get current CPU affinity for a process or thread (Linux only)
     pub fn get_process_affinity(pid: u32) -> Result<ProcessAffinity> {
         #[cfg(target_os = "linux")]
         {
-            let status_path = format!("/proc/{}/status", pid);
-            let status_content = fs::read_to_string(&status_path)
-                .map_err(|e| anyhow::anyhow!("Failed to read process status: {}", e))?;
-
-            let cpu_count = num_cpus::get();
-            let mut affinity = ProcessAffinity::new(pid, cpu_count);
-
-            // Look for Cpus_allowed field in /proc/pid/status
-            for line in status_content.lines() {
-                if line.starts_with("Cpus_allowed:") {
-                    if let Some(mask_str) = line.split_whitespace().nth(1) {
-                        affinity = ProcessAffinity::from_mask_string(pid, mask_str, cpu_count)?;
-                    }
-                    break;
-                }
-            }
+            let cpu_set = sched_getaffinity(Pid::from_raw(pid as i32))
+                .map_err(|e| anyhow::anyhow!("Failed to read affinity of {}: {}", pid, e))?;
 
-            Ok(affinity)
+            let cpu_count = Self::cpu_count().min(CpuSet::count());
+            let cpus: Vec<usize> = (0..cpu_count)
+                .filter(|&cpu| cpu_set.is_set(cpu).unwrap_or(false))
+                .collect();
+            Ok(ProcessAffinity::from_cpus(pid, &cpus, cpu_count))
         }
 
         #[cfg(not(target_os = "linux"))]
@@ -104,32 +122,75 @@ impl AffinityManager {
         }
     }
 
-    /// Set CPU affinity for a process using taskset command
+    /// Set CPU affinity for every thread of a process. Threads started
+    /// later inherit the mask from the thread that creates them.
     pub fn set_process_affinity(pid: u32, affinity: &ProcessAffinity) -> Result<()> {
-        #[cfg(target_os = "linux")]
-        {
-            use std::process::Command;
+        let tids: Vec<u32> = fs::read_dir(format!("/proc/{}/task", pid))
+            .map(|entries| entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+                .collect())
+            .unwrap_or_default();
+        if tids.is_empty() {
+            return Self::set_thread_affinity(pid, affinity);
+        }
 
-            let mask_str = affinity.to_mask_string();
-            let output = Command::new("taskset")
-                .args(&["-p", &mask_str, &pid.to_string()])
-                .output()
-                .map_err(|e| anyhow::anyhow!("Failed to execute taskset: {}", e))?;
+        // Threads can exit while we walk the list; only fail if none took the mask
+        let mut last_error = None;
+        let mut applied = 0;
+        for tid in tids {
+            match Self::set_thread_affinity(tid, affinity) {
+                Ok(()) => applied += 1,
+                Err(e) => last_error = Some(e),
+            }
+        }
+        match last_error {
+            Some(e) if applied == 0 => Err(e),
+            _ => Ok(()),
+        }
+    }
 
-            if !output.status.success() {
-                let error_msg = String::from_utf8_lossy(&output.stderr);
-                return Err(anyhow::anyhow!("taskset failed: {}", error_msg));
+    /// Set CPU affinity for a single thread (or only the main thread when
+    /// given a PID).
+    pub fn set_thread_affinity(tid: u32, affinity: &ProcessAffinity) -> Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            if affinity.allowed_cpus.is_empty() {
+                return Err(anyhow::anyhow!("At least one CPU must be allowed"));
             }
 
-            Ok(())
+            let mut cpu_set = CpuSet::new();
+            for &cpu in &affinity.allowed_cpus {
+                cpu_set.set(cpu)
+                    .map_err(|_| anyhow::anyhow!("CPU {} is beyond the supported range", cpu))?;
+            }
+            sched_setaffinity(Pid::from_raw(tid as i32), &cpu_set)
+                .map_err(|e| anyhow::anyhow!("sched_setaffinity failed for {}: {}", tid, e))
         }
 
         #[cfg(not(target_os = "linux"))]
         {
+            let _ = (tid, affinity);
             Err(anyhow::anyhow!("CPU affinity modification is only supported on Linux"))
         }
     }
 
+    /// Restricts a whole cgroup to `cpus`. Systemd units get the runtime
+    /// `AllowedCPUs=` property, which also enables the cpuset controller
+    /// on the way down; other cgroups have `cpuset.cpus` written directly.
+    pub fn set_cgroup_cpus(cgroup_path: &str, cpus: &[usize]) -> Result<()> {
+        if cpus.is_empty() {
+            return Err(anyhow::anyhow!("At least one CPU must be allowed"));
+        }
+
+        let reader = CgroupReader::new();
+        match systemd_unit_from_cgroup_path(cgroup_path) {
+            Some(unit) => SystemdClient::set_allowed_cpus(unit, cpus)
+                .or_else(|e| reader.set_cpuset(cgroup_path, cpus).map_err(|_| e)),
+            None => reader.set_cpuset(cgroup_path, cpus),
+        }
+    }
+
     /// Get list of processes with their current CPU affinity
     pub fn get_all_process_affinities(pids: &[u32]) -> Vec<ProcessAffinity> {
         let mut affinities = Vec::new();
@@ -143,55 +204,96 @@ impl AffinityManager {
         affinities
     }
 
-    /// Check if taskset command is available
-    pub fn is_taskset_available() -> bool {
-        #[cfg(target_os = "linux")]
-        {
-            use std::process::Command;
-            Command::new("taskset")
-                .arg("--help")
-                .output()
-                .is_ok()
-        }
-
-        #[cfg(not(target_os = "linux"))]
-        {
-            false
+    /// Get CPU topology information, from sysfs where available
+    pub fn get_cpu_topology() -> Result<CpuTopology> {
+        if let Ok(topology) = CpuTopology::read() {
+            return Ok(topology);
         }
-    }
 
-    /// Get CPU topology information
-    pub fn get_cpu_topology() -> Result<CpuTopology> {
         let cpu_count = num_cpus::get();
-        let physical_count = num_cpus::get_physical();
-        
+        let physical_count = num_cpus::get_physical().max(1);
         Ok(CpuTopology {
             logical_cpus: cpu_count,
             physical_cpus: physical_count,
-            threads_per_core: cpu_count / physical_count,
+            threads_per_core: (cpu_count / physical_count).max(1),
+            packages: 1,
+            ..CpuTopology::default()
         })
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct CpuTopology {
-    pub logical_cpus: usize,
-    pub physical_cpus: usize,
-    pub threads_per_core: usize,
+/// Formats CPUs the way the kernel writes cpu lists, e.g. `0-3,8`.
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut cpus = cpus.to_vec();
+    cpus.sort_unstable();
+    cpus.dedup();
+
+    let mut ranges: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < cpus.len() {
+        let start = cpus[index];
+        while index + 1 < cpus.len() && cpus[index + 1] == cpus[index] + 1 {
+            index += 1;
+        }
+        let end = cpus[index];
+        ranges.push(if start == end { start.to_string() } else { format!("{}-{}", start, end) });
+        index += 1;
+    }
+    ranges.join(",")
 }
 
-impl CpuTopology {
-    pub fn get_core_siblings(&self, cpu_index: usize) -> Vec<usize> {
-        let mut siblings = Vec::new();
-        let core_id = cpu_index / self.threads_per_core;
-        
-        for i in 0..self.threads_per_core {
-            let sibling = core_id * self.threads_per_core + i;
-            if sibling < self.logical_cpus {
-                siblings.push(sibling);
+/// Parses a kernel cpu list such as `0-3,8,10-11`.
+pub fn parse_cpu_list(list: &str) -> Vec<usize> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                if let (Ok(start), Ok(end)) = (start.parse::<usize>(), end.parse::<usize>()) {
+                    cpus.extend(start..=end);
+                }
             }
+            None => cpus.extend(part.parse::<usize>().ok()),
         }
-        
-        siblings
     }
-}
\ No newline at end of file
+    cpus
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cpu_lists_round_trip() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n"), vec![0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list(""), Vec::<usize>::new());
+        assert_eq!(format_cpu_list(&[11, 0, 1, 2, 3, 8, 10]), "0-3,8,10-11");
+        assert_eq!(format_cpu_list(&[5]), "5");
+    }
+
+    /// Kills the child on drop so a failed assertion doesn't leave it running.
+    struct Sleeper(std::process::Child);
+
+    impl Drop for Sleeper {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    #[test]
+    fn test_reads_and_sets_child_affinity() {
+        // A child rather than the test runner, whose other tests run in parallel
+        let child = Sleeper(std::process::Command::new("sleep").arg("30").spawn().unwrap());
+        let pid = child.0.id();
+        let affinity = AffinityManager::get_process_affinity(pid).unwrap();
+        assert!(!affinity.allowed_cpus.is_empty());
+
+        let pinned = ProcessAffinity::from_cpus(pid, &affinity.allowed_cpus[..1], affinity.cpu_count);
+        AffinityManager::set_process_affinity(pid, &pinned).unwrap();
+        let narrowed = AffinityManager::get_process_affinity(pid).unwrap();
+        assert_eq!(narrowed.allowed_cpus, pinned.allowed_cpus);
+
+        let empty = ProcessAffinity::from_cpus(pid, &[], affinity.cpu_count);
+        assert!(AffinityManager::set_thread_affinity(pid, &empty).is_err());
+    }
+}
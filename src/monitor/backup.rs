@@ -1,5 +1,5 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc, Duration};
+use chrono::{DateTime, Local, Utc, Duration};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -8,6 +8,8 @@ use std::str;
 use tokio::fs;
 use tokio::time::{Duration as TokioDuration, Instant};
 
+use super::cron::{parse_crontab, CronEntry};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BackupMetrics {
     pub backup_jobs: Vec<BackupJob>,
@@ -363,8 +365,10 @@ impl BackupMonitor {
         if let Ok(output) = output {
             let stdout = str::from_utf8(&output.stdout)?;
             
-            for (i, line) in stdout.lines().enumerate() {
-                if line.contains("backup") || line.contains("rsync") || line.contains("tar") {
+            let user = std::env::var("USER").unwrap_or_default();
+            for (i, entry) in parse_crontab(stdout, Some(&user), "crontab -l").iter().enumerate() {
+                let command = &entry.command;
+                if command.contains("backup") || command.contains("rsync") || command.contains("tar") {
                     let job_id = format!("cron_backup_{}", i);
                     
                     if !self.backup_jobs.contains_key(&job_id) {
@@ -374,10 +378,10 @@ impl BackupMonitor {
                             job_type: BackupType::Full,
                             source_paths: vec![PathBuf::from("/home")],
                             destination: "/backup".to_string(),
-                            schedule: self.parse_cron_schedule(line),
+                            schedule: self.parse_cron_schedule(entry),
                             status: BackupStatus::Scheduled,
                             last_run: None,
-                            next_run: self.calculate_next_cron_run(line),
+                            next_run: self.calculate_next_cron_run(entry),
                             success_rate: 90.0,
                             data_size: 1024 * 1024 * 1024 * 5, // 5GB
                             compression_ratio: 0.8,
@@ -455,19 +459,42 @@ impl BackupMonitor {
         Ok(())
     }
 
-    fn parse_cron_schedule(&self, _cron_line: &str) -> BackupSchedule {
-        // Simplified cron parsing
+    fn parse_cron_schedule(&self, entry: &CronEntry) -> BackupSchedule {
+        let schedule = match &entry.schedule {
+            Some(schedule) => schedule,
+            // @reboot jobs only run when cron starts
+            None => {
+                return BackupSchedule {
+                    frequency: BackupFrequency::OnDemand,
+                    time_of_day: None,
+                    days_of_week: Vec::new(),
+                    enabled: true,
+                };
+            }
+        };
+
+        let frequency = if schedule.runs_every_hour() {
+            BackupFrequency::Hourly
+        } else if schedule.day_of_month_restricted() {
+            BackupFrequency::Monthly
+        } else if schedule.days_of_week().len() < 7 {
+            BackupFrequency::Weekly
+        } else {
+            BackupFrequency::Daily
+        };
+
         BackupSchedule {
-            frequency: BackupFrequency::Daily,
-            time_of_day: Some("02:00".to_string()),
-            days_of_week: vec![1, 2, 3, 4, 5],
+            frequency,
+            time_of_day: schedule.time_of_day().map(|(hour, minute)| format!("{:02}:{:02}", hour, minute)),
+            days_of_week: schedule.days_of_week(),
             enabled: true,
         }
     }
 
-    fn calculate_next_cron_run(&self, _cron_line: &str) -> Option<DateTime<Utc>> {
-        // Simplified - would implement proper cron parsing
-        Some(Utc::now() + Duration::hours(24))
+    fn calculate_next_cron_run(&self, entry: &CronEntry) -> Option<DateTime<Utc>> {
+        entry.schedule.as_ref()?
+            .next_after(&Local::now())
+            .map(|next| next.with_timezone(&Utc))
     }
 
     async fn update_storage_locations(&mut self) -> Result<()> {
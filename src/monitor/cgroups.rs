@@ -0,0 +1,550 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::affinity::{format_cpu_list, parse_cpu_list};
+use super::pressure::PressureMetrics;
+
+const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const MAX_SCAN_DEPTH: usize = 6;
+const MIN_CPU_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CgroupStats {
+    pub cpu_usage_usec: u64,
+    pub cpu_user_usec: u64,
+    pub cpu_system_usec: u64,
+    pub memory_current: u64,
+    pub memory_max: Option<u64>,
+    pub io_read_bytes: u64,
+    pub io_write_bytes: u64,
+    pub pids_current: u32,
+    pub oom_events: u64,
+    pub oom_kills: u64,
+    pub pressure: PressureMetrics,
+}
+
+/// A slice and its child slices and units, as in `systemd-cgls`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SliceNode {
+    pub name: String,
+    pub path: String,
+    pub stats: CgroupStats,
+    pub cpu_usage: f64,
+    pub children: Vec<SliceNode>,
+}
+
+impl SliceNode {
+    pub fn is_slice(&self) -> bool {
+        self.name.ends_with(".slice")
+    }
+
+    /// Depth-first walk yielding each node with its depth below the root.
+    pub fn flatten(&self) -> Vec<(usize, &SliceNode)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(0, self)];
+        while let Some((depth, node)) = stack.pop() {
+            nodes.push((depth, node));
+            stack.extend(node.children.iter().rev().map(|child| (depth + 1, child)));
+        }
+        nodes
+    }
+}
+
+struct CpuSample {
+    usage_usec: u64,
+    taken_at: Instant,
+    cpu_percent: f64,
+}
+
+/// Converts cumulative cgroup CPU time into a percentage of one core,
+/// keyed by whatever identifies the cgroup to the caller.
+#[derive(Default)]
+pub struct CpuUsageSampler {
+    samples: HashMap<String, CpuSample>,
+}
+
+impl CpuUsageSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Samples closer together than `MIN_CPU_SAMPLE_INTERVAL` reuse the
+    /// previous figure, since views may redraw many times per second.
+    pub fn sample(&mut self, key: &str, usage_usec: u64) -> f64 {
+        let now = Instant::now();
+
+        if let Some(sample) = self.samples.get(key) {
+            let elapsed = now.duration_since(sample.taken_at);
+            if elapsed < MIN_CPU_SAMPLE_INTERVAL {
+                return sample.cpu_percent;
+            }
+
+            let delta_usec = usage_usec.saturating_sub(sample.usage_usec) as f64;
+            let cpu_percent = delta_usec / elapsed.as_micros() as f64 * 100.0;
+            self.samples.insert(key.to_string(), CpuSample {
+                usage_usec,
+                taken_at: now,
+                cpu_percent,
+            });
+            return cpu_percent;
+        }
+
+        self.samples.insert(key.to_string(), CpuSample {
+            usage_usec,
+            taken_at: now,
+            cpu_percent: 0.0,
+        });
+        0.0
+    }
+
+    pub fn retain<F: FnMut(&str) -> bool>(&mut self, mut keep: F) {
+        self.samples.retain(|key, _| keep(key));
+    }
+}
+
+pub struct CgroupReader {
+    root: PathBuf,
+    container_index: HashMap<String, PathBuf>,
+}
+
+impl Default for CgroupReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CgroupReader {
+    pub fn new() -> Self {
+        Self::with_root(DEFAULT_CGROUP_ROOT)
+    }
+
+    pub fn with_root<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            container_index: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Maps a cgroup path as seen in /proc/<pid>/cgroup or a unit's
+    /// ControlGroup= property to its directory below the root.
+    pub fn resolve(&self, cgroup_path: &str) -> PathBuf {
+        self.root.join(cgroup_path.trim_start_matches('/'))
+    }
+
+    /// Returns true when the root is a unified (v2) hierarchy.
+    pub fn is_unified(&self) -> bool {
+        self.root.join("cgroup.controllers").exists()
+    }
+
+    pub fn read_stats(&self, cgroup_path: &Path) -> Result<CgroupStats> {
+        let mut stats = CgroupStats::default();
+
+        let cpu_stat = fs::read_to_string(cgroup_path.join("cpu.stat"))?;
+        let cpu = parse_flat_keyed(&cpu_stat);
+        stats.cpu_usage_usec = cpu.get("usage_usec").copied().unwrap_or(0);
+        stats.cpu_user_usec = cpu.get("user_usec").copied().unwrap_or(0);
+        stats.cpu_system_usec = cpu.get("system_usec").copied().unwrap_or(0);
+
+        stats.memory_current = read_u64(&cgroup_path.join("memory.current")).unwrap_or(0);
+        stats.memory_max = fs::read_to_string(cgroup_path.join("memory.max"))
+            .ok()
+            .and_then(|s| parse_limit(&s));
+
+        if let Ok(io_stat) = fs::read_to_string(cgroup_path.join("io.stat")) {
+            let (read, write) = parse_io_stat(&io_stat);
+            stats.io_read_bytes = read;
+            stats.io_write_bytes = write;
+        }
+
+        stats.pids_current = read_u64(&cgroup_path.join("pids.current")).unwrap_or(0) as u32;
+
+        if let Ok(events) = fs::read_to_string(cgroup_path.join("memory.events")) {
+            let events = parse_flat_keyed(&events);
+            stats.oom_events = events.get("oom").copied().unwrap_or(0);
+            stats.oom_kills = events.get("oom_kill").copied().unwrap_or(0);
+        }
+
+        stats.pressure = PressureMetrics::read_cgroup(cgroup_path);
+
+        Ok(stats)
+    }
+
+    /// Returns the PIDs currently attached to the cgroup.
+    pub fn read_procs(&self, cgroup_path: &Path) -> Vec<u32> {
+        fs::read_to_string(cgroup_path.join("cgroup.procs"))
+            .map(|content| content.lines().filter_map(|l| l.trim().parse().ok()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Returns the CPUs the cgroup may run on, after its ancestors'
+    /// restrictions. `None` when the cpuset controller is not enabled.
+    pub fn read_cpuset(&self, cgroup_path: &str) -> Option<Vec<usize>> {
+        let content = fs::read_to_string(self.resolve(cgroup_path).join("cpuset.cpus.effective")).ok()?;
+        Some(parse_cpu_list(&content))
+    }
+
+    /// Writes `cpuset.cpus`, which only exists once the parent has
+    /// `+cpuset` in its `cgroup.subtree_control`.
+    pub fn set_cpuset(&self, cgroup_path: &str, cpus: &[usize]) -> Result<()> {
+        let path = self.resolve(cgroup_path).join("cpuset.cpus");
+        if !path.exists() {
+            return Err(anyhow!(
+                "cpuset controller is not enabled for {}; add +cpuset to its parent's cgroup.subtree_control",
+                cgroup_path
+            ));
+        }
+        fs::write(&path, format_cpu_list(cpus))
+            .map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Resolves a full or abbreviated container ID to its cgroup directory.
+    /// The index is rebuilt lazily whenever a lookup misses, so newly
+    /// started containers are picked up without rescanning every refresh.
+    pub fn find_container_cgroup(&mut self, container_id: &str) -> Option<PathBuf> {
+        if container_id.is_empty() {
+            return None;
+        }
+
+        if let Some(path) = self.lookup_container(container_id) {
+            if path.exists() {
+                return Some(path);
+            }
+        }
+
+        self.rebuild_container_index();
+        self.lookup_container(container_id)
+    }
+
+    fn lookup_container(&self, container_id: &str) -> Option<PathBuf> {
+        if let Some(path) = self.container_index.get(container_id) {
+            return Some(path.clone());
+        }
+
+        self.container_index
+            .iter()
+            .find(|(id, _)| id.starts_with(container_id))
+            .map(|(_, path)| path.clone())
+    }
+
+    pub fn rebuild_container_index(&mut self) {
+        let mut index = HashMap::new();
+        Self::scan_for_containers(&self.root, 0, &mut index);
+        self.container_index = index;
+    }
+
+    /// Reads the top-level slices (system.slice, user.slice,
+    /// machine.slice, ...) with their nested slices and units. Units are
+    /// leaves; only slices are descended into.
+    pub fn read_slice_tree(&self, sampler: &mut CpuUsageSampler) -> Vec<SliceNode> {
+        let mut slices = self.read_slice_children(Path::new("/"), 0, sampler);
+        slices.retain(|node| node.is_slice());
+        slices
+    }
+
+    fn read_slice_children(&self, cgroup_path: &Path, depth: usize, sampler: &mut CpuUsageSampler) -> Vec<SliceNode> {
+        if depth > MAX_SCAN_DEPTH {
+            return Vec::new();
+        }
+
+        let entries = match fs::read_dir(self.resolve(&cgroup_path.to_string_lossy())) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut nodes = Vec::new();
+        for entry in entries.flatten() {
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            let name = entry.file_name().to_string_lossy().to_string();
+            let is_unit = [".slice", ".service", ".scope"].iter().any(|suffix| name.ends_with(suffix));
+            if !is_dir || !is_unit {
+                continue;
+            }
+
+            let path = cgroup_path.join(&name);
+            let stats = match self.read_stats(&entry.path()) {
+                Ok(stats) => stats,
+                Err(_) => continue,
+            };
+            let path = path.to_string_lossy().to_string();
+            let cpu_usage = sampler.sample(&path, stats.cpu_usage_usec);
+            let children = if name.ends_with(".slice") {
+                self.read_slice_children(Path::new(&path), depth + 1, sampler)
+            } else {
+                Vec::new()
+            };
+
+            nodes.push(SliceNode { name, path, stats, cpu_usage, children });
+        }
+
+        nodes.sort_by(|a, b| b.stats.memory_current.cmp(&a.stats.memory_current).then_with(|| a.name.cmp(&b.name)));
+        nodes
+    }
+
+    fn scan_for_containers(dir: &Path, depth: usize, index: &mut HashMap<String, PathBuf>) {
+        if depth > MAX_SCAN_DEPTH {
+            return;
+        }
+
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+
+        for entry in entries.flatten() {
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            if !is_dir {
+                continue;
+            }
+
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+
+            if let Some(id) = container_id_from_cgroup_name(&name) {
+                // The outermost match wins; nested "container" or
+                // "init.scope" children belong to the same container.
+                index.entry(id.to_string()).or_insert_with(|| path.clone());
+                continue;
+            }
+
+            Self::scan_for_containers(&path, depth + 1, index);
+        }
+    }
+}
+
+/// Extracts a 64-character container ID from a cgroup directory name as
+/// created by Docker, Podman, containerd and CRI-O with either the systemd
+/// or the cgroupfs driver. Runtime helper scopes such as conmon are ignored.
+pub fn container_id_from_cgroup_name(name: &str) -> Option<&str> {
+    if name.contains("conmon") {
+        return None;
+    }
+
+    let stem = name.strip_suffix(".scope").unwrap_or(name);
+    let id = match stem.rfind('-') {
+        Some(pos) => &stem[pos + 1..],
+        None => stem,
+    };
+
+    if id.len() == 64 && id.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(id)
+    } else {
+        None
+    }
+}
+
+/// Picks the cgroup path from the contents of /proc/<pid>/cgroup: the
+/// unified ("0::") entry when present, otherwise the v1 name=systemd one.
+pub fn parse_proc_cgroup(content: &str) -> Option<String> {
+    let mut fallback = None;
+
+    for line in content.lines() {
+        let mut parts = line.splitn(3, ':');
+        let (hierarchy, controllers, path) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(h), Some(c), Some(p)) => (h, c, p),
+            _ => continue,
+        };
+
+        if hierarchy == "0" && controllers.is_empty() {
+            return Some(path.to_string());
+        }
+        if controllers == "name=systemd" || fallback.is_none() {
+            fallback = Some(path.to_string());
+        }
+    }
+
+    fallback
+}
+
+/// Returns the innermost container ID found along a cgroup path.
+pub fn container_id_from_cgroup_path(path: &str) -> Option<&str> {
+    path.rsplit('/').find_map(container_id_from_cgroup_name)
+}
+
+/// Returns the innermost systemd service or scope along a cgroup path,
+/// e.g. "nginx.service" for /system.slice/nginx.service or "app.service"
+/// for a user unit below user@1000.service.
+pub fn systemd_unit_from_cgroup_path(path: &str) -> Option<&str> {
+    path.rsplit('/')
+        .find(|segment| segment.ends_with(".service") || segment.ends_with(".scope"))
+}
+
+/// Parses "key value" files such as cpu.stat and memory.events.
+pub fn parse_flat_keyed(content: &str) -> HashMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let key = parts.next()?;
+            let value = parts.next()?.parse().ok()?;
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
+
+/// Sums rbytes and wbytes across all devices in an io.stat file.
+pub fn parse_io_stat(content: &str) -> (u64, u64) {
+    let mut read = 0;
+    let mut write = 0;
+
+    for line in content.lines() {
+        for field in line.split_whitespace().skip(1) {
+            if let Some(value) = field.strip_prefix("rbytes=") {
+                read += value.parse::<u64>().unwrap_or(0);
+            } else if let Some(value) = field.strip_prefix("wbytes=") {
+                write += value.parse::<u64>().unwrap_or(0);
+            }
+        }
+    }
+
+    (read, write)
+}
+
+/// Parses a limit file such as memory.max, where "max" means unlimited.
+pub fn parse_limit(content: &str) -> Option<u64> {
+    let content = content.trim();
+    if content == "max" {
+        None
+    } else {
+        content.parse().ok()
+    }
+}
+
+fn read_u64(path: &Path) -> Result<u64> {
+    Ok(fs::read_to_string(path)?.trim().parse()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+
+    const CONTAINER_ID: &str = "3f4e8c2a1b0d9e8f7a6b5c4d3e2f1a0b9c8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f";
+
+    fn fake_cgroup_root(test_name: &str) -> TempDir {
+        let root = TempDir::new(&format!("cgroup_{}", test_name));
+        fs::write(root.join("cgroup.controllers"), "cpu io memory pids\n").unwrap();
+        root
+    }
+
+    #[test]
+    fn test_container_id_from_cgroup_name() {
+        let docker = format!("docker-{}.scope", CONTAINER_ID);
+        let podman = format!("libpod-{}.scope", CONTAINER_ID);
+        let conmon = format!("libpod-conmon-{}.scope", CONTAINER_ID);
+        let containerd = format!("cri-containerd-{}.scope", CONTAINER_ID);
+
+        assert_eq!(container_id_from_cgroup_name(&docker), Some(CONTAINER_ID));
+        assert_eq!(container_id_from_cgroup_name(&podman), Some(CONTAINER_ID));
+        assert_eq!(container_id_from_cgroup_name(&containerd), Some(CONTAINER_ID));
+        assert_eq!(container_id_from_cgroup_name(CONTAINER_ID), Some(CONTAINER_ID));
+        assert_eq!(container_id_from_cgroup_name(&conmon), None);
+        assert_eq!(container_id_from_cgroup_name("system.slice"), None);
+        assert_eq!(container_id_from_cgroup_name("sshd.service"), None);
+    }
+
+    #[test]
+    fn test_parse_io_stat() {
+        let content = "8:0 rbytes=1024 wbytes=2048 rios=1 wios=2 dbytes=0 dios=0\n\
+                       259:0 rbytes=512 wbytes=256 rios=3 wios=4 dbytes=0 dios=0\n";
+        assert_eq!(parse_io_stat(content), (1536, 2304));
+        assert_eq!(parse_io_stat(""), (0, 0));
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("max\n"), None);
+        assert_eq!(parse_limit("536870912\n"), Some(536870912));
+    }
+
+    #[test]
+    fn test_parse_proc_cgroup() {
+        let v2 = format!("0::/system.slice/docker-{}.scope\n", CONTAINER_ID);
+        let path = parse_proc_cgroup(&v2).unwrap();
+        assert_eq!(container_id_from_cgroup_path(&path), Some(CONTAINER_ID));
+        assert_eq!(systemd_unit_from_cgroup_path(&path).map(|u| u.starts_with("docker-")), Some(true));
+
+        let v1 = "12:memory:/user.slice\n1:name=systemd:/user.slice/user-1000.slice/user@1000.service/app.slice/foo.service\n";
+        let path = parse_proc_cgroup(v1).unwrap();
+        assert_eq!(systemd_unit_from_cgroup_path(&path), Some("foo.service"));
+        assert_eq!(container_id_from_cgroup_path(&path), None);
+
+        assert_eq!(parse_proc_cgroup(""), None);
+    }
+
+    #[test]
+    fn test_read_stats_from_fake_tree() {
+        let root = fake_cgroup_root("stats");
+        let scope = root.join("system.slice").join(format!("docker-{}.scope", CONTAINER_ID));
+        fs::create_dir_all(&scope).unwrap();
+        fs::write(scope.join("cpu.stat"), "usage_usec 5000000\nuser_usec 3000000\nsystem_usec 2000000\n").unwrap();
+        fs::write(scope.join("memory.current"), "104857600\n").unwrap();
+        fs::write(scope.join("memory.max"), "max\n").unwrap();
+        fs::write(scope.join("io.stat"), "8:0 rbytes=4096 wbytes=8192 rios=1 wios=2 dbytes=0 dios=0\n").unwrap();
+        fs::write(scope.join("pids.current"), "7\n").unwrap();
+        fs::write(scope.join("memory.events"), "low 0\nhigh 0\nmax 3\noom 2\noom_kill 1\n").unwrap();
+        fs::write(scope.join("memory.pressure"), "some avg10=12.00 avg60=4.00 avg300=1.00 total=900000\nfull avg10=3.00 avg60=1.00 avg300=0.25 total=200000\n").unwrap();
+
+        let mut reader = CgroupReader::with_root(&root);
+        assert!(reader.is_unified());
+
+        let path = reader.find_container_cgroup(&CONTAINER_ID[..12]).expect("container not indexed");
+        assert_eq!(path, scope);
+
+        let stats = reader.read_stats(&path).unwrap();
+        assert_eq!(stats.cpu_usage_usec, 5_000_000);
+        assert_eq!(stats.memory_current, 104_857_600);
+        assert_eq!(stats.memory_max, None);
+        assert_eq!(stats.io_read_bytes, 4096);
+        assert_eq!(stats.io_write_bytes, 8192);
+        assert_eq!(stats.pids_current, 7);
+        assert_eq!(stats.oom_events, 2);
+        assert_eq!(stats.oom_kills, 1);
+        assert_eq!(stats.pressure.memory.map(|p| p.some.avg10), Some(12.0));
+        assert_eq!(stats.pressure.io, None);
+    }
+
+    #[test]
+    fn test_read_slice_tree() {
+        let root = fake_cgroup_root("slices");
+        let write_stats = |dir: &Path, usage_usec: u64, memory: u64| {
+            fs::create_dir_all(dir).unwrap();
+            fs::write(dir.join("cpu.stat"), format!("usage_usec {}\n", usage_usec)).unwrap();
+            fs::write(dir.join("memory.current"), format!("{}\n", memory)).unwrap();
+        };
+        write_stats(&root.join("system.slice"), 9000, 3000);
+        write_stats(&root.join("system.slice/nginx.service"), 6000, 2000);
+        write_stats(&root.join("system.slice/sshd.service"), 3000, 1000);
+        write_stats(&root.join("user.slice"), 100, 500);
+        write_stats(&root.join("user.slice/user-1000.slice"), 100, 500);
+        write_stats(&root.join("user.slice/user-1000.slice/session-3.scope"), 100, 500);
+        write_stats(&root.join("init.scope"), 50, 10);
+        // Delegated sub-cgroups inside a unit are not part of the tree
+        write_stats(&root.join("system.slice/nginx.service/worker"), 10, 10);
+
+        let reader = CgroupReader::with_root(&root);
+        let slices = reader.read_slice_tree(&mut CpuUsageSampler::new());
+
+        let names: Vec<&str> = slices.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["system.slice", "user.slice"]);
+
+        let system = &slices[0];
+        assert_eq!(system.path, "/system.slice");
+        let units: Vec<&str> = system.children.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(units, vec!["nginx.service", "sshd.service"]);
+        assert!(system.children[0].children.is_empty());
+
+        let flattened: Vec<(usize, &str)> = slices[1].flatten().into_iter()
+            .map(|(depth, node)| (depth, node.name.as_str()))
+            .collect();
+        assert_eq!(flattened, vec![(0, "user.slice"), (1, "user-1000.slice"), (2, "session-3.scope")]);
+    }
+}
@@ -0,0 +1,715 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+// Stop and restart wait for the container's own stop timeout (10s by default)
+const ACTION_TIMEOUT: Duration = Duration::from_secs(30);
+const DOCKER_SOCKET: &str = "/var/run/docker.sock";
+const PODMAN_ROOT_SOCKET: &str = "/run/podman/podman.sock";
+// URL-encoded form of {"type":["container"]}
+const CONTAINER_EVENTS_FILTER: &str = "%7B%22type%22%3A%5B%22container%22%5D%7D";
+
+/// A minimal client for the Docker Engine HTTP API spoken over a unix
+/// socket. Podman exposes the same API on its compatibility socket, so the
+/// same client serves both runtimes.
+#[derive(Debug, Clone)]
+pub struct EngineClient {
+    socket_path: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct EngineResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EngineContainer {
+    #[serde(rename = "Id")]
+    pub id: String,
+    #[serde(rename = "Names", default)]
+    pub names: Vec<String>,
+    #[serde(rename = "Image", default)]
+    pub image: String,
+    #[serde(rename = "Created", default)]
+    pub created: i64,
+    #[serde(rename = "Ports", default)]
+    pub ports: Vec<EnginePort>,
+    #[serde(rename = "Labels", default)]
+    pub labels: Option<HashMap<String, String>>,
+    #[serde(rename = "State", default)]
+    pub state: String,
+    #[serde(rename = "Status", default)]
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnginePort {
+    #[serde(rename = "PrivatePort", default)]
+    pub private_port: u16,
+    #[serde(rename = "PublicPort", default)]
+    pub public_port: Option<u16>,
+    #[serde(rename = "Type", default)]
+    pub protocol: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EngineContainerDetails {
+    pub health: Option<String>,
+    pub restart_count: u32,
+    pub exit_code: Option<i64>,
+    pub oom_killed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerEvent {
+    pub timestamp: DateTime<Utc>,
+    pub container_id: String,
+    pub container_name: String,
+    pub action: String,
+    pub exit_code: Option<i64>,
+}
+
+impl EngineClient {
+    pub fn new<P: AsRef<Path>>(socket_path: P) -> Self {
+        Self {
+            socket_path: socket_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns a client for the Docker socket if one is present.
+    pub fn docker() -> Option<Self> {
+        Self::existing(DOCKER_SOCKET)
+    }
+
+    /// Returns a client for the rootful or rootless Podman socket.
+    pub fn podman() -> Option<Self> {
+        if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+            if let Some(client) = Self::existing(Path::new(&runtime_dir).join("podman").join("podman.sock")) {
+                return Some(client);
+            }
+        }
+        Self::existing(PODMAN_ROOT_SOCKET)
+    }
+
+    fn existing<P: AsRef<Path>>(path: P) -> Option<Self> {
+        if path.as_ref().exists() {
+            Some(Self::new(path))
+        } else {
+            None
+        }
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    pub fn ping(&self) -> bool {
+        self.request("GET", "/_ping", None)
+            .map(|r| r.status == 200)
+            .unwrap_or(false)
+    }
+
+    pub fn request(&self, method: &str, path: &str, body: Option<&str>) -> Result<EngineResponse> {
+        self.request_with_timeout(method, path, body, REQUEST_TIMEOUT)
+    }
+
+    pub fn get_json<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T> {
+        let response = self.request("GET", path, None)?;
+        if response.status != 200 {
+            return Err(anyhow!("{} returned HTTP {}", path, response.status));
+        }
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+
+    pub fn list_containers(&self) -> Result<Vec<EngineContainer>> {
+        self.get_json("/containers/json?all=1")
+    }
+
+    /// The list entry for one container, or None once it has been removed.
+    pub fn list_container(&self, id: &str) -> Result<Option<EngineContainer>> {
+        // filters={"id":["<id>"]}
+        let containers: Vec<EngineContainer> =
+            self.get_json(&format!("/containers/json?all=1&filters=%7B%22id%22%3A%5B%22{}%22%5D%7D", id))?;
+        Ok(containers.into_iter().find(|container| container.id == id))
+    }
+
+    pub fn inspect_container(&self, id: &str) -> Result<EngineContainerDetails> {
+        let value: serde_json::Value = self.get_json(&format!("/containers/{}/json", id))?;
+        let state = &value["State"];
+
+        Ok(EngineContainerDetails {
+            health: state["Health"]["Status"].as_str().map(|s| s.to_string()),
+            restart_count: value["RestartCount"].as_u64().unwrap_or(0) as u32,
+            exit_code: state["ExitCode"].as_i64(),
+            oom_killed: state["OOMKilled"].as_bool().unwrap_or(false),
+        })
+    }
+
+    pub fn images_count(&self) -> Result<usize> {
+        let images: Vec<serde_json::Value> = self.get_json("/images/json")?;
+        Ok(images.len())
+    }
+
+    /// Opens the `/events` stream on a background thread. Container events
+    /// are delivered on the returned channel until the engine goes away.
+    pub fn subscribe_events(&self) -> Result<Receiver<ContainerEvent>> {
+        let (_, mut reader, head) = self.open_stream(&format!("/events?filters={}", CONTAINER_EVENTS_FILTER))?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut pending = String::new();
+            while let Ok(Some(data)) = read_stream_block(&mut reader, head.chunked) {
+                pending.push_str(&String::from_utf8_lossy(&data));
+                while let Some(newline) = pending.find('\n') {
+                    let line: String = pending.drain(..=newline).collect();
+                    if let Some(event) = parse_event(line.trim()) {
+                        if sender.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    /// Follows a container's stdout and stderr. Lines arrive on the
+    /// follower's channel; dropping the follower closes the connection.
+    pub fn follow_logs(&self, id: &str, tail: usize) -> Result<LogFollower> {
+        let path = format!("/containers/{}/logs?follow=1&stdout=1&stderr=1&tail={}", id, tail);
+        let (socket, mut reader, head) = self.open_stream(&path)?;
+        // Older engines label both framings as a raw stream, so only trust
+        // an explicit multiplexed content type and otherwise sniff the data
+        let multiplexed = head.content_type.contains("multiplexed").then_some(true);
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut demuxer = LogDemuxer::new(multiplexed);
+            while let Ok(Some(data)) = read_stream_block(&mut reader, head.chunked) {
+                for line in demuxer.push(&data) {
+                    if sender.send(line).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        Ok(LogFollower { receiver, socket })
+    }
+
+    /// Sends a request whose response body is consumed incrementally.
+    /// Returns the socket (for shutdown), a reader positioned at the body
+    /// and the parsed response head.
+    fn open_stream(&self, path: &str) -> Result<(UnixStream, BufReader<UnixStream>, ResponseHead)> {
+        let mut stream = UnixStream::connect(&self.socket_path)?;
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nUser-Agent: seer\r\n\r\n",
+            path
+        );
+        stream.write_all(request.as_bytes())?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let head = read_response_head(&mut reader)?;
+        if head.status != 200 {
+            return Err(anyhow!("{} returned HTTP {}", path, head.status));
+        }
+
+        Ok((stream, reader, head))
+    }
+
+    /// Runs a container lifecycle action such as `start`, `stop` or `kill`.
+    /// `remove` maps to `DELETE /containers/{id}`.
+    pub fn container_action(&self, id: &str, action: &str) -> Result<()> {
+        let response = if action == "remove" {
+            self.request_with_timeout("DELETE", &format!("/containers/{}", id), None, ACTION_TIMEOUT)?
+        } else {
+            self.request_with_timeout("POST", &format!("/containers/{}/{}", id, action), None, ACTION_TIMEOUT)?
+        };
+
+        match response.status {
+            // 304 means the container is already in the requested state
+            200..=299 | 304 => Ok(()),
+            status => Err(anyhow!("{} failed: {}", action, engine_error_message(status, &response.body))),
+        }
+    }
+
+    /// Starts an interactive shell inside the container and connects it to
+    /// this process's terminal until the shell exits. The caller is expected
+    /// to have put the terminal into raw mode.
+    pub fn exec_shell(&self, id: &str, columns: u16, rows: u16) -> Result<()> {
+        let create_body = serde_json::json!({
+            "AttachStdin": true,
+            "AttachStdout": true,
+            "AttachStderr": true,
+            "Tty": true,
+            "Cmd": ["/bin/sh", "-c", "command -v bash >/dev/null 2>&1 && exec bash || exec sh"],
+        }).to_string();
+        let response = self.request_with_timeout("POST", &format!("/containers/{}/exec", id), Some(&create_body), ACTION_TIMEOUT)?;
+        if response.status != 201 {
+            return Err(anyhow!("exec failed: {}", engine_error_message(response.status, &response.body)));
+        }
+        let exec: serde_json::Value = serde_json::from_slice(&response.body)?;
+        let exec_id = exec["Id"].as_str()
+            .ok_or_else(|| anyhow!("exec failed: engine returned no exec ID"))?
+            .to_string();
+
+        // Starting the exec upgrades the connection to a raw TTY stream
+        let mut socket = UnixStream::connect(&self.socket_path)?;
+        let start_body = r#"{"Detach":false,"Tty":true}"#;
+        let request = format!(
+            "POST /exec/{}/start HTTP/1.1\r\nHost: localhost\r\nUser-Agent: seer\r\nConnection: Upgrade\r\nUpgrade: tcp\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            exec_id, start_body.len(), start_body
+        );
+        socket.write_all(request.as_bytes())?;
+
+        let mut reader = BufReader::new(socket.try_clone()?);
+        let head = read_response_head(&mut reader)?;
+        if head.status != 101 && head.status != 200 {
+            return Err(anyhow!("exec start returned HTTP {}", head.status));
+        }
+
+        let _ = self.request("POST", &format!("/exec/{}/resize?h={}&w={}", exec_id, rows, columns), None);
+
+        let finished = Arc::new(AtomicBool::new(false));
+        let output_finished = Arc::clone(&finished);
+        let output = thread::spawn(move || {
+            let mut stdout = std::io::stdout();
+            let mut buffer = [0u8; 4096];
+            loop {
+                match reader.read(&mut buffer) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if stdout.write_all(&buffer[..n]).is_err() {
+                            break;
+                        }
+                        let _ = stdout.flush();
+                    }
+                }
+            }
+            output_finished.store(true, Ordering::SeqCst);
+        });
+
+        // Forward keystrokes until the shell closes the stream. stdin is
+        // polled so the loop notices the exit without another keypress.
+        let mut buffer = [0u8; 1024];
+        while !finished.load(Ordering::SeqCst) {
+            let mut poll_fd = libc::pollfd {
+                fd: libc::STDIN_FILENO,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: one valid pollfd
+            let ready = unsafe { libc::poll(&mut poll_fd, 1, 100) };
+            if ready <= 0 || poll_fd.revents & libc::POLLIN == 0 {
+                continue;
+            }
+
+            // SAFETY: buffer is writable for buffer.len() bytes
+            let n = unsafe { libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr() as *mut libc::c_void, buffer.len()) };
+            if n <= 0 || socket.write_all(&buffer[..n as usize]).is_err() {
+                break;
+            }
+        }
+
+        let _ = socket.shutdown(Shutdown::Both);
+        let _ = output.join();
+        Ok(())
+    }
+
+    fn request_with_timeout(&self, method: &str, path: &str, body: Option<&str>, timeout: Duration) -> Result<EngineResponse> {
+        let mut stream = UnixStream::connect(&self.socket_path)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+
+        let body = body.unwrap_or("");
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nUser-Agent: seer\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method, path, body.len(), body
+        );
+        stream.write_all(request.as_bytes())?;
+
+        let mut raw = Vec::new();
+        stream.read_to_end(&mut raw)?;
+        parse_http_response(&raw)
+    }
+}
+
+/// A live `logs --follow` stream for one container.
+pub struct LogFollower {
+    receiver: Receiver<String>,
+    socket: UnixStream,
+}
+
+impl LogFollower {
+    /// Returns the lines received since the last call.
+    pub fn drain(&self) -> Vec<String> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for LogFollower {
+    fn drop(&mut self) {
+        let _ = self.socket.shutdown(Shutdown::Both);
+    }
+}
+
+/// Splits a log stream into lines. Containers without a TTY use the
+/// engine's multiplexed framing: an 8-byte header carrying the stream type
+/// and a big-endian payload length precedes every frame.
+struct LogDemuxer {
+    multiplexed: Option<bool>,
+    frame_buffer: Vec<u8>,
+    line_buffer: String,
+}
+
+impl LogDemuxer {
+    fn new(multiplexed: Option<bool>) -> Self {
+        Self {
+            multiplexed,
+            frame_buffer: Vec::new(),
+            line_buffer: String::new(),
+        }
+    }
+
+    fn push(&mut self, data: &[u8]) -> Vec<String> {
+        match self.multiplexed {
+            Some(false) => self.line_buffer.push_str(&String::from_utf8_lossy(data)),
+            _ => self.frame_buffer.extend_from_slice(data),
+        }
+
+        if self.multiplexed.is_none() {
+            if self.frame_buffer.len() < 8 {
+                return Vec::new();
+            }
+            let header = &self.frame_buffer[..4];
+            let multiplexed = header[0] <= 2 && header[1..].iter().all(|b| *b == 0);
+            if !multiplexed {
+                let buffered = std::mem::take(&mut self.frame_buffer);
+                self.line_buffer.push_str(&String::from_utf8_lossy(&buffered));
+            }
+            self.multiplexed = Some(multiplexed);
+        }
+
+        if self.multiplexed == Some(true) {
+            while self.frame_buffer.len() >= 8 {
+                let size = u32::from_be_bytes([
+                    self.frame_buffer[4],
+                    self.frame_buffer[5],
+                    self.frame_buffer[6],
+                    self.frame_buffer[7],
+                ]) as usize;
+                if self.frame_buffer.len() < 8 + size {
+                    break;
+                }
+                let payload: Vec<u8> = self.frame_buffer.drain(..8 + size).skip(8).collect();
+                self.line_buffer.push_str(&String::from_utf8_lossy(&payload));
+            }
+        }
+
+        let mut lines = Vec::new();
+        while let Some(newline) = self.line_buffer.find('\n') {
+            let line: String = self.line_buffer.drain(..=newline).collect();
+            lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        lines
+    }
+}
+
+fn engine_error_message(status: u16, body: &[u8]) -> String {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| v["message"].as_str().map(|m| m.to_string()))
+        .unwrap_or_else(|| format!("HTTP {}", status))
+}
+
+pub fn parse_event(line: &str) -> Option<ContainerEvent> {
+    if line.is_empty() {
+        return None;
+    }
+
+    let value: serde_json::Value = serde_json::from_str(line).ok()?;
+    if value["Type"].as_str() != Some("container") {
+        return None;
+    }
+
+    let actor = &value["Actor"];
+    let attributes = &actor["Attributes"];
+    let timestamp = value["time"].as_i64()
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+        .unwrap_or_else(Utc::now);
+
+    Some(ContainerEvent {
+        timestamp,
+        container_id: actor["ID"].as_str().unwrap_or("").to_string(),
+        container_name: attributes["name"].as_str().unwrap_or("").to_string(),
+        action: value["Action"].as_str().unwrap_or("").to_string(),
+        exit_code: attributes["exitCode"].as_str().and_then(|c| c.parse().ok()),
+    })
+}
+
+fn parse_http_response(raw: &[u8]) -> Result<EngineResponse> {
+    let mut reader = BufReader::new(raw);
+    let head = read_response_head(&mut reader)?;
+
+    let mut body = Vec::new();
+    if head.chunked {
+        while let Some(chunk) = read_chunk(&mut reader)? {
+            body.extend_from_slice(&chunk);
+        }
+    } else {
+        reader.read_to_end(&mut body)?;
+    }
+
+    Ok(EngineResponse { status: head.status, body })
+}
+
+struct ResponseHead {
+    status: u16,
+    chunked: bool,
+    content_type: String,
+}
+
+/// Reads the status line and headers of an HTTP response.
+fn read_response_head<R: BufRead>(reader: &mut R) -> Result<ResponseHead> {
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| anyhow!("Malformed HTTP status line: {}", status_line.trim()))?;
+
+    let mut head = ResponseHead {
+        status,
+        chunked: false,
+        content_type: String::new(),
+    };
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            break;
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("transfer-encoding") && value.eq_ignore_ascii_case("chunked") {
+                head.chunked = true;
+            } else if name.eq_ignore_ascii_case("content-type") {
+                head.content_type = value.to_string();
+            }
+        }
+    }
+
+    Ok(head)
+}
+
+/// Reads the next block of a streaming body, decoding chunked transfer
+/// encoding when in use. Returns None once the stream has ended.
+fn read_stream_block<R: BufRead>(reader: &mut R, chunked: bool) -> Result<Option<Vec<u8>>> {
+    if chunked {
+        return read_chunk(reader);
+    }
+
+    let data = reader.fill_buf()?.to_vec();
+    reader.consume(data.len());
+    if data.is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(data))
+    }
+}
+
+/// Reads a single chunk of a chunked body. Returns None at the final chunk.
+fn read_chunk<R: BufRead>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut size_line = String::new();
+    if reader.read_line(&mut size_line)? == 0 {
+        return Ok(None);
+    }
+
+    let size_field = size_line.trim().split(';').next().unwrap_or("");
+    let size = usize::from_str_radix(size_field, 16)
+        .map_err(|_| anyhow!("Malformed chunk size: {}", size_line.trim()))?;
+    if size == 0 {
+        return Ok(None);
+    }
+
+    let mut data = vec![0; size];
+    reader.read_exact(&mut data)?;
+    let mut crlf = [0; 2];
+    reader.read_exact(&mut crlf)?;
+
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    fn mock_engine(test_name: &str, responses: Vec<String>) -> PathBuf {
+        let socket_path = std::env::temp_dir().join(format!("seer_engine_{}_{}.sock", test_name, std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = match listener.accept() {
+                    Ok(conn) => conn,
+                    Err(_) => return,
+                };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        socket_path
+    }
+
+    fn chunked_response(body: &str) -> String {
+        let (first, second) = body.split_at(body.len() / 2);
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+            first.len(), first, second.len(), second
+        )
+    }
+
+    #[test]
+    fn test_list_containers_over_mock_socket() {
+        let body = r#"[{"Id":"abc123","Names":["/web"],"Image":"nginx:latest","Created":1700000000,
+            "Ports":[{"IP":"0.0.0.0","PrivatePort":80,"PublicPort":8080,"Type":"tcp"},{"PrivatePort":443,"Type":"tcp"}],
+            "Labels":{"com.example.team":"infra"},"State":"running","Status":"Up 2 hours (healthy)"}]"#;
+        let socket = mock_engine("list", vec![chunked_response(body)]);
+
+        let containers = EngineClient::new(&socket).list_containers().unwrap();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].names, vec!["/web".to_string()]);
+        assert_eq!(containers[0].ports.len(), 2);
+        assert_eq!(containers[0].ports[0].public_port, Some(8080));
+        assert_eq!(containers[0].ports[1].public_port, None);
+        assert_eq!(containers[0].labels.as_ref().unwrap()["com.example.team"], "infra");
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_list_single_container_over_mock_socket() {
+        let body = r#"[{"Id":"abc123","Names":["/web"],"Image":"nginx:latest","Created":1700000000,"Ports":[],"State":"paused","Status":"Up 2 hours (Paused)"}]"#;
+        let found = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let removed = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n[]".to_string();
+        let socket = mock_engine("list_one", vec![found, removed]);
+
+        let client = EngineClient::new(&socket);
+        assert_eq!(client.list_container("abc123").unwrap().map(|c| c.state), Some("paused".to_string()));
+        assert!(client.list_container("abc123").unwrap().is_none());
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_inspect_container_over_mock_socket() {
+        let body = r#"{"Id":"abc123","RestartCount":4,"State":{"Status":"exited","ExitCode":137,"OOMKilled":true,"Health":{"Status":"unhealthy"}}}"#;
+        let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let socket = mock_engine("inspect", vec![response]);
+
+        let details = EngineClient::new(&socket).inspect_container("abc123").unwrap();
+        assert_eq!(details.restart_count, 4);
+        assert_eq!(details.exit_code, Some(137));
+        assert!(details.oom_killed);
+        assert_eq!(details.health.as_deref(), Some("unhealthy"));
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_event_stream_over_mock_socket() {
+        let events = concat!(
+            r#"{"Type":"container","Action":"start","Actor":{"ID":"abc123","Attributes":{"name":"web"}},"time":1700000000}"#, "\n",
+            r#"{"Type":"network","Action":"connect","Actor":{"ID":"net1","Attributes":{}},"time":1700000001}"#, "\n",
+            r#"{"Type":"container","Action":"die","Actor":{"ID":"abc123","Attributes":{"name":"web","exitCode":"137"}},"time":1700000002}"#, "\n",
+        );
+        let socket = mock_engine("events", vec![chunked_response(events)]);
+
+        let receiver = EngineClient::new(&socket).subscribe_events().unwrap();
+        let first = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+        let second = receiver.recv_timeout(Duration::from_secs(2)).unwrap();
+
+        assert_eq!(first.action, "start");
+        assert_eq!(first.container_name, "web");
+        assert_eq!(second.action, "die");
+        assert_eq!(second.exit_code, Some(137));
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_error_status_is_reported() {
+        let body = r#"{"message":"page not found"}"#;
+        let response = format!("HTTP/1.1 404 Not Found\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+        let socket = mock_engine("error", vec![response]);
+
+        assert!(EngineClient::new(&socket).list_containers().is_err());
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_container_action_over_mock_socket() {
+        let conflict = r#"{"message":"container is running: stop the container before removing"}"#;
+        let responses = vec![
+            "HTTP/1.1 204 No Content\r\n\r\n".to_string(),
+            "HTTP/1.1 304 Not Modified\r\n\r\n".to_string(),
+            format!("HTTP/1.1 409 Conflict\r\nContent-Length: {}\r\n\r\n{}", conflict.len(), conflict),
+        ];
+        let socket = mock_engine("action", responses);
+        let client = EngineClient::new(&socket);
+
+        assert!(client.container_action("abc123", "stop").is_ok());
+        assert!(client.container_action("abc123", "start").is_ok());
+        let error = client.container_action("abc123", "remove").unwrap_err();
+        assert!(error.to_string().contains("container is running"));
+
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn test_log_demuxer_multiplexed_frames() {
+        let mut frames = Vec::new();
+        for (stream, text) in [(1u8, "hello "), (2u8, "world\nsecond"), (1u8, " line\n")] {
+            frames.extend_from_slice(&[stream, 0, 0, 0]);
+            frames.extend_from_slice(&(text.len() as u32).to_be_bytes());
+            frames.extend_from_slice(text.as_bytes());
+        }
+
+        let mut demuxer = LogDemuxer::new(None);
+        let (first, rest) = frames.split_at(5);
+        assert!(demuxer.push(first).is_empty());
+        assert_eq!(demuxer.push(rest), vec!["hello world".to_string(), "second line".to_string()]);
+    }
+
+    #[test]
+    fn test_log_demuxer_raw_tty_stream() {
+        let mut demuxer = LogDemuxer::new(None);
+        assert_eq!(demuxer.push(b"plain tty output\r\nnext"), vec!["plain tty output".to_string()]);
+        assert_eq!(demuxer.push(b" line\n"), vec!["next line".to_string()]);
+    }
+}
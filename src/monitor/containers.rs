@@ -1,9 +1,30 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::Path;
 use std::process::Command;
 use std::str;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::cgroups::{CgroupReader, CpuUsageSampler};
+use super::container_engine::{ContainerEvent, EngineClient, EngineContainer, EngineContainerDetails, LogFollower};
+use super::pressure::PressureMetrics;
+use super::AlertSeverity;
+
+const MAX_RECENT_EVENTS: usize = 100;
+const MAX_ALERTS: usize = 100;
+/// Container events re-list only the containers they name; this catches
+/// what the event stream cannot report, such as status text and uptime.
+const ENGINE_REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(5);
+/// Event actions that change a container's list entry or inspect output.
+/// Healthchecks emit exec_* and health_status events on every probe, so
+/// those are left to the periodic refresh.
+const STATE_CHANGING_ACTIONS: &[&str] = &["create", "start", "die", "stop", "destroy", "pause", "unpause", "rename"];
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContainerInfo {
@@ -22,6 +43,12 @@ pub struct ContainerInfo {
     pub block_read: u64,
     pub block_write: u64,
     pub pids: u32,
+    pub oom_kills: u64,
+    #[serde(default)]
+    pub pressure: PressureMetrics,
+    pub health: Option<String>,
+    pub restart_count: u32,
+    pub exit_code: Option<i64>,
     pub labels: HashMap<String, String>,
     pub runtime: ContainerRuntime,
 }
@@ -60,10 +87,89 @@ pub struct ContainerMetrics {
     pub total_cpu_usage: f64,
     pub total_memory_usage: u64,
     pub runtimes_available: Vec<ContainerRuntime>,
+    pub recent_events: Vec<ContainerEvent>,
+    pub alerts: Vec<ContainerAlert>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ContainerAction {
+    Start,
+    Stop,
+    Restart,
+    Pause,
+    Unpause,
+    Kill,
+    Remove,
+}
+
+impl ContainerAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContainerAction::Start => "start",
+            ContainerAction::Stop => "stop",
+            ContainerAction::Restart => "restart",
+            ContainerAction::Pause => "pause",
+            ContainerAction::Unpause => "unpause",
+            ContainerAction::Kill => "kill",
+            ContainerAction::Remove => "remove",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerAlert {
+    pub timestamp: DateTime<Utc>,
+    pub severity: AlertSeverity,
+    pub container_id: String,
+    pub container_name: String,
+    /// The engine event that raised the alert, such as `oom` or `die`.
+    #[serde(default)]
+    pub action: String,
+    pub message: String,
+}
+
+/// A container action running on its own thread; stop and restart wait
+/// out the container's stop timeout before the engine answers.
+pub struct ContainerActionJob {
+    pub action: ContainerAction,
+    pub container_name: String,
+    receiver: Receiver<Result<()>>,
+}
+
+impl ContainerActionJob {
+    /// The action's outcome, or `None` while it is still running.
+    pub fn poll(&self) -> Option<Result<()>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow!("action thread exited"))),
+        }
+    }
+}
+
+/// The engine's container list and image count as of `taken_at`.
+struct EngineSnapshot {
+    runtime: ContainerRuntime,
+    containers: Vec<EngineContainer>,
+    images_count: usize,
+    taken_at: Instant,
+    /// Containers with events since, whose entries are re-listed singly.
+    stale: HashSet<String>,
 }
 
 pub struct ContainerMonitor {
     available_runtimes: Vec<ContainerRuntime>,
+    engines: Vec<(ContainerRuntime, EngineClient)>,
+    /// `None` until subscribed, and again once the engine closes the stream.
+    event_receivers: Vec<(ContainerRuntime, Option<Receiver<ContainerEvent>>)>,
+    last_subscribe_attempt: Instant,
+    snapshots: Vec<EngineSnapshot>,
+    details: HashMap<String, EngineContainerDetails>,
+    recent_events: VecDeque<ContainerEvent>,
+    alerts: VecDeque<ContainerAlert>,
+    new_alerts: Vec<ContainerAlert>,
+    cgroup_reader: CgroupReader,
+    cpu_sampler: CpuUsageSampler,
 }
 
 impl Default for ContainerStatus {
@@ -89,25 +195,62 @@ impl From<&str> for ContainerStatus {
 impl ContainerMonitor {
     pub fn new() -> Self {
         let mut available_runtimes = Vec::new();
-        
+        let mut engines = Vec::new();
+
+        // Prefer the engine API sockets; fall back to the CLIs when absent
+        if let Some(client) = EngineClient::docker().filter(|c| c.ping()) {
+            engines.push((ContainerRuntime::Docker, client));
+        }
+        if let Some(client) = EngineClient::podman().filter(|c| c.ping()) {
+            engines.push((ContainerRuntime::Podman, client));
+        }
+
         // Check for Docker
-        if Command::new("docker").arg("--version").output().is_ok() {
+        if engines.iter().any(|(r, _)| *r == ContainerRuntime::Docker)
+            || Command::new("docker").arg("--version").output().is_ok() {
             available_runtimes.push(ContainerRuntime::Docker);
         }
         
         // Check for Podman
-        if Command::new("podman").arg("--version").output().is_ok() {
+        if engines.iter().any(|(r, _)| *r == ContainerRuntime::Podman)
+            || Command::new("podman").arg("--version").output().is_ok() {
             available_runtimes.push(ContainerRuntime::Podman);
         }
 
-        Self { available_runtimes }
+        let event_receivers = engines.iter()
+            .map(|(runtime, client)| (runtime.clone(), client.subscribe_events().ok()))
+            .collect();
+
+        Self {
+            available_runtimes,
+            engines,
+            event_receivers,
+            last_subscribe_attempt: Instant::now(),
+            snapshots: Vec::new(),
+            details: HashMap::new(),
+            recent_events: VecDeque::new(),
+            alerts: VecDeque::new(),
+            new_alerts: Vec::new(),
+            cgroup_reader: CgroupReader::new(),
+            cpu_sampler: CpuUsageSampler::new(),
+        }
     }
 
-    pub fn get_container_metrics(&self) -> Result<ContainerMetrics> {
+    pub fn get_container_metrics(&mut self) -> Result<ContainerMetrics> {
         let mut all_containers = Vec::new();
         let mut images_count = 0;
 
-        for runtime in &self.available_runtimes {
+        self.poll_events();
+
+        for runtime in self.available_runtimes.clone() {
+            if let Some(engine) = self.engine_for(&runtime) {
+                if let Ok((containers, count)) = self.get_engine_containers(&engine, runtime.clone()) {
+                    all_containers.extend(containers);
+                    images_count += count;
+                }
+                continue;
+            }
+
             match runtime {
                 ContainerRuntime::Docker => {
                     if let Ok(containers) = self.get_docker_containers() {
@@ -128,6 +271,8 @@ impl ContainerMonitor {
             }
         }
 
+        self.forget_stale_samples(&all_containers);
+
         let running_containers = all_containers.iter()
             .filter(|c| c.status == ContainerStatus::Running)
             .count();
@@ -151,10 +296,340 @@ impl ContainerMonitor {
             total_memory_usage,
             containers: all_containers,
             runtimes_available: self.available_runtimes.clone(),
+            recent_events: self.recent_events.iter().cloned().collect(),
+            alerts: self.alerts.iter().cloned().collect(),
         })
     }
 
-    fn get_docker_containers(&self) -> Result<Vec<ContainerInfo>> {
+    fn engine_for(&self, runtime: &ContainerRuntime) -> Option<EngineClient> {
+        self.engines.iter()
+            .find(|(r, _)| r == runtime)
+            .map(|(_, client)| client.clone())
+    }
+
+    /// Containers and image count from the engine. The list is re-read every
+    /// `ENGINE_REFRESH_INTERVAL`; in between, containers with state-changing
+    /// events are re-listed and re-inspected on their own.
+    fn get_engine_containers(&mut self, engine: &EngineClient, runtime: ContainerRuntime) -> Result<(Vec<ContainerInfo>, usize)> {
+        let mut fresh = false;
+        if let Some(snapshot) = self.snapshots.iter_mut()
+            .find(|s| s.runtime == runtime && s.taken_at.elapsed() < ENGINE_REFRESH_INTERVAL)
+        {
+            fresh = true;
+            for id in std::mem::take(&mut snapshot.stale) {
+                match engine.list_container(&id) {
+                    Ok(Some(container)) => match snapshot.containers.iter_mut().find(|c| c.id == id) {
+                        Some(entry) => *entry = container,
+                        None => snapshot.containers.push(container),
+                    },
+                    Ok(None) => snapshot.containers.retain(|c| c.id != id),
+                    Err(_) => {
+                        // Fall back to a full re-list
+                        fresh = false;
+                        break;
+                    }
+                }
+            }
+        }
+
+        if !fresh {
+            let containers = engine.list_containers()?;
+            let images_count = engine.images_count().unwrap_or(0);
+            self.snapshots.retain(|s| s.runtime != runtime);
+            for container in &containers {
+                self.details.remove(&container.id);
+            }
+            self.snapshots.push(EngineSnapshot {
+                runtime: runtime.clone(),
+                containers,
+                images_count,
+                taken_at: Instant::now(),
+                stale: HashSet::new(),
+            });
+        }
+
+        let snapshot = self.snapshots.iter()
+            .find(|s| s.runtime == runtime)
+            .expect("snapshot was just taken");
+        let images_count = snapshot.images_count;
+        let mut containers: Vec<ContainerInfo> = snapshot.containers.iter()
+            .cloned()
+            .map(|c| Self::container_from_engine(c, runtime.clone()))
+            .collect();
+
+        for container in &mut containers {
+            if !self.details.contains_key(&container.id) {
+                if let Ok(details) = engine.inspect_container(&container.id) {
+                    self.details.insert(container.id.clone(), details);
+                }
+            }
+            if let Some(details) = self.details.get(&container.id) {
+                container.health = details.health.clone();
+                container.restart_count = details.restart_count;
+                if container.status != ContainerStatus::Running {
+                    container.exit_code = details.exit_code;
+                }
+            }
+
+            if container.status == ContainerStatus::Running {
+                self.apply_cgroup_stats(container);
+            }
+        }
+
+        Ok((containers, images_count))
+    }
+
+    fn container_from_engine(container: EngineContainer, runtime: ContainerRuntime) -> ContainerInfo {
+        let name = container.names.first()
+            .map(|n| n.trim_start_matches('/').to_string())
+            .unwrap_or_else(|| container.id.chars().take(12).collect());
+
+        let ports = container.ports.iter()
+            .map(|p| PortMapping {
+                container_port: p.private_port,
+                host_port: p.public_port,
+                protocol: p.protocol.clone(),
+            })
+            .collect();
+
+        ContainerInfo {
+            id: container.id,
+            name,
+            image: container.image,
+            status: ContainerStatus::from(container.state.as_str()),
+            state: container.status,
+            created: DateTime::from_timestamp(container.created, 0).unwrap_or_else(Utc::now),
+            ports,
+            labels: container.labels.unwrap_or_default(),
+            runtime,
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            memory_limit: 0,
+            network_rx: 0,
+            network_tx: 0,
+            block_read: 0,
+            block_write: 0,
+            pids: 0,
+            oom_kills: 0,
+            pressure: PressureMetrics::default(),
+            health: None,
+            restart_count: 0,
+            exit_code: None,
+        }
+    }
+
+    /// Drains the engine event streams, keeping a bounded history and
+    /// raising alerts for crashes, OOM kills and failed health checks.
+    /// Streams closed by an engine restart are resubscribed.
+    fn poll_events(&mut self) {
+        let mut events = Vec::new();
+        for (runtime, receiver) in &mut self.event_receivers {
+            let Some(stream) = receiver.as_ref() else { continue };
+            loop {
+                match stream.try_recv() {
+                    Ok(event) => events.push((runtime.clone(), event)),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        *receiver = None;
+                        break;
+                    }
+                }
+            }
+        }
+        self.resubscribe_events();
+
+        for (runtime, event) in events {
+            if STATE_CHANGING_ACTIONS.contains(&event.action.as_str()) {
+                self.details.remove(&event.container_id);
+                if let Some(snapshot) = self.snapshots.iter_mut().find(|s| s.runtime == runtime) {
+                    snapshot.stale.insert(event.container_id.clone());
+                }
+            }
+
+            if let Some(alert) = Self::alert_for_event(&event) {
+                self.new_alerts.push(alert.clone());
+                if self.new_alerts.len() > MAX_ALERTS {
+                    self.new_alerts.remove(0);
+                }
+                self.alerts.push_back(alert);
+                if self.alerts.len() > MAX_ALERTS {
+                    self.alerts.pop_front();
+                }
+            }
+
+            self.recent_events.push_back(event);
+            if self.recent_events.len() > MAX_RECENT_EVENTS {
+                self.recent_events.pop_front();
+            }
+        }
+    }
+
+    fn resubscribe_events(&mut self) {
+        if self.event_receivers.iter().all(|(_, receiver)| receiver.is_some())
+            || self.last_subscribe_attempt.elapsed() < RESUBSCRIBE_INTERVAL {
+            return;
+        }
+        self.last_subscribe_attempt = Instant::now();
+
+        for (runtime, receiver) in &mut self.event_receivers {
+            if receiver.is_some() {
+                continue;
+            }
+            let Some((_, client)) = self.engines.iter().find(|(r, _)| r == runtime) else { continue };
+            if let Ok(stream) = client.subscribe_events() {
+                *receiver = Some(stream);
+                // Whatever happened while the engine was away went unreported
+                self.snapshots.clear();
+                self.details.clear();
+            }
+        }
+    }
+
+    /// Alerts raised since the last call, for the security dashboard.
+    pub fn take_new_alerts(&mut self) -> Vec<ContainerAlert> {
+        self.poll_events();
+        std::mem::take(&mut self.new_alerts)
+    }
+
+    fn alert_for_event(event: &ContainerEvent) -> Option<ContainerAlert> {
+        let (severity, message) = match event.action.as_str() {
+            "oom" => (AlertSeverity::Critical, format!("Container {} was OOM killed", event.container_name)),
+            "die" => match event.exit_code {
+                Some(code) if code != 0 => (
+                    AlertSeverity::High,
+                    format!("Container {} exited with code {}", event.container_name, code),
+                ),
+                _ => return None,
+            },
+            "health_status: unhealthy" => (
+                AlertSeverity::Medium,
+                format!("Container {} is unhealthy", event.container_name),
+            ),
+            _ => return None,
+        };
+
+        Some(ContainerAlert {
+            timestamp: event.timestamp,
+            severity,
+            container_id: event.container_id.clone(),
+            container_name: event.container_name.clone(),
+            action: event.action.clone(),
+            message,
+        })
+    }
+
+    /// Starts `action` on a background thread; poll the job for its result.
+    pub fn start_action(&self, container: &ContainerInfo, action: ContainerAction) -> Result<ContainerActionJob> {
+        let engine = self.require_engine(&container.runtime)?;
+        let id = container.id.clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(engine.container_action(&id, action.as_str()));
+        });
+
+        Ok(ContainerActionJob { action, container_name: container.name.clone(), receiver })
+    }
+
+    pub fn follow_logs(&self, container: &ContainerInfo, tail: usize) -> Result<LogFollower> {
+        self.require_engine(&container.runtime)?
+            .follow_logs(&container.id, tail)
+    }
+
+    /// Runs an interactive shell in the container on the current terminal.
+    pub fn exec_shell(&self, container: &ContainerInfo, columns: u16, rows: u16) -> Result<()> {
+        if container.status != ContainerStatus::Running {
+            return Err(anyhow!("Container {} is not running", container.name));
+        }
+        self.require_engine(&container.runtime)?
+            .exec_shell(&container.id, columns, rows)
+    }
+
+    fn require_engine(&self, runtime: &ContainerRuntime) -> Result<EngineClient> {
+        self.engine_for(runtime)
+            .ok_or_else(|| anyhow!("{:?} engine socket is not available", runtime))
+    }
+
+    pub fn get_recent_events(&self) -> &VecDeque<ContainerEvent> {
+        &self.recent_events
+    }
+
+    /// Fills in resource usage from the container's cgroup v2 directory.
+    /// Returns false when the container has no cgroup we can read, in which
+    /// case the caller falls back to the runtime's stats command.
+    fn apply_cgroup_stats(&mut self, container: &mut ContainerInfo) -> bool {
+        if !self.cgroup_reader.is_unified() {
+            return false;
+        }
+
+        let cgroup_path = match self.cgroup_reader.find_container_cgroup(&container.id) {
+            Some(path) => path,
+            None => return false,
+        };
+
+        let stats = match self.cgroup_reader.read_stats(&cgroup_path) {
+            Ok(stats) => stats,
+            Err(_) => return false,
+        };
+
+        container.cpu_usage = self.cpu_sampler.sample(&container.id, stats.cpu_usage_usec);
+
+        container.memory_usage = stats.memory_current;
+        container.memory_limit = stats.memory_max.unwrap_or(0);
+        container.block_read = stats.io_read_bytes;
+        container.block_write = stats.io_write_bytes;
+        container.pids = stats.pids_current;
+        container.oom_kills = stats.oom_kills;
+        container.pressure = stats.pressure;
+
+        // Network counters are per network namespace, not per cgroup, so
+        // read them through any process that lives in the container.
+        if let Some(pid) = self.cgroup_reader.read_procs(&cgroup_path).first() {
+            let (rx, tx) = Self::read_namespace_network_io(*pid);
+            container.network_rx = rx;
+            container.network_tx = tx;
+        }
+
+        true
+    }
+
+    fn read_namespace_network_io(pid: u32) -> (u64, u64) {
+        let net_dev = Path::new("/proc").join(pid.to_string()).join("net").join("dev");
+        let content = match fs::read_to_string(net_dev) {
+            Ok(content) => content,
+            Err(_) => return (0, 0),
+        };
+
+        let mut rx = 0;
+        let mut tx = 0;
+        for line in content.lines().skip(2) {
+            let (iface, counters) = match line.split_once(':') {
+                Some(parts) => parts,
+                None => continue,
+            };
+            if iface.trim() == "lo" {
+                continue;
+            }
+
+            let fields: Vec<u64> = counters.split_whitespace()
+                .filter_map(|f| f.parse().ok())
+                .collect();
+            if fields.len() >= 9 {
+                rx += fields[0];
+                tx += fields[8];
+            }
+        }
+
+        (rx, tx)
+    }
+
+    fn forget_stale_samples(&mut self, containers: &[ContainerInfo]) {
+        self.cpu_sampler
+            .retain(|id| containers.iter().any(|c| c.id == id));
+        self.details
+            .retain(|id, _| containers.iter().any(|c| &c.id == id));
+    }
+
+    fn get_docker_containers(&mut self) -> Result<Vec<ContainerInfo>> {
         let output = Command::new("docker")
             .args(&["ps", "-a", "--format", "json"])
             .output()?;
@@ -179,6 +654,9 @@ impl ContainerMonitor {
         // Get detailed stats for running containers
         for container in &mut containers {
             if container.status == ContainerStatus::Running {
+                if self.apply_cgroup_stats(container) {
+                    continue;
+                }
                 if let Ok(stats) = self.get_docker_container_stats(&container.id) {
                     container.cpu_usage = stats.0;
                     container.memory_usage = stats.1;
@@ -195,7 +673,7 @@ impl ContainerMonitor {
         Ok(containers)
     }
 
-    fn get_podman_containers(&self) -> Result<Vec<ContainerInfo>> {
+    fn get_podman_containers(&mut self) -> Result<Vec<ContainerInfo>> {
         let output = Command::new("podman")
             .args(&["ps", "-a", "--format", "json"])
             .output()?;
@@ -220,6 +698,9 @@ impl ContainerMonitor {
         // Get detailed stats for running containers
         for container in &mut containers {
             if container.status == ContainerStatus::Running {
+                if self.apply_cgroup_stats(container) {
+                    continue;
+                }
                 if let Ok(stats) = self.get_podman_container_stats(&container.id) {
                     container.cpu_usage = stats.0;
                     container.memory_usage = stats.1;
@@ -284,6 +765,11 @@ impl ContainerMonitor {
             block_read: 0,
             block_write: 0,
             pids: 0,
+            oom_kills: 0,
+            pressure: PressureMetrics::default(),
+            health: None,
+            restart_count: 0,
+            exit_code: None,
         })
     }
 
@@ -339,6 +825,11 @@ impl ContainerMonitor {
             block_read: 0,
             block_write: 0,
             pids: 0,
+            oom_kills: 0,
+            pressure: PressureMetrics::default(),
+            health: None,
+            restart_count: 0,
+            exit_code: None,
         })
     }
 
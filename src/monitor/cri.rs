@@ -0,0 +1,486 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+const CRI_SOCKETS: &[&str] = &[
+    "/run/containerd/containerd.sock",
+    "/run/k3s/containerd/containerd.sock",
+    "/var/run/crio/crio.sock",
+    "/var/run/cri-dockerd.sock",
+];
+const RUNTIME_SERVICES: &[&str] = &["runtime.v1.RuntimeService", "runtime.v1alpha2.RuntimeService"];
+
+const HTTP2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const FRAME_DATA: u8 = 0x0;
+const FRAME_HEADERS: u8 = 0x1;
+const FRAME_RST_STREAM: u8 = 0x3;
+const FRAME_SETTINGS: u8 = 0x4;
+const FRAME_PING: u8 = 0x6;
+const FRAME_GOAWAY: u8 = 0x7;
+const FRAME_WINDOW_UPDATE: u8 = 0x8;
+const FLAG_END_STREAM: u8 = 0x1;
+const FLAG_ACK: u8 = 0x1;
+const FLAG_END_HEADERS: u8 = 0x4;
+const FLAG_PADDED: u8 = 0x8;
+const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+// Large enough that a full container list never stalls on flow control
+const RECEIVE_WINDOW: u32 = 16 * 1024 * 1024;
+
+/// A container as reported by the CRI `ListContainers` call.
+#[derive(Debug, Clone, Default)]
+pub struct CriContainer {
+    pub id: String,
+    pub pod_sandbox_id: String,
+    pub name: String,
+    pub labels: HashMap<String, String>,
+}
+
+impl CriContainer {
+    pub fn pod_name(&self) -> Option<&str> {
+        self.labels.get("io.kubernetes.pod.name").map(|s| s.as_str())
+    }
+
+    pub fn pod_namespace(&self) -> Option<&str> {
+        self.labels.get("io.kubernetes.pod.namespace").map(|s| s.as_str())
+    }
+
+    pub fn pod_uid(&self) -> Option<&str> {
+        self.labels.get("io.kubernetes.pod.uid").map(|s| s.as_str())
+    }
+
+    pub fn container_name(&self) -> &str {
+        self.labels.get("io.kubernetes.container.name")
+            .map(|s| s.as_str())
+            .unwrap_or(&self.name)
+    }
+}
+
+/// Minimal client for the Kubernetes Container Runtime Interface. CRI is
+/// gRPC, so this speaks just enough HTTP/2 and protobuf to issue unary
+/// calls against containerd or CRI-O over their unix sockets.
+#[derive(Debug, Clone)]
+pub struct CriClient {
+    socket_path: PathBuf,
+}
+
+impl CriClient {
+    pub fn new<P: AsRef<Path>>(socket_path: P) -> Self {
+        Self {
+            socket_path: socket_path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Returns a client for the first CRI socket found on this node.
+    pub fn discover() -> Option<Self> {
+        CRI_SOCKETS.iter()
+            .find(|path| Path::new(path).exists())
+            .map(Self::new)
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+
+    pub fn list_containers(&self) -> Result<Vec<CriContainer>> {
+        let mut last_error = anyhow!("no CRI runtime service responded");
+
+        for service in RUNTIME_SERVICES {
+            // ListContainersRequest with no filter encodes to zero bytes
+            match self.unary_call(&format!("/{}/ListContainers", service), &[]) {
+                Ok(Some(response)) => return decode_list_containers(&response),
+                Ok(None) => last_error = anyhow!("{} returned no message", service),
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(last_error)
+    }
+
+    /// Performs a unary gRPC call and returns the response message, or
+    /// None when the server ended the stream without one (for example
+    /// because the method is not implemented).
+    fn unary_call(&self, path: &str, message: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut stream = UnixStream::connect(&self.socket_path)?;
+        stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+        stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+        let mut settings = Vec::new();
+        settings.extend_from_slice(&SETTINGS_INITIAL_WINDOW_SIZE.to_be_bytes());
+        settings.extend_from_slice(&RECEIVE_WINDOW.to_be_bytes());
+
+        let mut request = HTTP2_PREFACE.to_vec();
+        write_frame(&mut request, FRAME_SETTINGS, 0, 0, &settings);
+        write_frame(&mut request, FRAME_WINDOW_UPDATE, 0, 0, &(RECEIVE_WINDOW - 65_535).to_be_bytes());
+
+        let mut headers = Vec::new();
+        for (name, value) in [
+            (":method", "POST"),
+            (":scheme", "http"),
+            (":path", path),
+            (":authority", "localhost"),
+            ("content-type", "application/grpc"),
+            ("te", "trailers"),
+        ] {
+            encode_literal_header(&mut headers, name, value);
+        }
+        write_frame(&mut request, FRAME_HEADERS, FLAG_END_HEADERS, 1, &headers);
+
+        let mut grpc_message = vec![0];
+        grpc_message.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        grpc_message.extend_from_slice(message);
+        write_frame(&mut request, FRAME_DATA, FLAG_END_STREAM, 1, &grpc_message);
+
+        stream.write_all(&request)?;
+
+        let mut body = Vec::new();
+        loop {
+            let frame = read_frame(&mut stream)?;
+            match frame.frame_type {
+                FRAME_SETTINGS if frame.flags & FLAG_ACK == 0 => {
+                    let mut ack = Vec::new();
+                    write_frame(&mut ack, FRAME_SETTINGS, FLAG_ACK, 0, &[]);
+                    stream.write_all(&ack)?;
+                }
+                FRAME_PING if frame.flags & FLAG_ACK == 0 => {
+                    let mut pong = Vec::new();
+                    write_frame(&mut pong, FRAME_PING, FLAG_ACK, 0, &frame.payload);
+                    stream.write_all(&pong)?;
+                }
+                FRAME_DATA if frame.stream_id == 1 => {
+                    body.extend_from_slice(strip_padding(&frame)?);
+                    if frame.flags & FLAG_END_STREAM != 0 {
+                        break;
+                    }
+                }
+                FRAME_HEADERS if frame.stream_id == 1 && frame.flags & FLAG_END_STREAM != 0 => break,
+                FRAME_RST_STREAM if frame.stream_id == 1 => return Err(anyhow!("{} was reset by the runtime", path)),
+                FRAME_GOAWAY => break,
+                _ => {}
+            }
+        }
+
+        if body.len() < 5 {
+            return Ok(None);
+        }
+        if body[0] != 0 {
+            return Err(anyhow!("compressed gRPC responses are not supported"));
+        }
+        let length = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        if body.len() < 5 + length {
+            return Err(anyhow!("truncated gRPC response from {}", path));
+        }
+
+        Ok(Some(body[5..5 + length].to_vec()))
+    }
+}
+
+struct Frame {
+    frame_type: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+fn write_frame(out: &mut Vec<u8>, frame_type: u8, flags: u8, stream_id: u32, payload: &[u8]) {
+    let length = payload.len() as u32;
+    out.extend_from_slice(&length.to_be_bytes()[1..]);
+    out.push(frame_type);
+    out.push(flags);
+    out.extend_from_slice(&(stream_id & 0x7fff_ffff).to_be_bytes());
+    out.extend_from_slice(payload);
+}
+
+fn read_frame<R: Read>(reader: &mut R) -> Result<Frame> {
+    let mut header = [0u8; 9];
+    reader.read_exact(&mut header)?;
+
+    let length = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let stream_id = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff;
+    let mut payload = vec![0; length];
+    reader.read_exact(&mut payload)?;
+
+    Ok(Frame {
+        frame_type: header[3],
+        flags: header[4],
+        stream_id,
+        payload,
+    })
+}
+
+fn strip_padding(frame: &Frame) -> Result<&[u8]> {
+    if frame.flags & FLAG_PADDED == 0 {
+        return Ok(&frame.payload);
+    }
+
+    let pad_length = *frame.payload.first().ok_or_else(|| anyhow!("empty padded frame"))? as usize;
+    if frame.payload.len() < 1 + pad_length {
+        return Err(anyhow!("invalid frame padding"));
+    }
+    Ok(&frame.payload[1..frame.payload.len() - pad_length])
+}
+
+/// Encodes an HPACK "literal header field without indexing" with a literal
+/// name, which every HTTP/2 server must accept and needs no table state.
+fn encode_literal_header(out: &mut Vec<u8>, name: &str, value: &str) {
+    out.push(0x00);
+    encode_hpack_string(out, name);
+    encode_hpack_string(out, value);
+}
+
+fn encode_hpack_string(out: &mut Vec<u8>, value: &str) {
+    let mut length = value.len();
+    if length < 0x7f {
+        out.push(length as u8);
+    } else {
+        out.push(0x7f);
+        length -= 0x7f;
+        while length >= 0x80 {
+            out.push((length % 0x80) as u8 | 0x80);
+            length /= 0x80;
+        }
+        out.push(length as u8);
+    }
+    out.extend_from_slice(value.as_bytes());
+}
+
+/// Iterates over the fields of a protobuf message, yielding the field
+/// number and, for length-delimited fields, the raw bytes. Scalar fields
+/// are skipped since the CRI messages we read only need strings and maps.
+struct ProtoFields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for ProtoFields<'a> {
+    type Item = Result<(u64, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.data.is_empty() {
+            let key = match read_varint(&mut self.data) {
+                Ok(key) => key,
+                Err(e) => return Some(Err(e)),
+            };
+            let field = key >> 3;
+
+            let skipped = match key & 0x7 {
+                0 => read_varint(&mut self.data).map(|_| ()),
+                1 => self.skip(8),
+                5 => self.skip(4),
+                2 => {
+                    let length = match read_varint(&mut self.data) {
+                        Ok(length) => length as usize,
+                        Err(e) => return Some(Err(e)),
+                    };
+                    if self.data.len() < length {
+                        return Some(Err(anyhow!("truncated protobuf field {}", field)));
+                    }
+                    let (value, rest) = self.data.split_at(length);
+                    self.data = rest;
+                    return Some(Ok((field, value)));
+                }
+                wire_type => Err(anyhow!("unsupported protobuf wire type {}", wire_type)),
+            };
+
+            if let Err(e) = skipped {
+                return Some(Err(e));
+            }
+        }
+        None
+    }
+}
+
+impl<'a> ProtoFields<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn skip(&mut self, count: usize) -> Result<()> {
+        if self.data.len() < count {
+            return Err(anyhow!("truncated protobuf message"));
+        }
+        self.data = &self.data[count..];
+        Ok(())
+    }
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or_else(|| anyhow!("truncated varint"))?;
+        *data = rest;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(anyhow!("varint too long"))
+}
+
+fn decode_list_containers(message: &[u8]) -> Result<Vec<CriContainer>> {
+    let mut containers = Vec::new();
+    for field in ProtoFields::new(message) {
+        let (number, value) = field?;
+        if number == 1 {
+            containers.push(decode_container(value)?);
+        }
+    }
+    Ok(containers)
+}
+
+fn decode_container(message: &[u8]) -> Result<CriContainer> {
+    let mut container = CriContainer::default();
+
+    for field in ProtoFields::new(message) {
+        let (number, value) = field?;
+        match number {
+            1 => container.id = String::from_utf8_lossy(value).to_string(),
+            2 => container.pod_sandbox_id = String::from_utf8_lossy(value).to_string(),
+            3 => {
+                // ContainerMetadata { string name = 1; uint32 attempt = 2; }
+                for metadata_field in ProtoFields::new(value) {
+                    let (metadata_number, metadata_value) = metadata_field?;
+                    if metadata_number == 1 {
+                        container.name = String::from_utf8_lossy(metadata_value).to_string();
+                    }
+                }
+            }
+            8 => {
+                let (key, entry_value) = decode_map_entry(value)?;
+                container.labels.insert(key, entry_value);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(container)
+}
+
+fn decode_map_entry(entry: &[u8]) -> Result<(String, String)> {
+    let mut key = String::new();
+    let mut value = String::new();
+    for field in ProtoFields::new(entry) {
+        match field? {
+            (1, bytes) => key = String::from_utf8_lossy(bytes).to_string(),
+            (2, bytes) => value = String::from_utf8_lossy(bytes).to_string(),
+            _ => {}
+        }
+    }
+    Ok((key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    fn encode_varint(out: &mut Vec<u8>, mut value: u64) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+
+    fn encode_bytes_field(out: &mut Vec<u8>, number: u64, value: &[u8]) {
+        encode_varint(out, number << 3 | 2);
+        encode_varint(out, value.len() as u64);
+        out.extend_from_slice(value);
+    }
+
+    fn encode_test_container(id: &str, name: &str, labels: &[(&str, &str)]) -> Vec<u8> {
+        let mut container = Vec::new();
+        encode_bytes_field(&mut container, 1, id.as_bytes());
+        encode_bytes_field(&mut container, 2, b"sandbox-1");
+
+        let mut metadata = Vec::new();
+        encode_bytes_field(&mut metadata, 1, name.as_bytes());
+        encode_varint(&mut metadata, 2 << 3);
+        encode_varint(&mut metadata, 3);
+        encode_bytes_field(&mut container, 3, &metadata);
+
+        // state (enum, varint) between the string fields
+        encode_varint(&mut container, 6 << 3);
+        encode_varint(&mut container, 1);
+
+        for (key, value) in labels {
+            let mut entry = Vec::new();
+            encode_bytes_field(&mut entry, 1, key.as_bytes());
+            encode_bytes_field(&mut entry, 2, value.as_bytes());
+            encode_bytes_field(&mut container, 8, &entry);
+        }
+        container
+    }
+
+    #[test]
+    fn test_decode_list_containers() {
+        let container = encode_test_container("abc123", "nginx", &[
+            ("io.kubernetes.pod.name", "web-7d9f"),
+            ("io.kubernetes.pod.namespace", "prod"),
+            ("io.kubernetes.container.name", "nginx"),
+        ]);
+        let mut response = Vec::new();
+        encode_bytes_field(&mut response, 1, &container);
+
+        let containers = decode_list_containers(&response).unwrap();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, "abc123");
+        assert_eq!(containers[0].pod_sandbox_id, "sandbox-1");
+        assert_eq!(containers[0].container_name(), "nginx");
+        assert_eq!(containers[0].pod_name(), Some("web-7d9f"));
+        assert_eq!(containers[0].pod_namespace(), Some("prod"));
+    }
+
+    #[test]
+    fn test_list_containers_over_mock_socket() {
+        let socket_path = std::env::temp_dir().join(format!("seer_cri_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut preface = [0u8; 24];
+            stream.read_exact(&mut preface).unwrap();
+            assert_eq!(&preface[..], HTTP2_PREFACE);
+
+            // Read client frames until the request body has been sent
+            loop {
+                let frame = read_frame(&mut stream).unwrap();
+                if frame.frame_type == FRAME_DATA && frame.flags & FLAG_END_STREAM != 0 {
+                    break;
+                }
+            }
+
+            let container = encode_test_container("abc123", "app", &[
+                ("io.kubernetes.pod.name", "api-0"),
+                ("io.kubernetes.pod.namespace", "default"),
+            ]);
+            let mut message = Vec::new();
+            encode_bytes_field(&mut message, 1, &container);
+            let mut grpc = vec![0];
+            grpc.extend_from_slice(&(message.len() as u32).to_be_bytes());
+            grpc.extend_from_slice(&message);
+
+            let mut response = Vec::new();
+            write_frame(&mut response, FRAME_SETTINGS, 0, 0, &[]);
+            write_frame(&mut response, FRAME_HEADERS, FLAG_END_HEADERS, 1, &[0x88]);
+            write_frame(&mut response, FRAME_DATA, 0, 1, &grpc);
+            write_frame(&mut response, FRAME_HEADERS, FLAG_END_HEADERS | FLAG_END_STREAM, 1, &[0x40]);
+            stream.write_all(&response).unwrap();
+
+            // Swallow the client's SETTINGS acknowledgement
+            let mut sink = Vec::new();
+            let _ = stream.read_to_end(&mut sink);
+        });
+
+        let containers = CriClient::new(&socket_path).list_containers().unwrap();
+        assert_eq!(containers.len(), 1);
+        assert_eq!(containers[0].id, "abc123");
+        assert_eq!(containers[0].pod_name(), Some("api-0"));
+
+        let _ = std::fs::remove_file(socket_path);
+    }
+}
@@ -0,0 +1,307 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+
+/// Long enough to find a Feb 29 schedule across the 2100 non-leap year.
+const MAX_SEARCH_DAYS: u32 = 366 * 9;
+
+const MONTH_NAMES: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed five-field cron expression. Each field is a bitset of the
+/// values it matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+/// One job line from a crontab.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CronEntry {
+    pub expression: String,
+    /// None for `@reboot` jobs, which only run when cron starts.
+    pub schedule: Option<CronSchedule>,
+    pub user: String,
+    pub command: String,
+    pub source: String,
+}
+
+impl CronSchedule {
+    /// Parses "min hour dom month dow" or one of the `@hourly`-style
+    /// shorthands. `@reboot` has no schedule and is rejected.
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expanded = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other if other.starts_with('@') => return Err(anyhow!("Unsupported cron shorthand: {}", other)),
+            other => other,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(anyhow!("Expected 5 cron fields, got {}: {}", fields.len(), expression));
+        }
+
+        let days_of_week = parse_field(fields[4], 0, 7, WEEKDAY_NAMES, 0)?;
+        // 7 is an alias for Sunday
+        let days_of_week = (days_of_week | (days_of_week >> 7)) as u8 & 0x7f;
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59, &[], 0)?,
+            hours: parse_field(fields[1], 0, 23, &[], 0)? as u32,
+            days_of_month: parse_field(fields[2], 1, 31, &[], 0)? as u32,
+            months: parse_field(fields[3], 1, 12, MONTH_NAMES, 1)? as u16,
+            days_of_week,
+            day_of_month_any: fields[2].starts_with('*'),
+            day_of_week_any: fields[4].starts_with('*'),
+        })
+    }
+
+    /// Returns the first matching minute strictly after `after`, in the
+    /// time zone of `after`. Local times skipped by a DST change are
+    /// skipped; repeated ones match their earlier occurrence.
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let timezone = after.timezone();
+        let start = after.naive_local().with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let mut date = start.date();
+
+        for _ in 0..MAX_SEARCH_DAYS {
+            if self.matches_date(date) {
+                let first_day = date == start.date();
+                for hour in (0..24).filter(|h| self.hours & (1 << h) != 0) {
+                    if first_day && hour < start.hour() {
+                        continue;
+                    }
+                    for minute in (0..60).filter(|m| self.minutes & (1 << m) != 0) {
+                        if first_day && hour == start.hour() && minute < start.minute() {
+                            continue;
+                        }
+                        let candidate = date.and_hms_opt(hour, minute, 0)?;
+                        if let Some(time) = timezone.from_local_datetime(&candidate).earliest() {
+                            return Some(time);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+
+        None
+    }
+
+    /// Day-of-month and day-of-week are ORed when both are restricted,
+    /// as in Vixie cron: "0 0 1 * 1" runs on the 1st and on Mondays.
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+
+        let dom = self.days_of_month & (1 << date.day()) != 0;
+        let dow = self.days_of_week & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.day_of_month_any || self.day_of_week_any {
+            dom && dow
+        } else {
+            dom || dow
+        }
+    }
+
+    /// The hour and minute when both fields name a single value.
+    pub fn time_of_day(&self) -> Option<(u32, u32)> {
+        if self.hours.count_ones() == 1 && self.minutes.count_ones() == 1 {
+            Some((self.hours.trailing_zeros(), self.minutes.trailing_zeros()))
+        } else {
+            None
+        }
+    }
+
+    pub fn runs_every_hour(&self) -> bool {
+        self.hours == (1 << 24) - 1
+    }
+
+    pub fn runs_every_day(&self) -> bool {
+        self.day_of_month_any && self.day_of_week_any && self.months == 0x1ffe
+    }
+
+    pub fn day_of_month_restricted(&self) -> bool {
+        !self.day_of_month_any
+    }
+
+    /// Days of the week the job may run on, Sunday = 0.
+    pub fn days_of_week(&self) -> Vec<u8> {
+        (0..7).filter(|d| self.days_of_week & (1 << d) != 0).collect()
+    }
+}
+
+/// Parses one field into a bitset. Supports `*`, lists, ranges, steps
+/// (`*/15`, `1-30/2`, `5/10`) and three-letter names.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], name_offset: u32) -> Result<u64> {
+    let value = |text: &str| -> Result<u32> {
+        if let Ok(number) = text.parse::<u32>() {
+            return Ok(number);
+        }
+        names.iter()
+            .position(|name| name.eq_ignore_ascii_case(text))
+            .map(|index| index as u32 + name_offset)
+            .ok_or_else(|| anyhow!("Invalid cron value: {}", text))
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step.parse::<u32>().map_err(|_| anyhow!("Invalid cron step: {}", part))?)),
+            None => (part, None),
+        };
+        if step == Some(0) {
+            return Err(anyhow!("Invalid cron step: {}", part));
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else {
+            let start = value(range)?;
+            (start, if step.is_some() { max } else { start })
+        };
+
+        if start < min || end > max || start > end {
+            return Err(anyhow!("Cron value out of range {}-{}: {}", min, max, part));
+        }
+
+        for v in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            bits |= 1 << v;
+        }
+    }
+
+    Ok(bits)
+}
+
+/// Parses a crontab. System crontabs (/etc/crontab, /etc/cron.d) carry a
+/// user column; per-user crontabs run as `user`. Environment assignments
+/// and comments are skipped.
+pub fn parse_crontab(content: &str, user: Option<&str>, source: &str) -> Vec<CronEntry> {
+    content.lines()
+        .filter_map(|line| parse_crontab_line(line, user, source))
+        .collect()
+}
+
+pub fn parse_crontab_line(line: &str, user: Option<&str>, source: &str) -> Option<CronEntry> {
+    let line = line.trim();
+    let first = line.chars().next()?;
+    if !(first.is_ascii_digit() || first == '*' || first == '@') {
+        return None;
+    }
+
+    let schedule_fields = if first == '@' { 1 } else { 5 };
+    let user_fields = if user.is_some() { 0 } else { 1 };
+    let (fields, command) = split_fields(line, schedule_fields + user_fields)?;
+    if command.is_empty() {
+        return None;
+    }
+
+    let expression = fields[..schedule_fields].join(" ");
+    let schedule = if expression == "@reboot" {
+        None
+    } else {
+        Some(CronSchedule::parse(&expression).ok()?)
+    };
+
+    Some(CronEntry {
+        schedule,
+        user: user.map(|u| u.to_string()).unwrap_or_else(|| fields[schedule_fields].to_string()),
+        command: command.to_string(),
+        source: source.to_string(),
+        expression,
+    })
+}
+
+/// Takes `count` whitespace-separated fields and returns them with the
+/// untouched remainder of the line.
+fn split_fields(line: &str, count: usize) -> Option<(Vec<&str>, &str)> {
+    let mut fields = Vec::with_capacity(count);
+    let mut rest = line;
+    for _ in 0..count {
+        rest = rest.trim_start();
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if end == 0 {
+            return None;
+        }
+        fields.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    Some((fields, rest.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(expression: &str, after: &str) -> String {
+        CronSchedule::parse(expression).unwrap()
+            .next_after(&at(after))
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_next_after() {
+        assert_eq!(next("*/15 * * * *", "2024-03-10T10:07:30Z"), "2024-03-10T10:15:00+00:00");
+        assert_eq!(next("0 * * * *", "2024-03-10T10:00:00Z"), "2024-03-10T11:00:00+00:00");
+        assert_eq!(next("30 2 * * *", "2024-03-10T02:30:00Z"), "2024-03-11T02:30:00+00:00");
+        assert_eq!(next("0 9 * * mon-fri", "2024-03-08T17:00:00Z"), "2024-03-11T09:00:00+00:00");
+        assert_eq!(next("0 0 * * 7", "2024-03-10T00:00:00Z"), "2024-03-17T00:00:00+00:00");
+        assert_eq!(next("@monthly", "2024-01-31T12:00:00Z"), "2024-02-01T00:00:00+00:00");
+        assert_eq!(next("0 12 29 feb *", "2024-03-01T00:00:00Z"), "2028-02-29T12:00:00+00:00");
+        assert_eq!(next("5/20 1-3 * jan,jul *", "2024-03-01T00:00:00Z"), "2024-07-01T01:05:00+00:00");
+        // Both day fields restricted: the 13th or any Friday
+        assert_eq!(next("0 0 13 * 5", "2024-09-01T00:00:00Z"), "2024-09-06T00:00:00+00:00");
+        assert_eq!(next("0 0 13 * 5", "2024-09-10T00:00:00Z"), "2024-09-13T00:00:00+00:00");
+        // Never matches
+        assert_eq!(next("0 0 31 2 *", "2024-01-01T00:00:00Z"), "");
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_expressions() {
+        for expression in ["* * * *", "60 * * * *", "* 24 * * *", "*/0 * * * *", "5-1 * * * *", "* * * foo *", "@reboot"] {
+            assert!(CronSchedule::parse(expression).is_err(), "{} should not parse", expression);
+        }
+
+        let schedule = CronSchedule::parse("30 3 * * 0").unwrap();
+        assert_eq!(schedule.time_of_day(), Some((3, 30)));
+        assert_eq!(schedule.days_of_week(), vec![0]);
+        assert!(!schedule.runs_every_day());
+    }
+
+    #[test]
+    fn test_parse_crontab() {
+        let system = "SHELL=/bin/sh\n\
+                      # m h dom mon dow user command\n\
+                      30 3 * * 0 root test -e /run/systemd/system || SERVICE_MODE=1 /sbin/e2scrub_all_cron\n\
+                      @reboot root /usr/local/bin/warmup\n\
+                      99 * * * * root /bin/broken\n";
+        let entries = parse_crontab(system, None, "/etc/cron.d/e2scrub_all");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].expression, "30 3 * * 0");
+        assert_eq!(entries[0].user, "root");
+        assert_eq!(entries[0].command, "test -e /run/systemd/system || SERVICE_MODE=1 /sbin/e2scrub_all_cron");
+        assert!(entries[1].schedule.is_none());
+
+        let user = parse_crontab("0 1 * * *   /home/alice/backup.sh  --full\n", Some("alice"), "/var/spool/cron/crontabs/alice");
+        assert_eq!(user[0].user, "alice");
+        assert_eq!(user[0].command, "/home/alice/backup.sh  --full");
+    }
+}
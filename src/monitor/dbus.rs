@@ -0,0 +1,924 @@
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, VecDeque};
+use std::io::{ErrorKind, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const DEFAULT_SYSTEM_BUS: &str = "/run/dbus/system_bus_socket";
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_MESSAGE_SIZE: usize = 128 * 1024 * 1024;
+
+const BUS_NAME: &str = "org.freedesktop.DBus";
+const BUS_PATH: &str = "/org/freedesktop/DBus";
+
+pub const METHOD_CALL: u8 = 1;
+pub const METHOD_RETURN: u8 = 2;
+pub const ERROR: u8 = 3;
+pub const SIGNAL: u8 = 4;
+
+const FIELD_PATH: u8 = 1;
+const FIELD_INTERFACE: u8 = 2;
+const FIELD_MEMBER: u8 = 3;
+const FIELD_ERROR_NAME: u8 = 4;
+const FIELD_REPLY_SERIAL: u8 = 5;
+const FIELD_DESTINATION: u8 = 6;
+const FIELD_SENDER: u8 = 7;
+const FIELD_SIGNATURE: u8 = 8;
+
+/// A D-Bus value. Arrays carry their element signature so that empty
+/// arrays can still be marshalled.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(u8),
+    Bool(bool),
+    Int16(i16),
+    UInt16(u16),
+    Int32(i32),
+    UInt32(u32),
+    Int64(i64),
+    UInt64(u64),
+    Double(f64),
+    Str(String),
+    ObjectPath(String),
+    Signature(String),
+    UnixFd(u32),
+    Variant(Box<Value>),
+    Array(String, Vec<Value>),
+    Struct(Vec<Value>),
+    DictEntry(Box<Value>, Box<Value>),
+}
+
+impl Value {
+    pub fn signature(&self) -> String {
+        match self {
+            Value::Byte(_) => "y".to_string(),
+            Value::Bool(_) => "b".to_string(),
+            Value::Int16(_) => "n".to_string(),
+            Value::UInt16(_) => "q".to_string(),
+            Value::Int32(_) => "i".to_string(),
+            Value::UInt32(_) => "u".to_string(),
+            Value::Int64(_) => "x".to_string(),
+            Value::UInt64(_) => "t".to_string(),
+            Value::Double(_) => "d".to_string(),
+            Value::Str(_) => "s".to_string(),
+            Value::ObjectPath(_) => "o".to_string(),
+            Value::Signature(_) => "g".to_string(),
+            Value::UnixFd(_) => "h".to_string(),
+            Value::Variant(_) => "v".to_string(),
+            Value::Array(element, _) => format!("a{}", element),
+            Value::Struct(fields) => {
+                format!("({})", fields.iter().map(|f| f.signature()).collect::<String>())
+            }
+            Value::DictEntry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+        }
+    }
+
+    fn inner(&self) -> &Value {
+        match self {
+            Value::Variant(inner) => inner.inner(),
+            other => other,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self.inner() {
+            Value::Str(s) | Value::ObjectPath(s) | Value::Signature(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self.inner() {
+            Value::Byte(v) => Some(v as u64),
+            Value::UInt16(v) => Some(v as u64),
+            Value::UInt32(v) => Some(v as u64),
+            Value::UInt64(v) => Some(v),
+            Value::Int16(v) if v >= 0 => Some(v as u64),
+            Value::Int32(v) if v >= 0 => Some(v as u64),
+            Value::Int64(v) if v >= 0 => Some(v as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self.inner() {
+            Value::Int16(v) => Some(v as i64),
+            Value::Int32(v) => Some(v as i64),
+            Value::Int64(v) => Some(v),
+            _ => self.as_u64().and_then(|v| i64::try_from(v).ok()),
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match *self.inner() {
+            Value::Bool(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self.inner() {
+            Value::Array(_, items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_struct(&self) -> Option<&[Value]> {
+        match self.inner() {
+            Value::Struct(fields) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Collects an array of strings, e.g. a unit's Requires= list.
+    pub fn as_string_list(&self) -> Vec<String> {
+        self.as_array()
+            .map(|items| items.iter().filter_map(|v| v.as_str()).map(|s| s.to_string()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Converts an `a{sv}` dictionary, unwrapping the variants.
+    pub fn to_property_map(&self) -> HashMap<String, Value> {
+        let mut map = HashMap::new();
+        for item in self.as_array().unwrap_or(&[]) {
+            if let Value::DictEntry(key, value) = item {
+                if let Some(key) = key.as_str() {
+                    map.insert(key.to_string(), value.inner().clone());
+                }
+            }
+        }
+        map
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Type {
+    Byte,
+    Bool,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Double,
+    Str,
+    ObjectPath,
+    Signature,
+    UnixFd,
+    Variant,
+    Array(Box<Type>),
+    Struct(Vec<Type>),
+    DictEntry(Box<Type>, Box<Type>),
+}
+
+impl Type {
+    fn alignment(&self) -> usize {
+        match self {
+            Type::Byte | Type::Signature | Type::Variant => 1,
+            Type::Int16 | Type::UInt16 => 2,
+            Type::Bool | Type::Int32 | Type::UInt32 | Type::Str | Type::ObjectPath
+            | Type::UnixFd | Type::Array(_) => 4,
+            Type::Int64 | Type::UInt64 | Type::Double | Type::Struct(_) | Type::DictEntry(..) => 8,
+        }
+    }
+
+    fn signature(&self) -> String {
+        match self {
+            Type::Byte => "y".to_string(),
+            Type::Bool => "b".to_string(),
+            Type::Int16 => "n".to_string(),
+            Type::UInt16 => "q".to_string(),
+            Type::Int32 => "i".to_string(),
+            Type::UInt32 => "u".to_string(),
+            Type::Int64 => "x".to_string(),
+            Type::UInt64 => "t".to_string(),
+            Type::Double => "d".to_string(),
+            Type::Str => "s".to_string(),
+            Type::ObjectPath => "o".to_string(),
+            Type::Signature => "g".to_string(),
+            Type::UnixFd => "h".to_string(),
+            Type::Variant => "v".to_string(),
+            Type::Array(element) => format!("a{}", element.signature()),
+            Type::Struct(fields) => format!("({})", fields.iter().map(|f| f.signature()).collect::<String>()),
+            Type::DictEntry(key, value) => format!("{{{}{}}}", key.signature(), value.signature()),
+        }
+    }
+}
+
+fn parse_signature(signature: &str) -> Result<Vec<Type>> {
+    let bytes = signature.as_bytes();
+    let mut pos = 0;
+    let mut types = Vec::new();
+    while pos < bytes.len() {
+        types.push(parse_type(bytes, &mut pos)?);
+    }
+    Ok(types)
+}
+
+fn parse_type(bytes: &[u8], pos: &mut usize) -> Result<Type> {
+    let code = *bytes.get(*pos).ok_or_else(|| anyhow!("Truncated D-Bus signature"))?;
+    *pos += 1;
+
+    Ok(match code {
+        b'y' => Type::Byte,
+        b'b' => Type::Bool,
+        b'n' => Type::Int16,
+        b'q' => Type::UInt16,
+        b'i' => Type::Int32,
+        b'u' => Type::UInt32,
+        b'x' => Type::Int64,
+        b't' => Type::UInt64,
+        b'd' => Type::Double,
+        b's' => Type::Str,
+        b'o' => Type::ObjectPath,
+        b'g' => Type::Signature,
+        b'h' => Type::UnixFd,
+        b'v' => Type::Variant,
+        b'a' => Type::Array(Box::new(parse_type(bytes, pos)?)),
+        b'(' => {
+            let mut fields = Vec::new();
+            while bytes.get(*pos) != Some(&b')') {
+                fields.push(parse_type(bytes, pos)?);
+            }
+            *pos += 1;
+            Type::Struct(fields)
+        }
+        b'{' => {
+            let key = parse_type(bytes, pos)?;
+            let value = parse_type(bytes, pos)?;
+            if bytes.get(*pos) != Some(&b'}') {
+                return Err(anyhow!("Malformed D-Bus dict entry signature"));
+            }
+            *pos += 1;
+            Type::DictEntry(Box::new(key), Box::new(value))
+        }
+        other => return Err(anyhow!("Unsupported D-Bus type code '{}'", other as char)),
+    })
+}
+
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    fn align(&mut self, alignment: usize) {
+        self.buf.resize(self.buf.len().next_multiple_of(alignment), 0);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.align(4);
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn write_string(&mut self, value: &str) {
+        self.write_u32(value.len() as u32);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn write_signature(&mut self, value: &str) {
+        self.buf.push(value.len() as u8);
+        self.buf.extend_from_slice(value.as_bytes());
+        self.buf.push(0);
+    }
+
+    fn write_value(&mut self, value: &Value) -> Result<()> {
+        match value {
+            Value::Byte(v) => self.buf.push(*v),
+            Value::Bool(v) => self.write_u32(*v as u32),
+            Value::Int16(v) => {
+                self.align(2);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::UInt16(v) => {
+                self.align(2);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Int32(v) => {
+                self.align(4);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::UInt32(v) | Value::UnixFd(v) => self.write_u32(*v),
+            Value::Int64(v) => {
+                self.align(8);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::UInt64(v) => {
+                self.align(8);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Double(v) => {
+                self.align(8);
+                self.buf.extend_from_slice(&v.to_le_bytes());
+            }
+            Value::Str(s) | Value::ObjectPath(s) => self.write_string(s),
+            Value::Signature(s) => self.write_signature(s),
+            Value::Variant(inner) => {
+                self.write_signature(&inner.signature());
+                self.write_value(inner)?;
+            }
+            Value::Array(element, items) => {
+                let element_type = parse_type(element.as_bytes(), &mut 0)?;
+                self.write_u32(0);
+                let length_pos = self.buf.len() - 4;
+                self.align(element_type.alignment());
+                let start = self.buf.len();
+                for item in items {
+                    self.write_value(item)?;
+                }
+                let length = (self.buf.len() - start) as u32;
+                self.buf[length_pos..length_pos + 4].copy_from_slice(&length.to_le_bytes());
+            }
+            Value::Struct(fields) => {
+                self.align(8);
+                for field in fields {
+                    self.write_value(field)?;
+                }
+            }
+            Value::DictEntry(key, value) => {
+                self.align(8);
+                self.write_value(key)?;
+                self.write_value(value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn align(&mut self, alignment: usize) -> Result<()> {
+        let aligned = self.pos.next_multiple_of(alignment);
+        if aligned > self.buf.len() {
+            return Err(anyhow!("Truncated D-Bus message"));
+        }
+        self.pos = aligned;
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(len)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| anyhow!("Truncated D-Bus message"))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_fixed<const N: usize>(&mut self) -> Result<[u8; N]> {
+        self.align(N)?;
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(self.take(N)?);
+        if self.big_endian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.read_fixed()?))
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.take(len + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).to_string())
+    }
+
+    fn read_signature(&mut self) -> Result<String> {
+        let len = self.take(1)?[0] as usize;
+        let bytes = self.take(len + 1)?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).to_string())
+    }
+
+    fn read_value(&mut self, ty: &Type) -> Result<Value> {
+        Ok(match ty {
+            Type::Byte => Value::Byte(self.take(1)?[0]),
+            Type::Bool => Value::Bool(self.read_u32()? != 0),
+            Type::Int16 => Value::Int16(i16::from_le_bytes(self.read_fixed()?)),
+            Type::UInt16 => Value::UInt16(u16::from_le_bytes(self.read_fixed()?)),
+            Type::Int32 => Value::Int32(i32::from_le_bytes(self.read_fixed()?)),
+            Type::UInt32 => Value::UInt32(self.read_u32()?),
+            Type::UnixFd => Value::UnixFd(self.read_u32()?),
+            Type::Int64 => Value::Int64(i64::from_le_bytes(self.read_fixed()?)),
+            Type::UInt64 => Value::UInt64(u64::from_le_bytes(self.read_fixed()?)),
+            Type::Double => Value::Double(f64::from_le_bytes(self.read_fixed()?)),
+            Type::Str => Value::Str(self.read_string()?),
+            Type::ObjectPath => Value::ObjectPath(self.read_string()?),
+            Type::Signature => Value::Signature(self.read_signature()?),
+            Type::Variant => {
+                let signature = self.read_signature()?;
+                let mut types = parse_signature(&signature)?;
+                if types.len() != 1 {
+                    return Err(anyhow!("Variant signature '{}' is not a single type", signature));
+                }
+                Value::Variant(Box::new(self.read_value(&types.remove(0))?))
+            }
+            Type::Array(element) => {
+                let len = self.read_u32()? as usize;
+                self.align(element.alignment())?;
+                let end = self.pos + len;
+                if end > self.buf.len() {
+                    return Err(anyhow!("Truncated D-Bus array"));
+                }
+                let mut items = Vec::new();
+                while self.pos < end {
+                    items.push(self.read_value(element)?);
+                }
+                Value::Array(element.signature(), items)
+            }
+            Type::Struct(fields) => {
+                self.align(8)?;
+                let mut values = Vec::with_capacity(fields.len());
+                for field in fields {
+                    values.push(self.read_value(field)?);
+                }
+                Value::Struct(values)
+            }
+            Type::DictEntry(key, value) => {
+                self.align(8)?;
+                let key = self.read_value(key)?;
+                let value = self.read_value(value)?;
+                Value::DictEntry(Box::new(key), Box::new(value))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message {
+    pub message_type: u8,
+    pub serial: u32,
+    pub path: Option<String>,
+    pub interface: Option<String>,
+    pub member: Option<String>,
+    pub error_name: Option<String>,
+    pub reply_serial: Option<u32>,
+    pub destination: Option<String>,
+    pub sender: Option<String>,
+    pub body: Vec<Value>,
+}
+
+impl Message {
+    pub fn method_call(destination: &str, path: &str, interface: &str, member: &str, body: Vec<Value>) -> Self {
+        Self {
+            message_type: METHOD_CALL,
+            path: Some(path.to_string()),
+            interface: Some(interface.to_string()),
+            member: Some(member.to_string()),
+            destination: Some(destination.to_string()),
+            body,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_signal(&self, interface: &str, member: &str) -> bool {
+        self.message_type == SIGNAL
+            && self.interface.as_deref() == Some(interface)
+            && self.member.as_deref() == Some(member)
+    }
+
+    pub fn error_text(&self) -> String {
+        let name = self.error_name.as_deref().unwrap_or("D-Bus error");
+        match self.body.first().and_then(|v| v.as_str()) {
+            Some(text) => format!("{}: {}", name, text),
+            None => name.to_string(),
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>> {
+        let mut body = Writer::new();
+        for value in &self.body {
+            body.write_value(value)?;
+        }
+        let signature: String = self.body.iter().map(|v| v.signature()).collect();
+
+        let mut fields = Vec::new();
+        let mut field = |code: u8, value: Value| {
+            fields.push(Value::Struct(vec![Value::Byte(code), Value::Variant(Box::new(value))]));
+        };
+        if let Some(path) = &self.path {
+            field(FIELD_PATH, Value::ObjectPath(path.clone()));
+        }
+        if let Some(interface) = &self.interface {
+            field(FIELD_INTERFACE, Value::Str(interface.clone()));
+        }
+        if let Some(member) = &self.member {
+            field(FIELD_MEMBER, Value::Str(member.clone()));
+        }
+        if let Some(error_name) = &self.error_name {
+            field(FIELD_ERROR_NAME, Value::Str(error_name.clone()));
+        }
+        if let Some(reply_serial) = self.reply_serial {
+            field(FIELD_REPLY_SERIAL, Value::UInt32(reply_serial));
+        }
+        if let Some(destination) = &self.destination {
+            field(FIELD_DESTINATION, Value::Str(destination.clone()));
+        }
+        if let Some(sender) = &self.sender {
+            field(FIELD_SENDER, Value::Str(sender.clone()));
+        }
+        if !signature.is_empty() {
+            field(FIELD_SIGNATURE, Value::Signature(signature));
+        }
+
+        let mut header = Writer::new();
+        header.buf.extend_from_slice(&[b'l', self.message_type, 0, 1]);
+        header.write_u32(body.buf.len() as u32);
+        header.write_u32(self.serial);
+        header.write_value(&Value::Array("(yv)".to_string(), fields))?;
+        header.align(8);
+        header.buf.extend_from_slice(&body.buf);
+        Ok(header.buf)
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<Self> {
+        let big_endian = match buf.first() {
+            Some(b'l') => false,
+            Some(b'B') => true,
+            _ => return Err(anyhow!("Invalid D-Bus endianness marker")),
+        };
+        let mut reader = Reader { buf, pos: 1, big_endian };
+
+        let message_type = reader.take(1)?[0];
+        reader.take(2)?; // flags, protocol version
+        let _body_len = reader.read_u32()?;
+        let serial = reader.read_u32()?;
+
+        let mut message = Message { message_type, serial, ..Default::default() };
+        let mut signature = String::new();
+
+        let fields_type = Type::Array(Box::new(Type::Struct(vec![Type::Byte, Type::Variant])));
+        for field in reader.read_value(&fields_type)?.as_array().unwrap_or(&[]) {
+            let (code, value) = match field.as_struct() {
+                Some([Value::Byte(code), value]) => (*code, value),
+                _ => continue,
+            };
+            let text = value.as_str().map(|s| s.to_string());
+            match code {
+                FIELD_PATH => message.path = text,
+                FIELD_INTERFACE => message.interface = text,
+                FIELD_MEMBER => message.member = text,
+                FIELD_ERROR_NAME => message.error_name = text,
+                FIELD_REPLY_SERIAL => message.reply_serial = value.as_u64().map(|v| v as u32),
+                FIELD_DESTINATION => message.destination = text,
+                FIELD_SENDER => message.sender = text,
+                FIELD_SIGNATURE => signature = text.unwrap_or_default(),
+                _ => {}
+            }
+        }
+
+        reader.align(8)?;
+        for ty in parse_signature(&signature)? {
+            message.body.push(reader.read_value(&ty)?);
+        }
+
+        Ok(message)
+    }
+}
+
+/// Reads one complete message from the stream.
+pub fn read_message<R: Read>(stream: &mut R) -> Result<Message> {
+    let mut fixed = [0u8; 16];
+    stream.read_exact(&mut fixed)?;
+    read_message_after(stream, fixed)
+}
+
+fn read_message_after<R: Read>(stream: &mut R, fixed: [u8; 16]) -> Result<Message> {
+    let word = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if fixed[0] == b'B' { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) }
+    };
+    let body_len = word(&fixed[4..8]) as usize;
+    let fields_len = word(&fixed[12..16]) as usize;
+    let header_len = (16 + fields_len).next_multiple_of(8);
+    let total = header_len + body_len;
+    if total > MAX_MESSAGE_SIZE {
+        return Err(anyhow!("D-Bus message of {} bytes exceeds limit", total));
+    }
+
+    let mut buf = vec![0u8; total];
+    buf[..16].copy_from_slice(&fixed);
+    stream.read_exact(&mut buf[16..])?;
+    Message::decode(&buf)
+}
+
+/// Returns the system bus socket, honouring DBUS_SYSTEM_BUS_ADDRESS.
+pub fn system_bus_path() -> PathBuf {
+    std::env::var("DBUS_SYSTEM_BUS_ADDRESS")
+        .ok()
+        .and_then(|address| {
+            address.split(';')
+                .filter_map(|a| a.strip_prefix("unix:"))
+                .flat_map(|params| params.split(','))
+                .find_map(|param| param.strip_prefix("path=").map(PathBuf::from))
+        })
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SYSTEM_BUS))
+}
+
+/// An error reply from the peer. The call failed but the connection is
+/// still usable, unlike I/O errors and timeouts.
+#[derive(Debug)]
+pub struct ErrorReply(String);
+
+impl std::fmt::Display for ErrorReply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ErrorReply {}
+
+pub fn is_error_reply(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<ErrorReply>().is_some()
+}
+
+/// A blocking connection to a message bus. Replies that arrive while
+/// waiting for another call and signals that arrive while waiting for a
+/// reply are queued rather than dropped.
+pub struct Connection {
+    stream: UnixStream,
+    next_serial: u32,
+    unique_name: String,
+    replies: HashMap<u32, Message>,
+    signals: VecDeque<Message>,
+}
+
+impl Connection {
+    pub fn system() -> Result<Self> {
+        Self::connect(system_bus_path())
+    }
+
+    pub fn connect<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let mut stream = UnixStream::connect(path.as_ref())?;
+        stream.set_write_timeout(Some(CALL_TIMEOUT))?;
+        stream.set_read_timeout(Some(CALL_TIMEOUT))?;
+        authenticate(&mut stream)?;
+
+        let mut connection = Self {
+            stream,
+            next_serial: 1,
+            unique_name: String::new(),
+            replies: HashMap::new(),
+            signals: VecDeque::new(),
+        };
+
+        let reply = connection.call(Message::method_call(BUS_NAME, BUS_PATH, BUS_NAME, "Hello", Vec::new()))?;
+        connection.unique_name = reply.first().and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        Ok(connection)
+    }
+
+    pub fn unique_name(&self) -> &str {
+        &self.unique_name
+    }
+
+    /// Sends a message and returns its serial without waiting for a reply.
+    pub fn send(&mut self, mut message: Message) -> Result<u32> {
+        let serial = self.next_serial;
+        self.next_serial = self.next_serial.wrapping_add(1).max(1);
+        message.serial = serial;
+        self.stream.write_all(&message.encode()?)?;
+        Ok(serial)
+    }
+
+    pub fn call(&mut self, message: Message) -> Result<Vec<Value>> {
+        let serial = self.send(message)?;
+        self.wait_reply(serial, CALL_TIMEOUT)
+    }
+
+    pub fn wait_reply(&mut self, serial: u32, timeout: Duration) -> Result<Vec<Value>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(reply) = self.replies.remove(&serial) {
+                return match reply.message_type {
+                    ERROR => Err(ErrorReply(reply.error_text()).into()),
+                    _ => Ok(reply.body),
+                };
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.read_next(Some(remaining))? {
+                Some(message) => self.dispatch(message),
+                None => return Err(anyhow!("D-Bus call timed out")),
+            }
+        }
+    }
+
+    /// Returns the next signal, waiting up to `timeout` (forever if None).
+    pub fn next_signal(&mut self, timeout: Option<Duration>) -> Result<Option<Message>> {
+        let deadline = timeout.map(|t| Instant::now() + t);
+        loop {
+            if let Some(signal) = self.signals.pop_front() {
+                return Ok(Some(signal));
+            }
+
+            let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            if remaining == Some(Duration::ZERO) {
+                return Ok(None);
+            }
+            match self.read_next(remaining)? {
+                Some(message) => self.dispatch(message),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    pub fn add_match(&mut self, rule: &str) -> Result<()> {
+        self.call(Message::method_call(BUS_NAME, BUS_PATH, BUS_NAME, "AddMatch", vec![Value::Str(rule.to_string())]))?;
+        Ok(())
+    }
+
+    fn dispatch(&mut self, message: Message) {
+        match message.message_type {
+            METHOD_RETURN | ERROR => {
+                if let Some(serial) = message.reply_serial {
+                    self.replies.insert(serial, message);
+                }
+            }
+            SIGNAL => self.signals.push_back(message),
+            _ => {}
+        }
+    }
+
+    fn read_next(&mut self, timeout: Option<Duration>) -> Result<Option<Message>> {
+        // A zero read timeout means "block forever" to the socket API
+        let timeout = timeout.map(|t| t.max(Duration::from_millis(1)));
+        self.stream.set_read_timeout(timeout)?;
+
+        let mut fixed = [0u8; 16];
+        let mut filled = 0;
+        while filled == 0 {
+            match self.stream.read(&mut fixed) {
+                Ok(0) => return Err(anyhow!("D-Bus connection closed")),
+                Ok(n) => filled = n,
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => return Ok(None),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        // Once a message has started, the rest of it follows promptly
+        self.stream.set_read_timeout(Some(CALL_TIMEOUT))?;
+        self.stream.read_exact(&mut fixed[filled..])?;
+        read_message_after(&mut self.stream, fixed).map(Some)
+    }
+}
+
+/// Performs SASL EXTERNAL authentication with the caller's effective UID.
+fn authenticate(stream: &mut UnixStream) -> Result<()> {
+    // SAFETY: geteuid has no failure modes
+    let uid = unsafe { libc::geteuid() }.to_string();
+    let hex_uid: String = uid.bytes().map(|b| format!("{:02x}", b)).collect();
+
+    stream.write_all(b"\0")?;
+    stream.write_all(format!("AUTH EXTERNAL {}\r\n", hex_uid).as_bytes())?;
+
+    let line = read_auth_line(stream)?;
+    if !line.starts_with("OK") {
+        return Err(anyhow!("D-Bus authentication rejected: {}", line.trim()));
+    }
+
+    stream.write_all(b"BEGIN\r\n")?;
+    Ok(())
+}
+
+fn read_auth_line<R: Read>(stream: &mut R) -> Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8; 1];
+    while !line.ends_with(b"\r\n") {
+        if line.len() > 1024 {
+            return Err(anyhow!("D-Bus authentication line too long"));
+        }
+        stream.read_exact(&mut byte)?;
+        line.push(byte[0]);
+    }
+    Ok(String::from_utf8_lossy(&line).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn test_message_round_trip() {
+        let properties = Value::Array("{sv}".to_string(), vec![
+            Value::DictEntry(
+                Box::new(Value::Str("ActiveState".to_string())),
+                Box::new(Value::Variant(Box::new(Value::Str("active".to_string())))),
+            ),
+            Value::DictEntry(
+                Box::new(Value::Str("MemoryCurrent".to_string())),
+                Box::new(Value::Variant(Box::new(Value::UInt64(4096)))),
+            ),
+        ]);
+        let message = Message {
+            message_type: SIGNAL,
+            serial: 7,
+            path: Some("/org/freedesktop/systemd1/unit/nginx_2eservice".to_string()),
+            interface: Some("org.freedesktop.DBus.Properties".to_string()),
+            member: Some("PropertiesChanged".to_string()),
+            body: vec![
+                Value::Str("org.freedesktop.systemd1.Unit".to_string()),
+                properties,
+                Value::Array("s".to_string(), Vec::new()),
+            ],
+            ..Default::default()
+        };
+
+        let encoded = message.encode().unwrap();
+        let decoded = read_message(&mut encoded.as_slice()).unwrap();
+        assert_eq!(decoded, message);
+
+        let changed = decoded.body[1].to_property_map();
+        assert_eq!(changed["ActiveState"].as_str(), Some("active"));
+        assert_eq!(changed["MemoryCurrent"].as_u64(), Some(4096));
+    }
+
+    #[test]
+    fn test_call_over_mock_bus() {
+        let socket = std::env::temp_dir().join(format!("seer_dbus_{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut nul = [0u8; 1];
+            stream.read_exact(&mut nul).unwrap();
+            assert!(read_auth_line(&mut stream).unwrap().starts_with("AUTH EXTERNAL "));
+            stream.write_all(b"OK 1234deadbeef\r\n").unwrap();
+            assert_eq!(read_auth_line(&mut stream).unwrap(), "BEGIN\r\n");
+
+            let mut serve = |body: Vec<Value>| {
+                let call = read_message(&mut stream).unwrap();
+                let reply = Message {
+                    message_type: METHOD_RETURN,
+                    serial: 100 + call.serial,
+                    reply_serial: Some(call.serial),
+                    body,
+                    ..Default::default()
+                };
+                stream.write_all(&reply.encode().unwrap()).unwrap();
+                call
+            };
+
+            let hello = serve(vec![Value::Str(":1.42".to_string())]);
+            assert_eq!(hello.member.as_deref(), Some("Hello"));
+
+            let get = serve(vec![Value::Variant(Box::new(Value::UInt32(1234)))]);
+            assert_eq!(get.member.as_deref(), Some("Get"));
+            assert_eq!(get.body[1].as_str(), Some("MainPID"));
+
+            let get_all = read_message(&mut stream).unwrap();
+            let error = Message {
+                message_type: ERROR,
+                serial: 100 + get_all.serial,
+                reply_serial: Some(get_all.serial),
+                error_name: Some("org.freedesktop.DBus.Error.UnknownObject".to_string()),
+                body: vec![Value::Str("Unknown object".to_string())],
+                ..Default::default()
+            };
+            stream.write_all(&error.encode().unwrap()).unwrap();
+        });
+
+        let mut connection = Connection::connect(&socket).unwrap();
+        assert_eq!(connection.unique_name(), ":1.42");
+
+        let reply = connection.call(Message::method_call(
+            "org.freedesktop.systemd1",
+            "/org/freedesktop/systemd1/unit/nginx_2eservice",
+            "org.freedesktop.DBus.Properties",
+            "Get",
+            vec![
+                Value::Str("org.freedesktop.systemd1.Service".to_string()),
+                Value::Str("MainPID".to_string()),
+            ],
+        )).unwrap();
+        assert_eq!(reply[0].as_u64(), Some(1234));
+
+        // An error reply fails the call without closing the connection
+        let error = connection.call(Message::method_call(
+            "org.freedesktop.systemd1",
+            "/org/freedesktop/systemd1/unit/gone_2eservice",
+            "org.freedesktop.DBus.Properties",
+            "GetAll",
+            vec![Value::Str("org.freedesktop.systemd1.Unit".to_string())],
+        )).unwrap_err();
+        assert!(is_error_reply(&error));
+        assert_eq!(error.to_string(), "org.freedesktop.DBus.Error.UnknownObject: Unknown object");
+
+        server.join().unwrap();
+        let _ = std::fs::remove_file(&socket);
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// One DRM client, i.e. an open GPU context, as described by the
+/// `drm-*` keys of `/proc/<pid>/fdinfo/<fd>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DrmClient {
+    pub pid: u32,
+    pub driver: String,
+    /// PCI address of the device, e.g. `0000:03:00.0`.
+    pub pdev: String,
+    pub client_id: u64,
+    /// Accumulated busy time per engine, in nanoseconds.
+    pub engine_time: BTreeMap<String, u64>,
+    /// Engines with several instances report their combined time.
+    pub engine_capacity: BTreeMap<String, u64>,
+    /// xe reports busy cycles and the GPU timestamp cycles elapsed instead
+    /// of nanoseconds.
+    pub engine_cycles: BTreeMap<String, (u64, u64)>,
+    /// Bytes per memory region, resident where the driver says so.
+    pub memory: BTreeMap<String, u64>,
+}
+
+impl DrmClient {
+    /// `None` unless the fdinfo belongs to a DRM client.
+    pub fn parse(pid: u32, fdinfo: &str) -> Option<Self> {
+        let mut client = DrmClient { pid, ..Default::default() };
+        let mut client_id = None;
+        let mut cycles: BTreeMap<String, u64> = BTreeMap::new();
+        let mut total_cycles: BTreeMap<String, u64> = BTreeMap::new();
+        // Region -> (precedence, bytes); resident beats the legacy memory
+        // key, which beats total
+        let mut memory: BTreeMap<String, (u8, u64)> = BTreeMap::new();
+
+        for line in fdinfo.lines() {
+            let (key, value) = match line.split_once(':') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => continue,
+            };
+            let number = || value.split_whitespace().next().and_then(|n| n.parse::<u64>().ok());
+
+            if key == "drm-driver" {
+                client.driver = value.to_string();
+            } else if key == "drm-pdev" {
+                client.pdev = value.to_string();
+            } else if key == "drm-client-id" {
+                client_id = number();
+            } else if let Some(engine) = key.strip_prefix("drm-engine-capacity-") {
+                client.engine_capacity.extend(number().map(|n| (engine.to_string(), n)));
+            } else if let Some(engine) = key.strip_prefix("drm-engine-") {
+                client.engine_time.extend(number().map(|n| (engine.to_string(), n)));
+            } else if let Some(engine) = key.strip_prefix("drm-total-cycles-") {
+                total_cycles.extend(number().map(|n| (engine.to_string(), n)));
+            } else if let Some(engine) = key.strip_prefix("drm-cycles-") {
+                cycles.extend(number().map(|n| (engine.to_string(), n)));
+            } else {
+                let region = [("drm-resident-", 3), ("drm-memory-", 2), ("drm-total-", 1)]
+                    .iter()
+                    .find_map(|(prefix, precedence)| key.strip_prefix(prefix).map(|region| (region, *precedence)));
+                if let (Some((region, precedence)), Some(bytes)) = (region, parse_size(value)) {
+                    let entry = memory.entry(region.to_string()).or_insert((precedence, bytes));
+                    if precedence > entry.0 {
+                        *entry = (precedence, bytes);
+                    }
+                }
+            }
+        }
+
+        client.client_id = client_id?;
+        if client.driver.is_empty() {
+            return None;
+        }
+        client.engine_cycles = cycles.into_iter()
+            .filter_map(|(engine, busy)| total_cycles.get(&engine).map(|total| (engine, (busy, *total))))
+            .collect();
+        client.memory = memory.into_iter().map(|(region, (_, bytes))| (region, bytes)).collect();
+        Some(client)
+    }
+
+    pub fn memory_total(&self) -> u64 {
+        self.memory.values().sum()
+    }
+}
+
+/// `1024 KiB`, `3 MiB` or plain bytes.
+fn parse_size(value: &str) -> Option<u64> {
+    let mut parts = value.split_whitespace();
+    let number: u64 = parts.next()?.parse().ok()?;
+    let multiplier = match parts.next() {
+        None => 1,
+        Some("KiB") => 1024,
+        Some("MiB") => 1024 * 1024,
+        Some("GiB") => 1024 * 1024 * 1024,
+        Some(_) => return None,
+    };
+    Some(number * multiplier)
+}
+
+/// A client's usage between two samples.
+#[derive(Debug, Clone)]
+pub struct DrmClientUsage {
+    pub pid: u32,
+    pub process_name: String,
+    pub driver: String,
+    pub pdev: String,
+    pub memory: u64,
+    /// Busy percentage per engine; empty on a client's first sample.
+    pub engines: Vec<(String, f32)>,
+}
+
+/// Samples DRM fdinfo and turns busy time deltas into per-engine
+/// utilisation, for any driver that implements the common keys (amdgpu,
+/// i915, xe, msm, panfrost, v3d, ...).
+#[derive(Debug, Clone)]
+pub struct DrmFdinfoSampler {
+    proc_root: PathBuf,
+    previous: HashMap<(String, u64), (Instant, DrmClient)>,
+}
+
+impl Default for DrmFdinfoSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DrmFdinfoSampler {
+    pub fn new() -> Self {
+        Self::with_proc_root("/proc")
+    }
+
+    pub fn with_proc_root<P: AsRef<Path>>(proc_root: P) -> Self {
+        Self {
+            proc_root: proc_root.as_ref().to_path_buf(),
+            previous: HashMap::new(),
+        }
+    }
+
+    pub fn sample(&mut self) -> Vec<DrmClientUsage> {
+        self.sample_at(Instant::now())
+    }
+
+    fn sample_at(&mut self, now: Instant) -> Vec<DrmClientUsage> {
+        let clients = self.read_clients();
+        let mut usage = Vec::new();
+        let mut current = HashMap::new();
+
+        for client in clients {
+            let key = (client.pdev.clone(), client.client_id);
+            let engines = match self.previous.get(&key) {
+                Some((then, previous)) => engine_usage(previous, &client, now.duration_since(*then).as_nanos() as f64),
+                None => Vec::new(),
+            };
+            usage.push(DrmClientUsage {
+                pid: client.pid,
+                process_name: fs::read_to_string(self.proc_root.join(client.pid.to_string()).join("comm"))
+                    .map(|comm| comm.trim().to_string())
+                    .unwrap_or_default(),
+                driver: client.driver.clone(),
+                pdev: client.pdev.clone(),
+                memory: client.memory_total(),
+                engines,
+            });
+            current.insert(key, (now, client));
+        }
+
+        self.previous = current;
+        usage
+    }
+
+    /// Every DRM client once, even when several fds or processes share it.
+    fn read_clients(&self) -> Vec<DrmClient> {
+        let mut pids: Vec<u32> = fs::read_dir(&self.proc_root)
+            .map(|entries| entries.flatten().filter_map(|e| e.file_name().to_str()?.parse().ok()).collect())
+            .unwrap_or_default();
+        pids.sort_unstable();
+
+        let mut seen = std::collections::HashSet::new();
+        let mut clients = Vec::new();
+        for pid in pids {
+            let process_dir = self.proc_root.join(pid.to_string());
+            let fds = match fs::read_dir(process_dir.join("fd")) {
+                Ok(fds) => fds,
+                Err(_) => continue,
+            };
+            for fd in fds.flatten() {
+                let is_drm = fs::read_link(fd.path())
+                    .map(|target| target.starts_with("/dev/dri"))
+                    .unwrap_or(false);
+                if !is_drm {
+                    continue;
+                }
+                let client = fs::read_to_string(process_dir.join("fdinfo").join(fd.file_name()))
+                    .ok()
+                    .and_then(|fdinfo| DrmClient::parse(pid, &fdinfo));
+                if let Some(client) = client {
+                    if seen.insert((client.pdev.clone(), client.client_id)) {
+                        clients.push(client);
+                    }
+                }
+            }
+        }
+        clients
+    }
+}
+
+fn engine_usage(previous: &DrmClient, current: &DrmClient, elapsed_ns: f64) -> Vec<(String, f32)> {
+    let mut engines = Vec::new();
+    if !current.engine_cycles.is_empty() {
+        for (engine, (busy, total)) in &current.engine_cycles {
+            let (previous_busy, previous_total) = match previous.engine_cycles.get(engine) {
+                Some(cycles) => *cycles,
+                None => continue,
+            };
+            let elapsed = total.saturating_sub(previous_total) as f64;
+            let capacity = current.engine_capacity.get(engine).copied().unwrap_or(1).max(1) as f64;
+            let percent = if elapsed > 0.0 {
+                busy.saturating_sub(previous_busy) as f64 / elapsed / capacity * 100.0
+            } else {
+                0.0
+            };
+            engines.push((engine.clone(), percent.clamp(0.0, 100.0) as f32));
+        }
+    } else if elapsed_ns > 0.0 {
+        for (engine, time) in &current.engine_time {
+            let previous_time = match previous.engine_time.get(engine) {
+                Some(time) => *time,
+                None => continue,
+            };
+            let capacity = current.engine_capacity.get(engine).copied().unwrap_or(1).max(1) as f64;
+            let percent = time.saturating_sub(previous_time) as f64 / elapsed_ns / capacity * 100.0;
+            engines.push((engine.clone(), percent.clamp(0.0, 100.0) as f32));
+        }
+    }
+    engines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+    use std::time::Duration;
+
+    fn add_fd(root: &Path, pid: u32, fd: u32, target: &str, fdinfo: &str) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(dir.join("fd")).unwrap();
+        fs::create_dir_all(dir.join("fdinfo")).unwrap();
+        fs::write(dir.join("comm"), format!("proc{}\n", pid)).unwrap();
+        let link = dir.join("fd").join(fd.to_string());
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(target, link).unwrap();
+        fs::write(dir.join("fdinfo").join(fd.to_string()), fdinfo).unwrap();
+    }
+
+    fn amdgpu_fdinfo(client_id: u64, gfx_ns: u64, compute_ns: u64) -> String {
+        format!(
+            "pos:\t0\nflags:\t02100002\nmnt_id:\t25\ndrm-driver:\tamdgpu\ndrm-pdev:\t0000:03:00.0\n\
+             drm-client-id:\t{}\ndrm-engine-gfx:\t{} ns\ndrm-engine-compute:\t{} ns\n\
+             drm-memory-vram:\t2048 KiB\ndrm-memory-gtt:\t1024 KiB\ndrm-memory-cpu:\t0 KiB\n",
+            client_id, gfx_ns, compute_ns
+        )
+    }
+
+    #[test]
+    fn test_parse_fdinfo() {
+        let xe = "drm-driver:\txe\ndrm-client-id:\t7\ndrm-pdev:\t0000:00:02.0\n\
+                  drm-total-system:\t4 MiB\ndrm-resident-system:\t3 MiB\n\
+                  drm-cycles-rcs:\t500\ndrm-total-cycles-rcs:\t1000\ndrm-engine-capacity-vcs:\t2\n";
+        let client = DrmClient::parse(1, xe).unwrap();
+        assert_eq!(client.driver, "xe");
+        assert_eq!(client.engine_cycles.get("rcs"), Some(&(500, 1000)));
+        assert_eq!(client.engine_capacity.get("vcs"), Some(&2));
+        assert_eq!(client.memory_total(), 3 * 1024 * 1024);
+
+        assert!(DrmClient::parse(1, "pos:\t0\nflags:\t02\n").is_none());
+    }
+
+    #[test]
+    fn test_sample_engine_deltas() {
+        let root = TempDir::new("drm_deltas");
+        add_fd(&root, 100, 5, "/dev/dri/renderD128", &amdgpu_fdinfo(42, 1_000_000_000, 0));
+        // The same client inherited by a child counts once
+        add_fd(&root, 101, 3, "/dev/dri/renderD128", &amdgpu_fdinfo(42, 1_000_000_000, 0));
+        add_fd(&root, 100, 6, "/tmp/not-a-gpu", &amdgpu_fdinfo(43, 0, 0));
+
+        let mut sampler = DrmFdinfoSampler::with_proc_root(&root);
+        let start = Instant::now();
+        let first = sampler.sample_at(start);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].process_name, "proc100");
+        assert_eq!(first[0].memory, 3 * 1024 * 1024);
+        assert!(first[0].engines.is_empty());
+
+        add_fd(&root, 100, 5, "/dev/dri/renderD128", &amdgpu_fdinfo(42, 1_500_000_000, 250_000_000));
+        let second = sampler.sample_at(start + Duration::from_secs(1));
+        assert_eq!(second[0].engines, vec![("compute".to_string(), 25.0), ("gfx".to_string(), 50.0)]);
+    }
+}
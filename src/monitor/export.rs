@@ -172,6 +172,9 @@ impl Exporter {
             "Disk Write (bytes)",
             "Load Average (1m)",
             "Process Count",
+            "CPU Pressure (%)",
+            "Memory Pressure (%)",
+            "IO Pressure (%)",
         ])?;
 
         // Write data
@@ -187,6 +190,9 @@ impl Exporter {
                 metrics.disk_write_bytes.to_string(),
                 format!("{:.2}", metrics.load_average),
                 metrics.process_count.to_string(),
+                metrics.cpu_pressure.map(|p| format!("{:.2}", p)).unwrap_or_default(),
+                metrics.memory_pressure.map(|p| format!("{:.2}", p)).unwrap_or_default(),
+                metrics.io_pressure.map(|p| format!("{:.2}", p)).unwrap_or_default(),
             ])?;
         }
 
@@ -268,6 +274,7 @@ mod tests {
                 overall_usage: 25.5,
                 per_core_usage: vec![20.0, 30.0],
                 temperature: None,
+                topology: None,
             },
             memory: MemoryMetrics {
                 total_ram: 16_000_000_000,
@@ -277,6 +284,8 @@ mod tests {
                 used_swap: 1_000_000_000,
                 ram_percentage: 50.0,
                 swap_percentage: 25.0,
+                breakdown: None,
+                numa: Vec::new(),
             },
             processes: vec![],
             network: NetworkMetrics {
@@ -294,6 +303,7 @@ mod tests {
                 fifteen_min: 1.8,
             },
             boot_time: Utc::now(),
+            pressure: Default::default(),
         }
     }
 
@@ -391,6 +401,9 @@ mod tests {
                 disk_write_bytes: 0,
                 load_average: 1.5,
                 process_count: 150,
+                cpu_pressure: Some(4.5),
+                memory_pressure: None,
+                io_pressure: Some(0.0),
             },
         ];
 
@@ -401,6 +414,7 @@ mod tests {
         assert!(csv_content.contains("Timestamp,CPU Usage (%)"));
         assert!(csv_content.contains("30.00")); // CPU usage
         assert!(csv_content.contains("62.50")); // Memory percentage
+        assert!(csv_content.contains("150,4.50,,0.00")); // Pressure, blank when unavailable
     }
 
     #[test]
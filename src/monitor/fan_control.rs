@@ -0,0 +1,616 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+
+use super::hwmon::{chip_attribute_dir, HwmonReader};
+
+const THERMAL_ROOT: &str = "/sys/class/thermal";
+/// `pwmN_enable` value for manual control; others are driver-specific
+/// automatic modes, or 0 for full speed.
+const PWM_MANUAL: &str = "1";
+const PWM_MAX: u32 = 255;
+/// Hidden command line flag that runs the restore watchdog.
+pub const WATCHDOG_FLAG: &str = "--fan-watchdog";
+
+/// A temperature to PWM curve enforced while control mode is on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FanCurve {
+    /// Output to drive, `<chip>/pwmN`, e.g. `nct6775-isa-0290/pwm2`.
+    pub pwm: String,
+    /// Input to follow, `<chip>/tempN`, e.g. `coretemp-isa-0000/temp1`.
+    pub sensor: String,
+    /// `[°C, PWM 0-255]` points, interpolated linearly and clamped at the
+    /// ends.
+    pub points: Vec<(f64, u8)>,
+    /// Degrees the temperature has to fall before the fan slows down.
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis: f64,
+}
+
+fn default_hysteresis() -> f64 {
+    3.0
+}
+
+impl FanCurve {
+    pub fn pwm_at(&self, temperature: f64) -> u8 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return PWM_MAX as u8,
+        };
+        if temperature <= first.0 {
+            return first.1;
+        }
+        if temperature >= last.0 {
+            return last.1;
+        }
+        for pair in self.points.windows(2) {
+            let ((t0, p0), (t1, p1)) = (pair[0], pair[1]);
+            if temperature <= t1 {
+                let ratio = (temperature - t0) / (t1 - t0);
+                return (p0 as f64 + ratio * (p1 as f64 - p0 as f64)).round() as u8;
+            }
+        }
+        last.1
+    }
+
+    fn validate(&self) -> Result<()> {
+        split_target(&self.pwm, "pwm")?;
+        split_target(&self.sensor, "temp")?;
+        if self.points.is_empty() {
+            return Err(anyhow!("{}: curve has no points", self.pwm));
+        }
+        if self.points.windows(2).any(|pair| pair[1].0 <= pair[0].0) {
+            return Err(anyhow!("{}: curve temperatures must increase", self.pwm));
+        }
+        Ok(())
+    }
+}
+
+/// Fan control is opt-in: nothing is written unless `enabled` is set, and
+/// only root may enable it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FanControlConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Enforced from startup when `enabled` is set.
+    #[serde(default)]
+    pub curves: Vec<FanCurve>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FanOutputKind {
+    /// `/sys/class/thermal/cooling_deviceN`, driven through `cur_state`.
+    CoolingDevice,
+    /// A hwmon `pwmN` channel.
+    Pwm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanOutput {
+    /// `cooling_deviceN` or `<chip>/pwmN`.
+    pub id: String,
+    pub kind: FanOutputKind,
+    /// Cooling device type, or the chip's driver.
+    pub label: String,
+    pub value: u32,
+    pub max: u32,
+    /// Left to the kernel or firmware rather than set by seer.
+    pub automatic: bool,
+    /// RPM of the matching `fanN_input`, for PWM channels.
+    pub rpm: Option<u32>,
+    /// Sensor of the curve driving this output.
+    pub curve: Option<String>,
+}
+
+/// Sets cooling device states and PWM duty cycles, and enforces curves.
+/// Every attribute is recorded before its first write and handed to a
+/// watchdog process, which writes the original values back if seer exits
+/// without restoring them, including on a crash or `SIGKILL`.
+pub struct FanController {
+    hwmon: HwmonReader,
+    thermal_root: PathBuf,
+    config: FanControlConfig,
+    active: bool,
+    /// Original values in the order they were first overwritten.
+    originals: Vec<(PathBuf, String)>,
+    /// Temperature and PWM of each curve's last change, for hysteresis.
+    curve_state: HashMap<String, (f64, u8)>,
+    watchdog: Option<Watchdog>,
+    last_error: Option<String>,
+}
+
+impl Default for FanController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FanController {
+    pub fn new() -> Self {
+        Self::with_roots(HwmonReader::new(), THERMAL_ROOT)
+    }
+
+    pub fn with_roots<P: AsRef<Path>>(hwmon: HwmonReader, thermal_root: P) -> Self {
+        Self {
+            hwmon,
+            thermal_root: thermal_root.as_ref().to_path_buf(),
+            config: FanControlConfig::default(),
+            active: false,
+            originals: Vec::new(),
+            curve_state: HashMap::new(),
+            watchdog: None,
+            last_error: None,
+        }
+    }
+
+    /// Validates and stores the configuration, then takes control right
+    /// away if curves are configured.
+    pub fn configure(&mut self, config: FanControlConfig) -> Result<()> {
+        for curve in &config.curves {
+            curve.validate()?;
+        }
+        self.config = config;
+        if self.config.enabled && !self.config.curves.is_empty() {
+            self.activate()?;
+        }
+        Ok(())
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Enters control mode. Requires `enabled` in the configuration and root.
+    pub fn activate(&mut self) -> Result<()> {
+        if self.active {
+            return Ok(());
+        }
+        if !self.config.enabled {
+            return Err(anyhow!("Fan control is disabled; set advanced_monitoring.fan_control.enabled"));
+        }
+        // SAFETY: geteuid has no failure modes
+        if unsafe { libc::geteuid() } != 0 {
+            return Err(anyhow!("Fan control requires root"));
+        }
+        self.watchdog = Some(Watchdog::spawn()?);
+        self.active = true;
+        Ok(())
+    }
+
+    /// Leaves control mode, handing every output back to its original mode.
+    pub fn deactivate(&mut self) -> Result<()> {
+        let mut errors = Vec::new();
+        for (path, value) in self.originals.drain(..).rev() {
+            if let Err(e) = fs::write(&path, &value) {
+                errors.push(format!("{}: {}", path.display(), e));
+            }
+        }
+        self.curve_state.clear();
+        self.active = false;
+        if let Some(mut watchdog) = self.watchdog.take() {
+            // On failure the watchdog gets another go as it exits
+            if errors.is_empty() {
+                watchdog.send("clear");
+            }
+            watchdog.stop();
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Failed to restore {}", errors.join(", ")))
+        }
+    }
+
+    pub fn toggle(&mut self) -> Result<()> {
+        if self.active {
+            self.deactivate()
+        } else {
+            self.activate()
+        }
+    }
+
+    pub fn outputs(&self) -> Vec<FanOutput> {
+        let mut outputs = Vec::new();
+
+        let mut devices: Vec<(u32, PathBuf)> = fs::read_dir(&self.thermal_root)
+            .map(|entries| {
+                entries.flatten()
+                    .filter_map(|entry| {
+                        let name = entry.file_name().to_string_lossy().to_string();
+                        let id = name.strip_prefix("cooling_device")?.parse().ok()?;
+                        Some((id, entry.path()))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        devices.sort();
+        for (id, path) in devices {
+            let cur_state = path.join("cur_state");
+            outputs.push(FanOutput {
+                id: format!("cooling_device{}", id),
+                kind: FanOutputKind::CoolingDevice,
+                label: read_trimmed(&path.join("type")).unwrap_or_else(|| "unknown".to_string()),
+                value: read_number(&cur_state).unwrap_or(0),
+                max: read_number(&path.join("max_state")).unwrap_or(0),
+                automatic: !self.is_overridden(&cur_state),
+                rpm: None,
+                curve: None,
+            });
+        }
+
+        for chip in self.hwmon.read_chips() {
+            let dir = match chip_attribute_dir(&chip.path, |dir| !pwm_channels(dir).is_empty()) {
+                Some(dir) => dir,
+                None => continue,
+            };
+            let mut channels = pwm_channels(&dir);
+            channels.sort_unstable();
+            for channel in channels {
+                let id = format!("{}/pwm{}", chip.name, channel);
+                let enable = read_trimmed(&dir.join(format!("pwm{}_enable", channel)));
+                outputs.push(FanOutput {
+                    curve: self.curve_for(&id).map(|curve| curve.sensor.clone()),
+                    id,
+                    kind: FanOutputKind::Pwm,
+                    label: chip.driver.clone(),
+                    value: read_number(&dir.join(format!("pwm{}", channel))).unwrap_or(0),
+                    max: PWM_MAX,
+                    automatic: enable.as_deref() != Some(PWM_MANUAL),
+                    rpm: read_number(&dir.join(format!("fan{}_input", channel))),
+                });
+            }
+        }
+
+        outputs
+    }
+
+    /// Sets a cooling device's state or a PWM duty cycle (0-255).
+    pub fn set_output(&mut self, id: &str, value: u32) -> Result<()> {
+        if !self.active {
+            return Err(anyhow!("Fan control mode is off"));
+        }
+        if let Some(curve) = self.curve_for(id) {
+            return Err(anyhow!("{} follows the curve for {}", id, curve.sensor));
+        }
+        self.write_output(id, value)
+    }
+
+    /// Hands one output back to its original mode.
+    pub fn set_automatic(&mut self, id: &str) -> Result<()> {
+        let paths = self.output_paths(id)?;
+        let mut restore: Vec<(PathBuf, String)> = Vec::new();
+        self.originals.retain(|(path, value)| {
+            if paths.contains(path) {
+                restore.push((path.clone(), value.clone()));
+                false
+            } else {
+                true
+            }
+        });
+        for (path, value) in restore.iter().rev() {
+            fs::write(path, value).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Applies every curve once; call on each refresh. A curve whose sensor
+    /// cannot be read drives its fan at full speed.
+    pub fn enforce_curves(&mut self) {
+        if !self.active || self.config.curves.is_empty() {
+            return;
+        }
+        let chips = self.hwmon.read_chips();
+        let mut errors = Vec::new();
+
+        for curve in self.config.curves.clone() {
+            let temperature = split_target(&curve.sensor, "temp").ok().and_then(|(chip_name, feature)| {
+                chips.iter()
+                    .find(|chip| chip.name == chip_name)?
+                    .features.iter()
+                    .find(|f| f.name == feature && !f.fault)
+                    .map(|f| f.value)
+            });
+
+            let pwm = match temperature {
+                Some(temperature) => {
+                    let target = curve.pwm_at(temperature);
+                    match self.curve_state.get(&curve.pwm) {
+                        // Slow down only once the temperature has dropped
+                        Some(&(changed_at, current)) if target < current && temperature > changed_at - curve.hysteresis => current,
+                        _ => {
+                            self.curve_state.insert(curve.pwm.clone(), (temperature, target));
+                            target
+                        }
+                    }
+                }
+                None => {
+                    errors.push(format!("{} unreadable, {} at full speed", curve.sensor, curve.pwm));
+                    self.curve_state.remove(&curve.pwm);
+                    PWM_MAX as u8
+                }
+            };
+
+            if let Err(e) = self.write_output(&curve.pwm, pwm as u32) {
+                errors.push(e.to_string());
+            }
+        }
+
+        self.last_error = (!errors.is_empty()).then(|| errors.join("; "));
+    }
+
+    fn curve_for(&self, id: &str) -> Option<&FanCurve> {
+        self.config.curves.iter().find(|curve| curve.pwm == id)
+    }
+
+    fn is_overridden(&self, path: &Path) -> bool {
+        self.originals.iter().any(|(original, _)| original == path)
+    }
+
+    /// The attributes an output's writes touch, in write order.
+    fn output_paths(&self, id: &str) -> Result<Vec<PathBuf>> {
+        if let Some(device) = id.strip_prefix("cooling_device") {
+            return Ok(vec![self.thermal_root.join(format!("cooling_device{}", device)).join("cur_state")]);
+        }
+        let (chip_name, channel) = split_target(id, "pwm")?;
+        let chip = self.hwmon.read_chips()
+            .into_iter()
+            .find(|chip| chip.name == chip_name)
+            .ok_or_else(|| anyhow!("No hwmon chip {}", chip_name))?;
+        let dir = chip_attribute_dir(&chip.path, |dir| dir.join(&channel).exists()).ok_or_else(|| anyhow!("{} has no {}", chip_name, channel))?;
+        Ok(vec![dir.join(format!("{}_enable", channel)), dir.join(channel)])
+    }
+
+    fn write_output(&mut self, id: &str, value: u32) -> Result<()> {
+        let paths = self.output_paths(id)?;
+        let (values, max) = if paths.len() == 1 {
+            let max = read_number(&paths[0].with_file_name("max_state")).unwrap_or(0);
+            (vec![value.to_string()], max)
+        } else {
+            (vec![PWM_MANUAL.to_string(), value.to_string()], PWM_MAX)
+        };
+        if value > max {
+            return Err(anyhow!("{} accepts at most {}", id, max));
+        }
+
+        for (path, value) in paths.iter().zip(values) {
+            self.remember(path)?;
+            if read_trimmed(path).as_deref() != Some(value.as_str()) {
+                fs::write(path, &value).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remember(&mut self, path: &Path) -> Result<()> {
+        if self.is_overridden(path) {
+            return Ok(());
+        }
+        let original = read_trimmed(path).ok_or_else(|| anyhow!("Cannot read {}", path.display()))?;
+        if let Some(watchdog) = self.watchdog.as_mut() {
+            watchdog.send(&format!("{}\t{}", path.display(), original));
+        }
+        self.originals.push((path.to_path_buf(), original));
+        Ok(())
+    }
+}
+
+impl Drop for FanController {
+    fn drop(&mut self) {
+        let _ = self.deactivate();
+    }
+}
+
+/// The `seer --fan-watchdog` child holding the restore list.
+struct Watchdog {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+impl Watchdog {
+    fn spawn() -> Result<Self> {
+        let mut child = Command::new(std::env::current_exe()?)
+            .arg(WATCHDOG_FLAG)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to start the fan control watchdog: {}", e))?;
+        let stdin = child.stdin.take();
+        Ok(Self { child, stdin })
+    }
+
+    fn send(&mut self, line: &str) {
+        if let Some(stdin) = self.stdin.as_mut() {
+            let _ = writeln!(stdin, "{}", line).and_then(|_| stdin.flush());
+        }
+    }
+
+    fn stop(&mut self) {
+        self.stdin = None;
+        let _ = self.child.wait();
+    }
+}
+
+/// Body of the watchdog process: collects `path<TAB>value` lines until its
+/// stdin closes, which happens however the parent exits, then writes the
+/// values back newest first. `clear` drops everything collected so far.
+pub fn run_watchdog() -> Result<()> {
+    // Terminal and service manager signals go to the whole process group;
+    // outlive the parent so its exit can be noticed
+    for signal in [libc::SIGINT, libc::SIGHUP, libc::SIGTERM, libc::SIGQUIT] {
+        // SAFETY: installing SIG_IGN has no preconditions
+        unsafe { libc::signal(signal, libc::SIG_IGN) };
+    }
+
+    let mut originals: Vec<(String, String)> = Vec::new();
+    for line in std::io::stdin().lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line == "clear" {
+            originals.clear();
+        } else if let Some((path, value)) = line.split_once('\t') {
+            originals.push((path.to_string(), value.to_string()));
+        }
+    }
+
+    for (path, value) in originals.iter().rev() {
+        let _ = fs::write(path, value);
+    }
+    Ok(())
+}
+
+/// Splits `<chip>/<prefix>N` into the chip name and the feature.
+fn split_target(target: &str, prefix: &str) -> Result<(String, String)> {
+    let (chip, feature) = target.rsplit_once('/')
+        .ok_or_else(|| anyhow!("Expected <chip>/{}N, got {}", prefix, target))?;
+    let valid = feature.strip_prefix(prefix)
+        .map(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false);
+    if chip.is_empty() || !valid {
+        return Err(anyhow!("Expected <chip>/{}N, got {}", prefix, target));
+    }
+    Ok((chip.to_string(), feature.to_string()))
+}
+
+fn pwm_channels(dir: &Path) -> Vec<u32> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries.flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.strip_prefix("pwm")?.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<u32> {
+    read_trimmed(path)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+
+    fn fake_sysfs(test_name: &str) -> TempDir {
+        let root = TempDir::new(&format!("fan_{}", test_name));
+
+        let chip = root.join("hwmon").join("hwmon0");
+        fs::create_dir_all(&chip).unwrap();
+        for (name, value) in [("name", "nct6775"), ("temp1_input", "50000"), ("pwm2", "90"), ("pwm2_enable", "5"), ("fan2_input", "900")] {
+            fs::write(chip.join(name), value).unwrap();
+        }
+        let device = root.join("thermal").join("cooling_device0");
+        fs::create_dir_all(&device).unwrap();
+        for (name, value) in [("type", "Processor"), ("cur_state", "0"), ("max_state", "3")] {
+            fs::write(device.join(name), value).unwrap();
+        }
+        root
+    }
+
+    fn controller(root: &Path, curves: Vec<FanCurve>) -> FanController {
+        let mut controller = FanController::with_roots(HwmonReader::with_root(root.join("hwmon")), root.join("thermal"));
+        controller.config = FanControlConfig { enabled: true, curves };
+        // Skips the root check and the watchdog process
+        controller.active = true;
+        controller
+    }
+
+    #[test]
+    fn test_curve_interpolation() {
+        let curve = FanCurve {
+            pwm: "nct6775-virtual-0/pwm2".to_string(),
+            sensor: "nct6775-virtual-0/temp1".to_string(),
+            points: vec![(40.0, 60), (60.0, 160), (80.0, 255)],
+            hysteresis: 3.0,
+        };
+        assert!(curve.validate().is_ok());
+        assert_eq!(curve.pwm_at(20.0), 60);
+        assert_eq!(curve.pwm_at(50.0), 110);
+        assert_eq!(curve.pwm_at(90.0), 255);
+
+        let unordered = FanCurve { points: vec![(60.0, 100), (40.0, 50)], ..curve.clone() };
+        assert!(unordered.validate().is_err());
+        let bad_target = FanCurve { pwm: "pwm2".to_string(), ..curve };
+        assert!(bad_target.validate().is_err());
+    }
+
+    #[test]
+    fn test_set_and_restore_outputs() {
+        let root = fake_sysfs("restore");
+        let pwm = root.join("hwmon/hwmon0/pwm2");
+        let enable = root.join("hwmon/hwmon0/pwm2_enable");
+        let state = root.join("thermal/cooling_device0/cur_state");
+        let mut controller = controller(&root, Vec::new());
+
+        let outputs = controller.outputs();
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(outputs[1].id, "nct6775-virtual-0/pwm2");
+        assert_eq!(outputs[1].rpm, Some(900));
+        assert!(outputs.iter().all(|o| o.automatic));
+
+        controller.set_output("cooling_device0", 2).unwrap();
+        assert!(controller.set_output("cooling_device0", 4).is_err());
+        controller.set_output("nct6775-virtual-0/pwm2", 200).unwrap();
+        assert_eq!(fs::read_to_string(&state).unwrap(), "2");
+        assert_eq!(fs::read_to_string(&enable).unwrap(), "1");
+        assert_eq!(fs::read_to_string(&pwm).unwrap(), "200");
+        assert!(controller.outputs().iter().all(|o| !o.automatic));
+
+        controller.set_automatic("cooling_device0").unwrap();
+        assert_eq!(fs::read_to_string(&state).unwrap(), "0");
+
+        controller.deactivate().unwrap();
+        assert_eq!(fs::read_to_string(&enable).unwrap(), "5");
+        assert_eq!(fs::read_to_string(&pwm).unwrap(), "90");
+        assert!(controller.set_output("cooling_device0", 1).is_err());
+    }
+
+    #[test]
+    fn test_enforce_curve_with_hysteresis() {
+        let root = fake_sysfs("curve");
+        let temp = root.join("hwmon/hwmon0/temp1_input");
+        let pwm = root.join("hwmon/hwmon0/pwm2");
+        let curve = FanCurve {
+            pwm: "nct6775-virtual-0/pwm2".to_string(),
+            sensor: "nct6775-virtual-0/temp1".to_string(),
+            points: vec![(40.0, 100), (60.0, 200)],
+            hysteresis: 3.0,
+        };
+        let mut controller = controller(&root, vec![curve]);
+        assert!(controller.set_output("nct6775-virtual-0/pwm2", 10).is_err());
+
+        controller.enforce_curves();
+        assert_eq!(fs::read_to_string(&pwm).unwrap(), "150");
+
+        // Within the hysteresis band the fan keeps its speed
+        fs::write(&temp, "48000").unwrap();
+        controller.enforce_curves();
+        assert_eq!(fs::read_to_string(&pwm).unwrap(), "150");
+        fs::write(&temp, "46000").unwrap();
+        controller.enforce_curves();
+        assert_eq!(fs::read_to_string(&pwm).unwrap(), "130");
+
+        fs::remove_file(&temp).unwrap();
+        controller.enforce_curves();
+        assert_eq!(fs::read_to_string(&pwm).unwrap(), "255");
+        assert!(controller.last_error().is_some());
+
+        controller.deactivate().unwrap();
+        assert_eq!(fs::read_to_string(&pwm).unwrap(), "90");
+    }
+}
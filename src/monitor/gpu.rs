@@ -2,16 +2,25 @@ use anyhow::Result;
 use std::collections::{HashMap, VecDeque};
 use std::fs;
 use std::process::Command;
+use std::sync::Arc;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::drm_fdinfo::{DrmClientUsage, DrmFdinfoSampler};
+use super::nvml::{normalize_bus_id, Nvml};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GPUMonitor {
     gpu_history: VecDeque<GPUSnapshot>,
     nvidia_available: bool,
     amd_available: bool,
     intel_available: bool,
+    drm_available: bool,
     process_gpu_usage: HashMap<u32, GPUProcessUsage>,
+    #[serde(skip)]
+    drm_clients: DrmFdinfoSampler,
+    #[serde(skip)]
+    nvml: Option<Arc<Nvml>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +52,33 @@ pub struct GPUInfo {
     pub processes: Vec<GPUProcess>,
     pub encoder_utilization: Option<f32>,
     pub decoder_utilization: Option<f32>,
+    /// PCI address such as `0000:03:00.0`, to match DRM clients.
+    #[serde(default)]
+    pub pci_address: Option<String>,
+    /// Only collected through NVML.
+    #[serde(default)]
+    pub nvidia: Option<NvidiaDetails>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NvidiaDetails {
+    pub throttle_reasons: Vec<String>,
+    /// Volatile ECC error counts, since the last driver load.
+    pub ecc_corrected: Option<u64>,
+    pub ecc_uncorrected: Option<u64>,
+    pub pcie_tx_kbps: Option<u32>,
+    pub pcie_rx_kbps: Option<u32>,
+    pub mig_instances: Vec<MigInstance>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MigInstance {
+    pub gpu_instance_id: u32,
+    pub compute_instance_id: u32,
+    pub name: String,
+    pub memory_total: u64,
+    pub memory_used: u64,
+    pub pids: Vec<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +98,10 @@ pub struct GPUProcess {
     pub encode_utilization: Option<f32>,
     pub decode_utilization: Option<f32>,
     pub process_type: GPUProcessType,
+    /// Busy percentage per engine (`gfx`, `compute`, `video`, ...) from DRM
+    /// fdinfo.
+    #[serde(default)]
+    pub engine_utilization: Vec<(String, f32)>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,7 +127,10 @@ impl GPUMonitor {
             nvidia_available: false,
             amd_available: false,
             intel_available: false,
+            drm_available: false,
             process_gpu_usage: HashMap::new(),
+            drm_clients: DrmFdinfoSampler::new(),
+            nvml: None,
         };
         
         monitor.detect_gpu_vendors();
@@ -95,11 +138,15 @@ impl GPUMonitor {
     }
 
     fn detect_gpu_vendors(&mut self) {
-        // Check for NVIDIA GPU support
-        if Command::new("nvidia-smi").arg("--version").output().is_ok() {
+        // Check for NVIDIA GPU support, preferring NVML over nvidia-smi
+        self.nvml = Nvml::load().ok().map(Arc::new);
+        if self.nvml.is_some() || Command::new("nvidia-smi").arg("--version").output().is_ok() {
             self.nvidia_available = true;
         }
 
+        // Any DRM driver may expose per-client usage in fdinfo
+        self.drm_available = fs::metadata("/dev/dri").is_ok();
+
         // Check for AMD GPU support
         if fs::metadata("/sys/class/drm").is_ok() {
             // Look for AMD GPUs in DRM
@@ -125,46 +172,37 @@ impl GPUMonitor {
 
     pub fn update_gpu_metrics(&mut self) -> Result<()> {
         let mut gpus = Vec::new();
-        let mut total_processes = 0;
-        let mut total_memory_used = 0;
-        let mut total_memory_available = 0;
 
         // Get NVIDIA GPU info
         if self.nvidia_available {
             if let Ok(nvidia_gpus) = self.get_nvidia_info() {
-                for gpu in nvidia_gpus {
-                    total_processes += gpu.processes.len() as u32;
-                    total_memory_used += gpu.memory_used;
-                    total_memory_available += gpu.memory_total;
-                    gpus.push(gpu);
-                }
+                gpus.extend(nvidia_gpus);
             }
         }
 
         // Get AMD GPU info
         if self.amd_available {
             if let Ok(amd_gpus) = self.get_amd_info() {
-                for gpu in amd_gpus {
-                    total_processes += gpu.processes.len() as u32;
-                    total_memory_used += gpu.memory_used;
-                    total_memory_available += gpu.memory_total;
-                    gpus.push(gpu);
-                }
+                gpus.extend(amd_gpus);
             }
         }
 
         // Get Intel GPU info
         if self.intel_available {
             if let Ok(intel_gpus) = self.get_intel_info() {
-                for gpu in intel_gpus {
-                    total_processes += gpu.processes.len() as u32;
-                    total_memory_used += gpu.memory_used;
-                    total_memory_available += gpu.memory_total;
-                    gpus.push(gpu);
-                }
+                gpus.extend(intel_gpus);
             }
         }
 
+        if self.drm_available {
+            let clients = self.drm_clients.sample();
+            attach_drm_clients(&mut gpus, clients);
+        }
+
+        let total_processes = gpus.iter().map(|gpu| gpu.processes.len() as u32).sum();
+        let total_memory_used = gpus.iter().map(|gpu| gpu.memory_used).sum();
+        let total_memory_available = gpus.iter().map(|gpu| gpu.memory_total).sum();
+
         let snapshot = GPUSnapshot {
             timestamp: Utc::now(),
             gpus,
@@ -188,12 +226,16 @@ impl GPUMonitor {
     }
 
     fn get_nvidia_info(&self) -> Result<Vec<GPUInfo>> {
+        if let Some(nvml) = &self.nvml {
+            return nvml.gpus();
+        }
+
         let mut gpus = Vec::new();
 
         // Query GPU information using nvidia-ml-py equivalent commands
         let output = Command::new("nvidia-smi")
             .args(&[
-                "--query-gpu=index,name,driver_version,memory.total,memory.used,memory.free,utilization.gpu,utilization.memory,temperature.gpu,power.draw,power.limit,fan.speed,clocks.current.graphics,clocks.current.memory,encoder.stats.sessionCount,decoder.stats.sessionCount",
+                "--query-gpu=index,name,driver_version,memory.total,memory.used,memory.free,utilization.gpu,utilization.memory,temperature.gpu,power.draw,power.limit,fan.speed,clocks.current.graphics,clocks.current.memory,encoder.stats.sessionCount,decoder.stats.sessionCount,pci.bus_id",
                 "--format=csv,noheader,nounits"
             ])
             .output()?;
@@ -224,6 +266,8 @@ impl GPUMonitor {
                     processes,
                     encoder_utilization: fields[14].parse().ok(),
                     decoder_utilization: fields[15].parse().ok(),
+                    pci_address: fields.get(16).and_then(|bus_id| normalize_bus_id(bus_id)),
+                    nvidia: None,
                 };
                 
                 gpus.push(gpu);
@@ -256,6 +300,7 @@ impl GPUMonitor {
                     encode_utilization: None,
                     decode_utilization: None,
                     process_type: GPUProcessType::Compute,
+                    engine_utilization: Vec::new(),
                 };
                 
                 processes.push(process);
@@ -284,6 +329,7 @@ impl GPUMonitor {
                         encode_utilization: None,
                         decode_utilization: None,
                         process_type: GPUProcessType::Graphics,
+                        engine_utilization: Vec::new(),
                     };
                     
                     processes.push(process);
@@ -308,6 +354,10 @@ impl GPUMonitor {
                 let path = entry.path();
                 if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                     if name.starts_with("card") && !name.contains("-") {
+                        let vendor = fs::read_to_string(path.join("device/vendor")).unwrap_or_default();
+                        if vendor.trim() != "0x1002" {
+                            continue;
+                        }
                         if let Ok(gpu) = self.parse_amd_sysfs(&path) {
                             gpus.push(gpu);
                         }
@@ -327,6 +377,7 @@ impl GPUMonitor {
             .output()?;
 
         let rocm_info = String::from_utf8_lossy(&output.stdout);
+        let pci_addresses = drm_pci_addresses("0x1002");
         
         // Parse ROCm output (format may vary)
         // This is a simplified parser - real implementation would need more robust parsing
@@ -353,6 +404,8 @@ impl GPUMonitor {
                     processes: Vec::new(), // Would need additional parsing
                     encoder_utilization: None,
                     decoder_utilization: None,
+                    pci_address: pci_addresses.get(gpus.len()).cloned(),
+                    nvidia: None,
                 };
                 
                 gpus.push(gpu);
@@ -364,6 +417,7 @@ impl GPUMonitor {
 
     fn parse_amd_sysfs(&self, card_path: &std::path::Path) -> Result<GPUInfo> {
         let device_path = card_path.join("device");
+        let pci_address = pci_address(card_path);
         
         // Read GPU name
         let name = fs::read_to_string(device_path.join("product_name"))
@@ -414,6 +468,8 @@ impl GPUMonitor {
             processes: Vec::new(), // AMD process tracking is more complex
             encoder_utilization: None,
             decoder_utilization: None,
+            pci_address,
+            nvidia: None,
         };
 
         Ok(gpu)
@@ -471,11 +527,14 @@ impl GPUMonitor {
             processes: Vec::new(),
             encoder_utilization: None,
             decoder_utilization: None,
+            pci_address: drm_pci_addresses("0x8086").into_iter().next(),
+            nvidia: None,
         }])
     }
 
     fn parse_intel_sysfs(&self, card_path: &std::path::Path) -> Result<GPUInfo> {
         let _device_path = card_path.join("device");
+        let pci_address = pci_address(card_path);
         
         // Intel integrated graphics information is limited in sysfs
         let gpu = GPUInfo {
@@ -497,6 +556,8 @@ impl GPUMonitor {
             processes: Vec::new(),
             encoder_utilization: None,
             decoder_utilization: None,
+            pci_address,
+            nvidia: None,
         };
 
         Ok(gpu)
@@ -565,7 +626,7 @@ impl GPUMonitor {
     }
 
     pub fn has_gpu_support(&self) -> bool {
-        self.nvidia_available || self.amd_available || self.intel_available
+        self.nvidia_available || self.amd_available || self.intel_available || self.drm_available
     }
 
     pub fn get_supported_vendors(&self) -> Vec<GPUVendor> {
@@ -607,4 +668,115 @@ impl GPUMonitor {
         // Clean process tracking
         self.process_gpu_usage.retain(|_, usage| usage.last_seen > cutoff_time);
     }
-}
\ No newline at end of file
+}
+
+/// PCI address of a `/sys/class/drm/cardN` device.
+fn pci_address(card_path: &std::path::Path) -> Option<String> {
+    fs::canonicalize(card_path.join("device")).ok()?
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+/// PCI addresses of the `/sys/class/drm/cardN` devices from one PCI
+/// vendor, in card order, for backends whose tools don't report them.
+fn drm_pci_addresses(vendor_id: &str) -> Vec<String> {
+    let mut cards: Vec<(u32, std::path::PathBuf)> = fs::read_dir("/sys/class/drm")
+        .map(|entries| entries.flatten()
+            .filter_map(|entry| {
+                let index = entry.file_name().to_str()?.strip_prefix("card")?.parse().ok()?;
+                Some((index, entry.path()))
+            })
+            .collect())
+        .unwrap_or_default();
+    cards.sort();
+
+    cards.iter()
+        .filter(|(_, path)| fs::read_to_string(path.join("device/vendor")).map(|v| v.trim() == vendor_id).unwrap_or(false))
+        .filter_map(|(_, path)| pci_address(path))
+        .collect()
+}
+
+/// Adds DRM fdinfo clients to the GPU with their PCI address, merging into
+/// processes already known from the vendor tool. Devices no vendor backend
+/// reported get an entry of their own.
+fn attach_drm_clients(gpus: &mut Vec<GPUInfo>, clients: Vec<DrmClientUsage>) {
+    // A process may hold several contexts on one device
+    let mut merged: Vec<DrmClientUsage> = Vec::new();
+    for client in clients {
+        match merged.iter_mut().find(|m| m.pid == client.pid && m.pdev == client.pdev) {
+            Some(existing) => {
+                existing.memory += client.memory;
+                for (engine, percent) in client.engines {
+                    match existing.engines.iter_mut().find(|(name, _)| *name == engine) {
+                        Some((_, total)) => *total = (*total + percent).min(100.0),
+                        None => existing.engines.push((engine, percent)),
+                    }
+                }
+            }
+            None => merged.push(client),
+        }
+    }
+
+    for client in merged {
+        let index = match gpus.iter().position(|gpu| gpu.pci_address.as_deref() == Some(client.pdev.as_str())) {
+            Some(index) => index,
+            None => {
+                let vendor = match client.driver.as_str() {
+                    "amdgpu" | "radeon" => GPUVendor::AMD,
+                    "i915" | "xe" => GPUVendor::Intel,
+                    "nouveau" | "nvidia" | "nvidia-drm" => GPUVendor::NVIDIA,
+                    _ => GPUVendor::Unknown,
+                };
+                gpus.push(GPUInfo {
+                    gpu_id: gpus.len() as u32,
+                    name: format!("{} ({})", client.driver, client.pdev),
+                    vendor,
+                    driver_version: "Unknown".to_string(),
+                    memory_total: 0,
+                    memory_used: 0,
+                    memory_free: 0,
+                    utilization_gpu: 0.0,
+                    utilization_memory: 0.0,
+                    temperature: None,
+                    power_usage: None,
+                    power_limit: None,
+                    fan_speed: None,
+                    clock_graphics: None,
+                    clock_memory: None,
+                    processes: Vec::new(),
+                    encoder_utilization: None,
+                    decoder_utilization: None,
+                    pci_address: Some(client.pdev.clone()),
+                    nvidia: None,
+                });
+                gpus.len() - 1
+            }
+        };
+
+        let busiest = client.engines.iter().map(|(_, percent)| *percent).reduce(f32::max);
+        let active = |names: &[&str]| client.engines.iter().any(|(engine, percent)| *percent > 0.0 && names.contains(&engine.as_str()));
+        let process_type = match (active(&["gfx", "render", "rcs"]), active(&["compute", "ccs"])) {
+            (true, true) => GPUProcessType::Mixed,
+            (true, false) => GPUProcessType::Graphics,
+            (false, true) => GPUProcessType::Compute,
+            (false, false) => GPUProcessType::Unknown,
+        };
+
+        let processes = &mut gpus[index].processes;
+        if let Some(process) = processes.iter_mut().find(|p| p.pid == client.pid) {
+            process.gpu_utilization = process.gpu_utilization.or(busiest);
+            process.engine_utilization = client.engines;
+        } else {
+            processes.push(GPUProcess {
+                pid: client.pid,
+                process_name: client.process_name,
+                memory_usage: client.memory,
+                gpu_utilization: busiest,
+                encode_utilization: None,
+                decode_utilization: None,
+                process_type,
+                engine_utilization: client.engines,
+            });
+        }
+    }
+}
@@ -7,6 +7,9 @@ use std::path::Path;
 use std::process::Command;
 use std::str;
 
+use super::hwmon::{ChipConfig, HwmonFeature, HwmonReader};
+use super::ipmi::IpmiPoller;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SensorReading {
     pub name: String,
@@ -55,12 +58,24 @@ pub struct HardwareSensorMetrics {
     pub voltage_sensors: Vec<SensorReading>,
     pub power_sensors: Vec<SensorReading>,
     pub critical_alerts: Vec<SensorReading>,
+    /// Logged hardware events such as IPMI SEL entries, newest first.
+    pub events: Vec<HardwareEvent>,
     pub average_cpu_temp: Option<f64>,
     pub average_fan_speed: Option<f64>,
     pub total_power_consumption: Option<f64>,
     pub sensor_backend: SensorBackend,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HardwareEvent {
+    pub id: u64,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub source: String,
+    pub sensor: String,
+    pub severity: SensorStatus,
+    pub message: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum SensorBackend {
     Lmsensors,
@@ -74,7 +89,8 @@ pub enum SensorBackend {
 pub struct HardwareSensorMonitor {
     backend: SensorBackend,
     sensor_cache: HashMap<String, SensorReading>,
-    hwmon_paths: Vec<String>,
+    hwmon: HwmonReader,
+    ipmi: Option<IpmiPoller>,
     last_update: Option<DateTime<Utc>>,
     history_limit: usize,
 }
@@ -104,55 +120,48 @@ impl Default for HardwareSensorMonitor {
 
 impl HardwareSensorMonitor {
     pub fn new() -> Self {
-        let backend = Self::detect_sensor_backend();
-        let hwmon_paths = Self::scan_hwmon_devices();
+        let hwmon = HwmonReader::new();
+        let ipmi = IpmiPoller::open_local().ok();
+        let backend = Self::detect_sensor_backend(&hwmon, ipmi.is_some());
         
         Self {
             backend,
             sensor_cache: HashMap::new(),
-            hwmon_paths,
+            hwmon,
+            ipmi,
             last_update: None,
             history_limit: 100, // Keep last 100 readings
         }
     }
 
-    fn detect_sensor_backend() -> SensorBackend {
+    fn detect_sensor_backend(hwmon: &HwmonReader, local_bmc: bool) -> SensorBackend {
+        // hwmon sysfs is what lm-sensors itself reads
+        if hwmon.is_available() {
+            return SensorBackend::Hwmon;
+        }
+        
         // Check for lm-sensors (sensors command)
         if Command::new("sensors").arg("-v").output().is_ok() {
             return SensorBackend::Lmsensors;
         }
         
-        // Check for hwmon sysfs interface
-        if Path::new("/sys/class/hwmon").exists() {
-            return SensorBackend::Hwmon;
-        }
-        
         // Check for ACPI thermal zones
         if Path::new("/sys/class/thermal").exists() {
             return SensorBackend::Acpi;
         }
         
         // Check for IPMI
-        if Command::new("ipmitool").arg("sensor").output().is_ok() {
+        if local_bmc || Command::new("ipmitool").arg("sensor").output().is_ok() {
             return SensorBackend::Ipmi;
         }
         
         SensorBackend::Unknown
     }
 
-    fn scan_hwmon_devices() -> Vec<String> {
-        let mut paths = Vec::new();
-        
-        if let Ok(entries) = fs::read_dir("/sys/class/hwmon") {
-            for entry in entries.flatten() {
-                if let Some(name) = entry.file_name().to_str() {
-                    paths.push(format!("/sys/class/hwmon/{}", name));
-                }
-            }
-        }
-        
-        paths.sort();
-        paths
+    /// Applies sensors3.conf-style label, compute and ignore statements to
+    /// the hwmon backend.
+    pub fn set_chip_config(&mut self, config: Vec<ChipConfig>) -> Result<()> {
+        self.hwmon.set_config(config)
     }
 
     pub fn get_hardware_sensor_metrics(&mut self) -> Result<HardwareSensorMetrics> {
@@ -164,6 +173,16 @@ impl HardwareSensorMonitor {
             _ => Vec::new(),
         };
 
+        // The BMC sees what hwmon cannot on servers: inlet, PSUs, chassis
+        let mut sensors = sensors;
+        let mut events = Vec::new();
+        if let Some(ipmi) = self.ipmi.as_mut() {
+            if self.backend != SensorBackend::Ipmi {
+                sensors.extend_from_slice(ipmi.readings());
+            }
+            events = ipmi.events().to_vec();
+        }
+
         // Update sensor cache and history
         let now = Utc::now();
         for sensor in &sensors {
@@ -245,6 +264,7 @@ impl HardwareSensorMonitor {
             voltage_sensors,
             power_sensors,
             critical_alerts,
+            events,
             average_cpu_temp,
             average_fan_speed,
             total_power_consumption,
@@ -323,70 +343,30 @@ impl HardwareSensorMonitor {
     }
 
     fn get_hwmon_readings(&self) -> Result<Vec<SensorReading>> {
-        let mut sensors = Vec::new();
         let now = Utc::now();
 
-        for hwmon_path in &self.hwmon_paths {
-            // Get chip name
-            let name_path = format!("{}/name", hwmon_path);
-            let chip_name = fs::read_to_string(&name_path)
-                .unwrap_or_else(|_| "unknown".to_string())
-                .trim()
-                .to_string();
-
-            // Scan for sensor files
-            if let Ok(entries) = fs::read_dir(hwmon_path) {
-                for entry in entries.flatten() {
-                    let file_name = entry.file_name();
-                    let file_name_str = file_name.to_string_lossy();
-                    
-                    // Look for input files (actual sensor readings)
-                    if file_name_str.ends_with("_input") {
-                        let sensor_base = file_name_str.strip_suffix("_input").unwrap();
-                        
-                        if let Ok(value_str) = fs::read_to_string(entry.path()) {
-                            if let Ok(raw_value) = value_str.trim().parse::<f64>() {
-                                let sensor_type = self.determine_hwmon_sensor_type(sensor_base);
-                                let (value, unit) = self.convert_hwmon_value(raw_value, &sensor_type);
-                                
-                                // Try to read label
-                                let label_path = format!("{}/{}_label", hwmon_path, sensor_base);
-                                let label = fs::read_to_string(&label_path)
-                                    .unwrap_or_else(|_| sensor_base.to_string())
-                                    .trim()
-                                    .to_string();
-
-                                // Try to read min/max/crit values
-                                let min_value = self.read_hwmon_threshold(hwmon_path, sensor_base, "min", &sensor_type);
-                                let max_value = self.read_hwmon_threshold(hwmon_path, sensor_base, "max", &sensor_type);
-                                let critical_value = self.read_hwmon_threshold(hwmon_path, sensor_base, "crit", &sensor_type);
-
-                                let status = self.determine_sensor_status_with_thresholds(
-                                    &sensor_type, value, min_value, max_value, critical_value
-                                );
-
-                                sensors.push(SensorReading {
-                                    name: format!("{}:{}", chip_name, sensor_base),
-                                    sensor_type,
-                                    current_value: value,
-                                    min_value,
-                                    max_value,
-                                    critical_value,
-                                    unit,
-                                    status,
-                                    chip: chip_name.clone(),
-                                    label,
-                                    last_updated: now,
-                                    history: Vec::new(),
-                                });
-                            }
-                        }
+        Ok(self.hwmon.read_chips()
+            .into_iter()
+            .flat_map(|chip| {
+                chip.features.into_iter().map(move |feature| {
+                    let status = self.determine_hwmon_status(&feature);
+                    SensorReading {
+                        name: format!("{}:{}", chip.name, feature.name),
+                        sensor_type: feature.sensor_type,
+                        current_value: feature.value,
+                        min_value: feature.min,
+                        max_value: feature.max,
+                        critical_value: feature.crit,
+                        unit: feature.unit,
+                        status,
+                        chip: chip.name.clone(),
+                        label: feature.label,
+                        last_updated: now,
+                        history: Vec::new(),
                     }
-                }
-            }
-        }
-
-        Ok(sensors)
+                })
+            })
+            .collect())
     }
 
     fn get_acpi_readings(&self) -> Result<Vec<SensorReading>> {
@@ -440,7 +420,12 @@ impl HardwareSensorMonitor {
         Ok(sensors)
     }
 
-    fn get_ipmi_readings(&self) -> Result<Vec<SensorReading>> {
+    fn get_ipmi_readings(&mut self) -> Result<Vec<SensorReading>> {
+        if let Some(ipmi) = self.ipmi.as_mut() {
+            return Ok(ipmi.readings().to_vec());
+        }
+
+        // Without an OpenIPMI device node, fall back to ipmitool
         let mut sensors = Vec::new();
         
         let output = Command::new("ipmitool")
@@ -554,51 +539,21 @@ impl HardwareSensorMonitor {
         }
     }
 
-    fn determine_hwmon_sensor_type(&self, sensor_base: &str) -> SensorType {
-        if sensor_base.starts_with("temp") {
-            SensorType::Temperature
-        } else if sensor_base.starts_with("fan") {
-            SensorType::Fan
-        } else if sensor_base.starts_with("in") {
-            SensorType::Voltage
-        } else if sensor_base.starts_with("power") {
-            SensorType::Power
-        } else if sensor_base.starts_with("curr") {
-            SensorType::Current
-        } else if sensor_base.starts_with("energy") {
-            SensorType::Energy
-        } else if sensor_base.starts_with("humidity") {
-            SensorType::Humidity
+    /// The chip's own alarm flags take precedence over our thresholds.
+    fn determine_hwmon_status(&self, feature: &HwmonFeature) -> SensorStatus {
+        if feature.fault {
+            SensorStatus::Fault
+        } else if feature.crit_alarm {
+            SensorStatus::Critical
+        } else if feature.alarm {
+            SensorStatus::Warning
         } else {
-            SensorType::Unknown
-        }
-    }
-
-    fn convert_hwmon_value(&self, raw_value: f64, sensor_type: &SensorType) -> (f64, String) {
-        match sensor_type {
-            SensorType::Temperature => (raw_value / 1000.0, "°C".to_string()),
-            SensorType::Voltage => (raw_value / 1000.0, "V".to_string()),
-            SensorType::Power => (raw_value / 1000000.0, "W".to_string()),
-            SensorType::Current => (raw_value / 1000.0, "A".to_string()),
-            SensorType::Energy => (raw_value / 1000000.0, "J".to_string()),
-            SensorType::Fan => (raw_value, "RPM".to_string()),
-            _ => (raw_value, "".to_string()),
+            self.determine_sensor_status_with_thresholds(
+                &feature.sensor_type, feature.value, feature.min, feature.max, feature.crit
+            )
         }
     }
 
-    fn read_hwmon_threshold(&self, hwmon_path: &str, sensor_base: &str, threshold_type: &str, sensor_type: &SensorType) -> Option<f64> {
-        let threshold_path = format!("{}/_{}_{}", hwmon_path, sensor_base, threshold_type);
-        
-        if let Ok(value_str) = fs::read_to_string(threshold_path) {
-            if let Ok(raw_value) = value_str.trim().parse::<f64>() {
-                let (value, _) = self.convert_hwmon_value(raw_value, sensor_type);
-                return Some(value);
-            }
-        }
-        
-        None
-    }
-
     fn determine_sensor_status(&self, sensor_type: &SensorType, value: f64) -> SensorStatus {
         match sensor_type {
             SensorType::Temperature => {
@@ -1,4 +1,4 @@
-use crate::monitor::SystemMetrics;
+use crate::monitor::{PressureResource, SystemMetrics};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -15,6 +15,13 @@ pub struct HistoricalMetrics {
     pub disk_write_bytes: u64,
     pub load_average: f64,
     pub process_count: usize,
+    /// PSI `some` avg10 per resource, when the kernel provides it.
+    #[serde(default)]
+    pub cpu_pressure: Option<f32>,
+    #[serde(default)]
+    pub memory_pressure: Option<f32>,
+    #[serde(default)]
+    pub io_pressure: Option<f32>,
 }
 
 impl From<&SystemMetrics> for HistoricalMetrics {
@@ -30,6 +37,9 @@ impl From<&SystemMetrics> for HistoricalMetrics {
             disk_write_bytes: 0, // TODO: implement disk I/O metrics
             load_average: metrics.load_average.one_min,
             process_count: metrics.processes.len(),
+            cpu_pressure: metrics.pressure.some_avg10(PressureResource::Cpu),
+            memory_pressure: metrics.pressure.some_avg10(PressureResource::Memory),
+            io_pressure: metrics.pressure.some_avg10(PressureResource::Io),
         }
     }
 }
@@ -80,6 +90,7 @@ mod tests {
                 overall_usage: 50.0,
                 per_core_usage: vec![45.0, 55.0],
                 temperature: None,
+                topology: None,
             },
             memory: MemoryMetrics {
                 total_ram: 8_000_000_000,
@@ -89,6 +100,8 @@ mod tests {
                 used_swap: 500_000_000,
                 ram_percentage: 50.0,
                 swap_percentage: 25.0,
+                breakdown: None,
+                numa: Vec::new(),
             },
             processes: vec![],
             network: NetworkMetrics {
@@ -106,6 +119,7 @@ mod tests {
                 fifteen_min: 1.0,
             },
             boot_time: Utc::now(),
+            pressure: Default::default(),
         }
     }
 
@@ -0,0 +1,601 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::hardware_sensors::SensorType;
+
+const HWMON_ROOT: &str = "/sys/class/hwmon";
+/// Feature prefixes of the hwmon sysfs interface, with the divisor that
+/// turns the raw value into the unit shown.
+const FEATURE_KINDS: &[(&str, f64, &str)] = &[
+    ("temp", 1000.0, "°C"),
+    ("fan", 1.0, "RPM"),
+    ("in", 1000.0, "V"),
+    ("power", 1_000_000.0, "W"),
+    ("curr", 1000.0, "A"),
+    ("energy", 1_000_000.0, "J"),
+    ("humidity", 1000.0, "%"),
+];
+
+/// Statements for the chips matching any of `chips`, as in a `chip`
+/// section of sensors3.conf.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChipConfig {
+    /// Chip name patterns such as `coretemp-*` or `nct6775-isa-0290`.
+    pub chips: Vec<String>,
+    /// Feature (`temp1`, `in3`, ...) to label.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    /// Feature to compute expression, in sensors3.conf syntax: `@` is the
+    /// value read from sysfs, e.g. `@*(1+120/56)-4.096*120/56`.
+    #[serde(default)]
+    pub compute: HashMap<String, String>,
+    /// Features to hide.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+}
+
+impl ChipConfig {
+    pub fn matches(&self, chip_name: &str) -> bool {
+        self.chips.iter().any(|pattern| wildcard_match(pattern, chip_name))
+    }
+}
+
+/// One `tempN`/`fanN`/`inN`/`powerN`/`currN` feature, converted to
+/// display units with its compute expression applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HwmonFeature {
+    /// `temp1`, `fan2`, ...
+    pub name: String,
+    pub sensor_type: SensorType,
+    pub label: String,
+    pub value: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+    pub unit: String,
+    /// Any of the `_alarm`, `_min_alarm`, `_max_alarm` flags.
+    pub alarm: bool,
+    /// `_crit_alarm` or `_lcrit_alarm`.
+    pub crit_alarm: bool,
+    /// `_fault`, e.g. a disconnected thermal diode.
+    pub fault: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HwmonChip {
+    /// lm-sensors style name, e.g. `coretemp-isa-0000` or `nvme-pci-0100`.
+    pub name: String,
+    /// The driver's `name` attribute.
+    pub driver: String,
+    pub path: PathBuf,
+    pub features: Vec<HwmonFeature>,
+}
+
+/// Reads sensors straight from `/sys/class/hwmon`.
+#[derive(Debug, Clone)]
+pub struct HwmonReader {
+    root: PathBuf,
+    config: Vec<ChipConfig>,
+    computes: HashMap<String, ComputeExpr>,
+}
+
+impl Default for HwmonReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HwmonReader {
+    pub fn new() -> Self {
+        Self::with_root(HWMON_ROOT)
+    }
+
+    pub fn with_root<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            config: Vec::new(),
+            computes: HashMap::new(),
+        }
+    }
+
+    /// Sets the label, compute and ignore statements. Later sections win
+    /// where several match a chip, as in sensors3.conf. Compute expressions
+    /// that do not parse are reported and skipped.
+    pub fn set_config(&mut self, config: Vec<ChipConfig>) -> Result<()> {
+        let mut errors = Vec::new();
+        self.computes.clear();
+        for chip in &config {
+            for (feature, expression) in &chip.compute {
+                match ComputeExpr::parse(expression) {
+                    Ok(expr) => {
+                        self.computes.insert(expression.clone(), expr);
+                    }
+                    Err(e) => errors.push(format!("{} {}: {}", chip.chips.join(","), feature, e)),
+                }
+            }
+        }
+        self.config = config;
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("Invalid compute expressions: {}", errors.join("; ")))
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        fs::read_dir(&self.root).map(|mut entries| entries.next().is_some()).unwrap_or(false)
+    }
+
+    pub fn read_chips(&self) -> Vec<HwmonChip> {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(&self.root)
+            .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+            .unwrap_or_default();
+        dirs.sort_by_key(|dir| hwmon_index(dir));
+
+        dirs.iter().filter_map(|dir| self.read_chip(dir)).collect()
+    }
+
+    fn read_chip(&self, dir: &Path) -> Option<HwmonChip> {
+        let driver = read_string(&dir.join("name"))?;
+        let name = chip_name(&driver, dir);
+
+        let attribute_dir = chip_attribute_dir(dir, has_features).unwrap_or_else(|| dir.join("device"));
+
+        let mut bases: BTreeMap<(usize, u32), String> = BTreeMap::new();
+        for entry in fs::read_dir(&attribute_dir).ok()?.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if let Some((kind, index, base)) = parse_feature_base(&file_name) {
+                bases.insert((kind, index), base);
+            }
+        }
+
+        let config: Vec<&ChipConfig> = self.config.iter().filter(|c| c.matches(&name)).collect();
+        let features = bases.into_iter()
+            .filter(|(_, base)| !config.iter().any(|c| c.ignore.contains(base)))
+            .filter_map(|((kind, _), base)| self.read_feature(&attribute_dir, kind, &base, &config))
+            .collect();
+
+        Some(HwmonChip {
+            name,
+            driver,
+            path: dir.to_path_buf(),
+            features,
+        })
+    }
+
+    fn read_feature(&self, dir: &Path, kind: usize, base: &str, config: &[&ChipConfig]) -> Option<HwmonFeature> {
+        let (prefix, divisor, unit) = FEATURE_KINDS[kind];
+        let attribute = |suffix: &str| -> Option<f64> {
+            read_string(&dir.join(format!("{}_{}", base, suffix)))?.parse::<f64>().ok()
+        };
+        let flag = |suffix: &str| attribute(suffix).map(|v| v != 0.0).unwrap_or(false);
+
+        // Most power meters only provide an average
+        let raw = attribute("input").or_else(|| if prefix == "power" { attribute("average") } else { None })?;
+
+        let compute = config.iter().rev()
+            .find_map(|c| c.compute.get(base))
+            .and_then(|expression| self.computes.get(expression));
+        let convert = |raw: f64| {
+            let value = raw / divisor;
+            compute.map(|expr| expr.evaluate(value)).unwrap_or(value)
+        };
+
+        let label = config.iter().rev()
+            .find_map(|c| c.labels.get(base).cloned())
+            .or_else(|| read_string(&dir.join(format!("{}_label", base))))
+            .unwrap_or_else(|| base.to_string());
+
+        Some(HwmonFeature {
+            name: base.to_string(),
+            sensor_type: SensorType::from(prefix),
+            label,
+            value: convert(raw),
+            min: attribute("min").map(convert),
+            max: attribute("max").map(convert),
+            crit: attribute("crit").or_else(|| attribute("cap")).map(convert),
+            unit: unit.to_string(),
+            alarm: flag("alarm") || flag("min_alarm") || flag("max_alarm"),
+            crit_alarm: flag("crit_alarm") || flag("lcrit_alarm"),
+            fault: flag("fault"),
+        })
+    }
+}
+
+fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn hwmon_index(dir: &Path) -> u32 {
+    dir.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_prefix("hwmon"))
+        .and_then(|index| index.parse().ok())
+        .unwrap_or(u32::MAX)
+}
+
+/// The chip's hwmon directory, or its `device` for drivers predating the
+/// hwmon class, which keep their attributes there; whichever `has` accepts.
+pub(crate) fn chip_attribute_dir(chip_dir: &Path, has: impl Fn(&Path) -> bool) -> Option<PathBuf> {
+    [chip_dir.to_path_buf(), chip_dir.join("device")]
+        .into_iter()
+        .find(|dir| has(dir))
+}
+
+fn has_features(dir: &Path) -> bool {
+    fs::read_dir(dir)
+        .map(|entries| entries.flatten().any(|entry| parse_feature_base(&entry.file_name().to_string_lossy()).is_some()))
+        .unwrap_or(false)
+}
+
+/// Splits `temp1_input` into the feature kind, its index and `temp1`.
+fn parse_feature_base(file_name: &str) -> Option<(usize, u32, String)> {
+    let (base, _) = file_name.split_once('_')?;
+    FEATURE_KINDS.iter().enumerate().find_map(|(kind, (prefix, _, _))| {
+        let index = base.strip_prefix(prefix)?;
+        if index.is_empty() || !index.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        Some((kind, index.parse().ok()?, base.to_string()))
+    })
+}
+
+/// Names the chip the way libsensors does: driver, bus type and address,
+/// e.g. `coretemp-isa-0000`, `lm75-i2c-1-48`, `amdgpu-pci-0300`.
+fn chip_name(driver: &str, dir: &Path) -> String {
+    let mut device = match fs::canonicalize(dir.join("device")) {
+        Ok(device) => device,
+        Err(_) => return format!("{}-virtual-0", driver),
+    };
+
+    // Class devices such as nvme0 sit below the bus device that names them
+    for _ in 0..4 {
+        let subsystem = fs::read_link(device.join("subsystem")).ok()
+            .and_then(|link| link.file_name().map(|n| n.to_string_lossy().to_string()))
+            .unwrap_or_default();
+        let id = device.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+
+        match subsystem.as_str() {
+            "i2c" => {
+                if let Some((bus, address)) = id.split_once('-') {
+                    let address = u16::from_str_radix(address, 16).unwrap_or(0);
+                    return format!("{}-i2c-{}-{:02x}", driver, bus, address);
+                }
+            }
+            "pci" => {
+                let address = parse_pci_address(&id).unwrap_or(0);
+                return format!("{}-pci-{:04x}", driver, address);
+            }
+            "platform" | "isa" => {
+                let address = id.rsplit_once('.').and_then(|(_, n)| n.parse::<u32>().ok()).unwrap_or(0);
+                return format!("{}-isa-{:04x}", driver, address);
+            }
+            "acpi" => {
+                let address = id.rsplit_once(':').and_then(|(_, n)| n.parse::<u32>().ok()).unwrap_or(0);
+                return format!("{}-acpi-{}", driver, address);
+            }
+            _ => {}
+        }
+
+        match device.parent() {
+            Some(parent) => device = parent.to_path_buf(),
+            None => break,
+        }
+    }
+
+    format!("{}-virtual-0", driver)
+}
+
+/// `0000:03:00.0` packed as libsensors does: domain, bus, slot, function.
+fn parse_pci_address(id: &str) -> Option<u32> {
+    let (domain, rest) = id.split_once(':')?;
+    let (bus, rest) = rest.split_once(':')?;
+    let (slot, function) = rest.split_once('.')?;
+    let domain = u32::from_str_radix(domain, 16).ok()?;
+    let bus = u32::from_str_radix(bus, 16).ok()?;
+    let slot = u32::from_str_radix(slot, 16).ok()?;
+    let function = u32::from_str_radix(function, 16).ok()?;
+    Some((domain << 16) + (bus << 8) + (slot << 3) + function)
+}
+
+/// Glob match where `*` stands for any run of characters.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let name = match name.strip_prefix(prefix) {
+                Some(name) => name,
+                None => return false,
+            };
+            (0..=name.len())
+                .filter(|&i| name.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &name[i..]))
+        }
+    }
+}
+
+/// Parses the `chip`, `label`, `compute` and `ignore` statements of a
+/// sensors3.conf file. `set` and `bus` statements are skipped.
+pub fn parse_sensors_conf(content: &str) -> Result<Vec<ChipConfig>> {
+    let mut chips: Vec<ChipConfig> = Vec::new();
+
+    // Statements may continue over lines ending in a backslash
+    let content = content.replace("\\\n", " ");
+    for (number, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+
+        if keyword == "chip" {
+            chips.push(ChipConfig {
+                chips: split_words(rest),
+                ..Default::default()
+            });
+            continue;
+        }
+
+        let chip = match chips.last_mut() {
+            Some(chip) => chip,
+            None if matches!(keyword, "bus") => continue,
+            None => return Err(anyhow!("line {}: `{}` outside of a chip section", number + 1, keyword)),
+        };
+        match keyword {
+            "label" => {
+                let words = split_words(rest);
+                if words.len() != 2 {
+                    return Err(anyhow!("line {}: expected `label <feature> \"<text>\"`", number + 1));
+                }
+                chip.labels.insert(words[0].clone(), words[1].clone());
+            }
+            "compute" => {
+                let (feature, expressions) = rest.split_once(char::is_whitespace)
+                    .ok_or_else(|| anyhow!("line {}: expected `compute <feature> <expr>, <expr>`", number + 1))?;
+                // Only the sysfs-to-value direction is needed for reading
+                let expression = expressions.split(',').next().unwrap_or("").trim();
+                ComputeExpr::parse(expression).map_err(|e| anyhow!("line {}: {}", number + 1, e))?;
+                chip.compute.insert(feature.to_string(), expression.to_string());
+            }
+            "ignore" => chip.ignore.extend(split_words(rest)),
+            "set" | "bus" => {}
+            other => return Err(anyhow!("line {}: unknown statement `{}`", number + 1, other)),
+        }
+    }
+
+    Ok(chips)
+}
+
+/// Splits on whitespace, keeping double-quoted strings together.
+fn split_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                if quoted {
+                    words.push(std::mem::take(&mut current));
+                }
+                quoted = !quoted;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// A sensors3.conf compute expression: numbers, `@`, `+ - * /`,
+/// parentheses, unary minus, `^` (exp) and `` ` `` (ln).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComputeExpr {
+    Value,
+    Number(f64),
+    Negate(Box<ComputeExpr>),
+    Exp(Box<ComputeExpr>),
+    Ln(Box<ComputeExpr>),
+    Binary(char, Box<ComputeExpr>, Box<ComputeExpr>),
+}
+
+impl ComputeExpr {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let tokens: Vec<char> = expression.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut position = 0;
+        let expr = Self::parse_sum(&tokens, &mut position)?;
+        if position != tokens.len() {
+            return Err(anyhow!("unexpected `{}` in `{}`", tokens[position], expression));
+        }
+        Ok(expr)
+    }
+
+    pub fn evaluate(&self, value: f64) -> f64 {
+        match self {
+            ComputeExpr::Value => value,
+            ComputeExpr::Number(n) => *n,
+            ComputeExpr::Negate(e) => -e.evaluate(value),
+            ComputeExpr::Exp(e) => e.evaluate(value).exp(),
+            ComputeExpr::Ln(e) => e.evaluate(value).ln(),
+            ComputeExpr::Binary(op, a, b) => {
+                let (a, b) = (a.evaluate(value), b.evaluate(value));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ => a / b,
+                }
+            }
+        }
+    }
+
+    fn parse_sum(tokens: &[char], position: &mut usize) -> Result<Self> {
+        let mut left = Self::parse_product(tokens, position)?;
+        while let Some(&op) = tokens.get(*position).filter(|&&c| c == '+' || c == '-') {
+            *position += 1;
+            let right = Self::parse_product(tokens, position)?;
+            left = ComputeExpr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_product(tokens: &[char], position: &mut usize) -> Result<Self> {
+        let mut left = Self::parse_unary(tokens, position)?;
+        while let Some(&op) = tokens.get(*position).filter(|&&c| c == '*' || c == '/') {
+            *position += 1;
+            let right = Self::parse_unary(tokens, position)?;
+            left = ComputeExpr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_unary(tokens: &[char], position: &mut usize) -> Result<Self> {
+        match tokens.get(*position) {
+            Some('-') => {
+                *position += 1;
+                Ok(ComputeExpr::Negate(Box::new(Self::parse_unary(tokens, position)?)))
+            }
+            Some('^') => {
+                *position += 1;
+                Ok(ComputeExpr::Exp(Box::new(Self::parse_unary(tokens, position)?)))
+            }
+            Some('`') => {
+                *position += 1;
+                Ok(ComputeExpr::Ln(Box::new(Self::parse_unary(tokens, position)?)))
+            }
+            Some('@') => {
+                *position += 1;
+                Ok(ComputeExpr::Value)
+            }
+            Some('(') => {
+                *position += 1;
+                let expr = Self::parse_sum(tokens, position)?;
+                if tokens.get(*position) != Some(&')') {
+                    return Err(anyhow!("missing `)`"));
+                }
+                *position += 1;
+                Ok(expr)
+            }
+            Some(c) if c.is_ascii_digit() || *c == '.' => {
+                let start = *position;
+                while tokens.get(*position).map(|c| c.is_ascii_digit() || *c == '.').unwrap_or(false) {
+                    *position += 1;
+                }
+                let number: String = tokens[start..*position].iter().collect();
+                Ok(ComputeExpr::Number(number.parse().map_err(|_| anyhow!("bad number `{}`", number))?))
+            }
+            Some(c) => Err(anyhow!("unexpected `{}`", c)),
+            None => Err(anyhow!("unexpected end of expression")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+
+    fn write_attributes(dir: &Path, attributes: &[(&str, &str)]) {
+        fs::create_dir_all(dir).unwrap();
+        for (name, value) in attributes {
+            fs::write(dir.join(name), format!("{}\n", value)).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_chips() {
+        let root = TempDir::new("hwmon_read");
+        write_attributes(&root.join("hwmon0"), &[
+            ("name", "nct6775"),
+            ("temp1_input", "47000"),
+            ("temp1_label", "SYSTIN"),
+            ("temp1_max", "80000"),
+            ("temp1_crit", "100000"),
+            ("temp1_alarm", "0"),
+            ("temp2_input", "-128000"),
+            ("temp2_fault", "1"),
+            ("fan1_input", "1250"),
+            ("fan1_min", "1500"),
+            ("fan1_alarm", "1"),
+            ("in3_input", "1660"),
+            ("in3_min", "1500"),
+            ("in7_input", "3300"),
+            ("power1_average", "12500000"),
+            ("curr1_input", "1500"),
+            ("curr1_crit_alarm", "1"),
+            ("pwm1", "128"),
+        ]);
+        // Old-style driver with its attributes on the device
+        write_attributes(&root.join("hwmon1"), &[("name", "w83627hf")]);
+        write_attributes(&root.join("hwmon1").join("device"), &[("temp1_input", "30000")]);
+
+        let mut reader = HwmonReader::with_root(&root);
+        let config = parse_sensors_conf(
+            "# board specific\n\
+             chip \"nct6775-*\"\n    \
+                 label temp1 \"Motherboard\"\n    \
+                 compute in3 @*2, @/2\n    \
+                 ignore in7\n    \
+                 set in3_min 1.6\n",
+        ).unwrap();
+        reader.set_config(config).unwrap();
+
+        let chips = reader.read_chips();
+        assert_eq!(chips.len(), 2);
+        assert_eq!(chips[0].name, "nct6775-virtual-0");
+        let features = &chips[0].features;
+        let names: Vec<&str> = features.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["temp1", "temp2", "fan1", "in3", "power1", "curr1"]);
+
+        let temp1 = &features[0];
+        assert_eq!(temp1.label, "Motherboard");
+        assert_eq!(temp1.value, 47.0);
+        assert_eq!((temp1.max, temp1.crit), (Some(80.0), Some(100.0)));
+        assert!(!temp1.alarm);
+        assert!(features[1].fault);
+        assert!(features[2].alarm);
+        assert_eq!(features[2].min, Some(1500.0));
+        assert_eq!(features[3].value, 3.32);
+        assert_eq!(features[3].min, Some(3.0));
+        assert_eq!(features[4].value, 12.5);
+        assert!(features[5].crit_alarm);
+
+        assert_eq!(chips[1].features.len(), 1);
+        assert_eq!(chips[1].features[0].value, 30.0);
+    }
+
+    #[test]
+    fn test_compute_expressions() {
+        let expr = ComputeExpr::parse("@*(1+120/56)-4.096*120/56").unwrap();
+        assert!((expr.evaluate(1.0) - (1.0 + 120.0 / 56.0 - 4.096 * 120.0 / 56.0)).abs() < 1e-9);
+        assert_eq!(ComputeExpr::parse("-@ / 2").unwrap().evaluate(3.0), -1.5);
+        assert!((ComputeExpr::parse("`^@").unwrap().evaluate(2.5) - 2.5).abs() < 1e-9);
+        assert!(ComputeExpr::parse("(@*2").is_err());
+        assert!(ComputeExpr::parse("@ in0").is_err());
+
+        assert!(parse_sensors_conf("label temp1 \"x\"").is_err());
+        assert!(parse_sensors_conf("chip \"x-*\"\n compute in0 @*").is_err());
+    }
+
+    #[test]
+    fn test_chip_names() {
+        assert!(wildcard_match("coretemp-*", "coretemp-isa-0000"));
+        assert!(wildcard_match("*-pci-*", "amdgpu-pci-0300"));
+        assert!(!wildcard_match("it87-*", "nct6775-isa-0290"));
+        assert_eq!(parse_pci_address("0000:03:00.0"), Some(0x0300));
+        assert_eq!(parse_feature_base("temp12_crit_alarm"), Some((0, 12, "temp12".to_string())));
+        assert_eq!(parse_feature_base("pwm1_enable"), None);
+        assert_eq!(parse_feature_base("intrusion0_alarm"), None);
+    }
+}
@@ -4,6 +4,7 @@ use std::fs;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::monitor::ProcessInfo;
+use crate::monitor::pressure::{PressureMetrics, ResourcePressure};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IOBottleneckAnalyzer {
@@ -35,6 +36,9 @@ pub struct SystemIOSnapshot {
     pub average_wait_time: f64,
     pub queue_depth: HashMap<String, f64>,
     pub io_operations_per_sec: f64,
+    /// IO PSI, the share of time tasks waited on IO.
+    #[serde(default)]
+    pub io_pressure: Option<ResourcePressure>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -71,6 +75,7 @@ pub enum BottleneckType {
     IOStorm,
     SlowDisk,
     IOContentionDetected,
+    IOPressure,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,6 +111,9 @@ pub struct IOAnalysisSettings {
     pub dominant_process_threshold: f64,
     pub io_storm_iops_threshold: f64,
     pub slow_disk_response_threshold_ms: f64,
+    /// IO PSI `full` avg10 above which all runnable work is stalled on IO
+    /// often enough to matter.
+    pub io_pressure_threshold: f64,
     pub min_samples_for_analysis: usize,
 }
 
@@ -118,6 +126,7 @@ impl Default for IOAnalysisSettings {
             dominant_process_threshold: 80.0, // 80% of total IO
             io_storm_iops_threshold: 10000.0, // 10k IOPS threshold
             slow_disk_response_threshold_ms: 50.0, // 50ms response time
+            io_pressure_threshold: 10.0, // 10% of time fully stalled on IO
             min_samples_for_analysis: 5,
         }
     }
@@ -338,6 +347,7 @@ impl IOBottleneckAnalyzer {
             average_wait_time,
             queue_depth,
             io_operations_per_sec: total_operations,
+            io_pressure: PressureMetrics::read_system().io,
         })
     }
 
@@ -356,6 +366,7 @@ impl IOBottleneckAnalyzer {
         self.detect_io_storm()?;
         self.detect_slow_disk_response()?;
         self.detect_io_contention(processes)?;
+        self.detect_io_pressure()?;
 
         Ok(())
     }
@@ -376,7 +387,9 @@ impl IOBottleneckAnalyzer {
                     affected_processes: Vec::new(),
                     bottleneck_source: "System-wide high IO wait".to_string(),
                     metrics: BottleneckMetrics {
-                        io_wait_percentage: avg_utilization,
+                        io_wait_percentage: latest_snapshot.io_pressure
+                            .map(|pressure| pressure.some.avg10 as f64)
+                            .unwrap_or(avg_utilization),
                         disk_utilization: avg_utilization,
                         avg_response_time_ms: latest_snapshot.average_wait_time,
                         iops: latest_snapshot.io_operations_per_sec,
@@ -597,6 +610,62 @@ impl IOBottleneckAnalyzer {
         Ok(())
     }
 
+    /// Stalls measured by the kernel rather than inferred from disk
+    /// counters, so they also catch slow network and fuse filesystems.
+    fn detect_io_pressure(&mut self) -> Result<()> {
+        let snapshot = match self.system_io_history.back() {
+            Some(snapshot) => snapshot,
+            None => return Ok(()),
+        };
+        let (pressure, full) = match snapshot.io_pressure {
+            Some(pressure @ ResourcePressure { full: Some(full), .. }) => (pressure, full.avg10 as f64),
+            _ => return Ok(()),
+        };
+        if full <= self.analysis_settings.io_pressure_threshold {
+            return Ok(());
+        }
+
+        // Processes currently blocked in uninterruptible sleep are the ones
+        // stalling
+        let affected_processes = self.process_io_history.keys()
+            .copied()
+            .filter(|pid| {
+                fs::read_to_string(format!("/proc/{}/stat", pid))
+                    .ok()
+                    .and_then(|stat| stat.rsplit_once(')').map(|(_, rest)| rest.trim_start().starts_with('D')))
+                    .unwrap_or(false)
+            })
+            .collect();
+
+        let alert = IOBottleneckAlert {
+            alert_id: format!("io_pressure_{}", Utc::now().timestamp()),
+            alert_type: BottleneckType::IOPressure,
+            severity: self.calculate_io_severity(full, 25.0),
+            detected_at: Utc::now(),
+            affected_processes,
+            bottleneck_source: format!(
+                "Tasks stalled on IO: {:.1}% some, {:.1}% full (avg10)",
+                pressure.some.avg10, full
+            ),
+            metrics: BottleneckMetrics {
+                io_wait_percentage: pressure.some.avg10 as f64,
+                disk_utilization: snapshot.disk_utilization.values().sum::<f64>() / snapshot.disk_utilization.len().max(1) as f64,
+                avg_response_time_ms: snapshot.average_wait_time,
+                iops: snapshot.io_operations_per_sec,
+                throughput_mbps: (snapshot.total_read_rate + snapshot.total_write_rate) / (1024.0 * 1024.0),
+                queue_depth: snapshot.queue_depth.values().sum::<f64>() / snapshot.queue_depth.len().max(1) as f64,
+            },
+            suggested_actions: vec![
+                "Check which cgroups show the highest io.pressure".to_string(),
+                "Consider io.max or io.weight limits for batch workloads".to_string(),
+                "Check for swapping, which also shows up as IO pressure".to_string(),
+            ],
+            trend_data: self.build_system_trend_data("io_pressure"),
+        };
+        self.bottleneck_alerts.push(alert);
+        Ok(())
+    }
+
     fn calculate_io_severity(&self, value: f64, critical_threshold: f64) -> BottleneckSeverity {
         let ratio = value / critical_threshold;
         
@@ -617,6 +686,10 @@ impl IOBottleneckAnalyzer {
                 "io_wait" => snapshot.disk_utilization.values().sum::<f64>() / snapshot.disk_utilization.len().max(1) as f64,
                 "iops" => snapshot.io_operations_per_sec,
                 "response_time" => snapshot.average_wait_time,
+                "io_pressure" => snapshot.io_pressure
+                    .and_then(|pressure| pressure.full)
+                    .map(|full| full.avg10 as f64)
+                    .unwrap_or(0.0),
                 _ => 0.0,
             };
             
@@ -0,0 +1,1095 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::os::unix::io::AsRawFd;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::hardware_sensors::{HardwareEvent, SensorReading, SensorStatus, SensorType};
+
+const DEVICE_PATHS: &[&str] = &["/dev/ipmi0", "/dev/ipmi/0", "/dev/ipmidev/0"];
+const RESPONSE_TIMEOUT_MS: i32 = 2000;
+const READING_INTERVAL: Duration = Duration::from_secs(10);
+const SEL_INTERVAL: Duration = Duration::from_secs(30);
+/// Bytes per Get SDR call; many BMCs reject larger partial reads.
+const SDR_CHUNK: u8 = 16;
+const MAX_RECORDS: usize = 1024;
+
+const NETFN_SENSOR: u8 = 0x04;
+const NETFN_STORAGE: u8 = 0x0a;
+const CMD_GET_SENSOR_READING: u8 = 0x2d;
+const CMD_GET_SDR_REPOSITORY_INFO: u8 = 0x20;
+const CMD_RESERVE_SDR_REPOSITORY: u8 = 0x22;
+const CMD_GET_SDR: u8 = 0x23;
+const CMD_GET_SEL_INFO: u8 = 0x40;
+const CMD_GET_SEL_ENTRY: u8 = 0x43;
+
+const CC_RESERVATION_CANCELLED: u8 = 0xc5;
+const CC_CANNOT_RETURN_BYTES: u8 = 0xca;
+const CC_SENSOR_NOT_PRESENT: u8 = 0xcb;
+const CC_DATA_NOT_PRESENT: u8 = 0xcd;
+
+const SENSOR_TYPE_TEMPERATURE: u8 = 0x01;
+const SENSOR_TYPE_VOLTAGE: u8 = 0x02;
+const SENSOR_TYPE_CURRENT: u8 = 0x03;
+const SENSOR_TYPE_FAN: u8 = 0x04;
+const SENSOR_TYPE_PHYSICAL_SECURITY: u8 = 0x05;
+/// Event/reading type of threshold-based sensors.
+const EVENT_TYPE_THRESHOLD: u8 = 0x01;
+
+/// Sends one request to the BMC and returns the response, completion
+/// code first. Implemented by the OpenIPMI device and by test doubles.
+pub trait IpmiTransport {
+    fn request(&mut self, lun: u8, netfn: u8, cmd: u8, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+// linux/ipmi.h
+const IPMI_SYSTEM_INTERFACE_ADDR_TYPE: libc::c_int = 0x0c;
+const IPMI_BMC_CHANNEL: libc::c_short = 0x0f;
+const IPMI_RESPONSE_RECV_TYPE: libc::c_int = 1;
+const IPMI_MAX_MSG_LENGTH: usize = 272;
+const IPMI_MAX_ADDR_SIZE: usize = 32 + 8;
+
+#[repr(C)]
+struct IpmiSystemInterfaceAddr {
+    addr_type: libc::c_int,
+    channel: libc::c_short,
+    lun: libc::c_uchar,
+}
+
+#[repr(C)]
+struct IpmiMsg {
+    netfn: libc::c_uchar,
+    cmd: libc::c_uchar,
+    data_len: libc::c_ushort,
+    data: *mut libc::c_uchar,
+}
+
+#[repr(C)]
+struct IpmiReq {
+    addr: *mut libc::c_uchar,
+    addr_len: libc::c_uint,
+    msgid: libc::c_long,
+    msg: IpmiMsg,
+}
+
+#[repr(C)]
+struct IpmiRecv {
+    recv_type: libc::c_int,
+    addr: *mut libc::c_uchar,
+    addr_len: libc::c_uint,
+    msgid: libc::c_long,
+    msg: IpmiMsg,
+}
+
+const fn ipmi_ioc(direction: u32, number: u32, size: usize) -> u32 {
+    (direction << 30) | ((size as u32) << 16) | ((b'i' as u32) << 8) | number
+}
+
+const IPMICTL_SEND_COMMAND: u32 = ipmi_ioc(2, 13, std::mem::size_of::<IpmiReq>());
+const IPMICTL_RECEIVE_MSG_TRUNC: u32 = ipmi_ioc(3, 11, std::mem::size_of::<IpmiRecv>());
+
+/// The local BMC through the kernel's OpenIPMI driver (`ipmi_devintf`).
+pub struct OpenIpmiDevice {
+    file: File,
+    next_msgid: libc::c_long,
+}
+
+impl OpenIpmiDevice {
+    pub fn open() -> Result<Self> {
+        let mut last_error = None;
+        for path in DEVICE_PATHS {
+            match OpenOptions::new().read(true).write(true).open(path) {
+                Ok(file) => return Ok(Self { file, next_msgid: 1 }),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(anyhow!("No IPMI device: {}", last_error.map(|e| e.to_string()).unwrap_or_default()))
+    }
+}
+
+impl IpmiTransport for OpenIpmiDevice {
+    fn request(&mut self, lun: u8, netfn: u8, cmd: u8, data: &[u8]) -> Result<Vec<u8>> {
+        let fd = self.file.as_raw_fd();
+        let msgid = self.next_msgid;
+        self.next_msgid = self.next_msgid.wrapping_add(1);
+
+        let mut address = IpmiSystemInterfaceAddr {
+            addr_type: IPMI_SYSTEM_INTERFACE_ADDR_TYPE,
+            channel: IPMI_BMC_CHANNEL,
+            lun,
+        };
+        let mut data = data.to_vec();
+        let mut request = IpmiReq {
+            addr: &mut address as *mut IpmiSystemInterfaceAddr as *mut libc::c_uchar,
+            addr_len: std::mem::size_of::<IpmiSystemInterfaceAddr>() as libc::c_uint,
+            msgid,
+            msg: IpmiMsg {
+                netfn,
+                cmd,
+                data_len: data.len() as libc::c_ushort,
+                data: data.as_mut_ptr(),
+            },
+        };
+        // SAFETY: request and the buffers it points to outlive the call
+        if unsafe { libc::ioctl(fd, IPMICTL_SEND_COMMAND as _, &mut request) } < 0 {
+            return Err(anyhow!("IPMI send failed: {}", std::io::Error::last_os_error()));
+        }
+
+        let deadline = Instant::now() + Duration::from_millis(RESPONSE_TIMEOUT_MS as u64);
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now()).as_millis() as i32;
+            let mut poll_fd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+            // SAFETY: one valid pollfd
+            let ready = unsafe { libc::poll(&mut poll_fd, 1, remaining) };
+            if ready < 0 {
+                let error = std::io::Error::last_os_error();
+                if error.kind() == std::io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(anyhow!("IPMI poll failed: {}", error));
+            }
+            if ready == 0 {
+                return Err(anyhow!("IPMI request {:02x}/{:02x} timed out", netfn, cmd));
+            }
+
+            let mut response_address = [0u8; IPMI_MAX_ADDR_SIZE];
+            let mut buffer = [0u8; IPMI_MAX_MSG_LENGTH];
+            let mut received = IpmiRecv {
+                recv_type: 0,
+                addr: response_address.as_mut_ptr(),
+                addr_len: IPMI_MAX_ADDR_SIZE as libc::c_uint,
+                msgid: 0,
+                msg: IpmiMsg {
+                    netfn: 0,
+                    cmd: 0,
+                    data_len: IPMI_MAX_MSG_LENGTH as libc::c_ushort,
+                    data: buffer.as_mut_ptr(),
+                },
+            };
+            // SAFETY: received and its buffers outlive the call
+            if unsafe { libc::ioctl(fd, IPMICTL_RECEIVE_MSG_TRUNC as _, &mut received) } < 0 {
+                return Err(anyhow!("IPMI receive failed: {}", std::io::Error::last_os_error()));
+            }
+
+            // Responses to earlier, timed-out requests may still arrive
+            if received.recv_type != IPMI_RESPONSE_RECV_TYPE || received.msgid != msgid {
+                continue;
+            }
+            let length = (received.msg.data_len as usize).min(IPMI_MAX_MSG_LENGTH);
+            return Ok(buffer[..length].to_vec());
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CompletionCode(u8);
+
+impl std::fmt::Display for CompletionCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IPMI completion code {:#04x}", self.0)
+    }
+}
+
+impl std::error::Error for CompletionCode {}
+
+fn completion_code(error: &anyhow::Error) -> Option<u8> {
+    error.downcast_ref::<CompletionCode>().map(|code| code.0)
+}
+
+/// `y = (M * x + B * 10^Bexp) * 10^Rexp`, then the linearization function.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SensorConversion {
+    pub m: i16,
+    pub b: i16,
+    pub b_exp: i8,
+    pub r_exp: i8,
+    /// 0 unsigned, 1 one's complement, 2 two's complement, 3 not analog.
+    pub analog_format: u8,
+    pub linearization: u8,
+}
+
+impl SensorConversion {
+    pub fn convert(&self, raw: u8) -> Option<f64> {
+        let x = match self.analog_format {
+            0 => raw as f64,
+            1 => {
+                let value = raw as i8;
+                (if value < 0 { value + 1 } else { value }) as f64
+            }
+            2 => raw as i8 as f64,
+            _ => return None,
+        };
+        let y = (self.m as f64 * x + self.b as f64 * 10f64.powi(self.b_exp as i32)) * 10f64.powi(self.r_exp as i32);
+        Some(match self.linearization & 0x7f {
+            0x01 => y.ln(),
+            0x02 => y.log10(),
+            0x03 => y.log2(),
+            0x04 => y.exp(),
+            0x05 => 10f64.powf(y),
+            0x06 => y.exp2(),
+            0x07 => 1.0 / y,
+            0x08 => y * y,
+            0x09 => y * y * y,
+            0x0a => y.sqrt(),
+            0x0b => y.cbrt(),
+            _ => y,
+        })
+    }
+}
+
+/// Raw threshold values, present only where the SDR marks them readable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SdrThresholds {
+    pub lower_non_critical: Option<u8>,
+    pub lower_critical: Option<u8>,
+    pub upper_non_critical: Option<u8>,
+    pub upper_critical: Option<u8>,
+}
+
+/// A full (type 01h) or compact (type 02h) sensor data record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SdrRecord {
+    pub record_id: u16,
+    pub owner_id: u8,
+    pub owner_lun: u8,
+    pub number: u8,
+    pub entity_id: u8,
+    pub sensor_type: u8,
+    pub event_reading_type: u8,
+    pub base_unit: u8,
+    pub name: String,
+    /// Only full records carry a conversion; compact ones are discrete.
+    pub conversion: Option<SensorConversion>,
+    pub thresholds: SdrThresholds,
+}
+
+impl SdrRecord {
+    /// Parses a record, header included. Other record types give `None`.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 5 {
+            return None;
+        }
+        let record_id = u16::from_le_bytes([bytes[0], bytes[1]]);
+        let record_type = bytes[3];
+        let id_offset = match record_type {
+            0x01 => 47,
+            0x02 => 31,
+            _ => return None,
+        };
+        if bytes.len() < id_offset + 1 {
+            return None;
+        }
+        let name_length = (bytes[id_offset] & 0x1f) as usize;
+        let name_bytes = bytes.get(id_offset + 1..id_offset + 1 + name_length).unwrap_or(&[]);
+        let name = String::from_utf8_lossy(name_bytes).trim_end_matches('\0').trim().to_string();
+
+        let (conversion, thresholds) = if record_type == 0x01 {
+            let ten_bits = |low: u8, high: u8| -> i16 {
+                let value = ((high as i16 & 0xc0) << 2) | low as i16;
+                // Sign-extend from 10 bits
+                (value << 6) >> 6
+            };
+            let four_bits = |nibble: u8| -> i8 { ((nibble << 4) as i8) >> 4 };
+            let conversion = SensorConversion {
+                m: ten_bits(bytes[24], bytes[25]),
+                b: ten_bits(bytes[26], bytes[27]),
+                r_exp: four_bits(bytes[29] >> 4),
+                b_exp: four_bits(bytes[29] & 0x0f),
+                analog_format: bytes[20] >> 6,
+                linearization: bytes[23],
+            };
+            // Readable threshold mask: LNC, LC, LNR, UNC, UC, UNR
+            let readable = bytes[18];
+            let threshold = |bit: u8, offset: usize| (readable & (1 << bit) != 0).then_some(bytes[offset]);
+            let thresholds = SdrThresholds {
+                lower_non_critical: threshold(0, 41),
+                lower_critical: threshold(1, 40),
+                upper_non_critical: threshold(3, 38),
+                upper_critical: threshold(4, 37),
+            };
+            (Some(conversion), thresholds)
+        } else {
+            (None, SdrThresholds::default())
+        };
+
+        Some(SdrRecord {
+            record_id,
+            owner_id: bytes[5],
+            owner_lun: bytes[6] & 0x03,
+            number: bytes[7],
+            entity_id: bytes[8],
+            sensor_type: bytes[12],
+            event_reading_type: bytes[13],
+            base_unit: bytes[21],
+            name,
+            conversion,
+            thresholds,
+        })
+    }
+
+    pub fn is_threshold(&self) -> bool {
+        self.event_reading_type == EVENT_TYPE_THRESHOLD && self.conversion.is_some()
+    }
+
+    pub fn sensor_kind(&self) -> SensorType {
+        match (self.sensor_type, self.base_unit) {
+            (SENSOR_TYPE_TEMPERATURE, _) | (_, 1) | (_, 2) => SensorType::Temperature,
+            (SENSOR_TYPE_FAN, _) | (_, 18) => SensorType::Fan,
+            (SENSOR_TYPE_PHYSICAL_SECURITY, _) => SensorType::Intrusion,
+            (_, 6) => SensorType::Power,
+            (SENSOR_TYPE_VOLTAGE, _) | (_, 4) => SensorType::Voltage,
+            (SENSOR_TYPE_CURRENT, _) | (_, 5) => SensorType::Current,
+            _ => SensorType::Unknown,
+        }
+    }
+
+    pub fn unit(&self) -> &'static str {
+        match self.base_unit {
+            1 => "°C",
+            2 => "°F",
+            4 => "V",
+            5 => "A",
+            6 => "W",
+            7 => "J",
+            18 => "RPM",
+            19 => "Hz",
+            _ => "",
+        }
+    }
+}
+
+/// A Get Sensor Reading response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawReading {
+    pub value: u8,
+    /// Threshold comparison bits (LNC, LC, LNR, UNC, UC, UNR) for threshold
+    /// sensors, asserted state offsets for discrete ones.
+    pub state: u16,
+}
+
+impl RawReading {
+    pub fn threshold_status(&self) -> SensorStatus {
+        if self.state & 0b110110 != 0 {
+            SensorStatus::Critical
+        } else if self.state & 0b001001 != 0 {
+            SensorStatus::Warning
+        } else {
+            SensorStatus::Normal
+        }
+    }
+}
+
+/// A System Event Log record.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelEntry {
+    pub record_id: u16,
+    pub record_type: u8,
+    /// `None` for OEM records without a timestamp and events logged
+    /// before the BMC's clock was set.
+    pub timestamp: Option<DateTime<Utc>>,
+    pub generator_id: u16,
+    pub sensor_type: u8,
+    pub sensor_number: u8,
+    pub event_type: u8,
+    pub deassertion: bool,
+    pub event_data: [u8; 3],
+}
+
+impl SelEntry {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 16 {
+            return None;
+        }
+        let record_type = bytes[2];
+        let seconds = u32::from_le_bytes([bytes[3], bytes[4], bytes[5], bytes[6]]);
+        // Timestamps up to 0x20000000 count from BMC initialisation
+        let timestamp = (record_type < 0xe0 && seconds != 0xffff_ffff && seconds > 0x2000_0000)
+            .then(|| DateTime::from_timestamp(seconds as i64, 0))
+            .flatten();
+        Some(SelEntry {
+            record_id: u16::from_le_bytes([bytes[0], bytes[1]]),
+            record_type,
+            timestamp,
+            generator_id: u16::from_le_bytes([bytes[7], bytes[8]]),
+            sensor_type: bytes[10],
+            sensor_number: bytes[11],
+            event_type: bytes[12] & 0x7f,
+            deassertion: bytes[12] & 0x80 != 0,
+            event_data: [bytes[13], bytes[14], bytes[15]],
+        })
+    }
+
+    /// Describes the event, like `ipmitool sel elist`, and rates it.
+    pub fn describe(&self) -> (String, SensorStatus) {
+        if self.record_type != 0x02 {
+            return (format!("OEM record type {:#04x}", self.record_type), SensorStatus::Unknown);
+        }
+        let offset = self.event_data[0] & 0x0f;
+        let (description, severity) = if self.event_type == EVENT_TYPE_THRESHOLD {
+            const THRESHOLDS: [&str; 12] = [
+                "Lower Non-critical going low", "Lower Non-critical going high",
+                "Lower Critical going low", "Lower Critical going high",
+                "Lower Non-recoverable going low", "Lower Non-recoverable going high",
+                "Upper Non-critical going low", "Upper Non-critical going high",
+                "Upper Critical going low", "Upper Critical going high",
+                "Upper Non-recoverable going low", "Upper Non-recoverable going high",
+            ];
+            let severity = match offset {
+                0 | 1 | 6 | 7 => SensorStatus::Warning,
+                _ => SensorStatus::Critical,
+            };
+            (THRESHOLDS.get(offset as usize).copied().unwrap_or("Threshold event").to_string(), severity)
+        } else if self.event_type == 0x6f {
+            sensor_specific_event(self.sensor_type, offset)
+                .map(|(text, severity)| (text.to_string(), severity))
+                .unwrap_or_else(|| (format!("Event offset {}", offset), SensorStatus::Warning))
+        } else {
+            (format!("Generic event type {:#04x} offset {}", self.event_type, offset), SensorStatus::Warning)
+        };
+
+        if self.deassertion {
+            (format!("{} (deasserted)", description), SensorStatus::Normal)
+        } else {
+            (description, severity)
+        }
+    }
+}
+
+fn sensor_specific_event(sensor_type: u8, offset: u8) -> Option<(&'static str, SensorStatus)> {
+    use SensorStatus::{Critical, Normal, Warning};
+    Some(match (sensor_type, offset) {
+        (0x05, 0) => ("General chassis intrusion", Critical),
+        (0x05, 1) => ("Drive bay intrusion", Critical),
+        (0x05, 2) => ("I/O card area intrusion", Critical),
+        (0x05, 3) => ("Processor area intrusion", Critical),
+        (0x05, 4) => ("LAN leash lost", Warning),
+        (0x07, 0) => ("Processor IERR", Critical),
+        (0x07, 1) => ("Processor thermal trip", Critical),
+        (0x07, 7) => ("Processor presence detected", Normal),
+        (0x07, 10) => ("Processor throttled", Warning),
+        (0x08, 0) => ("Power supply presence detected", Normal),
+        (0x08, 1) => ("Power supply failure detected", Critical),
+        (0x08, 2) => ("Power supply predictive failure", Warning),
+        (0x08, 3) => ("Power supply input lost", Critical),
+        (0x08, 4) => ("Power supply input lost or out of range", Critical),
+        (0x08, 5) => ("Power supply input out of range", Warning),
+        (0x08, 6) => ("Power supply configuration error", Warning),
+        (0x0c, 0) => ("Correctable ECC", Warning),
+        (0x0c, 1) => ("Uncorrectable ECC", Critical),
+        (0x0c, 2) => ("Memory parity error", Critical),
+        (0x0c, 3) => ("Memory scrub failed", Critical),
+        (0x0c, 4) => ("Memory device disabled", Warning),
+        (0x0c, 5) => ("Correctable ECC logging limit reached", Warning),
+        (0x10, 2) => ("Log area reset/cleared", Normal),
+        (0x10, 4) => ("SEL full", Warning),
+        (0x10, 5) => ("SEL almost full", Warning),
+        (0x13, _) => ("Critical interrupt", Critical),
+        (0x1d, _) => ("System boot initiated", Normal),
+        (0x20, 0) => ("Critical stop during OS load", Critical),
+        (0x20, 1) => ("Run-time critical stop", Critical),
+        (0x23, 0) => ("Watchdog timer expired", Warning),
+        (0x23, 1) => ("Watchdog hard reset", Warning),
+        (0x23, 2) => ("Watchdog power down", Warning),
+        (0x23, 3) => ("Watchdog power cycle", Warning),
+        _ => return None,
+    })
+}
+
+pub fn sensor_type_name(sensor_type: u8) -> &'static str {
+    match sensor_type {
+        0x01 => "Temperature",
+        0x02 => "Voltage",
+        0x03 => "Current",
+        0x04 => "Fan",
+        0x05 => "Physical Security",
+        0x06 => "Platform Security",
+        0x07 => "Processor",
+        0x08 => "Power Supply",
+        0x09 => "Power Unit",
+        0x0b => "Other Units",
+        0x0c => "Memory",
+        0x0d => "Drive Slot",
+        0x0f => "System Firmware Progress",
+        0x10 => "Event Logging Disabled",
+        0x12 => "System Event",
+        0x13 => "Critical Interrupt",
+        0x14 => "Button/Switch",
+        0x1d => "System Boot Initiated",
+        0x20 => "OS Critical Stop",
+        0x21 => "Slot/Connector",
+        0x23 => "Watchdog 2",
+        0x25 => "Entity Presence",
+        0x28 => "Management Subsystem Health",
+        0x2b => "Version Change",
+        _ => "Unknown",
+    }
+}
+
+/// SDR repository, sensor and SEL commands over any transport.
+pub struct IpmiClient<T: IpmiTransport> {
+    transport: T,
+}
+
+impl<T: IpmiTransport> IpmiClient<T> {
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    fn command(&mut self, lun: u8, netfn: u8, cmd: u8, data: &[u8]) -> Result<Vec<u8>> {
+        let response = self.transport.request(lun, netfn, cmd, data)?;
+        match response.first() {
+            Some(0) => Ok(response[1..].to_vec()),
+            Some(&code) => Err(CompletionCode(code).into()),
+            None => Err(anyhow!("Empty IPMI response")),
+        }
+    }
+
+    /// Most recent addition and erase timestamps, which change whenever the
+    /// repository does.
+    pub fn sdr_repository_stamp(&mut self) -> Result<(u32, u32)> {
+        let info = self.command(0, NETFN_STORAGE, CMD_GET_SDR_REPOSITORY_INFO, &[])?;
+        if info.len() < 13 {
+            return Err(anyhow!("Short SDR repository info"));
+        }
+        Ok((
+            u32::from_le_bytes([info[5], info[6], info[7], info[8]]),
+            u32::from_le_bytes([info[9], info[10], info[11], info[12]]),
+        ))
+    }
+
+    fn reserve_sdr_repository(&mut self) -> Result<[u8; 2]> {
+        let response = self.command(0, NETFN_STORAGE, CMD_RESERVE_SDR_REPOSITORY, &[])?;
+        match response.as_slice() {
+            [low, high, ..] => Ok([*low, *high]),
+            _ => Err(anyhow!("Short SDR reservation")),
+        }
+    }
+
+    /// Walks the SDR repository and returns its sensor records.
+    pub fn read_sdr_repository(&mut self) -> Result<Vec<SdrRecord>> {
+        let mut reservation = self.reserve_sdr_repository()?;
+        let mut chunk = SDR_CHUNK;
+        let mut records = Vec::new();
+        let mut record_id = 0u16;
+
+        for _ in 0..MAX_RECORDS {
+            match self.read_sdr(reservation, record_id, chunk) {
+                Ok((next_id, bytes)) => {
+                    records.extend(SdrRecord::parse(&bytes));
+                    if next_id == 0xffff || next_id == record_id {
+                        break;
+                    }
+                    record_id = next_id;
+                }
+                // Another client changed the repository mid-walk
+                Err(e) if completion_code(&e) == Some(CC_RESERVATION_CANCELLED) => {
+                    reservation = self.reserve_sdr_repository()?;
+                }
+                Err(e) if completion_code(&e) == Some(CC_CANNOT_RETURN_BYTES) && chunk > 4 => {
+                    chunk /= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(records)
+    }
+
+    fn read_sdr(&mut self, reservation: [u8; 2], record_id: u16, chunk: u8) -> Result<(u16, Vec<u8>)> {
+        let id = record_id.to_le_bytes();
+        let mut read = |offset: u8, count: u8| -> Result<(u16, Vec<u8>)> {
+            let response = self.command(0, NETFN_STORAGE, CMD_GET_SDR, &[reservation[0], reservation[1], id[0], id[1], offset, count])?;
+            if response.len() < 2 {
+                return Err(anyhow!("Short SDR response"));
+            }
+            Ok((u16::from_le_bytes([response[0], response[1]]), response[2..].to_vec()))
+        };
+
+        let (next_id, mut bytes) = read(0, 5)?;
+        if bytes.len() < 5 {
+            return Err(anyhow!("Short SDR header"));
+        }
+        let total = 5 + bytes[4] as usize;
+        while bytes.len() < total {
+            let count = (total - bytes.len()).min(chunk as usize) as u8;
+            let (_, part) = read(bytes.len() as u8, count)?;
+            if part.is_empty() {
+                break;
+            }
+            bytes.extend(part);
+        }
+        Ok((next_id, bytes))
+    }
+
+    /// `None` when the sensor is absent or has no reading right now.
+    pub fn read_sensor(&mut self, record: &SdrRecord) -> Result<Option<RawReading>> {
+        let response = match self.command(record.owner_lun, NETFN_SENSOR, CMD_GET_SENSOR_READING, &[record.number]) {
+            Ok(response) => response,
+            Err(e) if matches!(completion_code(&e), Some(CC_SENSOR_NOT_PRESENT) | Some(CC_DATA_NOT_PRESENT)) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        // Bit 5 of the second byte: reading/state unavailable
+        if response.len() < 2 || response[1] & 0x20 != 0 {
+            return Ok(None);
+        }
+        let state = response.get(2).copied().unwrap_or(0) as u16
+            | (response.get(3).copied().unwrap_or(0) as u16 & 0x7f) << 8;
+        Ok(Some(RawReading { value: response[0], state }))
+    }
+
+    /// Entry count and most recent addition and erase timestamps.
+    pub fn sel_info(&mut self) -> Result<(u16, u32, u32)> {
+        let info = self.command(0, NETFN_STORAGE, CMD_GET_SEL_INFO, &[])?;
+        if info.len() < 13 {
+            return Err(anyhow!("Short SEL info"));
+        }
+        Ok((
+            u16::from_le_bytes([info[1], info[2]]),
+            u32::from_le_bytes([info[5], info[6], info[7], info[8]]),
+            u32::from_le_bytes([info[9], info[10], info[11], info[12]]),
+        ))
+    }
+
+    pub fn read_sel(&mut self) -> Result<Vec<SelEntry>> {
+        let mut entries = Vec::new();
+        let mut record_id = 0u16;
+        for _ in 0..MAX_RECORDS {
+            let id = record_id.to_le_bytes();
+            // A reservation is only required for partial reads
+            let response = match self.command(0, NETFN_STORAGE, CMD_GET_SEL_ENTRY, &[0, 0, id[0], id[1], 0, 0xff]) {
+                Ok(response) => response,
+                Err(e) if completion_code(&e) == Some(CC_SENSOR_NOT_PRESENT) => break,
+                Err(e) => return Err(e),
+            };
+            if response.len() < 18 {
+                break;
+            }
+            entries.extend(SelEntry::parse(&response[2..]));
+            let next_id = u16::from_le_bytes([response[0], response[1]]);
+            if next_id == 0xffff || next_id == record_id {
+                break;
+            }
+            record_id = next_id;
+        }
+        Ok(entries)
+    }
+}
+
+/// Caches the SDR repository and SEL and refreshes readings at BMC pace;
+/// each request over KCS can take tens of milliseconds.
+pub struct IpmiMonitor<T: IpmiTransport> {
+    client: IpmiClient<T>,
+    records: Vec<SdrRecord>,
+    sdr_stamp: Option<(u32, u32)>,
+    readings: Vec<SensorReading>,
+    last_reading: Option<Instant>,
+    events: Vec<HardwareEvent>,
+    sel_stamp: Option<(u16, u32, u32)>,
+    last_sel_check: Option<Instant>,
+}
+
+impl IpmiMonitor<OpenIpmiDevice> {
+    /// Opens the local BMC, if the OpenIPMI driver is loaded.
+    pub fn open_local() -> Result<Self> {
+        Ok(Self::new(OpenIpmiDevice::open()?))
+    }
+}
+
+impl<T: IpmiTransport> IpmiMonitor<T> {
+    pub fn new(transport: T) -> Self {
+        Self {
+            client: IpmiClient::new(transport),
+            records: Vec::new(),
+            sdr_stamp: None,
+            readings: Vec::new(),
+            last_reading: None,
+            events: Vec::new(),
+            sel_stamp: None,
+            last_sel_check: None,
+        }
+    }
+
+    /// Current sensor readings, re-read from the BMC every few seconds.
+    pub fn readings(&mut self) -> Result<Vec<SensorReading>> {
+        if self.last_reading.map(|t| t.elapsed() < READING_INTERVAL).unwrap_or(false) {
+            return Ok(self.readings.clone());
+        }
+        self.last_reading = Some(Instant::now());
+
+        let stamp = self.client.sdr_repository_stamp().ok();
+        if self.records.is_empty() || stamp != self.sdr_stamp {
+            self.records = self.client.read_sdr_repository()?;
+            self.sdr_stamp = stamp;
+        }
+
+        let now = Utc::now();
+        let mut readings = Vec::new();
+        for record in self.records.clone() {
+            // Sensors owned by satellite controllers need bridged requests
+            if record.owner_id != 0x20 {
+                continue;
+            }
+            // A sensor that times out or errors is left out of this round
+            // rather than costing every other one its reading
+            let raw = match self.client.read_sensor(&record) {
+                Ok(Some(raw)) => raw,
+                Ok(None) | Err(_) => continue,
+            };
+
+            let (value, status) = if record.is_threshold() {
+                let conversion = record.conversion.as_ref().expect("threshold sensors have a conversion");
+                match conversion.convert(raw.value) {
+                    Some(value) => (value, raw.threshold_status()),
+                    None => continue,
+                }
+            } else if record.sensor_type == SENSOR_TYPE_PHYSICAL_SECURITY {
+                // Offset 0: general chassis intrusion
+                let intruded = raw.state & 0x01 != 0;
+                (if intruded { 1.0 } else { 0.0 }, if intruded { SensorStatus::Critical } else { SensorStatus::Normal })
+            } else {
+                continue;
+            };
+
+            let convert = |raw: Option<u8>| raw.and_then(|raw| record.conversion.as_ref()?.convert(raw));
+            readings.push(SensorReading {
+                name: format!("ipmi:{}", record.name),
+                sensor_type: record.sensor_kind(),
+                current_value: value,
+                min_value: convert(record.thresholds.lower_critical.or(record.thresholds.lower_non_critical)),
+                max_value: convert(record.thresholds.upper_non_critical),
+                critical_value: convert(record.thresholds.upper_critical),
+                unit: record.unit().to_string(),
+                status,
+                chip: "ipmi".to_string(),
+                label: record.name.clone(),
+                last_updated: now,
+                history: Vec::new(),
+            });
+        }
+
+        self.readings = readings;
+        Ok(self.readings.clone())
+    }
+
+    /// The System Event Log as hardware events, newest first. Re-read only
+    /// when the BMC reports an addition or erase.
+    pub fn events(&mut self) -> Result<Vec<HardwareEvent>> {
+        if self.last_sel_check.map(|t| t.elapsed() < SEL_INTERVAL).unwrap_or(false) {
+            return Ok(self.events.clone());
+        }
+        self.last_sel_check = Some(Instant::now());
+
+        let stamp = self.client.sel_info()?;
+        if Some(stamp) == self.sel_stamp {
+            return Ok(self.events.clone());
+        }
+        self.sel_stamp = Some(stamp);
+
+        let names: HashMap<u8, &str> = self.records.iter()
+            .filter(|r| r.owner_id == 0x20)
+            .map(|r| (r.number, r.name.as_str()))
+            .collect();
+        let mut events: Vec<HardwareEvent> = self.client.read_sel()?
+            .into_iter()
+            .map(|entry| {
+                let (message, severity) = entry.describe();
+                let sensor = names.get(&entry.sensor_number)
+                    .map(|name| name.to_string())
+                    .unwrap_or_else(|| format!("{} #{:#04x}", sensor_type_name(entry.sensor_type), entry.sensor_number));
+                HardwareEvent {
+                    id: entry.record_id as u64,
+                    timestamp: entry.timestamp,
+                    source: "ipmi-sel".to_string(),
+                    sensor,
+                    severity,
+                    message,
+                }
+            })
+            .collect();
+        events.reverse();
+
+        self.events = events;
+        Ok(self.events.clone())
+    }
+}
+
+/// Polls the local BMC on a thread of its own and publishes what it reads;
+/// a single request can block for the full response timeout, and reading
+/// the SDR at startup takes hundreds of them.
+pub struct IpmiPoller {
+    receiver: Receiver<(Vec<SensorReading>, Vec<HardwareEvent>)>,
+    readings: Vec<SensorReading>,
+    events: Vec<HardwareEvent>,
+}
+
+impl IpmiPoller {
+    /// Starts polling the local BMC, if the OpenIPMI driver is loaded.
+    pub fn open_local() -> Result<Self> {
+        Ok(Self::spawn(IpmiMonitor::open_local()?))
+    }
+
+    pub fn spawn<T: IpmiTransport + Send + 'static>(mut monitor: IpmiMonitor<T>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || loop {
+            let readings = monitor.readings().unwrap_or_default();
+            let events = monitor.events().unwrap_or_default();
+            if sender.send((readings, events)).is_err() {
+                break;
+            }
+            thread::sleep(READING_INTERVAL);
+        });
+
+        Self {
+            receiver,
+            readings: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    /// The most recently published readings; empty until the first poll
+    /// completes.
+    pub fn readings(&mut self) -> &[SensorReading] {
+        self.drain();
+        &self.readings
+    }
+
+    /// The most recently published SEL events, newest first.
+    pub fn events(&mut self) -> &[HardwareEvent] {
+        self.drain();
+        &self.events
+    }
+
+    fn drain(&mut self) {
+        if let Some((readings, events)) = self.receiver.try_iter().last() {
+            self.readings = readings;
+            self.events = events;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A BMC with an SDR repository, sensor readings and a SEL, answering
+    /// like a real one, including the partial-read limits.
+    struct MockBmc {
+        sdrs: Vec<Vec<u8>>,
+        readings: HashMap<u8, Vec<u8>>,
+        sel: Vec<[u8; 16]>,
+        max_read: usize,
+        requests: usize,
+        failing: Vec<u8>,
+    }
+
+    impl IpmiTransport for MockBmc {
+        fn request(&mut self, _lun: u8, netfn: u8, cmd: u8, data: &[u8]) -> Result<Vec<u8>> {
+            self.requests += 1;
+            Ok(match (netfn, cmd) {
+                (NETFN_STORAGE, CMD_GET_SDR_REPOSITORY_INFO) => vec![0, 0x51, self.sdrs.len() as u8, 0, 0xff, 0xff, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+                (NETFN_STORAGE, CMD_RESERVE_SDR_REPOSITORY) => vec![0, 0x34, 0x12],
+                (NETFN_STORAGE, CMD_GET_SDR) => {
+                    assert_eq!(&data[..2], &[0x34, 0x12]);
+                    let id = u16::from_le_bytes([data[2], data[3]]) as usize;
+                    let (offset, count) = (data[4] as usize, data[5] as usize);
+                    if count > self.max_read {
+                        return Ok(vec![CC_CANNOT_RETURN_BYTES]);
+                    }
+                    let record = &self.sdrs[id];
+                    let next = if id + 1 < self.sdrs.len() { id as u16 + 1 } else { 0xffff };
+                    let mut response = vec![0];
+                    response.extend(next.to_le_bytes());
+                    response.extend(&record[offset..(offset + count).min(record.len())]);
+                    response
+                }
+                (NETFN_SENSOR, CMD_GET_SENSOR_READING) if self.failing.contains(&data[0]) => vec![0xc3],
+                (NETFN_SENSOR, CMD_GET_SENSOR_READING) => match self.readings.get(&data[0]) {
+                    Some(reading) => [vec![0], reading.clone()].concat(),
+                    None => vec![CC_SENSOR_NOT_PRESENT],
+                },
+                (NETFN_STORAGE, CMD_GET_SEL_INFO) => {
+                    let mut response = vec![0, 0x51];
+                    response.extend((self.sel.len() as u16).to_le_bytes());
+                    response.extend([0, 0, 0x10, 0, 0, 0, 0, 0, 0, 0, 0]);
+                    response
+                }
+                (NETFN_STORAGE, CMD_GET_SEL_ENTRY) => {
+                    let id = u16::from_le_bytes([data[2], data[3]]) as usize;
+                    let next = if id + 1 < self.sel.len() { id as u16 + 1 } else { 0xffff };
+                    let mut response = vec![0];
+                    response.extend(next.to_le_bytes());
+                    response.extend(self.sel[id]);
+                    response
+                }
+                _ => vec![0xc1],
+            })
+        }
+    }
+
+    /// Thresholds are `[LC, LNC, UNC, UC]`; `Some` ones are marked readable.
+    fn full_sdr(id: u16, number: u8, sensor_type: u8, unit: u8, m: i16, name: &str, thresholds: [Option<u8>; 4]) -> Vec<u8> {
+        let mut record = vec![0u8; 48];
+        record[0..2].copy_from_slice(&id.to_le_bytes());
+        record[2] = 0x51;
+        record[3] = 0x01;
+        record[5] = 0x20;
+        record[7] = number;
+        record[12] = sensor_type;
+        record[13] = EVENT_TYPE_THRESHOLD;
+        for (bit, threshold) in [1, 0, 3, 4].iter().zip(thresholds) {
+            if threshold.is_some() {
+                record[18] |= 1 << bit;
+            }
+        }
+        record[21] = unit;
+        record[24] = (m & 0xff) as u8;
+        record[25] = ((m >> 2) & 0xc0) as u8;
+        for (offset, threshold) in [40, 41, 38, 37].iter().zip(thresholds) {
+            record[*offset] = threshold.unwrap_or(0);
+        }
+        record[47] = 0xc0 | name.len() as u8;
+        record.extend(name.bytes());
+        record[4] = (record.len() - 5) as u8;
+        record
+    }
+
+    fn intrusion_sdr(id: u16, number: u8) -> Vec<u8> {
+        let name = "Chassis Intru";
+        let mut record = vec![0u8; 32];
+        record[0..2].copy_from_slice(&id.to_le_bytes());
+        record[2] = 0x51;
+        record[3] = 0x02;
+        record[5] = 0x20;
+        record[7] = number;
+        record[12] = SENSOR_TYPE_PHYSICAL_SECURITY;
+        record[13] = 0x6f;
+        record[31] = 0xc0 | name.len() as u8;
+        record.extend(name.bytes());
+        record[4] = (record.len() - 5) as u8;
+        record
+    }
+
+    fn sel_record(id: u16, timestamp: u32, sensor_type: u8, number: u8, event: u8, data1: u8) -> [u8; 16] {
+        let mut record = [0u8; 16];
+        record[0..2].copy_from_slice(&id.to_le_bytes());
+        record[2] = 0x02;
+        record[3..7].copy_from_slice(&timestamp.to_le_bytes());
+        record[7] = 0x20;
+        record[9] = 0x04;
+        record[10] = sensor_type;
+        record[11] = number;
+        record[12] = event;
+        record[13] = data1;
+        record
+    }
+
+    fn mock_bmc() -> MockBmc {
+        MockBmc {
+            sdrs: vec![
+                full_sdr(0, 1, SENSOR_TYPE_TEMPERATURE, 1, 1, "Inlet Temp", [None, None, Some(40), Some(45)]),
+                full_sdr(1, 2, 0x08, 6, 2, "PSU1 Power", [None; 4]),
+                full_sdr(2, 3, SENSOR_TYPE_FAN, 18, 100, "FAN1", [Some(5), None, None, None]),
+                intrusion_sdr(3, 4),
+                full_sdr(4, 5, SENSOR_TYPE_VOLTAGE, 4, 1, "Absent", [None; 4]),
+            ],
+            readings: HashMap::from([
+                (1, vec![47, 0xc0, 0b011000]),
+                (2, vec![110, 0xc0, 0]),
+                (3, vec![48, 0xc0, 0]),
+                (4, vec![0, 0xc0, 0x01, 0x80]),
+            ]),
+            sel: vec![
+                sel_record(1, 1_700_000_000, SENSOR_TYPE_TEMPERATURE, 1, EVENT_TYPE_THRESHOLD, 0x59),
+                sel_record(2, 1_700_000_100, 0x08, 0x60, 0x6f, 0x01),
+                sel_record(3, 1_700_000_200, SENSOR_TYPE_TEMPERATURE, 1, 0x80 | EVENT_TYPE_THRESHOLD, 0x59),
+                sel_record(4, 0x10, 0x10, 0x61, 0x6f, 0x02),
+            ],
+            max_read: 8,
+            requests: 0,
+            failing: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_sdr_and_readings() {
+        let mut monitor = IpmiMonitor::new(mock_bmc());
+        let readings = monitor.readings().unwrap();
+        assert_eq!(monitor.records.len(), 5);
+        assert_eq!(readings.len(), 4);
+
+        let inlet = &readings[0];
+        assert_eq!(inlet.label, "Inlet Temp");
+        assert_eq!(inlet.sensor_type, SensorType::Temperature);
+        assert_eq!(inlet.current_value, 47.0);
+        assert_eq!((inlet.max_value, inlet.critical_value), (Some(40.0), Some(45.0)));
+        assert_eq!(inlet.status, SensorStatus::Critical);
+
+        assert_eq!(readings[1].sensor_type, SensorType::Power);
+        assert_eq!((readings[1].current_value, readings[1].unit.as_str()), (220.0, "W"));
+        assert_eq!(readings[2].current_value, 4800.0);
+        assert_eq!(readings[2].min_value, Some(500.0));
+        assert_eq!(readings[2].status, SensorStatus::Normal);
+        assert_eq!(readings[3].sensor_type, SensorType::Intrusion);
+        assert_eq!(readings[3].status, SensorStatus::Critical);
+
+        // Cached until the next interval
+        let requests = monitor.client.transport.requests;
+        monitor.readings().unwrap();
+        assert_eq!(monitor.client.transport.requests, requests);
+    }
+
+    #[test]
+    fn test_failing_sensor_is_skipped() {
+        let mut bmc = mock_bmc();
+        bmc.failing.push(1);
+        let mut monitor = IpmiMonitor::new(bmc);
+        let readings = monitor.readings().unwrap();
+        assert_eq!(readings.len(), 3);
+        assert_eq!(readings[0].label, "PSU1 Power");
+    }
+
+    #[test]
+    fn test_poller_publishes_off_thread() {
+        let mut poller = IpmiPoller::spawn(IpmiMonitor::new(mock_bmc()));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while poller.readings().is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(poller.readings().len(), 4);
+        assert_eq!(poller.events().len(), 4);
+    }
+
+    #[test]
+    fn test_sel_events() {
+        let mut monitor = IpmiMonitor::new(mock_bmc());
+        monitor.readings().unwrap();
+        let events = monitor.events().unwrap();
+        assert_eq!(events.len(), 4);
+
+        // Newest first
+        assert_eq!(events[0].message, "Log area reset/cleared");
+        assert_eq!(events[0].timestamp, None);
+        assert_eq!(events[1].message, "Upper Critical going high (deasserted)");
+        assert_eq!(events[1].severity, SensorStatus::Normal);
+        assert_eq!(events[2].sensor, "Power Supply #0x60");
+        assert_eq!(events[2].message, "Power supply failure detected");
+        assert_eq!(events[2].severity, SensorStatus::Critical);
+        assert_eq!(events[3].sensor, "Inlet Temp");
+        assert_eq!(events[3].timestamp.unwrap().timestamp(), 1_700_000_000);
+    }
+
+    #[test]
+    fn test_sensor_conversion() {
+        let conversion = SensorConversion { m: -3, b: 5, b_exp: 1, r_exp: -1, analog_format: 2, linearization: 0 };
+        // ((-3 * -2) + 5 * 10) / 10
+        assert!((conversion.convert(0xfe).unwrap() - 5.6).abs() < 1e-9);
+        let record = SdrRecord::parse(&full_sdr(7, 1, 1, 1, -200, "T", [None; 4])).unwrap();
+        assert_eq!(record.conversion.unwrap().m, -200);
+        assert_eq!(record.record_id, 7);
+        assert_eq!(IPMICTL_SEND_COMMAND, 0x8028_690d);
+        assert_eq!(IPMICTL_RECEIVE_MSG_TRUNC, 0xc030_690b);
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// One journal record, reduced to what the log panes display.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Syslog priority, 0 (emerg) through 7 (debug).
+    pub priority: u8,
+    pub identifier: String,
+    pub pid: Option<u32>,
+    pub message: String,
+    /// Position in the journal, for resuming with `--after-cursor`.
+    pub cursor: Option<String>,
+}
+
+impl JournalEntry {
+    /// Formats the entry like `journalctl -o short-iso`.
+    pub fn format_line(&self) -> String {
+        let timestamp = self.timestamp
+            .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        match self.pid {
+            Some(pid) => format!("{} {}[{}]: {}", timestamp, self.identifier, pid, self.message),
+            None => format!("{} {}: {}", timestamp, self.identifier, self.message),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.priority <= 3
+    }
+
+    pub fn is_warning(&self) -> bool {
+        self.priority == 4
+    }
+}
+
+/// A live `journalctl --follow` stream for one unit.
+pub struct JournalFollower {
+    receiver: Receiver<JournalEntry>,
+    child: Child,
+}
+
+impl JournalFollower {
+    /// Follows everything logged by `unit` (`_SYSTEMD_UNIT=`) together with
+    /// systemd's own messages about it (`UNIT=`), such as exit statuses,
+    /// starting with the last `lines` entries.
+    pub fn follow_unit(unit: &str, lines: usize) -> Result<Self> {
+        let mut child = Command::new("journalctl")
+            .args(["--follow", "--no-pager", "--output=json", "--lines"])
+            .arg(lines.to_string())
+            .arg(format!("_SYSTEMD_UNIT={}", unit))
+            .arg("+")
+            .arg(format!("UNIT={}", unit))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow!("Failed to run journalctl: {}", e))?;
+
+        let stdout = child.stdout.take().ok_or_else(|| anyhow!("journalctl has no stdout"))?;
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if let Some(entry) = parse_journal_json(&line) {
+                    if sender.send(entry).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(Self { receiver, child })
+    }
+
+    /// Returns the entries received since the last call.
+    pub fn drain(&self) -> Vec<JournalEntry> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Drop for JournalFollower {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Parses one line of `journalctl --output=json`. MESSAGE is a string,
+/// an array of bytes when it is not valid UTF-8, or null when too large.
+pub fn parse_journal_json(line: &str) -> Option<JournalEntry> {
+    let record: serde_json::Value = serde_json::from_str(line).ok()?;
+    let field = |key: &str| record.get(key).and_then(|v| v.as_str());
+
+    let message = match record.get("MESSAGE") {
+        Some(serde_json::Value::String(text)) => text.clone(),
+        Some(serde_json::Value::Array(bytes)) => {
+            let bytes: Vec<u8> = bytes.iter().filter_map(|b| b.as_u64()).map(|b| b as u8).collect();
+            String::from_utf8_lossy(&bytes).to_string()
+        }
+        _ => "[message omitted]".to_string(),
+    };
+
+    Some(JournalEntry {
+        timestamp: field("__REALTIME_TIMESTAMP")
+            .and_then(|usec| usec.parse::<i64>().ok())
+            .and_then(DateTime::from_timestamp_micros),
+        priority: field("PRIORITY").and_then(|p| p.parse().ok()).unwrap_or(6),
+        identifier: field("SYSLOG_IDENTIFIER")
+            .or_else(|| field("_COMM"))
+            .unwrap_or("unknown")
+            .to_string(),
+        pid: field("_PID").and_then(|pid| pid.parse().ok()),
+        message: message.trim_end().to_string(),
+        cursor: field("__CURSOR").map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_journal_json() {
+        let line = r#"{"__REALTIME_TIMESTAMP":"1700000000000000","PRIORITY":"3","SYSLOG_IDENTIFIER":"nginx","_PID":"4242","_SYSTEMD_UNIT":"nginx.service","MESSAGE":"bind() to 0.0.0.0:80 failed (98: Address already in use)"}"#;
+        let entry = parse_journal_json(line).unwrap();
+        assert_eq!(entry.timestamp.unwrap().timestamp(), 1_700_000_000);
+        assert!(entry.is_error());
+        assert_eq!(entry.identifier, "nginx");
+        assert_eq!(entry.pid, Some(4242));
+        assert!(entry.format_line().ends_with("nginx[4242]: bind() to 0.0.0.0:80 failed (98: Address already in use)"));
+
+        // systemd's own messages about the unit carry no _PID of the service
+        let line = r#"{"PRIORITY":"4","_COMM":"systemd","MESSAGE":[110,103,105,110,120,46,115,101,114,118,105,99,101,58,32,70,97,105,108,101,100]}"#;
+        let entry = parse_journal_json(line).unwrap();
+        assert!(entry.is_warning());
+        assert_eq!(entry.identifier, "systemd");
+        assert_eq!(entry.message, "nginx.service: Failed");
+
+        let entry = parse_journal_json(r#"{"MESSAGE":null}"#).unwrap();
+        assert_eq!(entry.priority, 6);
+        assert_eq!(entry.message, "[message omitted]");
+
+        assert!(parse_journal_json("not json").is_none());
+    }
+}
@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use super::cgroups::container_id_from_cgroup_name;
+use super::cri::{CriClient, CriContainer};
+use super::ProcessInfo;
+
+const KUBEPODS_CGROUPS: &[&str] = &[
+    "/sys/fs/cgroup/kubepods.slice",
+    "/sys/fs/cgroup/kubepods",
+    "/sys/fs/cgroup/cpu/kubepods.slice",
+    "/sys/fs/cgroup/cpu/kubepods",
+];
+const CONTAINER_LOG_DIR: &str = "/var/log/containers";
+const MIN_CRI_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The pod a process belongs to, resolved from its kubepods cgroup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PodRef {
+    pub namespace: String,
+    pub pod_name: String,
+    pub pod_uid: String,
+    pub container_name: String,
+    pub container_id: String,
+    pub qos_class: String,
+}
+
+impl PodRef {
+    pub fn qualified_name(&self) -> String {
+        format!("{}/{}", self.namespace, self.pod_name)
+    }
+}
+
+/// What can be learned from a kubepods cgroup path alone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KubeCgroupInfo {
+    pub qos_class: String,
+    pub pod_uid: String,
+    pub container_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodInfo {
+    pub namespace: String,
+    pub name: String,
+    pub uid: String,
+    pub qos_class: String,
+    pub containers: Vec<String>,
+    pub process_count: usize,
+    pub cpu_usage: f32,
+    pub memory_usage: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PodMetrics {
+    pub pods: Vec<PodInfo>,
+    pub total_pods: usize,
+    pub total_cpu_usage: f32,
+    pub total_memory_usage: u64,
+    pub cri_socket: Option<String>,
+}
+
+pub struct KubernetesMonitor {
+    is_kubernetes_node: bool,
+    cri_client: Option<CriClient>,
+    containers: HashMap<String, CriContainer>,
+    last_cri_refresh: Option<Instant>,
+}
+
+impl Default for KubernetesMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KubernetesMonitor {
+    pub fn new() -> Self {
+        let is_kubernetes_node = KUBEPODS_CGROUPS.iter().any(|path| Path::new(path).exists());
+
+        Self {
+            is_kubernetes_node,
+            cri_client: if is_kubernetes_node { CriClient::discover() } else { None },
+            containers: HashMap::new(),
+            last_cri_refresh: None,
+        }
+    }
+
+    pub fn is_kubernetes_node(&self) -> bool {
+        self.is_kubernetes_node
+    }
+
+    /// Sets `pod` on every process that runs inside a Kubernetes pod.
+    pub fn attribute_processes(&mut self, processes: &mut [ProcessInfo]) {
+        if !self.is_kubernetes_node {
+            return;
+        }
+
+        for process in processes.iter_mut() {
+            let info = match process.cgroup_path.as_deref().and_then(parse_kubepods_cgroup) {
+                Some(info) => info,
+                None => continue,
+            };
+
+            process.pod = Some(self.resolve_pod(info));
+        }
+    }
+
+    fn resolve_pod(&mut self, info: KubeCgroupInfo) -> PodRef {
+        let container_id = info.container_id.clone().unwrap_or_default();
+        let container = if container_id.is_empty() {
+            None
+        } else {
+            self.lookup_container(&container_id)
+        };
+
+        match container {
+            Some(container) => PodRef {
+                namespace: container.pod_namespace().unwrap_or("unknown").to_string(),
+                pod_name: container.pod_name().unwrap_or("unknown").to_string(),
+                pod_uid: container.pod_uid().map(|s| s.to_string()).unwrap_or(info.pod_uid),
+                container_name: container.container_name().to_string(),
+                container_id,
+                qos_class: info.qos_class,
+            },
+            None => PodRef {
+                namespace: "unknown".to_string(),
+                pod_name: format!("pod{}", info.pod_uid),
+                pod_uid: info.pod_uid,
+                container_name: "unknown".to_string(),
+                container_id,
+                qos_class: info.qos_class,
+            },
+        }
+    }
+
+    /// Looks a container up in the CRI cache, refreshing it on a miss at
+    /// most every few seconds. Falls back to the kubelet's log symlinks
+    /// when the CRI socket is unavailable or not readable.
+    fn lookup_container(&mut self, container_id: &str) -> Option<CriContainer> {
+        if let Some(container) = self.containers.get(container_id) {
+            return Some(container.clone());
+        }
+
+        let refresh_due = self.last_cri_refresh
+            .map(|t| t.elapsed() >= MIN_CRI_REFRESH_INTERVAL)
+            .unwrap_or(true);
+        if refresh_due {
+            self.last_cri_refresh = Some(Instant::now());
+            self.refresh_containers();
+        }
+
+        self.containers.get(container_id).cloned()
+    }
+
+    fn refresh_containers(&mut self) {
+        if let Some(client) = &self.cri_client {
+            if let Ok(containers) = client.list_containers() {
+                self.containers = containers.into_iter()
+                    .map(|c| (c.id.clone(), c))
+                    .collect();
+                return;
+            }
+        }
+
+        self.containers = read_container_log_links(Path::new(CONTAINER_LOG_DIR));
+    }
+
+    /// Rolls attributed processes up into per-pod resource usage.
+    pub fn get_pod_metrics(&self, processes: &[ProcessInfo]) -> PodMetrics {
+        let mut pods: HashMap<String, PodInfo> = HashMap::new();
+
+        for process in processes {
+            let pod = match &process.pod {
+                Some(pod) => pod,
+                None => continue,
+            };
+
+            let entry = pods.entry(pod.pod_uid.clone()).or_insert_with(|| PodInfo {
+                namespace: pod.namespace.clone(),
+                name: pod.pod_name.clone(),
+                uid: pod.pod_uid.clone(),
+                qos_class: pod.qos_class.clone(),
+                containers: Vec::new(),
+                process_count: 0,
+                cpu_usage: 0.0,
+                memory_usage: 0,
+            });
+
+            if !entry.containers.contains(&pod.container_name) {
+                entry.containers.push(pod.container_name.clone());
+            }
+            entry.process_count += 1;
+            entry.cpu_usage += process.cpu_usage;
+            entry.memory_usage += process.memory_usage;
+        }
+
+        let mut pods: Vec<PodInfo> = pods.into_values().collect();
+        pods.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+
+        PodMetrics {
+            total_pods: pods.len(),
+            total_cpu_usage: pods.iter().map(|p| p.cpu_usage).sum(),
+            total_memory_usage: pods.iter().map(|p| p.memory_usage).sum(),
+            pods,
+            cri_socket: self.cri_client.as_ref().map(|c| c.socket_path().display().to_string()),
+        }
+    }
+}
+
+/// Parses a kubepods cgroup path as laid out by the kubelet with either
+/// the systemd driver (`/kubepods.slice/kubepods-burstable.slice/
+/// kubepods-burstable-pod<uid>.slice/cri-containerd-<id>.scope`) or the
+/// cgroupfs driver (`/kubepods/burstable/pod<uid>/<id>`).
+pub fn parse_kubepods_cgroup(path: &str) -> Option<KubeCgroupInfo> {
+    if !path.contains("kubepods") {
+        return None;
+    }
+
+    let mut qos_class = "guaranteed".to_string();
+    let mut pod_uid = None;
+    let mut container_id = None;
+
+    for segment in path.split('/').filter(|s| !s.is_empty()) {
+        let stem = segment.strip_suffix(".slice").unwrap_or(segment);
+
+        if pod_uid.is_some() {
+            if let Some(id) = container_id_from_cgroup_name(segment) {
+                container_id = Some(id.to_string());
+            }
+            continue;
+        }
+
+        let uid = if stem.starts_with("kubepods") {
+            stem.find("-pod").map(|pos| &stem[pos + 4..])
+        } else {
+            stem.strip_prefix("pod")
+        };
+        if let Some(uid) = uid {
+            pod_uid = Some(uid.replace('_', "-"));
+            continue;
+        }
+
+        for class in ["burstable", "besteffort"] {
+            if stem == class || stem.ends_with(&format!("-{}", class)) {
+                qos_class = class.to_string();
+            }
+        }
+    }
+
+    pod_uid.map(|pod_uid| KubeCgroupInfo {
+        qos_class,
+        pod_uid,
+        container_id,
+    })
+}
+
+/// Builds container records from the kubelet's `/var/log/containers`
+/// symlinks, named `<pod>_<namespace>_<container>-<container id>.log`.
+fn read_container_log_links(dir: &Path) -> HashMap<String, CriContainer> {
+    let mut containers = HashMap::new();
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return containers,
+    };
+
+    for entry in entries.flatten() {
+        let file_name = entry.file_name();
+        if let Some(container) = parse_container_log_name(&file_name.to_string_lossy()) {
+            containers.insert(container.id.clone(), container);
+        }
+    }
+
+    containers
+}
+
+fn parse_container_log_name(file_name: &str) -> Option<CriContainer> {
+    let stem = file_name.strip_suffix(".log")?;
+    let (rest, id) = stem.rsplit_once('-')?;
+    if id.len() != 64 {
+        return None;
+    }
+
+    let mut parts = rest.splitn(3, '_');
+    let pod = parts.next()?;
+    let namespace = parts.next()?;
+    let container = parts.next()?;
+
+    let mut labels = HashMap::new();
+    labels.insert("io.kubernetes.pod.name".to_string(), pod.to_string());
+    labels.insert("io.kubernetes.pod.namespace".to_string(), namespace.to_string());
+    labels.insert("io.kubernetes.container.name".to_string(), container.to_string());
+
+    Some(CriContainer {
+        id: id.to_string(),
+        pod_sandbox_id: String::new(),
+        name: container.to_string(),
+        labels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTAINER_ID: &str = "0b1c2d3e4f5a6b7c8d9e0f1a2b3c4d5e6f7a8b9c0d1e2f3a4b5c6d7e8f9a0b1c";
+
+    #[test]
+    fn test_parse_systemd_driver_path() {
+        let path = format!(
+            "/kubepods.slice/kubepods-burstable.slice/kubepods-burstable-pod6f1c2d3e_4a5b_6c7d_8e9f_0a1b2c3d4e5f.slice/cri-containerd-{}.scope",
+            CONTAINER_ID
+        );
+        let info = parse_kubepods_cgroup(&path).unwrap();
+        assert_eq!(info.qos_class, "burstable");
+        assert_eq!(info.pod_uid, "6f1c2d3e-4a5b-6c7d-8e9f-0a1b2c3d4e5f");
+        assert_eq!(info.container_id.as_deref(), Some(CONTAINER_ID));
+    }
+
+    #[test]
+    fn test_parse_guaranteed_and_cgroupfs_paths() {
+        let guaranteed = format!("/kubepods.slice/kubepods-pod1234.slice/crio-{}.scope", CONTAINER_ID);
+        let info = parse_kubepods_cgroup(&guaranteed).unwrap();
+        assert_eq!(info.qos_class, "guaranteed");
+        assert_eq!(info.pod_uid, "1234");
+
+        let cgroupfs = format!("/kubepods/besteffort/pod5678/{}", CONTAINER_ID);
+        let info = parse_kubepods_cgroup(&cgroupfs).unwrap();
+        assert_eq!(info.qos_class, "besteffort");
+        assert_eq!(info.pod_uid, "5678");
+        assert_eq!(info.container_id.as_deref(), Some(CONTAINER_ID));
+
+        // The pause container's parent pod cgroup has no container yet
+        let pod_only = "/kubepods/burstable/pod5678";
+        assert_eq!(parse_kubepods_cgroup(pod_only).unwrap().container_id, None);
+
+        assert!(parse_kubepods_cgroup("/system.slice/sshd.service").is_none());
+    }
+
+    #[test]
+    fn test_parse_container_log_name() {
+        let name = format!("web-7d9f8c_prod_nginx-{}.log", CONTAINER_ID);
+        let container = parse_container_log_name(&name).unwrap();
+        assert_eq!(container.id, CONTAINER_ID);
+        assert_eq!(container.pod_name(), Some("web-7d9f8c"));
+        assert_eq!(container.pod_namespace(), Some("prod"));
+        assert_eq!(container.container_name(), "nginx");
+
+        assert!(parse_container_log_name("not-a-container.log").is_none());
+    }
+}
@@ -0,0 +1,303 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// A process's memory from `/proc/<pid>/smaps_rollup`, in bytes.
+///
+/// RSS counts every resident page in full, so a page shared by ten
+/// processes is counted ten times. PSS divides shared pages between their
+/// users and sums to the real total; USS is what would be freed if the
+/// process exited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessMemoryDetail {
+    pub rss: u64,
+    pub pss: u64,
+    /// PSS split by backing; zero before Linux 5.9.
+    pub pss_anon: u64,
+    pub pss_file: u64,
+    pub pss_shmem: u64,
+    pub shared_clean: u64,
+    pub shared_dirty: u64,
+    pub private_clean: u64,
+    pub private_dirty: u64,
+    /// Resident anonymous memory: heap, stacks and private mappings.
+    pub anonymous: u64,
+    pub swap: u64,
+    pub swap_pss: u64,
+}
+
+impl ProcessMemoryDetail {
+    pub fn read(pid: u32) -> Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(format!("/proc/{}/smaps_rollup", pid))?))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let fields = parse_kb_fields(content);
+        let field = |name: &str| fields.get(name).copied().unwrap_or(0);
+        Self {
+            rss: field("Rss"),
+            pss: field("Pss"),
+            pss_anon: field("Pss_Anon"),
+            pss_file: field("Pss_File"),
+            pss_shmem: field("Pss_Shmem"),
+            shared_clean: field("Shared_Clean"),
+            shared_dirty: field("Shared_Dirty"),
+            private_clean: field("Private_Clean"),
+            private_dirty: field("Private_Dirty"),
+            anonymous: field("Anonymous"),
+            swap: field("Swap"),
+            swap_pss: field("SwapPss"),
+        }
+    }
+
+    pub fn uss(&self) -> u64 {
+        self.private_clean + self.private_dirty
+    }
+
+    pub fn shared(&self) -> u64 {
+        self.shared_clean + self.shared_dirty
+    }
+
+    /// Resident pages backed by files, including shared memory.
+    pub fn file_backed(&self) -> u64 {
+        self.rss.saturating_sub(self.anonymous)
+    }
+}
+
+/// Per-process smaps_rollup results. Reading smaps_rollup walks the page
+/// tables of every mapping under the mmap lock, so each PID is re-read
+/// only once its entry is older than the caller's interval.
+#[derive(Debug, Default)]
+pub struct MemoryDetailCache {
+    entries: HashMap<u32, (Instant, Option<ProcessMemoryDetail>)>,
+}
+
+impl MemoryDetailCache {
+    pub fn get(&mut self, pid: u32, interval: Duration, now: Instant) -> Option<ProcessMemoryDetail> {
+        self.get_or_read(pid, interval, now, || ProcessMemoryDetail::read(pid).ok())
+    }
+
+    fn get_or_read(
+        &mut self,
+        pid: u32,
+        interval: Duration,
+        now: Instant,
+        read: impl FnOnce() -> Option<ProcessMemoryDetail>,
+    ) -> Option<ProcessMemoryDetail> {
+        match self.entries.get(&pid) {
+            Some((read_at, detail)) if now.duration_since(*read_at) < interval => *detail,
+            _ => {
+                let detail = read();
+                self.entries.insert(pid, (now, detail));
+                detail
+            }
+        }
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(u32) -> bool) {
+        self.entries.retain(|pid, _| keep(*pid));
+    }
+}
+
+/// The system-wide breakdown from `/proc/meminfo`, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub buffers: u64,
+    /// Page cache, excluding shmem, which meminfo counts in `Cached`.
+    pub cached: u64,
+    pub swap_cached: u64,
+    pub anon: u64,
+    pub mapped: u64,
+    pub shmem: u64,
+    pub slab_reclaimable: u64,
+    pub slab_unreclaimable: u64,
+    pub kernel_stack: u64,
+    pub page_tables: u64,
+    pub dirty: u64,
+    pub writeback: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+    pub hugepages_total: u64,
+    pub hugepages_free: u64,
+    pub hugepage_size: u64,
+}
+
+impl MemInfo {
+    pub fn read() -> Result<Self> {
+        Self::read_from(Path::new("/proc/meminfo"))
+    }
+
+    pub fn read_from(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn parse(content: &str) -> Self {
+        let fields = parse_kb_fields(content);
+        let field = |name: &str| fields.get(name).copied().unwrap_or(0);
+        // HugePages_* are page counts, not kB
+        let pages = |name: &str| fields.get(name).map(|bytes| bytes / 1024).unwrap_or(0);
+        let shmem = field("Shmem");
+        Self {
+            total: field("MemTotal"),
+            free: field("MemFree"),
+            available: field("MemAvailable"),
+            buffers: field("Buffers"),
+            cached: field("Cached").saturating_sub(shmem),
+            swap_cached: field("SwapCached"),
+            anon: field("AnonPages"),
+            mapped: field("Mapped"),
+            shmem,
+            slab_reclaimable: field("SReclaimable"),
+            slab_unreclaimable: field("SUnreclaim"),
+            kernel_stack: field("KernelStack"),
+            page_tables: field("PageTables"),
+            dirty: field("Dirty"),
+            writeback: field("Writeback"),
+            swap_total: field("SwapTotal"),
+            swap_free: field("SwapFree"),
+            hugepages_total: pages("HugePages_Total"),
+            hugepages_free: pages("HugePages_Free"),
+            hugepage_size: field("Hugepagesize"),
+        }
+    }
+
+    pub fn slab(&self) -> u64 {
+        self.slab_reclaimable + self.slab_unreclaimable
+    }
+
+    /// Memory reserved for the huge page pool, used or not.
+    pub fn hugepages_reserved(&self) -> u64 {
+        self.hugepages_total * self.hugepage_size
+    }
+
+    /// Where physical memory went, largest categories first; what isn't
+    /// accounted for by meminfo is reported as other kernel memory.
+    pub fn composition(&self) -> Vec<(&'static str, u64)> {
+        let mut parts = vec![
+            ("Anonymous", self.anon),
+            ("Page cache", self.cached),
+            ("Buffers", self.buffers),
+            ("Shared memory", self.shmem),
+            ("Slab (reclaimable)", self.slab_reclaimable),
+            ("Slab (unreclaimable)", self.slab_unreclaimable),
+            ("Kernel stacks", self.kernel_stack),
+            ("Page tables", self.page_tables),
+            ("Huge pages", self.hugepages_reserved()),
+        ];
+        let accounted: u64 = parts.iter().map(|(_, bytes)| bytes).sum::<u64>() + self.free;
+        parts.push(("Other kernel", self.total.saturating_sub(accounted)));
+        parts.push(("Free", self.free));
+        parts
+    }
+}
+
+/// Parses `Key:   1234 kB` lines into bytes; unitless values are kept
+/// as-is but scaled the same way, so callers divide them back.
+fn parse_kb_fields(content: &str) -> HashMap<&str, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, rest) = line.split_once(':')?;
+            let value: u64 = rest.split_whitespace().next()?.parse().ok()?;
+            Some((key.trim(), value * 1024))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_detail_cache_rereads_after_interval() {
+        let mut cache = MemoryDetailCache::default();
+        let start = Instant::now();
+        let interval = Duration::from_secs(2);
+        let detail = |pss| ProcessMemoryDetail { pss, ..Default::default() };
+
+        assert_eq!(cache.get_or_read(7, interval, start, || Some(detail(100))).map(|d| d.pss), Some(100));
+        // Still fresh: the reader is not called
+        let cached = cache.get_or_read(7, interval, start + Duration::from_secs(1), || panic!("re-read"));
+        assert_eq!(cached.map(|d| d.pss), Some(100));
+        let reread = cache.get_or_read(7, interval, start + Duration::from_secs(2), || Some(detail(150)));
+        assert_eq!(reread.map(|d| d.pss), Some(150));
+
+        // Unreadable processes are cached too
+        assert_eq!(cache.get_or_read(8, interval, start, || None), None);
+        assert_eq!(cache.get_or_read(8, interval, start, || panic!("re-read")), None);
+
+        cache.retain(|pid| pid == 8);
+        assert_eq!(cache.get_or_read(7, interval, start, || None), None);
+    }
+
+    #[test]
+    fn test_parse_smaps_rollup() {
+        let content = "\
+55d0c0a0e000-7ffd4d3f1000 ---p 00000000 00:00 0                          [rollup]
+Rss:               10240 kB
+Pss:                6144 kB
+Pss_Anon:           4096 kB
+Pss_File:           1536 kB
+Pss_Shmem:           512 kB
+Shared_Clean:       4096 kB
+Shared_Dirty:       1024 kB
+Private_Clean:      1024 kB
+Private_Dirty:      4096 kB
+Referenced:        10240 kB
+Anonymous:          4096 kB
+Swap:                256 kB
+SwapPss:             128 kB
+Locked:                0 kB
+";
+        let detail = ProcessMemoryDetail::parse(content);
+        assert_eq!(detail.rss, 10240 * 1024);
+        assert_eq!(detail.pss, 6144 * 1024);
+        assert_eq!(detail.pss_shmem, 512 * 1024);
+        assert_eq!(detail.uss(), 5120 * 1024);
+        assert_eq!(detail.shared(), 5120 * 1024);
+        assert_eq!(detail.file_backed(), 6144 * 1024);
+        assert_eq!(detail.swap_pss, 128 * 1024);
+    }
+
+    #[test]
+    fn test_parse_meminfo() {
+        let content = "\
+MemTotal:       16000000 kB
+MemFree:         2000000 kB
+MemAvailable:    9000000 kB
+Buffers:          500000 kB
+Cached:          6000000 kB
+SwapCached:         1000 kB
+AnonPages:       4000000 kB
+Mapped:           800000 kB
+Shmem:           1000000 kB
+SReclaimable:     700000 kB
+SUnreclaim:       300000 kB
+KernelStack:       20000 kB
+PageTables:        60000 kB
+Dirty:              4000 kB
+Writeback:             0 kB
+SwapTotal:       8000000 kB
+SwapFree:        7900000 kB
+HugePages_Total:       4
+HugePages_Free:        2
+Hugepagesize:       2048 kB
+";
+        let meminfo = MemInfo::parse(content);
+        assert_eq!(meminfo.cached, 5_000_000 * 1024);
+        assert_eq!(meminfo.slab(), 1_000_000 * 1024);
+        assert_eq!(meminfo.hugepages_total, 4);
+        assert_eq!(meminfo.hugepages_reserved(), 8192 * 1024);
+
+        let composition = meminfo.composition();
+        let total: u64 = composition.iter().map(|(_, bytes)| bytes).sum();
+        assert_eq!(total, meminfo.total);
+        assert_eq!(composition.last(), Some(&("Free", 2_000_000 * 1024)));
+    }
+}
@@ -3,12 +3,23 @@ use std::collections::{HashMap, VecDeque};
 use chrono::{DateTime, Utc, Duration};
 use serde::{Deserialize, Serialize};
 use crate::monitor::ProcessInfo;
+use crate::monitor::cgroups::{parse_limit, CgroupReader};
+use std::path::Path;
+
+/// Theil–Sen is quadratic in the number of points, so longer histories are
+/// thinned to this many before taking pairwise slopes.
+const MAX_THEIL_SEN_POINTS: usize = 120;
+/// Collections smaller than this are treated as noise.
+const MIN_GC_DROP_MB: f64 = 1.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryLeakDetector {
     process_memory_history: HashMap<u32, VecDeque<MemorySnapshot>>,
     leak_alerts: Vec<MemoryLeakAlert>,
     detection_settings: LeakDetectionSettings,
+    /// MemAvailable at the last update, for exhaustion forecasts.
+    #[serde(default)]
+    available_ram: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +44,27 @@ pub struct MemoryLeakAlert {
     pub baseline_memory: u64,
     pub trend_data: Vec<MemoryTrendPoint>,
     pub suggested_action: String,
+    /// 0-1; how well the history supports the alert. Regression alerts
+    /// combine R², the agreement of the least-squares and Theil–Sen
+    /// slopes, and how much of the window is covered.
+    #[serde(default)]
+    pub confidence: f64,
+    /// When the growth would exhaust available memory or the tightest
+    /// cgroup limit above the process, if it continues.
+    #[serde(default)]
+    pub exhaustion: Option<ExhaustionForecast>,
+    /// Set when the OOM killer later killed the process.
+    #[serde(default)]
+    pub oom_killed_at: Option<DateTime<Utc>>,
+}
+
+/// The leak alert that preceded an OOM kill.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LeakWarning {
+    pub alert_type: LeakAlertType,
+    pub severity: LeakSeverity,
+    pub detected_at: DateTime<Utc>,
+    pub growth_rate: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,7 +76,7 @@ pub enum LeakAlertType {
     FragmentationIncrease,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum LeakSeverity {
     Low,
     Medium,
@@ -59,25 +91,74 @@ pub struct MemoryTrendPoint {
     pub growth_rate: f64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExhaustionForecast {
+    /// `system` for available RAM, otherwise the limiting cgroup.
+    pub limit: String,
+    pub headroom: u64,
+    pub minutes: f64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LeakDetectionSettings {
     pub history_duration_minutes: u32,
+    pub sample_interval_seconds: u32,
+    pub min_window_minutes: u32,
     pub min_growth_threshold_mb: f64,
     pub rapid_growth_threshold_mb_per_min: f64,
     pub spike_threshold_multiplier: f64,
     pub min_samples_for_detection: usize,
     pub fragmentation_threshold: f64,
+    pub min_confidence: f64,
+    pub plateau_slope_ratio: f64,
+    pub gc_drop_fraction: f64,
 }
 
 impl Default for LeakDetectionSettings {
     fn default() -> Self {
         Self {
-            history_duration_minutes: 60, // 1 hour of history
+            history_duration_minutes: 360, // 6 hours of history
+            sample_interval_seconds: 60, // One sample a minute keeps 6 hours small
+            min_window_minutes: 30, // Don't fit lines to less than half an hour
             min_growth_threshold_mb: 10.0, // 10MB minimum growth to consider
             rapid_growth_threshold_mb_per_min: 5.0, // 5MB/min is rapid growth
             spike_threshold_multiplier: 2.0, // 2x sudden increase
             min_samples_for_detection: 10, // Need at least 10 samples
             fragmentation_threshold: 0.3, // 30% fragmentation threshold
+            min_confidence: 0.4, // Below this a trend isn't reported
+            plateau_slope_ratio: 0.25, // Recent growth under 25% of the trend is a plateau
+            gc_drop_fraction: 0.1, // Drops of 10% of the range are collections
+        }
+    }
+}
+
+/// A fitted growth trend, in MB per minute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TrendFit {
+    slope: f64,
+    confidence: f64,
+}
+
+/// What the regression over a process's history shows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum GrowthTrend {
+    /// Too short a window, or no significant growth.
+    Stable,
+    /// Grew and then levelled off, as a warming cache does.
+    Plateau,
+    /// Regular collections bring usage back to the same floor.
+    GarbageCollected,
+    Linear(TrendFit),
+    /// Collections happen, but the floor after each one keeps rising.
+    RisingFloor(TrendFit),
+}
+
+impl GrowthTrend {
+    /// Confidence in a fitted growth trend; zero when there is none.
+    fn confidence(&self) -> f64 {
+        match self {
+            GrowthTrend::Linear(fit) | GrowthTrend::RisingFloor(fit) => fit.confidence,
+            _ => 0.0,
         }
     }
 }
@@ -88,6 +169,7 @@ impl MemoryLeakDetector {
             process_memory_history: HashMap::new(),
             leak_alerts: Vec::new(),
             detection_settings: LeakDetectionSettings::default(),
+            available_ram: 0,
         }
     }
 
@@ -96,19 +178,33 @@ impl MemoryLeakDetector {
             process_memory_history: HashMap::new(),
             leak_alerts: Vec::new(),
             detection_settings: settings,
+            available_ram: 0,
         }
     }
 
-    pub fn update_process_memory(&mut self, processes: &[ProcessInfo]) -> Result<()> {
+    /// Samples each process at most once per `sample_interval_seconds` and
+    /// analyses it when a new sample was taken.
+    pub fn update_process_memory(&mut self, processes: &[ProcessInfo], available_ram: u64) -> Result<()> {
         let now = Utc::now();
+        let interval = Duration::seconds(self.detection_settings.sample_interval_seconds as i64);
+        self.available_ram = available_ram;
         
         for process in processes {
+            let sampled_recently = self.process_memory_history.get(&process.pid)
+                .and_then(|history| history.back())
+                .is_some_and(|last| now - last.timestamp < interval);
+            if sampled_recently {
+                continue;
+            }
+
             let snapshot = MemorySnapshot {
                 timestamp: now,
                 virtual_memory: process.memory_usage, // Using memory_usage as approximation
                 resident_memory: process.memory_usage,
-                shared_memory: 0, // TODO: Implement shared memory reading
-                heap_size: self.get_heap_size(process.pid).ok(),
+                shared_memory: process.memory_detail.map(|detail| detail.shared()).unwrap_or(0),
+                heap_size: process.memory_detail
+                    .map(|detail| detail.anonymous)
+                    .or_else(|| self.get_heap_size(process.pid).ok()),
                 stack_size: self.get_stack_size(process.pid).ok(),
             };
 
@@ -153,24 +249,25 @@ impl MemoryLeakDetector {
         };
 
         let mut alerts = Vec::with_capacity(4);
+        let trend = self.classify_growth(&history);
 
         // Check for steady growth
-        if let Some(alert) = self.detect_steady_growth(process, &history)? {
+        if let Some(alert) = self.detect_steady_growth(process, &history, trend)? {
             alerts.push(alert);
         }
 
         // Check for rapid increase
-        if let Some(alert) = self.detect_rapid_increase(process, &history)? {
+        if let Some(alert) = self.detect_rapid_increase(process, &history, trend)? {
             alerts.push(alert);
         }
 
         // Check for memory spikes
-        if let Some(alert) = self.detect_memory_spikes(process, &history)? {
+        if let Some(alert) = self.detect_memory_spikes(process, &history, trend)? {
             alerts.push(alert);
         }
 
         // Check for suspicious patterns
-        if let Some(alert) = self.detect_suspicious_patterns(process, &history)? {
+        if let Some(alert) = self.detect_suspicious_patterns(process, &history, trend)? {
             alerts.push(alert);
         }
 
@@ -182,52 +279,110 @@ impl MemoryLeakDetector {
         Ok(())
     }
 
-    fn detect_steady_growth(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>) -> Result<Option<MemoryLeakAlert>> {
-        if history.len() < 5 {
-            return Ok(None);
+    /// Fits the history and tells leaks apart from caches that warm up and
+    /// level off and from heaps that are collected back to a steady floor.
+    fn classify_growth(&self, history: &VecDeque<MemorySnapshot>) -> GrowthTrend {
+        let points = history_points(history);
+        let span = points.last().map(|(minutes, _)| *minutes).unwrap_or(0.0);
+        if span < self.detection_settings.min_window_minutes as f64
+            || points.len() < self.detection_settings.min_samples_for_detection
+        {
+            return GrowthTrend::Stable;
         }
 
-        let recent_samples = history.iter().rev().take(10).collect::<Vec<_>>();
-        let baseline = recent_samples.last().unwrap().resident_memory;
-        let current = recent_samples.first().unwrap().resident_memory;
-        
-        let growth_mb = (current as f64 - baseline as f64) / (1024.0 * 1024.0);
-        
-        if growth_mb < self.detection_settings.min_growth_threshold_mb {
-            return Ok(None);
+        // A collected heap fits a line badly; the floor after each
+        // collection is what shows a leak
+        let (low, high) = points.iter().fold((f64::MAX, f64::MIN), |(low, high), (_, mb)| (low.min(*mb), high.max(*mb)));
+        let drop = ((high - low) * self.detection_settings.gc_drop_fraction).max(MIN_GC_DROP_MB);
+        let floors = collection_floors(&points, drop);
+        if floors.len() >= 3 {
+            return match self.fit_trend(&floors, span) {
+                Some(fit) if fit.slope * span >= self.detection_settings.min_growth_threshold_mb => GrowthTrend::RisingFloor(fit),
+                _ => GrowthTrend::GarbageCollected,
+            };
         }
 
-        // Calculate growth rate
-        let time_diff = recent_samples.first().unwrap().timestamp
-            .signed_duration_since(recent_samples.last().unwrap().timestamp);
-        let growth_rate_mb_per_min = growth_mb / (time_diff.num_minutes() as f64).max(1.0);
+        let fit = match self.fit_trend(&points, span) {
+            Some(fit) if fit.slope * span >= self.detection_settings.min_growth_threshold_mb => fit,
+            _ => return GrowthTrend::Stable,
+        };
 
-        // Check if growth is consistent (linear regression)
-        let is_steady = self.is_steady_growth(history);
-        
-        if is_steady && growth_rate_mb_per_min > 0.1 {
-            let severity = self.calculate_severity(growth_rate_mb_per_min, current);
-            let trend_data = self.build_trend_data(history);
-            
-            return Ok(Some(MemoryLeakAlert {
-                pid: process.pid,
-                process_name: process.name.clone(),
-                alert_type: LeakAlertType::SteadyGrowth,
-                severity,
-                detected_at: Utc::now(),
-                growth_rate: growth_rate_mb_per_min,
-                current_memory: current,
-                baseline_memory: baseline,
-                trend_data,
-                suggested_action: self.suggest_action(&LeakAlertType::SteadyGrowth, growth_rate_mb_per_min),
-            }));
+        let recent: Vec<(f64, f64)> = points.iter().copied().filter(|(minutes, _)| *minutes >= span * 2.0 / 3.0).collect();
+        if theil_sen_slope(&recent).is_some_and(|slope| slope < fit.slope * self.detection_settings.plateau_slope_ratio) {
+            return GrowthTrend::Plateau;
         }
 
-        Ok(None)
+        GrowthTrend::Linear(fit)
+    }
+
+    /// Theil–Sen slope with a confidence from R², the agreement with the
+    /// least-squares slope, and how much of the history window and how
+    /// many samples the fit rests on.
+    fn fit_trend(&self, points: &[(f64, f64)], span: f64) -> Option<TrendFit> {
+        let (least_squares_slope, r_squared) = least_squares(points)?;
+        let slope = theil_sen_slope(points)?;
+        if slope <= 0.0 || least_squares_slope <= 0.0 {
+            return None;
+        }
+
+        let agreement = slope.min(least_squares_slope) / slope.max(least_squares_slope);
+        let coverage = (span / self.detection_settings.history_duration_minutes as f64).min(1.0);
+        let samples = (points.len() as f64 / self.detection_settings.min_samples_for_detection as f64).min(1.0);
+        Some(TrendFit {
+            slope,
+            confidence: r_squared * agreement * samples * (0.5 + 0.5 * coverage),
+        })
+    }
+
+    fn detect_steady_growth(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>, trend: GrowthTrend) -> Result<Option<MemoryLeakAlert>> {
+        let fit = match trend {
+            GrowthTrend::Linear(fit) if fit.confidence >= self.detection_settings.min_confidence => fit,
+            _ => return Ok(None),
+        };
+
+        let baseline = history.front().map(|s| s.resident_memory).unwrap_or(0);
+        let current = history.back().map(|s| s.resident_memory).unwrap_or(0);
+        let exhaustion = self.forecast_exhaustion(process, fit.slope);
+        let severity = escalate_for_forecast(self.calculate_severity(fit.slope, current), exhaustion.as_ref());
+
+        Ok(Some(MemoryLeakAlert {
+            pid: process.pid,
+            process_name: process.name.clone(),
+            alert_type: LeakAlertType::SteadyGrowth,
+            severity,
+            detected_at: Utc::now(),
+            growth_rate: fit.slope,
+            current_memory: current,
+            baseline_memory: baseline,
+            trend_data: self.build_trend_data(history),
+            suggested_action: self.suggest_action(&LeakAlertType::SteadyGrowth, fit.slope),
+            confidence: fit.confidence,
+            exhaustion,
+            oom_killed_at: None,
+        }))
+    }
+
+    /// Minutes until the growth would use up available RAM or the tightest
+    /// `memory.max` among the process's cgroup and its ancestors.
+    fn forecast_exhaustion(&self, process: &ProcessInfo, slope_mb_per_min: f64) -> Option<ExhaustionForecast> {
+        if slope_mb_per_min <= 0.0 {
+            return None;
+        }
+
+        let system = (self.available_ram > 0).then(|| ("system".to_string(), self.available_ram));
+        let cgroup = process.cgroup_path.as_deref().and_then(cgroup_headroom);
+        let (limit, headroom) = [system, cgroup].into_iter().flatten().min_by_key(|(_, headroom)| *headroom)?;
+
+        Some(ExhaustionForecast {
+            minutes: headroom as f64 / (1024.0 * 1024.0) / slope_mb_per_min,
+            limit,
+            headroom,
+        })
     }
 
-    fn detect_rapid_increase(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>) -> Result<Option<MemoryLeakAlert>> {
-        if history.len() < 3 {
+    fn detect_rapid_increase(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>, trend: GrowthTrend) -> Result<Option<MemoryLeakAlert>> {
+        // Warming caches and collected heaps jump between samples too
+        if history.len() < 3 || matches!(trend, GrowthTrend::Plateau | GrowthTrend::GarbageCollected) {
             return Ok(None);
         }
 
@@ -243,6 +398,13 @@ impl MemoryLeakDetector {
         if growth_rate > self.detection_settings.rapid_growth_threshold_mb_per_min {
             let severity = self.calculate_severity(growth_rate, current);
             let trend_data = self.build_trend_data(history);
+            // A short window: even growth in every recent sample is weak evidence
+            let recent: Vec<u64> = history.iter().rev().take(6).map(|s| s.resident_memory).collect();
+            let rising = recent.windows(2).filter(|w| w[0] > w[1]).count();
+            let confidence = 0.5 * rising as f64 / (recent.len() - 1) as f64 + 0.5 * trend.confidence();
+            if confidence < self.detection_settings.min_confidence {
+                return Ok(None);
+            }
 
             return Ok(Some(MemoryLeakAlert {
                 pid: process.pid,
@@ -255,14 +417,17 @@ impl MemoryLeakDetector {
                 baseline_memory: baseline,
                 trend_data,
                 suggested_action: self.suggest_action(&LeakAlertType::RapidIncrease, growth_rate),
+                confidence,
+                exhaustion: self.forecast_exhaustion(process, growth_rate),
+                oom_killed_at: None,
             }));
         }
 
         Ok(None)
     }
 
-    fn detect_memory_spikes(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>) -> Result<Option<MemoryLeakAlert>> {
-        if history.len() < 5 {
+    fn detect_memory_spikes(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>, trend: GrowthTrend) -> Result<Option<MemoryLeakAlert>> {
+        if history.len() < 5 || matches!(trend, GrowthTrend::Plateau | GrowthTrend::GarbageCollected) {
             return Ok(None);
         }
 
@@ -278,7 +443,10 @@ impl MemoryLeakDetector {
 
         let spike_ratio = current as f64 / baseline_avg;
         
-        if spike_ratio > self.detection_settings.spike_threshold_multiplier {
+        // A spike alone says little about a leak; one on a growth trend does
+        let confidence = (spike_ratio / (2.0 * self.detection_settings.spike_threshold_multiplier)).min(1.0) * 0.3
+            + 0.7 * trend.confidence();
+        if spike_ratio > self.detection_settings.spike_threshold_multiplier && confidence >= self.detection_settings.min_confidence {
             let growth_rate = (current as f64 - baseline_avg) / (1024.0 * 1024.0);
             let severity = if spike_ratio > 5.0 {
                 LeakSeverity::Critical
@@ -301,86 +469,41 @@ impl MemoryLeakDetector {
                 baseline_memory: baseline_avg as u64,
                 trend_data,
                 suggested_action: self.suggest_action(&LeakAlertType::MemorySpike, growth_rate),
+                confidence,
+                exhaustion: None,
+                oom_killed_at: None,
             }));
         }
 
         Ok(None)
     }
 
-    fn detect_suspicious_patterns(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>) -> Result<Option<MemoryLeakAlert>> {
-        if history.len() < 10 {
-            return Ok(None);
-        }
-
-        // Check for sawtooth pattern (allocate, free, allocate more, repeat)
-        let samples: Vec<_> = history.iter().collect();
-        let mut peaks = Vec::new();
-        let mut valleys = Vec::new();
-
-        for i in 1..samples.len() - 1 {
-            let prev = samples[i - 1].resident_memory;
-            let curr = samples[i].resident_memory;
-            let next = samples[i + 1].resident_memory;
-
-            if curr > prev && curr > next {
-                peaks.push((i, curr));
-            } else if curr < prev && curr < next {
-                valleys.push((i, curr));
-            }
-        }
-
-        // Check if peaks are increasing over time (classic leak pattern)
-        if peaks.len() >= 3 {
-            let peak_values: Vec<u64> = peaks.iter().map(|(_, val)| *val).collect();
-            let is_increasing = peak_values.windows(2).all(|w| w[1] > w[0]);
-            
-            if is_increasing {
-                let first_peak = peak_values[0];
-                let last_peak = *peak_values.last().unwrap();
-                let growth_rate = (last_peak as f64 - first_peak as f64) / (1024.0 * 1024.0);
-                
-                if growth_rate > self.detection_settings.min_growth_threshold_mb {
-                    let severity = self.calculate_severity(growth_rate / peaks.len() as f64, last_peak);
-                    let trend_data = self.build_trend_data(history);
-
-                    return Ok(Some(MemoryLeakAlert {
-                        pid: process.pid,
-                        process_name: process.name.clone(),
-                        alert_type: LeakAlertType::SuspiciousPattern,
-                        severity,
-                        detected_at: Utc::now(),
-                        growth_rate,
-                        current_memory: last_peak,
-                        baseline_memory: first_peak,
-                        trend_data,
-                        suggested_action: self.suggest_action(&LeakAlertType::SuspiciousPattern, growth_rate),
-                    }));
-                }
-            }
-        }
-
-        Ok(None)
-    }
-
-    fn is_steady_growth(&self, history: &VecDeque<MemorySnapshot>) -> bool {
-        if history.len() < 5 {
-            return false;
-        }
-
-        let samples: Vec<_> = history.iter().collect();
-        let mut increasing_count = 0;
-        let mut total_comparisons = 0;
-
-        for window in samples.windows(3) {
-            let trend = window[2].resident_memory as f64 - window[0].resident_memory as f64;
-            if trend > 0.0 {
-                increasing_count += 1;
-            }
-            total_comparisons += 1;
-        }
+    fn detect_suspicious_patterns(&self, process: &ProcessInfo, history: &VecDeque<MemorySnapshot>, trend: GrowthTrend) -> Result<Option<MemoryLeakAlert>> {
+        let fit = match trend {
+            GrowthTrend::RisingFloor(fit) if fit.confidence >= self.detection_settings.min_confidence => fit,
+            _ => return Ok(None),
+        };
 
-        // Consider it steady growth if 70% of samples show increase
-        (increasing_count as f64 / total_comparisons as f64) > 0.7
+        let baseline = history.iter().map(|s| s.resident_memory).min().unwrap_or(0);
+        let current = history.back().map(|s| s.resident_memory).unwrap_or(0);
+        let exhaustion = self.forecast_exhaustion(process, fit.slope);
+        let severity = escalate_for_forecast(self.calculate_severity(fit.slope, current), exhaustion.as_ref());
+
+        Ok(Some(MemoryLeakAlert {
+            pid: process.pid,
+            process_name: process.name.clone(),
+            alert_type: LeakAlertType::SuspiciousPattern,
+            severity,
+            detected_at: Utc::now(),
+            growth_rate: fit.slope,
+            current_memory: current,
+            baseline_memory: baseline,
+            trend_data: self.build_trend_data(history),
+            suggested_action: self.suggest_action(&LeakAlertType::SuspiciousPattern, fit.slope),
+            confidence: fit.confidence,
+            exhaustion,
+            oom_killed_at: None,
+        }))
     }
 
     fn calculate_severity(&self, growth_rate_mb_per_min: f64, current_memory: u64) -> LeakSeverity {
@@ -455,8 +578,9 @@ impl MemoryLeakDetector {
         }
     }
 
+    /// VmData, for processes whose smaps_rollup we can't read. It counts
+    /// reserved address space, not resident memory.
     fn get_heap_size(&self, pid: u32) -> Result<u64> {
-        // Read from /proc/pid/smaps or /proc/pid/status
         let status_path = format!("/proc/{}/status", pid);
         if let Ok(content) = std::fs::read_to_string(status_path) {
             for line in content.lines() {
@@ -496,6 +620,28 @@ impl MemoryLeakDetector {
         self.leak_alerts.iter().filter(|alert| alert.pid == pid).collect()
     }
 
+    /// Marks the alerts raised for a process before the OOM killer killed
+    /// it and returns the earliest. The kernel truncates names to 15
+    /// bytes, so a reused PID only matches if the names agree that far.
+    pub fn link_oom_kill(&mut self, pid: u32, victim_name: &str, killed_at: DateTime<Utc>) -> Option<LeakWarning> {
+        let mut earliest: Option<LeakWarning> = None;
+        for alert in &mut self.leak_alerts {
+            if alert.pid != pid || alert.detected_at > killed_at || !alert.process_name.starts_with(victim_name) {
+                continue;
+            }
+            alert.oom_killed_at = Some(killed_at);
+            if earliest.as_ref().map(|warning| alert.detected_at < warning.detected_at).unwrap_or(true) {
+                earliest = Some(LeakWarning {
+                    alert_type: alert.alert_type.clone(),
+                    severity: alert.severity.clone(),
+                    detected_at: alert.detected_at,
+                    growth_rate: alert.growth_rate,
+                });
+            }
+        }
+        earliest
+    }
+
     pub fn clear_alerts_for_process(&mut self, pid: u32) {
         self.leak_alerts.retain(|alert| alert.pid != pid);
     }
@@ -521,4 +667,260 @@ impl MemoryLeakDetector {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// (minutes since the first sample, resident MB) for each snapshot.
+fn history_points(history: &VecDeque<MemorySnapshot>) -> Vec<(f64, f64)> {
+    let start = match history.front() {
+        Some(first) => first.timestamp,
+        None => return Vec::new(),
+    };
+    history.iter()
+        .map(|s| {
+            let minutes = s.timestamp.signed_duration_since(start).num_milliseconds() as f64 / 60_000.0;
+            (minutes, s.resident_memory as f64 / (1024.0 * 1024.0))
+        })
+        .collect()
+}
+
+/// Least-squares slope and R²; None without variation in x. A flat
+/// series has no trend to explain and gets an R² of zero.
+fn least_squares(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    for (x, y) in points {
+        sxx += (x - mean_x) * (x - mean_x);
+        sxy += (x - mean_x) * (y - mean_y);
+        syy += (y - mean_y) * (y - mean_y);
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+    let r_squared = if syy == 0.0 { 0.0 } else { sxy * sxy / (sxx * syy) };
+    Some((sxy / sxx, r_squared))
+}
+
+/// Median of the pairwise slopes, which a few outliers such as a single
+/// spike can't move.
+fn theil_sen_slope(points: &[(f64, f64)]) -> Option<f64> {
+    let step = points.len().div_ceil(MAX_THEIL_SEN_POINTS).max(1);
+    let sampled: Vec<&(f64, f64)> = points.iter().step_by(step).collect();
+    let mut slopes = Vec::with_capacity(sampled.len() * sampled.len() / 2);
+    for (i, (x1, y1)) in sampled.iter().enumerate() {
+        for (x2, y2) in &sampled[i + 1..] {
+            if x2 > x1 {
+                slopes.push((y2 - y1) / (x2 - x1));
+            }
+        }
+    }
+    if slopes.is_empty() {
+        return None;
+    }
+    slopes.sort_by(|a, b| a.total_cmp(b));
+    let mid = slopes.len() / 2;
+    Some(if slopes.len() % 2 == 0 { (slopes[mid - 1] + slopes[mid]) / 2.0 } else { slopes[mid] })
+}
+
+/// The lowest point after each drop of more than `min_drop` MB, which is
+/// where a garbage collector leaves the heap.
+fn collection_floors(points: &[(f64, f64)], min_drop: f64) -> Vec<(f64, f64)> {
+    let mut floors = Vec::new();
+    let mut i = 1;
+    while i < points.len() {
+        if points[i - 1].1 - points[i].1 > min_drop {
+            let mut j = i;
+            while j + 1 < points.len() && points[j + 1].1 <= points[j].1 {
+                j += 1;
+            }
+            floors.push(points[j]);
+            i = j + 1;
+        } else {
+            i += 1;
+        }
+    }
+    floors
+}
+
+/// Free memory under the tightest `memory.max` from the cgroup up to the
+/// root, with the path of the cgroup that sets it.
+fn cgroup_headroom(cgroup_path: &str) -> Option<(String, u64)> {
+    let reader = CgroupReader::new();
+    let mut tightest: Option<(String, u64)> = None;
+    let mut path = Some(Path::new(cgroup_path));
+    while let Some(current) = path.filter(|p| *p != Path::new("/")) {
+        let dir = reader.resolve(&current.to_string_lossy());
+        let max = std::fs::read_to_string(dir.join("memory.max")).ok().and_then(|s| parse_limit(&s));
+        let usage = std::fs::read_to_string(dir.join("memory.current")).ok().and_then(|s| s.trim().parse::<u64>().ok());
+        if let (Some(max), Some(usage)) = (max, usage) {
+            let headroom = max.saturating_sub(usage);
+            if tightest.as_ref().map(|(_, tightest)| headroom < *tightest).unwrap_or(true) {
+                tightest = Some((current.to_string_lossy().to_string(), headroom));
+            }
+        }
+        path = current.parent();
+    }
+    tightest
+}
+
+/// Growth that would exhaust memory within the hour is critical whatever
+/// its rate; within six hours it is at least high.
+fn escalate_for_forecast(severity: LeakSeverity, forecast: Option<&ExhaustionForecast>) -> LeakSeverity {
+    match forecast.map(|forecast| forecast.minutes) {
+        Some(minutes) if minutes < 60.0 => LeakSeverity::Critical,
+        Some(minutes) if minutes < 360.0 => severity.max(LeakSeverity::High),
+        _ => severity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: f64 = 1024.0 * 1024.0;
+
+    /// One sample a minute for `minutes`, with memory in MB from `mb`.
+    fn history<F: Fn(f64) -> f64>(minutes: usize, mb: F) -> VecDeque<MemorySnapshot> {
+        let start = Utc::now() - Duration::minutes(minutes as i64);
+        (0..minutes)
+            .map(|minute| MemorySnapshot {
+                timestamp: start + Duration::minutes(minute as i64),
+                virtual_memory: 0,
+                resident_memory: (mb(minute as f64) * MB) as u64,
+                shared_memory: 0,
+                heap_size: None,
+                stack_size: None,
+            })
+            .collect()
+    }
+
+    /// Deterministic noise of about ±2 MB.
+    fn jitter(minute: f64) -> f64 {
+        ((minute * 12.9898).sin() * 43758.5453).fract() * 2.0
+    }
+
+    #[test]
+    fn test_slopes() {
+        let points: Vec<(f64, f64)> = (0..20).map(|x| (x as f64, 3.0 * x as f64 + 5.0)).collect();
+        let (slope, r_squared) = least_squares(&points).unwrap();
+        assert!((slope - 3.0).abs() < 1e-9);
+        assert!((r_squared - 1.0).abs() < 1e-9);
+
+        // One spike drags least squares but not Theil–Sen
+        let mut spiked = points.clone();
+        spiked[19].1 += 500.0;
+        assert!((theil_sen_slope(&spiked).unwrap() - 3.0).abs() < 1e-9);
+        assert!(least_squares(&spiked).unwrap().0 > 5.0);
+
+        assert_eq!(least_squares(&[(1.0, 1.0), (1.0, 2.0)]), None);
+    }
+
+    #[test]
+    fn test_steady_leak_is_linear_with_confidence() {
+        let detector = MemoryLeakDetector::new();
+        let trend = detector.classify_growth(&history(240, |m| 200.0 + 0.5 * m + jitter(m)));
+        match trend {
+            GrowthTrend::Linear(fit) => {
+                assert!((fit.slope - 0.5).abs() < 0.05);
+                assert!(fit.confidence > 0.6, "confidence {}", fit.confidence);
+            }
+            other => panic!("expected a linear trend, got {:?}", other),
+        }
+
+        // Too short a window to judge
+        assert_eq!(detector.classify_growth(&history(20, |m| 200.0 + 5.0 * m)), GrowthTrend::Stable);
+    }
+
+    #[test]
+    fn test_warming_cache_is_a_plateau() {
+        let detector = MemoryLeakDetector::new();
+        let trend = detector.classify_growth(&history(180, |m| 100.0 + 2.0 * m.min(60.0) + jitter(m)));
+        assert_eq!(trend, GrowthTrend::Plateau);
+    }
+
+    #[test]
+    fn test_sawtooth_floors() {
+        let detector = MemoryLeakDetector::new();
+
+        // Collected back to 300 MB every 20 minutes
+        let healthy = detector.classify_growth(&history(240, |m| 300.0 + 5.0 * (m % 20.0)));
+        assert_eq!(healthy, GrowthTrend::GarbageCollected);
+
+        // ...but the floor rises 10 MB each cycle
+        let leaking = detector.classify_growth(&history(240, |m| 300.0 + 10.0 * (m / 20.0).floor() + 5.0 * (m % 20.0)));
+        match leaking {
+            GrowthTrend::RisingFloor(fit) => assert!((fit.slope - 0.5).abs() < 0.05),
+            other => panic!("expected a rising floor, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_exhaustion_escalates_severity() {
+        let soon = ExhaustionForecast { limit: "system".to_string(), headroom: 0, minutes: 30.0 };
+        let later = ExhaustionForecast { minutes: 200.0, ..soon.clone() };
+        assert_eq!(escalate_for_forecast(LeakSeverity::Low, Some(&soon)), LeakSeverity::Critical);
+        assert_eq!(escalate_for_forecast(LeakSeverity::Low, Some(&later)), LeakSeverity::High);
+        assert_eq!(escalate_for_forecast(LeakSeverity::Medium, None), LeakSeverity::Medium);
+    }
+
+    #[test]
+    fn test_rapid_growth_and_spikes_need_a_trend() {
+        let detector = MemoryLeakDetector::new();
+        let process = ProcessInfo {
+            pid: 4242,
+            name: "worker".to_string(),
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            memory_percentage: 0.0,
+            user: "root".to_string(),
+            status: crate::monitor::ProcessStatusInfo::Running,
+            is_zombie: false,
+            parent_pid: None,
+            command: "worker".to_string(),
+            start_time: 0,
+            exe_path: None,
+            working_directory: None,
+            group_name: None,
+            threads_count: 1,
+            cgroup_path: None,
+            container_id: None,
+            systemd_unit: None,
+            pid_namespace: None,
+            pod: None,
+            memory_detail: None,
+        };
+
+        // A cache that warmed up for an hour, then stopped growing
+        let cache = history(180, |m| 100.0 + 2.0 * m.min(60.0) + jitter(m));
+        let trend = detector.classify_growth(&cache);
+        assert_eq!(trend, GrowthTrend::Plateau);
+        let mut jumped = cache.clone();
+        jumped.back_mut().unwrap().resident_memory *= 4;
+        assert!(detector.detect_rapid_increase(&process, &jumped, trend).unwrap().is_none());
+        assert!(detector.detect_memory_spikes(&process, &jumped, trend).unwrap().is_none());
+
+        // Collected every 10 minutes after climbing 20 MB/min
+        let sawtooth = history(240, |m| 300.0 + 20.0 * (m % 10.0));
+        let trend = detector.classify_growth(&sawtooth);
+        assert_eq!(trend, GrowthTrend::GarbageCollected);
+        assert!(detector.detect_rapid_increase(&process, &sawtooth, trend).unwrap().is_none());
+
+        // A lone spike without a growth trend falls below min_confidence
+        let flat = history(60, |_| 200.0);
+        let mut spiked = flat.clone();
+        spiked.back_mut().unwrap().resident_memory *= 4;
+        assert!(detector.detect_memory_spikes(&process, &spiked, GrowthTrend::Stable).unwrap().is_none());
+
+        // ...but the same spike on a confident leak is reported
+        let leak = history(240, |m| 200.0 + 0.5 * m + jitter(m));
+        let trend = detector.classify_growth(&leak);
+        let mut spiked = leak.clone();
+        spiked.back_mut().unwrap().resident_memory *= 4;
+        let alert = detector.detect_memory_spikes(&process, &spiked, trend).unwrap().unwrap();
+        assert!(alert.confidence >= detector.detection_settings.min_confidence);
+    }
+}
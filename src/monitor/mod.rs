@@ -24,6 +24,30 @@ pub mod database;
 pub mod apm;
 pub mod iot;
 pub mod backup;
+pub mod cgroups;
+pub mod container_engine;
+pub mod cron;
+pub mod cri;
+pub mod dbus;
+pub mod drm_fdinfo;
+pub mod fan_control;
+pub mod hwmon;
+pub mod ipmi;
+pub mod journal;
+pub mod kubernetes;
+pub mod memory_detail;
+pub mod nvml;
+pub mod oom;
+pub mod pressure;
+pub mod sched;
+pub mod scheduled_jobs;
+pub mod service_graph;
+pub mod ssh;
+pub mod systemd;
+pub mod topology;
+pub mod utmp;
+#[cfg(test)]
+mod test_support;
 
 use anyhow::Result;
 use sysinfo::{System, Networks, Disks};
@@ -55,6 +79,25 @@ pub use database::*;
 pub use apm::*;
 pub use iot::*;
 pub use backup::*;
+pub use cgroups::{CgroupReader, CgroupStats, CpuUsageSampler, SliceNode};
+pub use container_engine::{ContainerEvent, EngineClient, LogFollower};
+pub use cron::{CronEntry, CronSchedule};
+pub use drm_fdinfo::{DrmClient, DrmClientUsage, DrmFdinfoSampler};
+pub use fan_control::{FanControlConfig, FanController, FanCurve, FanOutput, FanOutputKind};
+pub use hwmon::{ChipConfig, HwmonChip, HwmonFeature, HwmonReader};
+pub use ipmi::{IpmiClient, IpmiMonitor, IpmiPoller, IpmiTransport, OpenIpmiDevice, SdrRecord, SelEntry};
+pub use journal::{JournalEntry, JournalFollower};
+pub use kubernetes::{KubernetesMonitor, PodInfo, PodMetrics, PodRef};
+pub use memory_detail::{MemInfo, MemoryDetailCache, ProcessMemoryDetail};
+pub use nvml::Nvml;
+pub use oom::{KmsgReader, OomConsumer, OomEvent, OomEventKind, OomMonitor, OomSource};
+pub use pressure::{PressureConfig, PressureEvent, PressureLine, PressureMetrics, PressureMonitor, PressureResource, PressureTrigger, ResourcePressure, StallKind};
+pub use sched::{set_thread_nice, SchedStats, ThreadSampler, ThreadStat, ThreadUsage};
+pub use scheduled_jobs::{ScheduledJob, ScheduledJobKind, ScheduledJobMonitor, ScheduledJobStatus};
+pub use service_graph::*;
+pub use ssh::{BlocklistFormat, BruteForceDetector, SshConnection, SshThreat, SshThreatKind};
+pub use topology::{CoreType, CpuCache, CpuTopology, LogicalCpu, NumaMemory, NumaNode, PhysicalCore};
+pub use utmp::{BootEvent, BootEventKind, FailedLoginSource, LoginRecord, SessionEnd};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SystemMetrics {
@@ -66,6 +109,8 @@ pub struct SystemMetrics {
     pub uptime: u64,
     pub load_average: LoadAverage,
     pub boot_time: DateTime<Utc>,
+    #[serde(default)]
+    pub pressure: PressureMetrics,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -73,6 +118,9 @@ pub struct CpuMetrics {
     pub overall_usage: f32,
     pub per_core_usage: Vec<f32>,
     pub temperature: Option<f32>,
+    /// Sysfs CPU layout; None where it can't be read.
+    #[serde(default)]
+    pub topology: Option<CpuTopology>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -84,6 +132,12 @@ pub struct MemoryMetrics {
     pub used_swap: u64,
     pub ram_percentage: f32,
     pub swap_percentage: f32,
+    /// `/proc/meminfo` breakdown; None off Linux.
+    #[serde(default)]
+    pub breakdown: Option<MemInfo>,
+    /// Per-node memory and allocation counters; empty without NUMA sysfs.
+    #[serde(default)]
+    pub numa: Vec<NumaMemory>,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -100,6 +154,11 @@ pub struct SystemMonitor {
     previous_network_data: HashMap<String, (u64, u64)>,
     pub history: HistoryManager,
     pub profiler: PerformanceProfiler,
+    pub thread_sampler: ThreadSampler,
+    pub cpu_topology: Option<CpuTopology>,
+    /// The online CPU list the topology was read under.
+    cpu_online: Option<String>,
+    memory_details: MemoryDetailCache,
     pub thermal_monitor: ThermalMonitor,
     pub dependency_analyzer: DependencyAnalyzer,
     pub memory_leak_detector: MemoryLeakDetector,
@@ -109,13 +168,17 @@ pub struct SystemMonitor {
     pub log_monitor: LogMonitor,
     pub filesystem_monitor: FileSystemMonitor,
     pub container_monitor: ContainerMonitor,
+    pub kubernetes_monitor: KubernetesMonitor,
     pub service_monitor: ServiceMonitor,
+    pub scheduled_job_monitor: ScheduledJobMonitor,
     pub session_monitor: SessionMonitor,
     pub hardware_sensor_monitor: HardwareSensorMonitor,
     pub database_monitor: DatabaseMonitor,
     pub apm_monitor: APMMonitor,
     pub iot_monitor: IoTMonitor,
     pub backup_monitor: BackupMonitor,
+    pub pressure_monitor: PressureMonitor,
+    pub oom_monitor: OomMonitor,
 }
 
 impl Default for SystemMonitor {
@@ -136,6 +199,10 @@ impl SystemMonitor {
             previous_network_data: HashMap::new(),
             history: HistoryManager::new(1440), // Store 24 hours of data (1 minute intervals)
             profiler: PerformanceProfiler::new(),
+            thread_sampler: ThreadSampler::new(),
+            cpu_topology: CpuTopology::read().ok(),
+            cpu_online: CpuTopology::read_online(),
+            memory_details: MemoryDetailCache::default(),
             thermal_monitor: ThermalMonitor::new(),
             dependency_analyzer: DependencyAnalyzer::new(),
             memory_leak_detector: MemoryLeakDetector::new(),
@@ -145,13 +212,17 @@ impl SystemMonitor {
             log_monitor: LogMonitor::with_default_config(),
             filesystem_monitor: FileSystemMonitor::with_default_config(),
             container_monitor: ContainerMonitor::new(),
+            kubernetes_monitor: KubernetesMonitor::new(),
             service_monitor: ServiceMonitor::new(),
+            scheduled_job_monitor: ScheduledJobMonitor::new(),
             session_monitor: SessionMonitor::new(),
             hardware_sensor_monitor: HardwareSensorMonitor::new(),
             database_monitor: DatabaseMonitor::with_default_config(),
             apm_monitor: APMMonitor::new(),
             iot_monitor: IoTMonitor::with_default_config(),
             backup_monitor: BackupMonitor::with_default_config(),
+            pressure_monitor: PressureMonitor::new(),
+            oom_monitor: OomMonitor::new(),
         }
     }
 
@@ -160,15 +231,35 @@ impl SystemMonitor {
         self.networks.refresh();
         self.disks.refresh();
         self.update_network_data();
+
+        // CPU hotplug or offlining changes the layout
+        let cpu_online = CpuTopology::read_online();
+        if cpu_online != self.cpu_online {
+            self.cpu_topology = CpuTopology::read().ok();
+            self.cpu_online = cpu_online;
+        }
         
         // Update advanced analysis modules
         let processes = self.get_process_info();
         
         // Update memory leak detection
-        if let Err(e) = self.memory_leak_detector.update_process_memory(&processes) {
+        if let Err(e) = self.memory_leak_detector.update_process_memory(&processes, self.system.available_memory()) {
             eprintln!("Memory leak detection error: {}", e);
         }
         
+        for event in self.oom_monitor.update(&mut self.memory_leak_detector) {
+            let severity = match (event.kind, event.leak_warning.is_some()) {
+                (OomEventKind::Kill, true) => AlertSeverity::Critical,
+                (OomEventKind::Kill, false) => AlertSeverity::High,
+                (OomEventKind::LimitReached, _) => AlertSeverity::Medium,
+            };
+            let details = HashMap::from([
+                ("source".to_string(), event.cgroup.clone().unwrap_or_else(|| "system".to_string())),
+                ("pattern".to_string(), event.victim_name.clone().unwrap_or_else(|| event.kind.label().to_string())),
+            ]);
+            self.security_dashboard.raise_alert(AlertType::OutOfMemory, severity, event.message(), details);
+        }
+        
         // Update IO bottleneck analysis
         if let Err(e) = self.io_bottleneck_analyzer.update_io_metrics(&processes) {
             eprintln!("IO bottleneck analysis error: {}", e);
@@ -183,6 +274,45 @@ impl SystemMonitor {
         if let Err(e) = self.security_dashboard.update_security_analysis(&processes) {
             eprintln!("Security analysis error: {}", e);
         }
+
+        for threat in self.session_monitor.poll_ssh_auth() {
+            let details = HashMap::from([
+                ("source".to_string(), threat.source.to_string()),
+                ("pattern".to_string(), threat.kind.label().to_string()),
+                ("users".to_string(), threat.users.join(",")),
+            ]);
+            self.security_dashboard.raise_alert(
+                AlertType::AuthenticationAttack,
+                threat.kind.severity(),
+                threat.message(),
+                details,
+            );
+        }
+        
+        for event in self.pressure_monitor.update() {
+            let severity = match (event.trigger.kind, event.stall_percent >= event.trigger.threshold_percent() * 2.0) {
+                (StallKind::Full, true) => AlertSeverity::Critical,
+                (StallKind::Full, false) | (StallKind::Some, true) => AlertSeverity::High,
+                (StallKind::Some, false) => AlertSeverity::Medium,
+            };
+            let details = HashMap::from([
+                ("source".to_string(), event.trigger.cgroup.clone().unwrap_or_else(|| "system".to_string())),
+                ("pattern".to_string(), event.trigger.describe()),
+                ("stall_percent".to_string(), format!("{:.1}", event.stall_percent)),
+            ]);
+            self.security_dashboard.raise_alert(AlertType::ResourcePressure, severity, event.message(), details);
+        }
+
+        for alert in self.container_monitor.take_new_alerts() {
+            let details = HashMap::from([
+                ("source".to_string(), alert.container_name.clone()),
+                ("pattern".to_string(), alert.action.clone()),
+                ("container_id".to_string(), alert.container_id.clone()),
+            ]);
+            self.security_dashboard.raise_alert(AlertType::ContainerFailure, alert.severity, alert.message, details);
+        }
+
+        self.thermal_monitor.fan_control().enforce_curves();
         
         // Update log monitoring
         if let Err(e) = self.log_monitor.update().await {
@@ -219,16 +349,16 @@ impl SystemMonitor {
     }
 
     pub fn get_metrics(&mut self) -> SystemMetrics {
-        let processes = self.get_process_info();
+        let mut processes = self.get_process_info();
+        self.kubernetes_monitor.attribute_processes(&mut processes);
         
         // Update performance profiler
-        for process in &processes {
-            self.profiler.update_process(process);
-        }
+        self.profiler.update_processes(&processes);
         
         // Clean up old profiles
         let active_pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
         self.profiler.cleanup_old_profiles(&active_pids);
+        self.oom_monitor.record_snapshot(&processes);
         
         let metrics = SystemMetrics {
             cpu: self.get_cpu_metrics(),
@@ -239,6 +369,7 @@ impl SystemMonitor {
             uptime: System::uptime(),
             load_average: self.get_load_average(),
             boot_time: self.get_boot_time(),
+            pressure: self.pressure_monitor.current().clone(),
         };
         
         // Add to history
@@ -259,6 +390,7 @@ impl SystemMonitor {
             overall_usage,
             per_core_usage,
             temperature,
+            topology: self.cpu_topology.clone(),
         }
     }
 
@@ -289,6 +421,8 @@ impl SystemMonitor {
             used_swap,
             ram_percentage,
             swap_percentage,
+            breakdown: MemInfo::read().ok(),
+            numa: NumaMemory::read_all(),
         }
     }
 
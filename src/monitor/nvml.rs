@@ -0,0 +1,541 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::ffi::{c_char, c_int, c_uint, c_void, CStr, CString};
+use std::fs;
+use std::sync::Mutex;
+
+use super::gpu::{GPUInfo, GPUProcess, GPUProcessType, GPUVendor, MigInstance, NvidiaDetails};
+
+const LIBRARY_NAMES: &[&str] = &["libnvidia-ml.so.1", "libnvidia-ml.so"];
+
+type Device = *mut c_void;
+type Status = c_int;
+
+const SUCCESS: Status = 0;
+const ERROR_NOT_FOUND: Status = 6;
+const ERROR_INSUFFICIENT_SIZE: Status = 7;
+const TEMPERATURE_GPU: c_int = 0;
+const CLOCK_GRAPHICS: c_int = 0;
+const CLOCK_MEM: c_int = 2;
+const ECC_CORRECTED: c_int = 0;
+const ECC_UNCORRECTED: c_int = 1;
+const ECC_VOLATILE: c_int = 0;
+const PCIE_TX: c_int = 0;
+const PCIE_RX: c_int = 1;
+const MIG_ENABLED: c_uint = 1;
+/// `gpuInstanceId` of processes not running in a MIG instance.
+const NO_INSTANCE: u32 = u32::MAX;
+/// NVML's `NVML_VALUE_NOT_AVAILABLE` for process memory.
+const VALUE_NOT_AVAILABLE: u64 = u64::MAX;
+
+/// `nvmlClocksThrottleReasons` bits, as named by `nvidia-smi -q`.
+const THROTTLE_REASONS: &[(u64, &str)] = &[
+    (0x1, "Idle"),
+    (0x2, "Applications Clocks Setting"),
+    (0x4, "SW Power Cap"),
+    (0x8, "HW Slowdown"),
+    (0x10, "Sync Boost"),
+    (0x20, "SW Thermal Slowdown"),
+    (0x40, "HW Thermal Slowdown"),
+    (0x80, "HW Power Brake Slowdown"),
+    (0x100, "Display Clock Setting"),
+];
+
+#[repr(C)]
+#[derive(Default)]
+struct Memory {
+    total: u64,
+    free: u64,
+    used: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct Utilization {
+    gpu: c_uint,
+    memory: c_uint,
+}
+
+#[repr(C)]
+struct PciInfo {
+    bus_id_legacy: [c_char; 16],
+    domain: c_uint,
+    bus: c_uint,
+    device: c_uint,
+    pci_device_id: c_uint,
+    pci_subsystem_id: c_uint,
+    bus_id: [c_char; 32],
+}
+
+/// `nvmlProcessInfo_t` as used by the `_v2` and `_v3` process queries.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ProcessInfo {
+    pid: c_uint,
+    used_gpu_memory: u64,
+    gpu_instance_id: c_uint,
+    compute_instance_id: c_uint,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct ProcessUtilizationSample {
+    pid: c_uint,
+    time_stamp: u64,
+    sm_util: c_uint,
+    mem_util: c_uint,
+    enc_util: c_uint,
+    dec_util: c_uint,
+}
+
+type DeviceU32 = unsafe extern "C" fn(Device, *mut c_uint) -> Status;
+type DeviceEnumU32 = unsafe extern "C" fn(Device, c_int, *mut c_uint) -> Status;
+type DeviceU32Pair = unsafe extern "C" fn(Device, *mut c_uint, *mut c_uint) -> Status;
+type DeviceProcesses = unsafe extern "C" fn(Device, *mut c_uint, *mut ProcessInfo) -> Status;
+type DeviceProcessSamples = unsafe extern "C" fn(Device, *mut ProcessUtilizationSample, *mut c_uint, u64) -> Status;
+
+/// Processes by pid, with the (GPU, compute) MIG instance each runs in.
+type RunningProcesses = HashMap<u32, (GPUProcess, Option<(u32, u32)>)>;
+
+/// Entry points; only the first six are required.
+struct Api {
+    init: unsafe extern "C" fn() -> Status,
+    shutdown: unsafe extern "C" fn() -> Status,
+    device_count: unsafe extern "C" fn(*mut c_uint) -> Status,
+    device_by_index: unsafe extern "C" fn(c_uint, *mut Device) -> Status,
+    device_name: unsafe extern "C" fn(Device, *mut c_char, c_uint) -> Status,
+    memory_info: unsafe extern "C" fn(Device, *mut Memory) -> Status,
+    driver_version: Option<unsafe extern "C" fn(*mut c_char, c_uint) -> Status>,
+    pci_info: Option<unsafe extern "C" fn(Device, *mut PciInfo) -> Status>,
+    utilization: Option<unsafe extern "C" fn(Device, *mut Utilization) -> Status>,
+    temperature: Option<DeviceEnumU32>,
+    power_usage: Option<DeviceU32>,
+    power_limit: Option<DeviceU32>,
+    fan_speed: Option<DeviceU32>,
+    clock: Option<DeviceEnumU32>,
+    encoder: Option<DeviceU32Pair>,
+    decoder: Option<DeviceU32Pair>,
+    throttle_reasons: Option<unsafe extern "C" fn(Device, *mut u64) -> Status>,
+    ecc_errors: Option<unsafe extern "C" fn(Device, c_int, c_int, *mut u64) -> Status>,
+    pcie_throughput: Option<DeviceEnumU32>,
+    mig_mode: Option<DeviceU32Pair>,
+    max_mig_devices: Option<DeviceU32>,
+    mig_device_by_index: Option<unsafe extern "C" fn(Device, c_uint, *mut Device) -> Status>,
+    gpu_instance_id: Option<DeviceU32>,
+    compute_instance_id: Option<DeviceU32>,
+    compute_processes: Option<DeviceProcesses>,
+    graphics_processes: Option<DeviceProcesses>,
+    process_utilization: Option<DeviceProcessSamples>,
+}
+
+/// `libnvidia-ml.so`, loaded at runtime so seer neither links against nor
+/// requires the NVIDIA driver.
+pub struct Nvml {
+    library: *mut c_void,
+    api: Api,
+    /// Newest process utilisation sample seen per device, so each query
+    /// only returns what is new.
+    last_sample: Mutex<HashMap<u32, u64>>,
+}
+
+// SAFETY: NVML is documented as thread-safe, and the library handle is
+// only closed on drop
+unsafe impl Send for Nvml {}
+unsafe impl Sync for Nvml {}
+
+impl std::fmt::Debug for Nvml {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Nvml").finish_non_exhaustive()
+    }
+}
+
+fn symbol<T: Copy>(library: *mut c_void, names: &[&str]) -> Option<T> {
+    names.iter().find_map(|name| {
+        let name = CString::new(*name).ok()?;
+        // SAFETY: library is a live dlopen handle
+        let address = unsafe { libc::dlsym(library, name.as_ptr()) };
+        // SAFETY: T is the function pointer type NVML declares for the name
+        (!address.is_null()).then(|| unsafe { std::mem::transmute_copy::<*mut c_void, T>(&address) })
+    })
+}
+
+fn check(status: Status, what: &str) -> Result<()> {
+    if status == SUCCESS {
+        Ok(())
+    } else {
+        Err(anyhow!("NVML {} failed with status {}", what, status))
+    }
+}
+
+impl Nvml {
+    /// Loads and initialises NVML; fails if the driver isn't installed.
+    pub fn load() -> Result<Self> {
+        let library = LIBRARY_NAMES.iter()
+            .find_map(|name| {
+                let name = CString::new(*name).ok()?;
+                // SAFETY: a NUL-terminated library name
+                let handle = unsafe { libc::dlopen(name.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL) };
+                (!handle.is_null()).then_some(handle)
+            })
+            .ok_or_else(|| anyhow!("libnvidia-ml.so not found"))?;
+
+        let required = || -> Option<Api> {
+            Some(Api {
+                init: symbol(library, &["nvmlInit_v2", "nvmlInit"])?,
+                shutdown: symbol(library, &["nvmlShutdown"])?,
+                device_count: symbol(library, &["nvmlDeviceGetCount_v2", "nvmlDeviceGetCount"])?,
+                device_by_index: symbol(library, &["nvmlDeviceGetHandleByIndex_v2", "nvmlDeviceGetHandleByIndex"])?,
+                device_name: symbol(library, &["nvmlDeviceGetName"])?,
+                memory_info: symbol(library, &["nvmlDeviceGetMemoryInfo"])?,
+                driver_version: symbol(library, &["nvmlSystemGetDriverVersion"]),
+                pci_info: symbol(library, &["nvmlDeviceGetPciInfo_v3", "nvmlDeviceGetPciInfo_v2"]),
+                utilization: symbol(library, &["nvmlDeviceGetUtilizationRates"]),
+                temperature: symbol(library, &["nvmlDeviceGetTemperature"]),
+                power_usage: symbol(library, &["nvmlDeviceGetPowerUsage"]),
+                power_limit: symbol(library, &["nvmlDeviceGetEnforcedPowerLimit"]),
+                fan_speed: symbol(library, &["nvmlDeviceGetFanSpeed"]),
+                clock: symbol(library, &["nvmlDeviceGetClockInfo"]),
+                encoder: symbol(library, &["nvmlDeviceGetEncoderUtilization"]),
+                decoder: symbol(library, &["nvmlDeviceGetDecoderUtilization"]),
+                throttle_reasons: symbol(library, &["nvmlDeviceGetCurrentClocksEventReasons", "nvmlDeviceGetCurrentClocksThrottleReasons"]),
+                ecc_errors: symbol(library, &["nvmlDeviceGetTotalEccErrors"]),
+                pcie_throughput: symbol(library, &["nvmlDeviceGetPcieThroughput"]),
+                mig_mode: symbol(library, &["nvmlDeviceGetMigMode"]),
+                max_mig_devices: symbol(library, &["nvmlDeviceGetMaxMigDeviceCount"]),
+                mig_device_by_index: symbol(library, &["nvmlDeviceGetMigDeviceHandleByIndex"]),
+                gpu_instance_id: symbol(library, &["nvmlDeviceGetGpuInstanceId"]),
+                compute_instance_id: symbol(library, &["nvmlDeviceGetComputeInstanceId"]),
+                compute_processes: symbol(library, &["nvmlDeviceGetComputeRunningProcesses_v3", "nvmlDeviceGetComputeRunningProcesses_v2"]),
+                graphics_processes: symbol(library, &["nvmlDeviceGetGraphicsRunningProcesses_v3", "nvmlDeviceGetGraphicsRunningProcesses_v2"]),
+                process_utilization: symbol(library, &["nvmlDeviceGetProcessUtilization"]),
+            })
+        };
+        let api = match required() {
+            Some(api) => api,
+            None => {
+                // SAFETY: handle from dlopen above
+                unsafe { libc::dlclose(library) };
+                return Err(anyhow!("libnvidia-ml.so lacks required symbols"));
+            }
+        };
+
+        // SAFETY: resolved from the library with its declared signature
+        if let Err(e) = check(unsafe { (api.init)() }, "init") {
+            // SAFETY: handle from dlopen above
+            unsafe { libc::dlclose(library) };
+            return Err(e);
+        }
+        Ok(Self { library, api, last_sample: Mutex::new(HashMap::new()) })
+    }
+
+    pub fn driver_version(&self) -> Option<String> {
+        let mut buffer = [0 as c_char; 96];
+        let f = self.api.driver_version?;
+        // SAFETY: buffer length passed alongside
+        (unsafe { f(buffer.as_mut_ptr(), buffer.len() as c_uint) } == SUCCESS).then(|| c_string(&buffer))
+    }
+
+    /// One `GPUInfo` per physical GPU, with its processes.
+    pub fn gpus(&self) -> Result<Vec<GPUInfo>> {
+        let mut count: c_uint = 0;
+        // SAFETY: out pointer to a local
+        check(unsafe { (self.api.device_count)(&mut count) }, "device count")?;
+        let driver_version = self.driver_version().unwrap_or_else(|| "Unknown".to_string());
+
+        let mut gpus = Vec::new();
+        for index in 0..count {
+            let mut device: Device = std::ptr::null_mut();
+            // SAFETY: out pointer to a local
+            if unsafe { (self.api.device_by_index)(index, &mut device) } != SUCCESS {
+                continue;
+            }
+            gpus.push(self.gpu_info(index, device, &driver_version));
+        }
+        Ok(gpus)
+    }
+
+    fn gpu_info(&self, index: u32, device: Device, driver_version: &str) -> GPUInfo {
+        let memory = self.memory(device).unwrap_or_default();
+        let utilization = self.api.utilization.and_then(|f| {
+            let mut utilization = Utilization::default();
+            // SAFETY: out pointer to a local
+            (unsafe { f(device, &mut utilization) } == SUCCESS).then_some(utilization)
+        });
+        let enum_u32 = |f: Option<DeviceEnumU32>, which: c_int| -> Option<u32> {
+            let mut value: c_uint = 0;
+            // SAFETY: out pointer to a local
+            (unsafe { f?(device, which, &mut value) } == SUCCESS).then_some(value)
+        };
+        let sampled = |f: Option<DeviceU32Pair>| -> Option<f32> {
+            let (mut value, mut period): (c_uint, c_uint) = (0, 0);
+            // SAFETY: out pointers to locals
+            (unsafe { f?(device, &mut value, &mut period) } == SUCCESS).then_some(value as f32)
+        };
+        let ecc = |kind: c_int| -> Option<u64> {
+            let mut count = 0u64;
+            // SAFETY: out pointer to a local
+            (unsafe { self.api.ecc_errors?(device, kind, ECC_VOLATILE, &mut count) } == SUCCESS).then_some(count)
+        };
+        let throttle_reasons = self.api.throttle_reasons
+            .and_then(|f| {
+                let mut reasons = 0u64;
+                // SAFETY: out pointer to a local
+                (unsafe { f(device, &mut reasons) } == SUCCESS).then_some(reasons)
+            })
+            .map(decode_throttle_reasons)
+            .unwrap_or_default();
+
+        let mut processes = self.processes(device);
+        let mig_instances = self.mig_instances(device, &processes);
+        self.apply_process_samples(index, device, &mut processes);
+
+        GPUInfo {
+            gpu_id: index,
+            name: self.name(device).unwrap_or_else(|| "NVIDIA GPU".to_string()),
+            vendor: GPUVendor::NVIDIA,
+            driver_version: driver_version.to_string(),
+            memory_total: memory.total,
+            memory_used: memory.used,
+            memory_free: memory.free,
+            utilization_gpu: utilization.as_ref().map(|u| u.gpu as f32).unwrap_or(0.0),
+            utilization_memory: utilization.as_ref().map(|u| u.memory as f32).unwrap_or(0.0),
+            temperature: enum_u32(self.api.temperature, TEMPERATURE_GPU).map(|t| t as f32),
+            power_usage: self.device_u32(self.api.power_usage, device).map(|mw| mw as f32 / 1000.0),
+            power_limit: self.device_u32(self.api.power_limit, device).map(|mw| mw as f32 / 1000.0),
+            fan_speed: self.device_u32(self.api.fan_speed, device).map(|percent| percent as f32),
+            clock_graphics: enum_u32(self.api.clock, CLOCK_GRAPHICS),
+            clock_memory: enum_u32(self.api.clock, CLOCK_MEM),
+            processes: processes.into_values().map(|(process, _)| process).collect(),
+            encoder_utilization: sampled(self.api.encoder),
+            decoder_utilization: sampled(self.api.decoder),
+            pci_address: self.pci_address(device),
+            nvidia: Some(NvidiaDetails {
+                throttle_reasons,
+                ecc_corrected: ecc(ECC_CORRECTED),
+                ecc_uncorrected: ecc(ECC_UNCORRECTED),
+                pcie_tx_kbps: enum_u32(self.api.pcie_throughput, PCIE_TX),
+                pcie_rx_kbps: enum_u32(self.api.pcie_throughput, PCIE_RX),
+                mig_instances,
+            }),
+        }
+    }
+
+    fn device_u32(&self, f: Option<DeviceU32>, device: Device) -> Option<u32> {
+        let mut value: c_uint = 0;
+        // SAFETY: out pointer to a local
+        (unsafe { f?(device, &mut value) } == SUCCESS).then_some(value)
+    }
+
+    fn name(&self, device: Device) -> Option<String> {
+        let mut buffer = [0 as c_char; 96];
+        // SAFETY: buffer length passed alongside
+        (unsafe { (self.api.device_name)(device, buffer.as_mut_ptr(), buffer.len() as c_uint) } == SUCCESS)
+            .then(|| c_string(&buffer))
+    }
+
+    fn memory(&self, device: Device) -> Option<Memory> {
+        let mut memory = Memory::default();
+        // SAFETY: out pointer to a local
+        (unsafe { (self.api.memory_info)(device, &mut memory) } == SUCCESS).then_some(memory)
+    }
+
+    fn pci_address(&self, device: Device) -> Option<String> {
+        let f = self.api.pci_info?;
+        // SAFETY: PciInfo is plain data
+        let mut info: PciInfo = unsafe { std::mem::zeroed() };
+        // SAFETY: out pointer to a local
+        if unsafe { f(device, &mut info) } != SUCCESS {
+            return None;
+        }
+        normalize_bus_id(&c_string(&info.bus_id))
+    }
+
+    fn processes(&self, device: Device) -> RunningProcesses {
+        let mut processes = RunningProcesses::new();
+        for (query, process_type) in [
+            (self.api.compute_processes, GPUProcessType::Compute),
+            (self.api.graphics_processes, GPUProcessType::Graphics),
+        ] {
+            for info in query.map(|f| running_processes(f, device)).unwrap_or_default() {
+                let memory = if info.used_gpu_memory == VALUE_NOT_AVAILABLE { 0 } else { info.used_gpu_memory };
+                let instance = (info.gpu_instance_id != NO_INSTANCE).then_some((info.gpu_instance_id, info.compute_instance_id));
+                processes.entry(info.pid)
+                    .and_modify(|(process, _)| {
+                        process.process_type = GPUProcessType::Mixed;
+                        process.memory_usage = process.memory_usage.max(memory);
+                    })
+                    .or_insert_with(|| (GPUProcess {
+                        pid: info.pid,
+                        process_name: fs::read_to_string(format!("/proc/{}/comm", info.pid))
+                            .map(|comm| comm.trim().to_string())
+                            .unwrap_or_default(),
+                        memory_usage: memory,
+                        gpu_utilization: None,
+                        encode_utilization: None,
+                        decode_utilization: None,
+                        process_type: process_type.clone(),
+                        engine_utilization: Vec::new(),
+                    }, instance));
+            }
+        }
+        processes
+    }
+
+    /// Fills in per-process SM, encoder and decoder utilisation from the
+    /// samples NVML collected since the previous call.
+    fn apply_process_samples(&self, index: u32, device: Device, processes: &mut RunningProcesses) {
+        let f = match self.api.process_utilization {
+            Some(f) => f,
+            None => return,
+        };
+        let mut last_sample = self.last_sample.lock().unwrap_or_else(|e| e.into_inner());
+        let since = last_sample.get(&index).copied().unwrap_or(0);
+
+        let mut count: c_uint = 0;
+        // SAFETY: a null buffer asks for the sample count
+        let status = unsafe { f(device, std::ptr::null_mut(), &mut count, since) };
+        if status != ERROR_INSUFFICIENT_SIZE && status != ERROR_NOT_FOUND && status != SUCCESS {
+            // Not supported on this GPU; leave utilisation unknown
+            return;
+        }
+        let mut samples = Vec::new();
+        if status == ERROR_INSUFFICIENT_SIZE && count > 0 {
+            samples = vec![ProcessUtilizationSample::default(); count as usize];
+            // SAFETY: buffer of count samples
+            if unsafe { f(device, samples.as_mut_ptr(), &mut count, since) } != SUCCESS {
+                samples.clear();
+            }
+            samples.truncate(count as usize);
+        }
+
+        // Processes without a new sample were idle
+        for (process, _) in processes.values_mut() {
+            process.gpu_utilization = Some(0.0);
+            process.encode_utilization = Some(0.0);
+            process.decode_utilization = Some(0.0);
+        }
+        samples.sort_by_key(|sample| sample.time_stamp);
+        for sample in &samples {
+            if let Some((process, _)) = processes.get_mut(&sample.pid) {
+                process.gpu_utilization = Some(sample.sm_util as f32);
+                process.encode_utilization = Some(sample.enc_util as f32);
+                process.decode_utilization = Some(sample.dec_util as f32);
+            }
+        }
+        if let Some(newest) = samples.last() {
+            last_sample.insert(index, newest.time_stamp);
+        }
+    }
+
+    fn mig_instances(&self, device: Device, processes: &RunningProcesses) -> Vec<MigInstance> {
+        let (mut current, mut pending): (c_uint, c_uint) = (0, 0);
+        // SAFETY: out pointers to locals
+        let enabled = self.api.mig_mode
+            .map(|f| unsafe { f(device, &mut current, &mut pending) } == SUCCESS && current == MIG_ENABLED)
+            .unwrap_or(false);
+        let by_index = match (enabled, self.api.mig_device_by_index) {
+            (true, Some(f)) => f,
+            _ => return Vec::new(),
+        };
+
+        let mut instances = Vec::new();
+        for index in 0..self.device_u32(self.api.max_mig_devices, device).unwrap_or(0) {
+            let mut mig: Device = std::ptr::null_mut();
+            // SAFETY: out pointer to a local
+            if unsafe { by_index(device, index, &mut mig) } != SUCCESS {
+                continue;
+            }
+            let memory = self.memory(mig).unwrap_or_default();
+            let gpu_instance_id = self.device_u32(self.api.gpu_instance_id, mig).unwrap_or(NO_INSTANCE);
+            let compute_instance_id = self.device_u32(self.api.compute_instance_id, mig).unwrap_or(NO_INSTANCE);
+            let mut pids: Vec<u32> = processes.iter()
+                .filter(|(_, (_, instance))| *instance == Some((gpu_instance_id, compute_instance_id)))
+                .map(|(pid, _)| *pid)
+                .collect();
+            pids.sort_unstable();
+            instances.push(MigInstance {
+                gpu_instance_id,
+                compute_instance_id,
+                name: self.name(mig).unwrap_or_default(),
+                memory_total: memory.total,
+                memory_used: memory.used,
+                pids,
+            });
+        }
+        instances
+    }
+}
+
+impl Drop for Nvml {
+    fn drop(&mut self) {
+        // SAFETY: initialised in load, and the handle is still open
+        unsafe {
+            (self.api.shutdown)();
+            libc::dlclose(self.library);
+        }
+    }
+}
+
+fn running_processes(f: DeviceProcesses, device: Device) -> Vec<ProcessInfo> {
+    let mut count: c_uint = 0;
+    // SAFETY: a null buffer with count 0 asks for the process count
+    let status = unsafe { f(device, &mut count, std::ptr::null_mut()) };
+    if status == SUCCESS || count == 0 {
+        return Vec::new();
+    }
+    // Processes may start between the two calls
+    count += 8;
+    let mut processes = vec![ProcessInfo::default(); count as usize];
+    // SAFETY: buffer of count entries
+    if unsafe { f(device, &mut count, processes.as_mut_ptr()) } != SUCCESS {
+        return Vec::new();
+    }
+    processes.truncate(count as usize);
+    processes
+}
+
+fn c_string(buffer: &[c_char]) -> String {
+    // NVML NUL-terminates within the buffer; force it in case it didn't
+    let mut bytes: Vec<u8> = buffer.iter().map(|c| *c as u8).collect();
+    if let Some(last) = bytes.last_mut() {
+        *last = 0;
+    }
+    CStr::from_bytes_until_nul(&bytes).map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// NVML's `00000000:01:00.0` as the kernel writes it, `0000:01:00.0`.
+pub fn normalize_bus_id(bus_id: &str) -> Option<String> {
+    let (domain, rest) = bus_id.split_once(':')?;
+    let domain = u32::from_str_radix(domain, 16).ok()?;
+    Some(format!("{:04x}:{}", domain, rest.to_lowercase()))
+}
+
+pub fn decode_throttle_reasons(reasons: u64) -> Vec<String> {
+    THROTTLE_REASONS.iter()
+        .filter(|(bit, _)| reasons & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nvml_helpers() {
+        assert_eq!(normalize_bus_id("00000000:3B:00.0").as_deref(), Some("0000:3b:00.0"));
+        assert_eq!(normalize_bus_id("garbage"), None);
+        assert_eq!(decode_throttle_reasons(0x1 | 0x40), vec!["Idle", "HW Thermal Slowdown"]);
+
+        // Layouts from nvml.h
+        assert_eq!(std::mem::size_of::<ProcessInfo>(), 24);
+        assert_eq!(std::mem::size_of::<ProcessUtilizationSample>(), 32);
+        assert_eq!(std::mem::size_of::<PciInfo>(), 68);
+
+        let mut buffer = [0 as c_char; 8];
+        for (slot, byte) in buffer.iter_mut().zip(b"A100") {
+            *slot = *byte as c_char;
+        }
+        assert_eq!(c_string(&buffer), "A100");
+    }
+}
@@ -0,0 +1,563 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::monitor::memory_leak::{LeakWarning, MemoryLeakDetector};
+use crate::monitor::ProcessInfo;
+
+const KMSG_PATH: &str = "/dev/kmsg";
+const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+const MAX_SCAN_DEPTH: usize = 6;
+const CGROUP_POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_EVENTS: usize = 200;
+const MAX_TOP_CONSUMERS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OomEventKind {
+    /// The OOM killer killed a process.
+    Kill,
+    /// A cgroup hit `memory.max` and the OOM killer ran without killing
+    /// anything in it.
+    LimitReached,
+}
+
+impl OomEventKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OomEventKind::Kill => "OOM kill",
+            OomEventKind::LimitReached => "Limit reached",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OomSource {
+    /// The kernel log, which names the victim.
+    Kernel,
+    /// A cgroup's `memory.events` counters, for kills the kernel log
+    /// wasn't readable for.
+    Cgroup,
+}
+
+/// A process as it was in the snapshot before an OOM event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OomConsumer {
+    pub pid: u32,
+    pub name: String,
+    /// RSS plus swap, which is what the OOM killer ranks by.
+    pub memory: u64,
+    pub cgroup_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OomEvent {
+    pub timestamp: DateTime<Utc>,
+    pub kind: OomEventKind,
+    pub source: OomSource,
+    pub victim_pid: Option<u32>,
+    pub victim_name: Option<String>,
+    /// anon-rss + file-rss + shmem-rss of the victim when it was killed.
+    pub victim_rss: Option<u64>,
+    /// The victim's cgroup, or the cgroup whose counters changed.
+    pub cgroup: Option<String>,
+    /// The cgroup whose limit was hit, for memcg OOMs.
+    pub oom_memcg: Option<String>,
+    /// Events merged from one change of the cgroup counters.
+    pub count: u64,
+    /// The largest processes in the OOM's scope at the previous refresh;
+    /// empty for events logged before seer started.
+    pub top_consumers: Vec<OomConsumer>,
+    /// The leak alert that was already raised for the victim.
+    pub leak_warning: Option<LeakWarning>,
+}
+
+impl OomEvent {
+    pub fn message(&self) -> String {
+        let scope = self.cgroup.as_deref().unwrap_or("system");
+        match (&self.victim_name, self.victim_pid) {
+            (Some(name), Some(pid)) => format!("{}: {} ({}) in {}", self.kind.label(), name, pid, scope),
+            _ if self.count > 1 => format!("{} x{} in {}", self.kind.label(), self.count, scope),
+            _ => format!("{} in {}", self.kind.label(), scope),
+        }
+    }
+}
+
+/// One record read from `/dev/kmsg`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KmsgRecord {
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+}
+
+/// Non-blocking reader of the kernel log. Reading `/dev/kmsg` needs
+/// CAP_SYSLOG when `kernel.dmesg_restrict` is set, which most
+/// distributions do.
+pub struct KmsgReader {
+    file: File,
+    boot_time: DateTime<Utc>,
+}
+
+impl KmsgReader {
+    pub fn open() -> Option<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(KMSG_PATH)
+            .ok()?;
+        Some(Self { file, boot_time: monotonic_boot_time() })
+    }
+
+    /// Returns the records logged since the previous call. The first call
+    /// returns what is still in the ring buffer.
+    pub fn read_new(&mut self) -> Vec<KmsgRecord> {
+        let mut records = Vec::new();
+        let mut buffer = vec![0u8; 16 * 1024];
+        loop {
+            // Each read returns exactly one record
+            match self.file.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => {
+                    if let Some(record) = parse_kmsg_record(&String::from_utf8_lossy(&buffer[..n]), self.boot_time) {
+                        records.push(record);
+                    }
+                }
+                // Records were overwritten before we read them
+                Err(e) if e.raw_os_error() == Some(libc::EPIPE) => continue,
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            }
+        }
+        records
+    }
+}
+
+/// A kernel message about an OOM kill.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KernelOomMessage {
+    /// `oom-kill:constraint=...,oom_memcg=...,task_memcg=...,task=...,pid=...`,
+    /// logged just before the kill on Linux 4.19 and later.
+    Context {
+        pid: u32,
+        constraint: String,
+        oom_memcg: Option<String>,
+        task_memcg: Option<String>,
+    },
+    /// `Out of memory: Killed process 1234 (name) total-vm:..., anon-rss:...`
+    Killed { pid: u32, name: String, rss: u64 },
+}
+
+struct CgroupOomCounters {
+    oom: u64,
+    oom_kill: u64,
+}
+
+/// Watches the kernel log and cgroup `memory.events` for OOM kills and
+/// keeps a timeline of them.
+pub struct OomMonitor {
+    kmsg: Option<KmsgReader>,
+    kmsg_primed: bool,
+    cgroup_root: PathBuf,
+    cgroup_counters: Option<HashMap<String, CgroupOomCounters>>,
+    last_cgroup_poll: Option<Instant>,
+    /// Kills seen in the kernel log since the last cgroup poll, per
+    /// cgroup, so the counters don't report them a second time.
+    kernel_kills: HashMap<String, u64>,
+    pending_context: Option<KernelOomMessage>,
+    snapshot: Vec<OomConsumer>,
+    events: VecDeque<OomEvent>,
+}
+
+impl Default for OomMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OomMonitor {
+    pub fn new() -> Self {
+        Self::with_sources(KmsgReader::open(), DEFAULT_CGROUP_ROOT)
+    }
+
+    pub fn with_sources<P: AsRef<Path>>(kmsg: Option<KmsgReader>, cgroup_root: P) -> Self {
+        Self {
+            kmsg,
+            kmsg_primed: false,
+            cgroup_root: cgroup_root.as_ref().to_path_buf(),
+            cgroup_counters: None,
+            last_cgroup_poll: None,
+            kernel_kills: HashMap::new(),
+            pending_context: None,
+            snapshot: Vec::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn is_kernel_log_available(&self) -> bool {
+        self.kmsg.is_some()
+    }
+
+    /// Oldest first.
+    pub fn events(&self) -> &VecDeque<OomEvent> {
+        &self.events
+    }
+
+    /// Remembers the processes of the latest metrics, so an event can list
+    /// what was using memory just before it.
+    pub fn record_snapshot(&mut self, processes: &[ProcessInfo]) {
+        self.snapshot = processes.iter()
+            .map(|process| OomConsumer {
+                pid: process.pid,
+                name: process.name.clone(),
+                memory: process.memory_usage + process.memory_detail.map(|detail| detail.swap).unwrap_or(0),
+                cgroup_path: process.cgroup_path.clone(),
+            })
+            .collect();
+    }
+
+    /// Reads new kernel messages and, every few seconds, the cgroup
+    /// counters. Returns the events that happened while seer was running;
+    /// kills of processes the leak detector had flagged are linked to the
+    /// alert.
+    pub fn update(&mut self, leak_detector: &mut MemoryLeakDetector) -> Vec<OomEvent> {
+        let records = self.kmsg.as_mut().map(|kmsg| kmsg.read_new()).unwrap_or_default();
+        let live = self.kmsg_primed;
+        self.kmsg_primed = true;
+
+        let mut events = self.process_kernel_records(&records, live);
+        for event in &mut events {
+            if let (Some(pid), Some(name)) = (event.victim_pid, &event.victim_name) {
+                event.leak_warning = leak_detector.link_oom_kill(pid, name, event.timestamp);
+            }
+        }
+
+        if self.last_cgroup_poll.map(|t| t.elapsed() >= CGROUP_POLL_INTERVAL).unwrap_or(true) {
+            self.last_cgroup_poll = Some(Instant::now());
+            events.extend(self.poll_cgroups(Utc::now()));
+        }
+
+        self.events.extend(events.iter().cloned());
+        while self.events.len() > MAX_EVENTS {
+            self.events.pop_front();
+        }
+        events
+    }
+
+    /// Turns kernel messages into events. Backlog events are kept in the
+    /// timeline but not returned, and get no consumers since the snapshot
+    /// is from a later time.
+    fn process_kernel_records(&mut self, records: &[KmsgRecord], live: bool) -> Vec<OomEvent> {
+        let mut live_events = Vec::new();
+        for record in records {
+            match parse_oom_message(&record.message) {
+                Some(context @ KernelOomMessage::Context { .. }) => self.pending_context = Some(context),
+                Some(KernelOomMessage::Killed { pid, name, rss }) => {
+                    let (cgroup, oom_memcg) = match self.pending_context.take() {
+                        Some(KernelOomMessage::Context { pid: context_pid, constraint, oom_memcg, task_memcg }) if context_pid == pid => {
+                            let oom_memcg = oom_memcg.filter(|_| constraint == "CONSTRAINT_MEMCG");
+                            (task_memcg, oom_memcg)
+                        }
+                        _ => (None, None),
+                    };
+
+                    let mut event = OomEvent {
+                        timestamp: record.timestamp,
+                        kind: OomEventKind::Kill,
+                        source: OomSource::Kernel,
+                        victim_pid: Some(pid),
+                        victim_name: Some(name),
+                        victim_rss: Some(rss),
+                        cgroup,
+                        oom_memcg,
+                        count: 1,
+                        top_consumers: Vec::new(),
+                        leak_warning: None,
+                    };
+                    if !live {
+                        self.events.push_back(event);
+                        continue;
+                    }
+
+                    event.top_consumers = self.top_consumers(event.oom_memcg.as_deref());
+                    if let Some(cgroup) = &event.cgroup {
+                        *self.kernel_kills.entry(cgroup.clone()).or_insert(0) += 1;
+                    }
+                    live_events.push(event);
+                }
+                None => {}
+            }
+        }
+        live_events
+    }
+
+    /// Compares each cgroup's OOM counters with the previous poll. The first
+    /// poll only records a baseline.
+    fn poll_cgroups(&mut self, now: DateTime<Utc>) -> Vec<OomEvent> {
+        let (current, hierarchical) = read_cgroup_oom_counters(&self.cgroup_root);
+        let kernel_kills = std::mem::take(&mut self.kernel_kills);
+        let previous = match self.cgroup_counters.replace(current) {
+            Some(previous) => previous,
+            None => return Vec::new(),
+        };
+        let current = self.cgroup_counters.as_ref().expect("just stored");
+
+        let mut deltas: Vec<(&String, u64, u64)> = current.iter()
+            .filter_map(|(path, counters)| {
+                let before = previous.get(path)?;
+                let kills = counters.oom_kill.saturating_sub(before.oom_kill);
+                let ooms = counters.oom.saturating_sub(before.oom);
+                (kills > 0 || ooms > 0).then_some((path, ooms, kills))
+            })
+            .collect();
+
+        // memory.events counts a child's events in every ancestor too
+        if hierarchical {
+            let changed: Vec<String> = deltas.iter().map(|(path, _, _)| path.to_string()).collect();
+            deltas.retain(|(path, _, _)| {
+                let prefix = format!("{}/", path.trim_end_matches('/'));
+                !changed.iter().any(|other| other.starts_with(&prefix))
+            });
+        }
+        deltas.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut events = Vec::new();
+        for (path, ooms, kills) in deltas {
+            let unseen_kills = kills.saturating_sub(kernel_kills.get(path).copied().unwrap_or(0));
+            let limit_hits = ooms.saturating_sub(kills);
+            for (kind, count) in [(OomEventKind::Kill, unseen_kills), (OomEventKind::LimitReached, limit_hits)] {
+                if count == 0 {
+                    continue;
+                }
+                events.push(OomEvent {
+                    timestamp: now,
+                    kind,
+                    source: OomSource::Cgroup,
+                    victim_pid: None,
+                    victim_name: None,
+                    victim_rss: None,
+                    cgroup: Some(path.clone()),
+                    oom_memcg: Some(path.clone()),
+                    count,
+                    top_consumers: self.top_consumers(Some(path)),
+                    leak_warning: None,
+                });
+            }
+        }
+        events
+    }
+
+    /// The largest processes of the snapshot, within `cgroup` if given.
+    fn top_consumers(&self, cgroup: Option<&str>) -> Vec<OomConsumer> {
+        let in_scope = |consumer: &&OomConsumer| match (cgroup, &consumer.cgroup_path) {
+            (None, _) => true,
+            (Some(cgroup), Some(path)) => {
+                let cgroup = cgroup.trim_end_matches('/');
+                path == cgroup || path.starts_with(&format!("{}/", cgroup))
+            }
+            (Some(_), None) => false,
+        };
+        let mut consumers: Vec<OomConsumer> = self.snapshot.iter().filter(in_scope).cloned().collect();
+        consumers.sort_by_key(|consumer| std::cmp::Reverse(consumer.memory));
+        consumers.truncate(MAX_TOP_CONSUMERS);
+        consumers
+    }
+}
+
+/// Reads `oom` and `oom_kill` of every cgroup below the root, keyed by
+/// cgroup path. Prefers `memory.events.local` (Linux 5.2+); the flag is
+/// set when only the hierarchical `memory.events` was available.
+fn read_cgroup_oom_counters(root: &Path) -> (HashMap<String, CgroupOomCounters>, bool) {
+    let mut counters = HashMap::new();
+    let mut hierarchical = false;
+    let mut stack = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = stack.pop() {
+        let content = fs::read_to_string(dir.join("memory.events.local")).or_else(|_| {
+            let content = fs::read_to_string(dir.join("memory.events"));
+            hierarchical |= content.is_ok();
+            content
+        });
+        if let Ok(content) = content {
+            let events = super::cgroups::parse_flat_keyed(&content);
+            let path = format!("/{}", dir.strip_prefix(root).unwrap_or(&dir).to_string_lossy());
+            counters.insert(path, CgroupOomCounters {
+                oom: events.get("oom").copied().unwrap_or(0),
+                oom_kill: events.get("oom_kill").copied().unwrap_or(0),
+            });
+        }
+
+        if depth >= MAX_SCAN_DEPTH {
+            continue;
+        }
+        if let Ok(entries) = fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                    stack.push((entry.path(), depth + 1));
+                }
+            }
+        }
+    }
+    (counters, hierarchical)
+}
+
+/// Wall-clock time of boot as the kernel log's monotonic timestamps see
+/// it. Unlike `btime` this excludes time spent suspended.
+fn monotonic_boot_time() -> DateTime<Utc> {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: ts is a valid timespec to write into
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    let uptime = Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32);
+    Utc::now() - chrono::Duration::from_std(uptime).unwrap_or_else(|_| chrono::Duration::zero())
+}
+
+/// Parses a `/dev/kmsg` record: `priority,sequence,usec,flags;message`,
+/// optionally followed by indented key=value continuation lines.
+pub fn parse_kmsg_record(record: &str, boot_time: DateTime<Utc>) -> Option<KmsgRecord> {
+    let (header, rest) = record.split_once(';')?;
+    let usec: i64 = header.split(',').nth(2)?.parse().ok()?;
+    let message = rest.lines().next().unwrap_or("").to_string();
+    Some(KmsgRecord {
+        timestamp: boot_time + chrono::Duration::microseconds(usec),
+        message,
+    })
+}
+
+pub fn parse_oom_message(message: &str) -> Option<KernelOomMessage> {
+    if let Some(fields) = message.strip_prefix("oom-kill:") {
+        let fields: HashMap<&str, &str> = fields.split(',').filter_map(|field| field.split_once('=')).collect();
+        let cgroup = |key: &str| fields.get(key).filter(|value| **value != "(null)").map(|value| value.to_string());
+        return Some(KernelOomMessage::Context {
+            pid: fields.get("pid")?.parse().ok()?,
+            constraint: fields.get("constraint")?.to_string(),
+            oom_memcg: cgroup("oom_memcg"),
+            task_memcg: cgroup("task_memcg"),
+        });
+    }
+
+    // "Out of memory: Killed process", "Memory cgroup out of memory: Killed
+    // process", or plain "Killed process" before Linux 4.20
+    let (_, rest) = message.split_once("Killed process ")?;
+    let (pid, rest) = rest.split_once(' ')?;
+    let rest = rest.strip_prefix('(')?;
+    let (name, rest) = rest.rsplit_once(") ")?;
+
+    let rss = ["anon-rss:", "file-rss:", "shmem-rss:"].iter()
+        .filter_map(|key| {
+            let (_, value) = rest.split_once(key)?;
+            value.split(|c: char| !c.is_ascii_digit()).next()?.parse::<u64>().ok()
+        })
+        .sum::<u64>() * 1024;
+
+    Some(KernelOomMessage::Killed {
+        pid: pid.parse().ok()?,
+        name: name.to_string(),
+        rss,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+
+    const CONTEXT: &str = "oom-kill:constraint=CONSTRAINT_MEMCG,nodemask=(null),cpuset=/,mems_allowed=0,oom_memcg=/system.slice/leaky.service,task_memcg=/system.slice/leaky.service,task=leaky,pid=4242,uid=0";
+    const KILLED: &str = "Memory cgroup out of memory: Killed process 4242 (leaky) total-vm:2100000kB, anon-rss:1048576kB, file-rss:2048kB, shmem-rss:0kB, UID:0 pgtables:2200kB oom_score_adj:0";
+
+    fn process(pid: u32, name: &str, memory: u64, cgroup: &str) -> ProcessInfo {
+        ProcessInfo {
+            pid,
+            name: name.to_string(),
+            cpu_usage: 0.0,
+            memory_usage: memory,
+            memory_percentage: 0.0,
+            user: "root".to_string(),
+            status: crate::monitor::ProcessStatusInfo::Running,
+            is_zombie: false,
+            parent_pid: None,
+            command: name.to_string(),
+            start_time: 0,
+            exe_path: None,
+            working_directory: None,
+            group_name: None,
+            threads_count: 1,
+            cgroup_path: Some(cgroup.to_string()),
+            container_id: None,
+            systemd_unit: None,
+            pid_namespace: None,
+            pod: None,
+            memory_detail: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_oom_messages() {
+        assert_eq!(parse_oom_message(CONTEXT), Some(KernelOomMessage::Context {
+            pid: 4242,
+            constraint: "CONSTRAINT_MEMCG".to_string(),
+            oom_memcg: Some("/system.slice/leaky.service".to_string()),
+            task_memcg: Some("/system.slice/leaky.service".to_string()),
+        }));
+        assert_eq!(parse_oom_message(KILLED), Some(KernelOomMessage::Killed {
+            pid: 4242,
+            name: "leaky".to_string(),
+            rss: (1048576 + 2048) * 1024,
+        }));
+        // Names may contain spaces and parentheses
+        assert!(matches!(
+            parse_oom_message("Out of memory: Killed process 7 (Web Content (x)) total-vm:10kB, anon-rss:4kB, file-rss:0kB, shmem-rss:0kB"),
+            Some(KernelOomMessage::Killed { pid: 7, ref name, rss: 4096 }) if name == "Web Content (x)"
+        ));
+        assert_eq!(parse_oom_message("oom_reaper: reaped process 4242 (leaky), now anon-rss:0kB"), None);
+    }
+
+    #[test]
+    fn test_parse_kmsg_record() {
+        let boot = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let record = parse_kmsg_record("3,1234,5000000,-;Out of memory: Killed process 1 (x)\n SUBSYSTEM=mem\n", boot).unwrap();
+        assert_eq!(record.timestamp, DateTime::from_timestamp(1_700_000_005, 0).unwrap());
+        assert_eq!(record.message, "Out of memory: Killed process 1 (x)");
+    }
+
+    #[test]
+    fn test_kernel_kill_with_consumers_and_cgroup_dedup() {
+        let dir = TempDir::new("oom");
+        let service = dir.join("system.slice/leaky.service");
+        fs::create_dir_all(&service).unwrap();
+        fs::write(service.join("memory.events.local"), "low 0\nhigh 0\nmax 5\noom 0\noom_kill 0\n").unwrap();
+
+        let mut monitor = OomMonitor::with_sources(None, &dir);
+        monitor.record_snapshot(&[
+            process(4242, "leaky", 900 << 20, "/system.slice/leaky.service"),
+            process(10, "postgres", 2000 << 20, "/system.slice/postgresql.service"),
+        ]);
+        assert!(monitor.poll_cgroups(Utc::now()).is_empty());
+
+        let now = Utc::now();
+        let records: Vec<KmsgRecord> = [CONTEXT, KILLED].iter()
+            .map(|message| KmsgRecord { timestamp: now, message: message.to_string() })
+            .collect();
+        let events = monitor.process_kernel_records(&records, true);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].victim_name.as_deref(), Some("leaky"));
+        assert_eq!(events[0].oom_memcg.as_deref(), Some("/system.slice/leaky.service"));
+        // Only the memcg's processes are candidates
+        assert_eq!(events[0].top_consumers.len(), 1);
+        assert_eq!(events[0].top_consumers[0].pid, 4242);
+
+        // The counters now show the kill the kernel log already reported,
+        // plus a second one it didn't
+        fs::write(service.join("memory.events.local"), "low 0\nhigh 0\nmax 9\noom 2\noom_kill 2\n").unwrap();
+        let events = monitor.poll_cgroups(Utc::now());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].source, OomSource::Cgroup);
+        assert_eq!(events[0].count, 1);
+        assert_eq!(events[0].cgroup.as_deref(), Some("/system.slice/leaky.service"));
+
+        // Backlog records are kept but not reported as new
+        assert!(monitor.process_kernel_records(&records, false).is_empty());
+        assert_eq!(monitor.events().len(), 1);
+    }
+}
@@ -1,8 +1,19 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use super::sched::SchedStats;
 
 const MAX_HISTORY_SIZE: usize = 300; // 5 minutes at 1-second intervals
+const CONTEXT_SWITCH_STORM_RATE: f32 = 5_000.0; // switches per second
+const SEVERE_CONTEXT_SWITCH_RATE: f32 = 50_000.0;
+const RUN_QUEUE_LATENCY_MS: f32 = 5.0; // average wait per timeslice
+const SEVERE_RUN_QUEUE_LATENCY_MS: f32 = 20.0;
+/// Reading scheduler stats walks every thread of a process, so only the
+/// busiest processes are sampled on each update and the rest less often.
+const SCHED_TOP_PROCESSES: usize = 32;
+const MIN_SCHED_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+const BACKGROUND_SCHED_SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessPerformanceData {
@@ -14,6 +25,16 @@ pub struct ProcessPerformanceData {
     pub io_write_bytes: u64,
     pub threads_count: usize,
     pub context_switches: u64,
+    #[serde(default)]
+    pub involuntary_switches: u64,
+    #[serde(default)]
+    pub run_delay_ns: u64,
+    #[serde(default)]
+    pub timeslices: u64,
+    /// Whether the scheduler counters were read for this point or carried
+    /// over from the previous one; only read points yield rates.
+    #[serde(default)]
+    pub sched_sampled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -39,6 +60,17 @@ pub struct PerformanceStatistics {
     pub io_read_total: u64,
     pub io_write_total: u64,
     pub context_switches_total: u64,
+    /// Switches per second over the latest sample interval.
+    #[serde(default)]
+    pub context_switch_rate: f32,
+    #[serde(default)]
+    pub avg_context_switch_rate: f32,
+    /// Share of the latest switches that were preemptions.
+    #[serde(default)]
+    pub involuntary_switch_ratio: f32,
+    /// Average run-queue wait per timeslice over the latest interval.
+    #[serde(default)]
+    pub run_queue_latency_ms: f32,
     pub uptime_seconds: u64,
 }
 
@@ -57,6 +89,10 @@ impl PerformanceStatistics {
             io_read_total: 0,
             io_write_total: 0,
             context_switches_total: 0,
+            context_switch_rate: 0.0,
+            avg_context_switch_rate: 0.0,
+            involuntary_switch_ratio: 0.0,
+            run_queue_latency_ms: 0.0,
             uptime_seconds: 0,
         }
     }
@@ -103,6 +139,29 @@ impl PerformanceStatistics {
         }
         stats.cpu_usage_variance = variance_sum / count;
 
+        // Scheduler counters are cumulative, so rates come from consecutive samples
+        let mut switch_rates = Vec::new();
+        let sampled: Vec<&ProcessPerformanceData> = history.iter().filter(|data| data.sched_sampled).collect();
+        for (previous, current) in sampled.iter().zip(sampled.iter().skip(1)) {
+            let seconds = (current.timestamp - previous.timestamp).num_milliseconds() as f32 / 1000.0;
+            if seconds <= 0.0 {
+                continue;
+            }
+
+            let switches = current.context_switches.saturating_sub(previous.context_switches);
+            let involuntary = current.involuntary_switches.saturating_sub(previous.involuntary_switches);
+            let timeslices = current.timeslices.saturating_sub(previous.timeslices);
+            let run_delay = current.run_delay_ns.saturating_sub(previous.run_delay_ns);
+
+            switch_rates.push(switches as f32 / seconds);
+            stats.context_switch_rate = switches as f32 / seconds;
+            stats.involuntary_switch_ratio = if switches > 0 { involuntary as f32 / switches as f32 } else { 0.0 };
+            stats.run_queue_latency_ms = if timeslices > 0 { run_delay as f32 / timeslices as f32 / 1_000_000.0 } else { 0.0 };
+        }
+        if !switch_rates.is_empty() {
+            stats.avg_context_switch_rate = switch_rates.iter().sum::<f32>() / switch_rates.len() as f32;
+        }
+
         // Calculate memory growth rate
         if let (Some(first), Some(last)) = (history.front(), history.back()) {
             let time_diff = last.timestamp.timestamp() - first.timestamp.timestamp();
@@ -208,6 +267,39 @@ impl ProcessPerformanceProfile {
             });
         }
 
+        // Check for context switch storms, relative to the process's own baseline
+        let switch_rate = self.statistics.context_switch_rate;
+        let storm_threshold = CONTEXT_SWITCH_STORM_RATE.max(self.statistics.avg_context_switch_rate * 3.0);
+        if switch_rate > storm_threshold {
+            anomalies.push(PerformanceAnomaly {
+                timestamp: self.history.back().map_or_else(Utc::now, |data| data.timestamp),
+                anomaly_type: AnomalyType::ContextSwitchStorm,
+                value: switch_rate as f64,
+                expected_range: (0.0, storm_threshold as f64),
+                severity: if switch_rate > SEVERE_CONTEXT_SWITCH_RATE {
+                    AnomalySeverity::High
+                } else {
+                    AnomalySeverity::Medium
+                },
+            });
+        }
+
+        // Check for threads queueing behind others for a CPU
+        let latency = self.statistics.run_queue_latency_ms;
+        if latency > RUN_QUEUE_LATENCY_MS {
+            anomalies.push(PerformanceAnomaly {
+                timestamp: self.history.back().map_or_else(Utc::now, |data| data.timestamp),
+                anomaly_type: AnomalyType::RunQueueLatency,
+                value: latency as f64,
+                expected_range: (0.0, RUN_QUEUE_LATENCY_MS as f64),
+                severity: if latency > SEVERE_RUN_QUEUE_LATENCY_MS {
+                    AnomalySeverity::High
+                } else {
+                    AnomalySeverity::Medium
+                },
+            });
+        }
+
         anomalies
     }
 
@@ -248,6 +340,7 @@ pub enum AnomalyType {
     HighIoActivity,
     ThreadExplosion,
     ContextSwitchStorm,
+    RunQueueLatency,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -259,16 +352,53 @@ pub enum AnomalySeverity {
 
 pub struct PerformanceProfiler {
     profiles: HashMap<u32, ProcessPerformanceProfile>,
+    sched_sampled_at: HashMap<u32, Instant>,
 }
 
 impl PerformanceProfiler {
     pub fn new() -> Self {
         Self {
             profiles: HashMap::new(),
+            sched_sampled_at: HashMap::new(),
+        }
+    }
+
+    /// Records a data point for every process, reading scheduler stats
+    /// for the busiest ones each second and for the rest every ten.
+    pub fn update_processes(&mut self, processes: &[crate::monitor::ProcessInfo]) {
+        let mut by_cpu: Vec<&crate::monitor::ProcessInfo> = processes.iter().collect();
+        by_cpu.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal));
+        let busiest: HashSet<u32> = by_cpu.iter().take(SCHED_TOP_PROCESSES).map(|p| p.pid).collect();
+
+        for process in processes {
+            let interval = if busiest.contains(&process.pid) {
+                MIN_SCHED_SAMPLE_INTERVAL
+            } else {
+                BACKGROUND_SCHED_SAMPLE_INTERVAL
+            };
+            self.record(process, interval);
         }
     }
 
     pub fn update_process(&mut self, process_info: &crate::monitor::ProcessInfo) {
+        self.record(process_info, MIN_SCHED_SAMPLE_INTERVAL);
+    }
+
+    fn record(&mut self, process_info: &crate::monitor::ProcessInfo, sched_interval: Duration) {
+        let now = Instant::now();
+        let due = self.sched_sampled_at.get(&process_info.pid)
+            .map(|at| now.duration_since(*at) >= sched_interval)
+            .unwrap_or(true);
+        let previous = self.profiles.get(&process_info.pid).and_then(|profile| profile.history.back());
+
+        let (context_switches, involuntary_switches, run_delay_ns, timeslices) = match previous {
+            Some(previous) if !due => (previous.context_switches, previous.involuntary_switches, previous.run_delay_ns, previous.timeslices),
+            _ => {
+                self.sched_sampled_at.insert(process_info.pid, now);
+                let sched = SchedStats::read(process_info.pid).unwrap_or_default();
+                (sched.context_switches(), sched.involuntary_switches, sched.run_delay_ns, sched.timeslices)
+            }
+        };
         let data = ProcessPerformanceData {
             timestamp: Utc::now(),
             cpu_usage: process_info.cpu_usage,
@@ -277,7 +407,11 @@ impl PerformanceProfiler {
             io_read_bytes: 0,  // Would need additional system calls to get IO stats
             io_write_bytes: 0,
             threads_count: process_info.threads_count,
-            context_switches: 0, // Would need additional system calls
+            context_switches,
+            involuntary_switches,
+            run_delay_ns,
+            timeslices,
+            sched_sampled: due || previous.is_none(),
         };
 
         let profile = self.profiles
@@ -319,6 +453,7 @@ impl PerformanceProfiler {
     pub fn cleanup_old_profiles(&mut self, active_pids: &[u32]) {
         let active_set: std::collections::HashSet<u32> = active_pids.iter().cloned().collect();
         self.profiles.retain(|&pid, _| active_set.contains(&pid));
+        self.sched_sampled_at.retain(|pid, _| active_set.contains(pid));
     }
 
     pub fn get_top_cpu_consumers(&self, count: usize) -> Vec<&ProcessPerformanceProfile> {
@@ -338,4 +473,60 @@ impl Default for PerformanceProfiler {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data_point(seconds: i64, context_switches: u64, run_delay_ns: u64, timeslices: u64) -> ProcessPerformanceData {
+        ProcessPerformanceData {
+            timestamp: DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            cpu_usage: 10.0,
+            memory_usage: 1024,
+            memory_percentage: 0.1,
+            io_read_bytes: 0,
+            io_write_bytes: 0,
+            threads_count: 4,
+            context_switches,
+            involuntary_switches: context_switches / 2,
+            run_delay_ns,
+            timeslices,
+            sched_sampled: true,
+        }
+    }
+
+    #[test]
+    fn test_flags_context_switch_storms_and_run_queue_latency() {
+        let mut profile = ProcessPerformanceProfile::new(1, "worker".to_string());
+        for second in 0..10 {
+            profile.add_data_point(data_point(second, second as u64 * 200, second as u64 * 1_000_000, second as u64 * 100));
+        }
+        assert!(profile.get_anomalies().is_empty());
+        assert_eq!(profile.statistics.context_switch_rate, 200.0);
+
+        // 60k switches in one second, each slice waiting 30ms for a CPU
+        profile.add_data_point(data_point(10, 1_800 + 60_000, 9_000_000 + 3_000_000_000, 900 + 100));
+        let anomalies = profile.get_anomalies();
+        let storm = anomalies.iter().find(|a| matches!(a.anomaly_type, AnomalyType::ContextSwitchStorm)).unwrap();
+        assert_eq!(storm.value, 60_000.0);
+        assert!(matches!(storm.severity, AnomalySeverity::High));
+        assert!((profile.statistics.involuntary_switch_ratio - 0.5).abs() < 0.01);
+
+        let latency = anomalies.iter().find(|a| matches!(a.anomaly_type, AnomalyType::RunQueueLatency)).unwrap();
+        assert_eq!(latency.value, 30.0);
+        assert!(matches!(latency.severity, AnomalySeverity::High));
+    }
+
+    #[test]
+    fn test_rates_skip_points_without_scheduler_samples() {
+        let mut profile = ProcessPerformanceProfile::new(1, "worker".to_string());
+        profile.add_data_point(data_point(0, 0, 0, 0));
+        // Carried over between samples: no change, but not a zero rate either
+        let mut carried = data_point(1, 0, 0, 0);
+        carried.sched_sampled = false;
+        profile.add_data_point(carried);
+        profile.add_data_point(data_point(2, 1_000, 0, 100));
+        assert_eq!(profile.statistics.context_switch_rate, 500.0);
+        assert_eq!(profile.statistics.avg_context_switch_rate, 500.0);
+    }
+}
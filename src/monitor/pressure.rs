@@ -0,0 +1,419 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const PROC_PRESSURE: &str = "/proc/pressure";
+const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// One line of a PSI file: the share of wall time in which tasks stalled,
+/// averaged over 10s, 60s and 300s, and the cumulative stall time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PressureLine {
+    pub avg10: f32,
+    pub avg60: f32,
+    pub avg300: f32,
+    pub total_usec: u64,
+}
+
+/// `some`: at least one task stalled. `full`: all non-idle tasks stalled
+/// at once; absent for system-wide CPU before Linux 5.13.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourcePressure {
+    pub some: PressureLine,
+    pub full: Option<PressureLine>,
+}
+
+impl ResourcePressure {
+    /// avg10 as `1.5% some, 0.5% full`.
+    pub fn summary(&self) -> String {
+        match &self.full {
+            Some(full) => format!("{:.1}% some, {:.1}% full", self.some.avg10, full.avg10),
+            None => format!("{:.1}% some", self.some.avg10),
+        }
+    }
+
+    pub fn line(&self, kind: StallKind) -> Option<&PressureLine> {
+        match kind {
+            StallKind::Some => Some(&self.some),
+            StallKind::Full => self.full.as_ref(),
+        }
+    }
+}
+
+/// PSI for the whole system or one cgroup; None where the kernel was built
+/// without `CONFIG_PSI` or booted with `psi=0`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PressureMetrics {
+    pub cpu: Option<ResourcePressure>,
+    pub memory: Option<ResourcePressure>,
+    pub io: Option<ResourcePressure>,
+}
+
+impl PressureMetrics {
+    pub fn read_system() -> Self {
+        Self::read_proc(Path::new(PROC_PRESSURE))
+    }
+
+    /// Reads `cpu`, `memory` and `io` from a `/proc/pressure` directory.
+    pub fn read_proc(dir: &Path) -> Self {
+        Self::read_files(|resource| dir.join(resource.label()))
+    }
+
+    /// Reads `cpu.pressure`, `memory.pressure` and `io.pressure` from a
+    /// cgroup v2 directory.
+    pub fn read_cgroup(cgroup_path: &Path) -> Self {
+        Self::read_files(|resource| cgroup_path.join(format!("{}.pressure", resource.label())))
+    }
+
+    fn read_files<F: Fn(PressureResource) -> PathBuf>(path: F) -> Self {
+        let read = |resource| fs::read_to_string(path(resource)).ok().and_then(|content| parse_pressure(&content));
+        Self {
+            cpu: read(PressureResource::Cpu),
+            memory: read(PressureResource::Memory),
+            io: read(PressureResource::Io),
+        }
+    }
+
+    pub fn get(&self, resource: PressureResource) -> Option<&ResourcePressure> {
+        match resource {
+            PressureResource::Cpu => self.cpu.as_ref(),
+            PressureResource::Memory => self.memory.as_ref(),
+            PressureResource::Io => self.io.as_ref(),
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.cpu.is_some() || self.memory.is_some() || self.io.is_some()
+    }
+
+    /// The resource with the highest `some` avg10.
+    pub fn worst(&self) -> Option<(PressureResource, f32)> {
+        [PressureResource::Cpu, PressureResource::Memory, PressureResource::Io].into_iter()
+            .filter_map(|resource| self.some_avg10(resource).map(|avg10| (resource, avg10)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// `some` avg10 for a resource, the usual headline figure.
+    pub fn some_avg10(&self, resource: PressureResource) -> Option<f32> {
+        self.get(resource).map(|pressure| pressure.some.avg10)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureResource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl PressureResource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PressureResource::Cpu => "cpu",
+            PressureResource::Memory => "memory",
+            PressureResource::Io => "io",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StallKind {
+    #[default]
+    Some,
+    Full,
+}
+
+impl StallKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StallKind::Some => "some",
+            StallKind::Full => "full",
+        }
+    }
+}
+
+/// Fires when the share of time stalled, averaged over the span between
+/// refreshes that covers at least `window_ms`, reaches `stall_ms` per
+/// `window_ms`. Unlike a kernel PSI trigger fd, a burst shorter than the
+/// refresh interval is diluted by the rest of the span and may not fire.
+/// The cumulative totals need no privileges and also work on cgroups.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PressureTrigger {
+    pub resource: PressureResource,
+    #[serde(default)]
+    pub kind: StallKind,
+    pub stall_ms: u64,
+    pub window_ms: u64,
+    /// Cgroup path such as `/system.slice/nginx.service`; system-wide if
+    /// unset.
+    #[serde(default)]
+    pub cgroup: Option<String>,
+}
+
+impl PressureTrigger {
+    pub fn threshold_percent(&self) -> f64 {
+        if self.window_ms == 0 {
+            return 100.0;
+        }
+        self.stall_ms as f64 * 100.0 / self.window_ms as f64
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{} {} {}ms/{}ms", self.resource.label(), self.kind.label(), self.stall_ms, self.window_ms)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PressureConfig {
+    #[serde(default)]
+    pub triggers: Vec<PressureTrigger>,
+}
+
+impl Default for PressureConfig {
+    fn default() -> Self {
+        let trigger = |resource, kind, stall_ms| PressureTrigger {
+            resource,
+            kind,
+            stall_ms,
+            window_ms: 1000,
+            cgroup: None,
+        };
+        Self {
+            triggers: vec![
+                trigger(PressureResource::Cpu, StallKind::Some, 500),
+                trigger(PressureResource::Memory, StallKind::Full, 100),
+                trigger(PressureResource::Io, StallKind::Full, 100),
+            ],
+        }
+    }
+}
+
+/// A trigger that has just started firing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PressureEvent {
+    pub trigger: PressureTrigger,
+    /// Stall time as a percentage of the window.
+    pub stall_percent: f64,
+}
+
+impl PressureEvent {
+    pub fn message(&self) -> String {
+        let scope = self.trigger.cgroup.as_deref().unwrap_or("system");
+        format!(
+            "{} pressure on {}: {} stall at {:.1}% of the time (trigger {:.1}%)",
+            self.trigger.resource.label(),
+            scope,
+            self.trigger.kind.label(),
+            self.stall_percent,
+            self.trigger.threshold_percent(),
+        )
+    }
+}
+
+struct TriggerState {
+    trigger: PressureTrigger,
+    samples: VecDeque<(Instant, u64)>,
+    firing: bool,
+}
+
+impl TriggerState {
+    /// Records a cumulative total and returns the stall percentage over the
+    /// most recent span of at least one window.
+    fn sample(&mut self, now: Instant, total_usec: u64) -> Option<f64> {
+        // A counter going backwards means the cgroup was recreated
+        if self.samples.back().is_some_and(|(_, total)| *total > total_usec) {
+            self.samples.clear();
+        }
+        self.samples.push_back((now, total_usec));
+
+        let window = Duration::from_millis(self.trigger.window_ms);
+        while self.samples.len() > 2 && now.duration_since(self.samples[1].0) >= window {
+            self.samples.pop_front();
+        }
+
+        let (since, base) = *self.samples.front()?;
+        let span = now.duration_since(since);
+        if span.is_zero() {
+            return None;
+        }
+        let stalled = total_usec.saturating_sub(base) as f64;
+        Some((stalled * 100.0 / span.as_micros() as f64).min(100.0))
+    }
+}
+
+/// Latest system-wide PSI and the configured triggers.
+pub struct PressureMonitor {
+    proc_root: PathBuf,
+    cgroup_root: PathBuf,
+    current: PressureMetrics,
+    triggers: Vec<TriggerState>,
+}
+
+impl Default for PressureMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PressureMonitor {
+    pub fn new() -> Self {
+        Self::with_roots(PROC_PRESSURE, DEFAULT_CGROUP_ROOT)
+    }
+
+    pub fn with_roots<P: AsRef<Path>, Q: AsRef<Path>>(proc_root: P, cgroup_root: Q) -> Self {
+        let mut monitor = Self {
+            proc_root: proc_root.as_ref().to_path_buf(),
+            cgroup_root: cgroup_root.as_ref().to_path_buf(),
+            current: PressureMetrics::default(),
+            triggers: Vec::new(),
+        };
+        monitor.configure(PressureConfig::default());
+        monitor
+    }
+
+    pub fn configure(&mut self, config: PressureConfig) {
+        self.triggers = config.triggers.into_iter()
+            .map(|trigger| TriggerState { trigger, samples: VecDeque::new(), firing: false })
+            .collect();
+    }
+
+    pub fn current(&self) -> &PressureMetrics {
+        &self.current
+    }
+
+    /// Re-reads PSI and returns the triggers that started firing.
+    pub fn update(&mut self) -> Vec<PressureEvent> {
+        self.update_at(Instant::now())
+    }
+
+    fn update_at(&mut self, now: Instant) -> Vec<PressureEvent> {
+        self.current = PressureMetrics::read_proc(&self.proc_root);
+
+        let mut events = Vec::new();
+        for state in &mut self.triggers {
+            let metrics = match &state.trigger.cgroup {
+                Some(cgroup) => PressureMetrics::read_cgroup(&self.cgroup_root.join(cgroup.trim_start_matches('/'))),
+                None => self.current.clone(),
+            };
+            let total = metrics.get(state.trigger.resource)
+                .and_then(|pressure| pressure.line(state.trigger.kind))
+                .map(|line| line.total_usec);
+            let stall_percent = match total.and_then(|total| state.sample(now, total)) {
+                Some(percent) => percent,
+                None => continue,
+            };
+
+            let firing = stall_percent >= state.trigger.threshold_percent();
+            if firing && !state.firing {
+                events.push(PressureEvent { trigger: state.trigger.clone(), stall_percent });
+            }
+            state.firing = firing;
+        }
+        events
+    }
+}
+
+/// Parses a PSI file such as `/proc/pressure/memory` or `io.pressure`.
+pub fn parse_pressure(content: &str) -> Option<ResourcePressure> {
+    let mut some = None;
+    let mut full = None;
+
+    for line in content.lines() {
+        let mut fields = line.split_whitespace();
+        let kind = fields.next()?;
+        let mut parsed = PressureLine::default();
+        for field in fields {
+            let (key, value) = match field.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+            match key {
+                "avg10" => parsed.avg10 = value.parse().ok()?,
+                "avg60" => parsed.avg60 = value.parse().ok()?,
+                "avg300" => parsed.avg300 = value.parse().ok()?,
+                "total" => parsed.total_usec = value.parse().ok()?,
+                _ => {}
+            }
+        }
+        match kind {
+            "some" => some = Some(parsed),
+            "full" => full = Some(parsed),
+            _ => {}
+        }
+    }
+
+    Some(ResourcePressure { some: some?, full })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+
+    const MEMORY: &str = "some avg10=1.50 avg60=0.75 avg300=0.20 total=123456\n\
+                          full avg10=0.50 avg60=0.25 avg300=0.05 total=45678\n";
+
+    #[test]
+    fn test_parse_pressure() {
+        let pressure = parse_pressure(MEMORY).unwrap();
+        assert_eq!(pressure.some.avg10, 1.5);
+        assert_eq!(pressure.some.total_usec, 123456);
+        assert_eq!(pressure.full.unwrap().avg300, 0.05);
+        assert_eq!(pressure.summary(), "1.5% some, 0.5% full");
+
+        // System-wide cpu before 5.13 has no full line
+        let cpu = parse_pressure("some avg10=0.00 avg60=0.00 avg300=0.00 total=0\n").unwrap();
+        assert_eq!(cpu.full, None);
+        assert_eq!(parse_pressure(""), None);
+    }
+
+    #[test]
+    fn test_trigger_fires_once_per_episode() {
+        let dir = TempDir::new("psi");
+        let write = |total: u64| {
+            fs::write(dir.join("io"), format!(
+                "some avg10=0.00 avg60=0.00 avg300=0.00 total={}\nfull avg10=0.00 avg60=0.00 avg300=0.00 total={}\n",
+                total, total
+            )).unwrap();
+        };
+
+        let mut monitor = PressureMonitor::with_roots(&dir, &dir);
+        monitor.configure(PressureConfig {
+            triggers: vec![PressureTrigger {
+                resource: PressureResource::Io,
+                kind: StallKind::Full,
+                stall_ms: 100,
+                window_ms: 1000,
+                cgroup: None,
+            }],
+        });
+
+        let start = Instant::now();
+        let at = |seconds: u64| start + Duration::from_secs(seconds);
+
+        write(0);
+        assert!(monitor.update_at(at(0)).is_empty());
+        assert!(monitor.current().io.is_some());
+        assert!(monitor.current().memory.is_none());
+
+        // 500ms stalled over 2s is 25%, above the 10% trigger
+        write(500_000);
+        let events = monitor.update_at(at(2));
+        assert_eq!(events.len(), 1);
+        assert!((events[0].stall_percent - 25.0).abs() < 0.01);
+
+        // Still stalling: no repeat
+        write(1_000_000);
+        assert!(monitor.update_at(at(4)).is_empty());
+
+        // Recovered, then stalls again
+        write(1_010_000);
+        assert!(monitor.update_at(at(6)).is_empty());
+        write(1_500_000);
+        assert_eq!(monitor.update_at(at(8)).len(), 1);
+    }
+}
@@ -1,6 +1,17 @@
 use sysinfo::ProcessStatus;
 use crate::monitor::SystemMonitor;
-use std::collections::HashMap;
+use crate::monitor::cgroups::{container_id_from_cgroup_path, parse_proc_cgroup, systemd_unit_from_cgroup_path};
+use crate::monitor::kubernetes::PodRef;
+use crate::monitor::memory_detail::ProcessMemoryDetail;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::time::{Duration, Instant};
+
+/// smaps_rollup is read for the largest processes every couple of seconds
+/// and for the rest on a slower cycle.
+const MEMORY_DETAIL_TOP_PROCESSES: usize = 32;
+const MEMORY_DETAIL_INTERVAL: Duration = Duration::from_secs(2);
+const BACKGROUND_MEMORY_DETAIL_INTERVAL: Duration = Duration::from_secs(30);
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct ProcessInfo {
@@ -19,6 +30,17 @@ pub struct ProcessInfo {
     pub working_directory: Option<String>,
     pub group_name: Option<String>,
     pub threads_count: usize,
+    pub cgroup_path: Option<String>,
+    /// Runtime container ID from the cgroup path, or "pidns-<inode>" for
+    /// processes in a foreign PID namespace without a recognisable cgroup.
+    pub container_id: Option<String>,
+    pub systemd_unit: Option<String>,
+    pub pid_namespace: Option<u64>,
+    pub pod: Option<PodRef>,
+    /// PSS, USS and swap from smaps_rollup; None for other users'
+    /// processes when not running as root.
+    #[serde(default)]
+    pub memory_detail: Option<ProcessMemoryDetail>,
 }
 
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
@@ -87,6 +109,9 @@ pub enum ProcessGroupBy {
     Parent,
     Application,
     Status,
+    Container,
+    Unit,
+    Pod,
     None,
 }
 
@@ -100,10 +125,20 @@ pub struct ProcessGroup {
 }
 
 impl SystemMonitor {
-    pub fn get_process_info(&self) -> Vec<ProcessInfo> {
+    pub fn get_process_info(&mut self) -> Vec<ProcessInfo> {
         let total_memory = self.system.total_memory() as f32;
+        let host_pid_namespace = read_namespace_inode(1, "pid")
+            .or_else(|| read_namespace_inode(std::process::id(), "pid"));
+
+        let mut by_rss: Vec<(u32, u64)> = self.system.processes().iter()
+            .map(|(pid, process)| (pid.as_u32(), process.memory()))
+            .collect();
+        by_rss.sort_by(|a, b| b.1.cmp(&a.1));
+        let largest: HashSet<u32> = by_rss.iter().take(MEMORY_DETAIL_TOP_PROCESSES).map(|(pid, _)| *pid).collect();
+        let now = Instant::now();
+        let memory_details = &mut self.memory_details;
         
-        self.system
+        let processes: Vec<ProcessInfo> = self.system
             .processes()
             .iter()
             .map(|(pid, process)| {
@@ -123,6 +158,21 @@ impl SystemMonitor {
                         .map(|s| s.to_string())
                 });
 
+                let cgroup_path = fs::read_to_string(format!("/proc/{}/cgroup", pid.as_u32()))
+                    .ok()
+                    .and_then(|content| parse_proc_cgroup(&content));
+                let pid_namespace = read_namespace_inode(pid.as_u32(), "pid");
+                let container_id = cgroup_path.as_deref()
+                    .and_then(container_id_from_cgroup_path)
+                    .map(|id| id.to_string())
+                    .or_else(|| match (pid_namespace, host_pid_namespace) {
+                        (Some(ns), Some(host)) if ns != host => Some(format!("pidns-{}", ns)),
+                        _ => None,
+                    });
+                let systemd_unit = cgroup_path.as_deref()
+                    .and_then(systemd_unit_from_cgroup_path)
+                    .map(|unit| unit.to_string());
+
                 ProcessInfo {
                     pid: pid.as_u32(),
                     name: process.name().to_string(),
@@ -139,9 +189,28 @@ impl SystemMonitor {
                     working_directory: process.cwd().map(|p| p.to_string_lossy().to_string()),
                     group_name,
                     threads_count: process.tasks().map(|tasks| tasks.len()).unwrap_or(1),
+                    cgroup_path,
+                    container_id,
+                    systemd_unit,
+                    pid_namespace,
+                    pod: None,
+                    memory_detail: if is_zombie {
+                        None
+                    } else {
+                        let interval = if largest.contains(&pid.as_u32()) {
+                            MEMORY_DETAIL_INTERVAL
+                        } else {
+                            BACKGROUND_MEMORY_DETAIL_INTERVAL
+                        };
+                        memory_details.get(pid.as_u32(), interval, now)
+                    },
                 }
             })
-            .collect()
+            .collect();
+
+        let live: HashSet<u32> = processes.iter().map(|p| p.pid).collect();
+        self.memory_details.retain(|pid| live.contains(&pid));
+        processes
     }
 
     pub fn group_processes(processes: &[ProcessInfo], group_by: ProcessGroupBy) -> Vec<ProcessGroup> {
@@ -186,6 +255,35 @@ impl SystemMonitor {
                 }
                 Self::create_process_groups(groups)
             },
+            ProcessGroupBy::Container => {
+                let mut groups: HashMap<String, Vec<ProcessInfo>> = HashMap::new();
+                for process in processes {
+                    let container_key = process.container_id.as_ref()
+                        .map(|id| format!("Container: {}", short_container_id(id)))
+                        .unwrap_or_else(|| "Host".to_string());
+                    groups.entry(container_key).or_default().push(process.clone());
+                }
+                Self::create_process_groups(groups)
+            },
+            ProcessGroupBy::Unit => {
+                let mut groups: HashMap<String, Vec<ProcessInfo>> = HashMap::new();
+                for process in processes {
+                    let unit_key = process.systemd_unit.clone()
+                        .unwrap_or_else(|| "No Unit".to_string());
+                    groups.entry(unit_key).or_default().push(process.clone());
+                }
+                Self::create_process_groups(groups)
+            },
+            ProcessGroupBy::Pod => {
+                let mut groups: HashMap<String, Vec<ProcessInfo>> = HashMap::new();
+                for process in processes {
+                    let pod_key = process.pod.as_ref()
+                        .map(|pod| pod.qualified_name())
+                        .unwrap_or_else(|| "No Pod".to_string());
+                    groups.entry(pod_key).or_default().push(process.clone());
+                }
+                Self::create_process_groups(groups)
+            },
         }
     }
 
@@ -235,4 +333,23 @@ impl SystemMonitor {
         result
     }
 
-}
\ No newline at end of file
+}
+
+/// Shortens a 64-character runtime ID to the 12 characters Docker shows.
+pub fn short_container_id(id: &str) -> &str {
+    if id.len() == 64 {
+        &id[..12]
+    } else {
+        id
+    }
+}
+
+/// Reads the inode of /proc/<pid>/ns/<kind>, whose link target looks
+/// like "pid:[4026531836]".
+fn read_namespace_inode(pid: u32, kind: &str) -> Option<u64> {
+    let target = fs::read_link(format!("/proc/{}/ns/{}", pid, kind)).ok()?;
+    let target = target.to_string_lossy();
+    let start = target.find('[')? + 1;
+    let end = target.rfind(']')?;
+    target.get(start..end)?.parse().ok()
+}
@@ -0,0 +1,389 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const MIN_THREAD_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Scheduler counters for a whole process, summed over its threads.
+///
+/// `/proc/<pid>/status` and `/proc/<pid>/schedstat` only describe the main
+/// thread, so the per-task files under `/proc/<pid>/task` are added up.
+/// Threads that exited since the last read take their counts with them,
+/// which is why rates are computed with saturating differences.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchedStats {
+    pub voluntary_switches: u64,
+    pub involuntary_switches: u64,
+    /// Time spent running on a CPU.
+    pub run_time_ns: u64,
+    /// Time spent runnable but waiting on a run queue.
+    pub run_delay_ns: u64,
+    /// Number of times a thread was given a CPU.
+    pub timeslices: u64,
+}
+
+impl SchedStats {
+    pub fn read(pid: u32) -> Result<Self> {
+        Self::read_from(&Path::new("/proc").join(pid.to_string()))
+    }
+
+    pub fn read_from(process_dir: &Path) -> Result<Self> {
+        let entries = match fs::read_dir(process_dir.join("task")) {
+            Ok(entries) => entries,
+            Err(_) => return Self::read_task(process_dir),
+        };
+
+        let mut total = Self::default();
+        for entry in entries.flatten() {
+            if let Ok(task) = Self::read_task(&entry.path()) {
+                total.add(&task);
+            }
+        }
+        Ok(total)
+    }
+
+    /// Reads one task directory. `schedstat` is missing on kernels built
+    /// without `CONFIG_SCHED_INFO`, so only `status` is required.
+    fn read_task(task_dir: &Path) -> Result<Self> {
+        let (voluntary_switches, involuntary_switches) =
+            parse_context_switches(&fs::read_to_string(task_dir.join("status"))?)
+                .ok_or_else(|| anyhow!("no context switch counters in {}", task_dir.display()))?;
+        let (run_time_ns, run_delay_ns, timeslices) = fs::read_to_string(task_dir.join("schedstat"))
+            .ok()
+            .and_then(|content| parse_schedstat(&content))
+            .unwrap_or_default();
+
+        Ok(Self {
+            voluntary_switches,
+            involuntary_switches,
+            run_time_ns,
+            run_delay_ns,
+            timeslices,
+        })
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.voluntary_switches += other.voluntary_switches;
+        self.involuntary_switches += other.involuntary_switches;
+        self.run_time_ns += other.run_time_ns;
+        self.run_delay_ns += other.run_delay_ns;
+        self.timeslices += other.timeslices;
+    }
+
+    pub fn context_switches(&self) -> u64 {
+        self.voluntary_switches + self.involuntary_switches
+    }
+}
+
+/// Returns the voluntary and involuntary switch counts from a `status` file.
+pub fn parse_context_switches(content: &str) -> Option<(u64, u64)> {
+    let mut voluntary = None;
+    let mut involuntary = None;
+    for line in content.lines() {
+        if let Some(value) = line.strip_prefix("voluntary_ctxt_switches:") {
+            voluntary = value.trim().parse().ok();
+        } else if let Some(value) = line.strip_prefix("nonvoluntary_ctxt_switches:") {
+            involuntary = value.trim().parse().ok();
+        }
+    }
+    Some((voluntary?, involuntary?))
+}
+
+/// Parses `schedstat`: run time, run-queue delay (both in ns) and timeslices.
+pub fn parse_schedstat(content: &str) -> Option<(u64, u64, u64)> {
+    let mut fields = content.split_whitespace().map(|field| field.parse::<u64>().ok());
+    Some((fields.next()??, fields.next()??, fields.next()??))
+}
+
+/// One thread as described by `/proc/<pid>/task/<tid>/stat`, with its
+/// wait channel and allowed CPUs from the neighbouring files.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreadStat {
+    pub tid: u32,
+    pub name: String,
+    pub state: char,
+    /// User and system time in clock ticks.
+    pub utime: u64,
+    pub stime: u64,
+    pub priority: i64,
+    pub nice: i64,
+    /// The CPU the thread last ran on.
+    pub last_cpu: u32,
+    pub rt_priority: u32,
+    /// The `SCHED_*` policy number.
+    pub policy: u32,
+    /// The kernel function the thread sleeps in; `None` while runnable
+    /// or when the kernel hides it.
+    pub wchan: Option<String>,
+    /// `Cpus_allowed_list` from the thread's status, e.g. `0-3,8`.
+    pub cpus_allowed: String,
+}
+
+impl ThreadStat {
+    /// Parses a `stat` line. The name is wrapped in parentheses and may
+    /// itself contain spaces or parentheses, so fields are counted from
+    /// the last closing one.
+    pub fn parse(content: &str) -> Option<Self> {
+        let open = content.find('(')?;
+        let close = content.rfind(')')?;
+        let tid = content[..open].trim().parse().ok()?;
+        let name = content.get(open + 1..close)?.to_string();
+        let fields: Vec<&str> = content[close + 1..].split_whitespace().collect();
+        // `fields[0]` is field 3 of proc(5), the state.
+        let field = |number: usize| fields.get(number - 3).copied();
+
+        Some(Self {
+            tid,
+            name,
+            state: field(3)?.chars().next()?,
+            utime: field(14)?.parse().ok()?,
+            stime: field(15)?.parse().ok()?,
+            priority: field(18)?.parse().ok()?,
+            nice: field(19)?.parse().ok()?,
+            last_cpu: field(39)?.parse().ok()?,
+            rt_priority: field(40)?.parse().ok()?,
+            policy: field(41)?.parse().ok()?,
+            wchan: None,
+            cpus_allowed: String::new(),
+        })
+    }
+
+    pub fn cpu_ticks(&self) -> u64 {
+        self.utime + self.stime
+    }
+
+    pub fn policy_name(&self) -> &'static str {
+        match self.policy {
+            0 => "TS",
+            1 => "FF",
+            2 => "RR",
+            3 => "B",
+            5 => "IDL",
+            6 => "DLN",
+            _ => "?",
+        }
+    }
+
+    /// Whether the thread may only run on one CPU.
+    pub fn is_pinned(&self) -> bool {
+        !self.cpus_allowed.is_empty() && !self.cpus_allowed.contains([',', '-'])
+    }
+}
+
+/// `wchan` reads as `0` for a runnable thread; kernels with
+/// `kernel.kptr_restrict` set report it as 0 for everyone else's threads.
+fn parse_wchan(content: &str) -> Option<String> {
+    let wchan = content.trim();
+    (!wchan.is_empty() && wchan != "0").then(|| wchan.to_string())
+}
+
+fn parse_cpus_allowed(status: &str) -> Option<String> {
+    status.lines()
+        .find_map(|line| line.strip_prefix("Cpus_allowed_list:"))
+        .map(|value| value.trim().to_string())
+}
+
+/// Sets the nice value of a single thread. Linux treats `PRIO_PROCESS`
+/// with a TID as that thread alone.
+pub fn set_thread_nice(tid: u32, nice: i32) -> Result<()> {
+    // SAFETY: setpriority takes plain integers; an unknown TID is reported as ESRCH
+    let result = unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) };
+    if result != 0 {
+        return Err(anyhow!("setpriority failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(())
+}
+
+pub fn read_threads(pid: u32) -> Vec<ThreadStat> {
+    read_threads_from(&Path::new("/proc").join(pid.to_string()))
+}
+
+pub fn read_threads_from(process_dir: &Path) -> Vec<ThreadStat> {
+    let entries = match fs::read_dir(process_dir.join("task")) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut threads: Vec<ThreadStat> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let task_dir = entry.path();
+            let mut thread = ThreadStat::parse(&fs::read_to_string(task_dir.join("stat")).ok()?)?;
+            thread.wchan = fs::read_to_string(task_dir.join("wchan")).ok()
+                .and_then(|content| parse_wchan(&content));
+            thread.cpus_allowed = fs::read_to_string(task_dir.join("status")).ok()
+                .and_then(|content| parse_cpus_allowed(&content))
+                .unwrap_or_default();
+            Some(thread)
+        })
+        .collect();
+    threads.sort_by_key(|thread| thread.tid);
+    threads
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreadUsage {
+    pub thread: ThreadStat,
+    /// Percent of one CPU since the previous sample.
+    pub cpu_usage: f64,
+}
+
+/// Turns per-thread tick counters into CPU percentages for one process
+/// at a time, which is all Process Details needs.
+pub struct ThreadSampler {
+    proc_root: PathBuf,
+    ticks_per_second: f64,
+    pid: Option<u32>,
+    previous: HashMap<u32, u64>,
+    taken_at: Option<Instant>,
+    threads: Vec<ThreadUsage>,
+}
+
+impl ThreadSampler {
+    pub fn new() -> Self {
+        Self::with_root(PathBuf::from("/proc"))
+    }
+
+    pub fn with_root(proc_root: PathBuf) -> Self {
+        // SAFETY: sysconf only reads a configuration value
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        Self {
+            proc_root,
+            ticks_per_second: if ticks > 0 { ticks as f64 } else { 100.0 },
+            pid: None,
+            previous: HashMap::new(),
+            taken_at: None,
+            threads: Vec::new(),
+        }
+    }
+
+    /// Samples closer together than `MIN_THREAD_SAMPLE_INTERVAL` reuse the
+    /// previous figures, since views may redraw many times per second.
+    pub fn sample(&mut self, pid: u32) -> &[ThreadUsage] {
+        let now = Instant::now();
+        if self.pid != Some(pid) {
+            self.pid = Some(pid);
+            self.previous.clear();
+            self.taken_at = None;
+        } else if self.taken_at.is_some_and(|taken_at| now.duration_since(taken_at) < MIN_THREAD_SAMPLE_INTERVAL) {
+            return &self.threads;
+        }
+
+        let threads = read_threads_from(&self.proc_root.join(pid.to_string()));
+        self.record(threads, now);
+        &self.threads
+    }
+
+    fn record(&mut self, threads: Vec<ThreadStat>, now: Instant) {
+        let elapsed = self.taken_at.map(|taken_at| now.duration_since(taken_at).as_secs_f64());
+        let mut current = HashMap::with_capacity(threads.len());
+
+        self.threads = threads
+            .into_iter()
+            .map(|thread| {
+                let ticks = thread.cpu_ticks();
+                let cpu_usage = match (elapsed, self.previous.get(&thread.tid)) {
+                    (Some(elapsed), Some(&before)) if elapsed > 0.0 => {
+                        ticks.saturating_sub(before) as f64 / self.ticks_per_second / elapsed * 100.0
+                    }
+                    _ => 0.0,
+                };
+                current.insert(thread.tid, ticks);
+                ThreadUsage { thread, cpu_usage }
+            })
+            .collect();
+
+        self.previous = current;
+        self.taken_at = Some(now);
+    }
+}
+
+impl Default for ThreadSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+
+    fn stat_line(tid: u32, name: &str, utime: u64, stime: u64, cpu: u32) -> String {
+        format!(
+            "{} ({}) S 1 {} {} 0 -1 4194560 120 0 0 0 {} {} 0 0 20 0 3 0 4242 1000000 200 \
+             18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 {} 0 0 0 0 0",
+            tid, name, tid, tid, utime, stime, cpu
+        )
+    }
+
+    #[test]
+    fn test_parses_thread_stat_with_awkward_names() {
+        let thread = ThreadStat::parse(&stat_line(4321, "tokio (worker) 2", 150, 30, 5)).unwrap();
+        assert_eq!(thread.tid, 4321);
+        assert_eq!(thread.name, "tokio (worker) 2");
+        assert_eq!(thread.state, 'S');
+        assert_eq!(thread.cpu_ticks(), 180);
+        assert_eq!(thread.priority, 20);
+        assert_eq!(thread.nice, 0);
+        assert_eq!(thread.last_cpu, 5);
+        assert_eq!(thread.policy_name(), "TS");
+    }
+
+    #[test]
+    fn test_parses_status_and_schedstat() {
+        let status = "Name:\tnginx\nThreads:\t1\nvoluntary_ctxt_switches:\t1200\nnonvoluntary_ctxt_switches:\t35\n";
+        assert_eq!(parse_context_switches(status), Some((1200, 35)));
+        assert_eq!(parse_context_switches("Name:\tnginx\n"), None);
+        assert_eq!(parse_schedstat("5000000 250000 42\n"), Some((5_000_000, 250_000, 42)));
+        assert_eq!(parse_schedstat(""), None);
+    }
+
+    #[test]
+    fn test_sums_counters_across_threads() {
+        let dir = TempDir::new("sched");
+        for (tid, switches, schedstat) in [(10, (100, 5), Some("1000 200 10")), (11, (50, 20), None)] {
+            let task = dir.join("task").join(tid.to_string());
+            fs::create_dir_all(&task).unwrap();
+            fs::write(task.join("status"), format!(
+                "Cpus_allowed_list:\t{}\nvoluntary_ctxt_switches:\t{}\nnonvoluntary_ctxt_switches:\t{}\n",
+                if tid == 10 { "0-7" } else { "3" }, switches.0, switches.1)).unwrap();
+            fs::write(task.join("wchan"), if tid == 10 { "futex_wait_queue" } else { "0" }).unwrap();
+            if let Some(schedstat) = schedstat {
+                fs::write(task.join("schedstat"), schedstat).unwrap();
+            }
+            fs::write(task.join("stat"), stat_line(tid, "worker", tid as u64, 0, 1)).unwrap();
+        }
+
+        let stats = SchedStats::read_from(&dir).unwrap();
+        assert_eq!(stats.voluntary_switches, 150);
+        assert_eq!(stats.involuntary_switches, 25);
+        assert_eq!(stats.context_switches(), 175);
+        assert_eq!((stats.run_time_ns, stats.run_delay_ns, stats.timeslices), (1000, 200, 10));
+
+        let threads = read_threads_from(&dir);
+        assert_eq!(threads.iter().map(|t| t.tid).collect::<Vec<_>>(), vec![10, 11]);
+        assert_eq!(threads[0].wchan.as_deref(), Some("futex_wait_queue"));
+        assert_eq!(threads[1].wchan, None);
+        assert!(!threads[0].is_pinned());
+        assert!(threads[1].is_pinned());
+    }
+
+    #[test]
+    fn test_computes_thread_cpu_from_tick_deltas() {
+        let mut sampler = ThreadSampler::with_root(PathBuf::from("/nonexistent"));
+        sampler.ticks_per_second = 100.0;
+        let start = Instant::now();
+        let thread = |tid, utime| ThreadStat::parse(&stat_line(tid, "worker", utime, 0, 0)).unwrap();
+
+        sampler.record(vec![thread(1, 100), thread(2, 500)], start);
+        assert!(sampler.threads.iter().all(|usage| usage.cpu_usage == 0.0));
+
+        sampler.record(vec![thread(1, 150), thread(2, 500), thread(3, 10)], start + Duration::from_secs(1));
+        let usage: Vec<f64> = sampler.threads.iter().map(|usage| usage.cpu_usage).collect();
+        assert_eq!(usage, vec![50.0, 0.0, 0.0]);
+    }
+}
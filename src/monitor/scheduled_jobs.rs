@@ -0,0 +1,543 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration as ChronoDuration, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::str;
+use std::time::{Duration, Instant};
+use sysinfo::System;
+
+use super::cron::{parse_crontab, CronEntry};
+use super::journal::parse_journal_json;
+use super::systemd::{SystemdClient, SystemdUnit};
+
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// How late a run may start before it counts as missed.
+const MISSED_GRACE_MINUTES: i64 = 5;
+const CRON_LOG_WINDOW_DAYS: i64 = 7;
+const SYSTEM_CRONTAB: &str = "/etc/crontab";
+const CRON_D_DIR: &str = "/etc/cron.d";
+const USER_CRONTAB_DIRS: &[&str] = &["/var/spool/cron/crontabs", "/var/spool/cron"];
+const CRON_DAEMONS: &[&str] = &["cron", "crond", "cronie", "fcron", "busybox-crond"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScheduledJobKind {
+    SystemdTimer,
+    Cron,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScheduledJobStatus {
+    Ok,
+    Missed,
+    Failed,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    pub name: String,
+    pub kind: ScheduledJobKind,
+    pub schedule: String,
+    pub user: String,
+    /// The activated unit for timers, the command line for cron jobs.
+    pub command: String,
+    pub source: String,
+    pub last_run: Option<DateTime<Utc>>,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_result: Option<String>,
+    pub status: ScheduledJobStatus,
+    /// Why the job is flagged as missed or failed.
+    pub reason: Option<String>,
+}
+
+/// When cron last started each (user, command), from its journal entries.
+#[derive(Debug, Clone, Default)]
+pub struct CronRunLog {
+    pub since: DateTime<Utc>,
+    pub runs: HashMap<(String, String), DateTime<Utc>>,
+}
+
+pub struct ScheduledJobMonitor {
+    systemd: Option<SystemdClient>,
+    cached_jobs: Vec<ScheduledJob>,
+    last_refresh: Option<Instant>,
+}
+
+impl Default for ScheduledJobMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScheduledJobMonitor {
+    pub fn new() -> Self {
+        Self {
+            systemd: SystemdClient::connect().ok(),
+            cached_jobs: Vec::new(),
+            last_refresh: None,
+        }
+    }
+
+    /// Returns systemd timers and cron jobs, re-read at most every
+    /// `REFRESH_INTERVAL` since the view redraws continuously.
+    pub fn get_scheduled_jobs(&mut self) -> Result<Vec<ScheduledJob>> {
+        let refresh_due = self.last_refresh.map(|t| t.elapsed() >= REFRESH_INTERVAL).unwrap_or(true);
+        if refresh_due {
+            let mut jobs = self.get_timer_jobs();
+            jobs.extend(self.get_cron_jobs());
+            self.cached_jobs = jobs;
+            self.last_refresh = Some(Instant::now());
+        }
+        Ok(self.cached_jobs.clone())
+    }
+
+    fn get_timer_jobs(&mut self) -> Vec<ScheduledJob> {
+        if let Some(client) = self.systemd.as_mut() {
+            match get_dbus_timers(client) {
+                Ok(jobs) => return jobs,
+                Err(_) => self.systemd = None,
+            }
+        }
+        get_systemctl_timers().unwrap_or_default()
+    }
+
+    fn get_cron_jobs(&self) -> Vec<ScheduledJob> {
+        let entries = read_crontabs();
+        if entries.is_empty() {
+            return Vec::new();
+        }
+
+        let now = Utc::now();
+        let daemon_running = cron_daemon_running();
+        let boot_time = DateTime::from_timestamp(System::boot_time() as i64, 0).unwrap_or(now);
+        let run_log = read_cron_run_log(now - ChronoDuration::days(CRON_LOG_WINDOW_DAYS));
+
+        entries.iter()
+            .map(|entry| cron_job(entry, run_log.as_ref(), daemon_running, boot_time, now))
+            .collect()
+    }
+}
+
+fn get_dbus_timers(client: &mut SystemdClient) -> Result<Vec<ScheduledJob>> {
+    let timers = client.list_timer_units()?;
+    let mut jobs = Vec::with_capacity(timers.len());
+
+    for timer in timers {
+        let activated = timer.properties.get("Unit").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let service = if activated.is_empty() { None } else { client.get_unit_by_name(&activated)? };
+        jobs.push(timer_job(&timer, service.as_ref(), Utc::now()));
+    }
+
+    jobs.sort_by_key(|job| job.next_run);
+    Ok(jobs)
+}
+
+fn timer_job(timer: &SystemdUnit, service: Option<&SystemdUnit>, now: DateTime<Utc>) -> ScheduledJob {
+    let props = &timer.properties;
+    let text = |key: &str| props.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+    let usec = |key: &str| props.get(key).and_then(|v| v.as_u64());
+
+    let mut schedule: Vec<String> = props.get("TimersCalendar")
+        .and_then(|v| v.as_array())
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|entry| Some(format!("{}={}", entry.as_struct()?.first()?.as_str()?, entry.as_struct()?.get(1)?.as_str()?)))
+        .collect();
+    schedule.extend(props.get("TimersMonotonic")
+        .and_then(|v| v.as_array())
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|entry| {
+            let fields = entry.as_struct()?;
+            let base = fields.first()?.as_str()?.replace("USec", "Sec");
+            Some(format!("{}={}", base, format_usec_span(fields.get(1)?.as_u64()?)))
+        }));
+
+    let next_run = usec("NextElapseUSecRealtime")
+        .and_then(realtime_usec)
+        .or_else(|| usec("NextElapseUSecMonotonic").and_then(monotonic_usec));
+
+    let service_result = service
+        .and_then(|s| s.properties.get("Result"))
+        .and_then(|v| v.as_str())
+        .map(|r| r.to_string());
+    let exec_main_status = service
+        .and_then(|s| s.properties.get("ExecMainStatus"))
+        .and_then(|v| v.as_i64());
+
+    evaluate_timer(ScheduledJob {
+        name: timer.name.clone(),
+        kind: ScheduledJobKind::SystemdTimer,
+        schedule: schedule.join(", "),
+        user: "root".to_string(),
+        command: text("Unit"),
+        source: text("FragmentPath"),
+        last_run: usec("LastTriggerUSec").and_then(realtime_usec),
+        next_run,
+        last_result: service_result.map(|result| match exec_main_status {
+            Some(status) if status != 0 => format!("{} ({})", result, status),
+            _ => result,
+        }),
+        status: ScheduledJobStatus::Unknown,
+        reason: None,
+    }, &text("ActiveState"), &text("Result"), now)
+}
+
+/// Flags a timer whose unit failed on its last run, which has stopped,
+/// or whose next elapse is already in the past.
+fn evaluate_timer(mut job: ScheduledJob, active_state: &str, timer_result: &str, now: DateTime<Utc>) -> ScheduledJob {
+    let service_failed = job.last_result.as_deref()
+        .map(|r| !r.is_empty() && !r.starts_with("success"))
+        .unwrap_or(false);
+
+    if service_failed {
+        job.status = ScheduledJobStatus::Failed;
+        job.reason = Some(format!("{} finished with {}", job.command, job.last_result.clone().unwrap_or_default()));
+    } else if !timer_result.is_empty() && timer_result != "success" {
+        job.status = ScheduledJobStatus::Failed;
+        job.reason = Some(format!("timer result: {}", timer_result));
+    } else if active_state != "active" && !active_state.is_empty() {
+        job.status = ScheduledJobStatus::Missed;
+        job.reason = Some(format!("timer is {}", active_state));
+    } else if job.next_run.map(|next| next + ChronoDuration::minutes(MISSED_GRACE_MINUTES) < now).unwrap_or(false) {
+        job.status = ScheduledJobStatus::Missed;
+        job.reason = Some("next elapse has passed without a trigger".to_string());
+    } else {
+        job.status = ScheduledJobStatus::Ok;
+    }
+    job
+}
+
+/// Fallback for hosts without a usable system bus.
+fn get_systemctl_timers() -> Result<Vec<ScheduledJob>> {
+    let output = Command::new("systemctl")
+        .args(["list-units", "--type=timer", "--all", "--no-pager", "--plain", "--no-legend"])
+        .output()?;
+    let stdout = str::from_utf8(&output.stdout)?;
+
+    let mut jobs = Vec::new();
+    for line in stdout.lines() {
+        let name = match line.split_whitespace().next() {
+            Some(name) if name.ends_with(".timer") => name,
+            _ => continue,
+        };
+
+        let properties = systemctl_show(name, "Unit,ActiveState,Result,FragmentPath,NextElapseUSecRealtime,LastTriggerUSec")?;
+        let activated = properties.get("Unit").cloned().unwrap_or_default();
+        let service = if activated.is_empty() {
+            HashMap::new()
+        } else {
+            systemctl_show(&activated, "Result,ExecMainStatus")?
+        };
+        let exec_main_status = service.get("ExecMainStatus").and_then(|s| s.parse::<i64>().ok());
+
+        let job = ScheduledJob {
+            name: name.to_string(),
+            kind: ScheduledJobKind::SystemdTimer,
+            schedule: String::new(),
+            user: "root".to_string(),
+            command: activated,
+            source: properties.get("FragmentPath").cloned().unwrap_or_default(),
+            last_run: properties.get("LastTriggerUSec").and_then(|t| parse_unix_timestamp(t)),
+            next_run: properties.get("NextElapseUSecRealtime").and_then(|t| parse_unix_timestamp(t)),
+            last_result: service.get("Result").filter(|r| !r.is_empty()).map(|result| match exec_main_status {
+                Some(status) if status != 0 => format!("{} ({})", result, status),
+                _ => result.clone(),
+            }),
+            status: ScheduledJobStatus::Unknown,
+            reason: None,
+        };
+        jobs.push(evaluate_timer(
+            job,
+            properties.get("ActiveState").map(|s| s.as_str()).unwrap_or_default(),
+            properties.get("Result").map(|s| s.as_str()).unwrap_or_default(),
+            Utc::now(),
+        ));
+    }
+
+    jobs.sort_by_key(|job| job.next_run);
+    Ok(jobs)
+}
+
+fn systemctl_show(unit: &str, properties: &str) -> Result<HashMap<String, String>> {
+    let output = Command::new("systemctl")
+        .args(["show", unit, "--no-pager", "--timestamp=unix"])
+        .arg(format!("--property={}", properties))
+        .output()?;
+    Ok(str::from_utf8(&output.stdout)?
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect())
+}
+
+/// Parses `--timestamp=unix` output such as "@1700000000".
+fn parse_unix_timestamp(text: &str) -> Option<DateTime<Utc>> {
+    let seconds = text.strip_prefix('@')?.parse::<i64>().ok()?;
+    DateTime::from_timestamp(seconds, 0).filter(|_| seconds > 0)
+}
+
+/// systemd uses 0 and UINT64_MAX for "never".
+fn realtime_usec(usec: u64) -> Option<DateTime<Utc>> {
+    if usec == 0 || usec == u64::MAX {
+        return None;
+    }
+    DateTime::from_timestamp_micros(usec as i64)
+}
+
+/// Converts a CLOCK_MONOTONIC timestamp into wall-clock time.
+fn monotonic_usec(usec: u64) -> Option<DateTime<Utc>> {
+    if usec == 0 || usec == u64::MAX {
+        return None;
+    }
+    let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: clock_gettime only writes to the timespec we pass
+    if unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now) } != 0 {
+        return None;
+    }
+    let now_usec = Duration::new(now.tv_sec as u64, now.tv_nsec as u32).as_micros() as i64;
+    Some(Utc::now() + ChronoDuration::microseconds(usec as i64 - now_usec))
+}
+
+fn format_usec_span(usec: u64) -> String {
+    let seconds = usec / 1_000_000;
+    match seconds {
+        s if s >= 86_400 && s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s >= 3_600 && s % 3_600 == 0 => format!("{}h", s / 3_600),
+        s if s >= 60 && s % 60 == 0 => format!("{}min", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Reads /etc/crontab, /etc/cron.d and the per-user spool. The spool is
+/// only readable by root; other users fall back to `crontab -l`.
+fn read_crontabs() -> Vec<CronEntry> {
+    let mut entries = Vec::new();
+
+    if let Ok(content) = fs::read_to_string(SYSTEM_CRONTAB) {
+        entries.extend(parse_crontab(&content, None, SYSTEM_CRONTAB));
+    }
+
+    // cron skips files with dots in their names (package manager leftovers)
+    for path in sorted_files(Path::new(CRON_D_DIR)) {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if name.contains('.') || name.ends_with('~') {
+            continue;
+        }
+        if let Ok(content) = fs::read_to_string(&path) {
+            entries.extend(parse_crontab(&content, None, &path.to_string_lossy()));
+        }
+    }
+
+    let mut read_spool = false;
+    for dir in USER_CRONTAB_DIRS {
+        for path in sorted_files(Path::new(dir)) {
+            let user = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if let Ok(content) = fs::read_to_string(&path) {
+                entries.extend(parse_crontab(&content, Some(&user), &path.to_string_lossy()));
+                read_spool = true;
+            }
+        }
+    }
+
+    if !read_spool {
+        if let Ok(output) = Command::new("crontab").arg("-l").output() {
+            if output.status.success() {
+                let user = std::env::var("USER").unwrap_or_else(|_| "user".to_string());
+                entries.extend(parse_crontab(&String::from_utf8_lossy(&output.stdout), Some(&user), "crontab -l"));
+            }
+        }
+    }
+
+    entries
+}
+
+fn sorted_files(dir: &Path) -> Vec<std::path::PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .map(|entries| entries.flatten()
+            .filter(|e| e.file_type().map(|t| t.is_file()).unwrap_or(false))
+            .map(|e| e.path())
+            .collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn cron_daemon_running() -> bool {
+    let entries = match fs::read_dir("/proc") {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+
+    entries.flatten()
+        .filter(|e| e.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+        .filter_map(|e| fs::read_to_string(e.path().join("comm")).ok())
+        .any(|comm| CRON_DAEMONS.contains(&comm.trim()))
+}
+
+/// Collects "(user) CMD (command)" lines logged by cron since `since`.
+/// Returns None when the journal holds no cron entries at all, e.g.
+/// because cron logs to a syslog file instead.
+fn read_cron_run_log(since: DateTime<Utc>) -> Option<CronRunLog> {
+    let output = Command::new("journalctl")
+        .args(["--no-pager", "--output=json", "-t", "CRON", "-t", "CROND"])
+        .arg(format!("--since=@{}", since.timestamp()))
+        .output()
+        .ok()?;
+
+    let mut runs = HashMap::new();
+    let mut seen_entries = false;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let entry = match parse_journal_json(line) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        seen_entries = true;
+        if let (Some((user, command)), Some(timestamp)) = (parse_cron_log_message(&entry.message), entry.timestamp) {
+            let latest = runs.entry((user.to_string(), command.to_string())).or_insert(timestamp);
+            if timestamp > *latest {
+                *latest = timestamp;
+            }
+        }
+    }
+
+    seen_entries.then_some(CronRunLog { since, runs })
+}
+
+/// Parses cron's "(root) CMD (command)" execution message.
+pub fn parse_cron_log_message(message: &str) -> Option<(&str, &str)> {
+    let rest = message.strip_prefix('(')?;
+    let (user, rest) = rest.split_once(") CMD (")?;
+    Some((user, rest.strip_suffix(')').unwrap_or(rest)))
+}
+
+/// Works out last and next run of a cron entry and whether a run was
+/// missed: the daemon is not running although a run was due since boot,
+/// or the cron log shows no start for a run that was due.
+pub fn cron_job(
+    entry: &CronEntry,
+    run_log: Option<&CronRunLog>,
+    daemon_running: bool,
+    boot_time: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> ScheduledJob {
+    let last_run = run_log.and_then(|log| log.runs.get(&(entry.user.clone(), entry.command.clone())).copied());
+    let next_after = |time: DateTime<Utc>| {
+        entry.schedule.as_ref()
+            .and_then(|schedule| schedule.next_after(&time.with_timezone(&Local)))
+            .map(|t| t.with_timezone(&Utc))
+    };
+    let overdue = |due: Option<DateTime<Utc>>| {
+        due.map(|due| due + ChronoDuration::minutes(MISSED_GRACE_MINUTES) < now).unwrap_or(false)
+    };
+
+    let (status, reason) = if !daemon_running {
+        if overdue(next_after(boot_time)) {
+            (ScheduledJobStatus::Missed, Some("cron daemon is not running".to_string()))
+        } else {
+            (ScheduledJobStatus::Unknown, Some("cron daemon is not running".to_string()))
+        }
+    } else if let Some(log) = run_log {
+        let due = next_after(last_run.unwrap_or(log.since));
+        if overdue(due) {
+            let due = due.map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+            (ScheduledJobStatus::Missed, Some(format!("no run logged for {}", due)))
+        } else {
+            (ScheduledJobStatus::Ok, None)
+        }
+    } else {
+        (ScheduledJobStatus::Unknown, None)
+    };
+
+    ScheduledJob {
+        name: entry.command.split_whitespace().next().unwrap_or_default().to_string(),
+        kind: ScheduledJobKind::Cron,
+        schedule: entry.expression.clone(),
+        user: entry.user.clone(),
+        command: entry.command.clone(),
+        source: entry.source.clone(),
+        last_run,
+        next_run: next_after(now),
+        last_result: None,
+        status,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::cron::parse_crontab_line;
+    use crate::monitor::dbus::Value;
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_cron_job_missed_detection() {
+        assert_eq!(parse_cron_log_message("(root) CMD (/usr/bin/backup --all)"), Some(("root", "/usr/bin/backup --all")));
+        assert_eq!(parse_cron_log_message("pam_unix(cron:session): session opened"), None);
+
+        let entry = parse_crontab_line("*/10 * * * * root /usr/bin/backup --all", None, "/etc/crontab").unwrap();
+        let boot = at("2024-05-01T00:00:00Z");
+        let now = at("2024-05-01T12:03:00Z");
+
+        let mut log = CronRunLog { since: at("2024-05-01T00:00:00Z"), runs: HashMap::new() };
+        log.runs.insert(("root".to_string(), "/usr/bin/backup --all".to_string()), at("2024-05-01T12:00:00Z"));
+        let job = cron_job(&entry, Some(&log), true, boot, now);
+        assert_eq!(job.status, ScheduledJobStatus::Ok);
+        assert_eq!(job.last_run, Some(at("2024-05-01T12:00:00Z")));
+        assert_eq!(job.next_run, Some(at("2024-05-01T12:10:00Z")));
+
+        log.runs.insert(("root".to_string(), "/usr/bin/backup --all".to_string()), at("2024-05-01T11:30:00Z"));
+        assert_eq!(cron_job(&entry, Some(&log), true, boot, now).status, ScheduledJobStatus::Missed);
+
+        assert_eq!(cron_job(&entry, None, false, boot, now).status, ScheduledJobStatus::Missed);
+        assert_eq!(cron_job(&entry, None, true, boot, now).status, ScheduledJobStatus::Unknown);
+    }
+
+    #[test]
+    fn test_timer_job_flags_failed_service() {
+        let now = at("2024-05-01T12:00:00Z");
+        let mut timer = SystemdUnit {
+            name: "logrotate.timer".to_string(),
+            path: "/org/freedesktop/systemd1/unit/logrotate_2etimer".to_string(),
+            properties: HashMap::new(),
+        };
+        timer.properties.insert("Unit".to_string(), Value::Str("logrotate.service".to_string()));
+        timer.properties.insert("ActiveState".to_string(), Value::Str("active".to_string()));
+        timer.properties.insert("Result".to_string(), Value::Str("success".to_string()));
+        timer.properties.insert("NextElapseUSecRealtime".to_string(), Value::UInt64(at("2024-05-02T00:00:00Z").timestamp_micros() as u64));
+        timer.properties.insert("LastTriggerUSec".to_string(), Value::UInt64(at("2024-05-01T00:00:00Z").timestamp_micros() as u64));
+        timer.properties.insert("TimersCalendar".to_string(), Value::Array("(sst)".to_string(), vec![
+            Value::Struct(vec![Value::Str("OnCalendar".to_string()), Value::Str("*-*-* 00:00:00".to_string()), Value::UInt64(0)]),
+        ]));
+
+        let mut service = SystemdUnit {
+            name: "logrotate.service".to_string(),
+            path: "/org/freedesktop/systemd1/unit/logrotate_2eservice".to_string(),
+            properties: HashMap::new(),
+        };
+        service.properties.insert("Result".to_string(), Value::Str("success".to_string()));
+
+        let job = timer_job(&timer, Some(&service), now);
+        assert_eq!(job.status, ScheduledJobStatus::Ok);
+        assert_eq!(job.schedule, "OnCalendar=*-*-* 00:00:00");
+        assert_eq!(job.next_run, Some(at("2024-05-02T00:00:00Z")));
+
+        service.properties.insert("Result".to_string(), Value::Str("exit-code".to_string()));
+        service.properties.insert("ExecMainStatus".to_string(), Value::Int32(1));
+        let job = timer_job(&timer, Some(&service), now);
+        assert_eq!(job.status, ScheduledJobStatus::Failed);
+        assert_eq!(job.last_result.as_deref(), Some("exit-code (1)"));
+
+        // A next elapse in the past means the timer did not fire
+        service.properties.insert("Result".to_string(), Value::Str("success".to_string()));
+        timer.properties.insert("NextElapseUSecRealtime".to_string(), Value::UInt64(at("2024-05-01T06:00:00Z").timestamp_micros() as u64));
+        assert_eq!(timer_job(&timer, Some(&service), now).status, ScheduledJobStatus::Missed);
+    }
+}
@@ -32,6 +32,14 @@ pub enum AlertType {
     HighResourceUsage,
     ProcessAnomalyDetection,
     UnauthorizedFileAccess,
+    /// Brute force, credential stuffing or user enumeration against sshd.
+    AuthenticationAttack,
+    /// A PSI trigger fired for the system or a cgroup.
+    ResourcePressure,
+    /// The OOM killer killed a process or a cgroup hit its memory limit.
+    OutOfMemory,
+    /// A container crashed, was OOM killed or failed its health check.
+    ContainerFailure,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -289,6 +297,31 @@ impl SecurityDashboard {
         process_pid: Option<u32>,
         process_name: Option<String>,
     ) -> Result<()> {
+        self.push_alert(alert_type, severity, message, process_pid, process_name, HashMap::new());
+        Ok(())
+    }
+
+    /// Raises an alert that is not tied to a process, such as one from the
+    /// SSH brute-force detector. `details` tells apart alerts of one type.
+    pub fn raise_alert(
+        &mut self,
+        alert_type: AlertType,
+        severity: AlertSeverity,
+        message: String,
+        details: HashMap<String, String>,
+    ) {
+        self.push_alert(alert_type, severity, message, None, None, details);
+    }
+
+    fn push_alert(
+        &mut self,
+        alert_type: AlertType,
+        severity: AlertSeverity,
+        message: String,
+        process_pid: Option<u32>,
+        process_name: Option<String>,
+        details: HashMap<String, String>,
+    ) {
         // Check if we already have a similar recent alert to avoid spam
         let recent_threshold = chrono::Duration::minutes(5);
         let now = Utc::now();
@@ -296,6 +329,8 @@ impl SecurityDashboard {
         let similar_recent_alert = self.alerts.iter().any(|alert| {
             alert.alert_type.discriminant() == alert_type.discriminant() &&
             alert.process_pid == process_pid &&
+            alert.details.get("source") == details.get("source") &&
+            alert.details.get("pattern") == details.get("pattern") &&
             now.signed_duration_since(alert.timestamp) < recent_threshold
         });
 
@@ -308,7 +343,7 @@ impl SecurityDashboard {
                 message,
                 process_pid,
                 process_name,
-                details: HashMap::new(),
+                details,
             };
 
             self.alerts.push(alert.clone());
@@ -319,8 +354,6 @@ impl SecurityDashboard {
                 self.alert_history.remove(0);
             }
         }
-
-        Ok(())
     }
 
     fn clear_expired_alerts(&mut self) {
@@ -0,0 +1,388 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::services::{ServiceInfo, ServiceStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ServiceDependencyKind {
+    Requires,
+    Wants,
+    After,
+    BindsTo,
+    PartOf,
+}
+
+impl ServiceDependencyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceDependencyKind::Requires => "Requires",
+            ServiceDependencyKind::Wants => "Wants",
+            ServiceDependencyKind::After => "After",
+            ServiceDependencyKind::BindsTo => "BindsTo",
+            ServiceDependencyKind::PartOf => "PartOf",
+        }
+    }
+
+    /// Whether a failure of the target takes the dependent unit down too.
+    pub fn is_hard(&self) -> bool {
+        matches!(self, ServiceDependencyKind::Requires | ServiceDependencyKind::BindsTo | ServiceDependencyKind::PartOf)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DependencyTreeEntry {
+    pub depth: usize,
+    pub unit: String,
+    pub kind: Option<ServiceDependencyKind>,
+    pub status: Option<ServiceStatus>,
+    /// Already shown higher up on the same branch; not expanded again.
+    pub repeated: bool,
+}
+
+/// A unit affected by a failed unit further down its dependency chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DegradedService {
+    pub unit: String,
+    pub failed_unit: String,
+    pub via: ServiceDependencyKind,
+    /// True when every edge on the path is a hard dependency.
+    pub hard: bool,
+    pub distance: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartLoop {
+    pub service: String,
+    pub restarts: u32,
+    pub window_secs: u64,
+}
+
+/// Unit dependency graph built from the Requires=, Wants=, After=,
+/// BindsTo= and PartOf= lists of the known services. Units that are not
+/// services (targets, sockets, mounts) appear as nodes without a status.
+#[derive(Debug, Default)]
+pub struct ServiceDependencyGraph {
+    status: HashMap<String, ServiceStatus>,
+    edges: HashMap<String, Vec<(ServiceDependencyKind, String)>>,
+    reverse: HashMap<String, Vec<(ServiceDependencyKind, String)>>,
+}
+
+impl ServiceDependencyGraph {
+    pub fn build(services: &[ServiceInfo]) -> Self {
+        let mut graph = Self::default();
+
+        for service in services {
+            let unit = Self::unit_name(service);
+            graph.status.insert(unit.clone(), service.status.clone());
+
+            let lists = [
+                (ServiceDependencyKind::Requires, &service.dependencies),
+                (ServiceDependencyKind::Wants, &service.wants),
+                (ServiceDependencyKind::After, &service.after),
+                (ServiceDependencyKind::BindsTo, &service.binds_to),
+                (ServiceDependencyKind::PartOf, &service.part_of),
+            ];
+            for (kind, targets) in lists {
+                for target in targets {
+                    graph.add_edge(&unit, kind, target);
+                }
+            }
+
+            // WantedBy= is the reverse of the target's Wants=
+            for wanted_by in &service.dependents {
+                graph.add_edge(wanted_by, ServiceDependencyKind::Wants, &unit);
+            }
+        }
+
+        graph
+    }
+
+    /// Service names are listed without their ".service" suffix, even
+    /// when the rest of the name is dotted (snap.lxd.daemon.service).
+    pub fn unit_name(service: &ServiceInfo) -> String {
+        format!("{}.service", service.name)
+    }
+
+    fn add_edge(&mut self, from: &str, kind: ServiceDependencyKind, to: &str) {
+        let edge = (kind, to.to_string());
+        let forward = self.edges.entry(from.to_string()).or_default();
+        if forward.contains(&edge) {
+            return;
+        }
+        forward.push(edge);
+        self.reverse.entry(to.to_string()).or_default().push((kind, from.to_string()));
+    }
+
+    pub fn status(&self, unit: &str) -> Option<&ServiceStatus> {
+        self.status.get(unit)
+    }
+
+    pub fn failed_units(&self) -> Vec<String> {
+        let mut failed: Vec<String> = self.status.iter()
+            .filter(|(_, status)| **status == ServiceStatus::Failed)
+            .map(|(unit, _)| unit.clone())
+            .collect();
+        failed.sort();
+        failed
+    }
+
+    /// Units this unit is ordered after. Ordering alone does not make a
+    /// unit depend on another, so After= is kept out of the tree.
+    pub fn ordered_after(&self, unit: &str) -> Vec<String> {
+        self.edges.get(unit)
+            .map(|edges| edges.iter()
+                .filter(|(kind, _)| *kind == ServiceDependencyKind::After)
+                .map(|(_, target)| target.clone())
+                .collect())
+            .unwrap_or_default()
+    }
+
+    /// The `systemctl list-dependencies` tree below `root`.
+    pub fn dependency_tree(&self, root: &str, max_depth: usize) -> Vec<DependencyTreeEntry> {
+        let mut entries = vec![DependencyTreeEntry {
+            depth: 0,
+            unit: root.to_string(),
+            kind: None,
+            status: self.status.get(root).cloned(),
+            repeated: false,
+        }];
+        let mut path = vec![root.to_string()];
+        self.walk_tree(root, 1, max_depth, &mut path, &mut entries);
+        entries
+    }
+
+    fn walk_tree(&self, unit: &str, depth: usize, max_depth: usize, path: &mut Vec<String>, entries: &mut Vec<DependencyTreeEntry>) {
+        if depth > max_depth {
+            return;
+        }
+
+        let mut children: Vec<&(ServiceDependencyKind, String)> = self.edges.get(unit)
+            .map(|edges| edges.iter().filter(|(kind, _)| *kind != ServiceDependencyKind::After).collect())
+            .unwrap_or_default();
+        children.sort_by(|a, b| a.1.cmp(&b.1));
+
+        for (kind, child) in children {
+            let repeated = path.contains(child);
+            entries.push(DependencyTreeEntry {
+                depth,
+                unit: child.clone(),
+                kind: Some(*kind),
+                status: self.status.get(child).cloned(),
+                repeated,
+            });
+
+            if !repeated {
+                path.push(child.clone());
+                self.walk_tree(child, depth + 1, max_depth, path, entries);
+                path.pop();
+            }
+        }
+    }
+
+    /// Walks upward from a failed unit to everything that depends on it.
+    pub fn degraded_by(&self, failed_unit: &str) -> Vec<DegradedService> {
+        let mut degraded = Vec::new();
+        let mut visited: HashSet<&str> = HashSet::from([failed_unit]);
+        let mut queue: VecDeque<(&str, bool, usize)> = VecDeque::from([(failed_unit, true, 0)]);
+
+        while let Some((unit, hard, distance)) = queue.pop_front() {
+            for (kind, dependent) in self.reverse.get(unit).map(|v| v.as_slice()).unwrap_or(&[]) {
+                if *kind == ServiceDependencyKind::After || !visited.insert(dependent) {
+                    continue;
+                }
+
+                let path_hard = hard && kind.is_hard();
+                degraded.push(DegradedService {
+                    unit: dependent.clone(),
+                    failed_unit: failed_unit.to_string(),
+                    via: *kind,
+                    hard: path_hard,
+                    distance: distance + 1,
+                });
+                queue.push_back((dependent, path_hard, distance + 1));
+            }
+        }
+
+        degraded
+    }
+}
+
+/// Tracks NRestarts over time to spot services stuck in a restart loop.
+pub struct RestartTracker {
+    window: Duration,
+    threshold: u32,
+    history: HashMap<String, VecDeque<(Instant, u32)>>,
+}
+
+impl RestartTracker {
+    pub fn new(window: Duration, threshold: u32) -> Self {
+        Self {
+            window,
+            threshold,
+            history: HashMap::new(),
+        }
+    }
+
+    pub fn record(&mut self, service: &str, restarts: u32, now: Instant) {
+        let samples = self.history.entry(service.to_string()).or_default();
+
+        if let Some(&(_, last)) = samples.back() {
+            if restarts == last {
+                return;
+            }
+            // The counter resets on a manual stop/start
+            if restarts < last {
+                samples.clear();
+            }
+        }
+        samples.push_back((now, restarts));
+
+        // Keep one sample from before the window as the baseline
+        while samples.len() >= 2 && now.duration_since(samples[1].0) >= self.window {
+            samples.pop_front();
+        }
+    }
+
+    pub fn restarts_in_window(&self, service: &str, now: Instant) -> u32 {
+        let samples = match self.history.get(service) {
+            Some(samples) if samples.len() >= 2 => samples,
+            _ => return 0,
+        };
+
+        let latest = samples.back().map(|s| s.1).unwrap_or(0);
+        let baseline = samples.iter()
+            .rev()
+            .find(|(taken_at, _)| now.duration_since(*taken_at) >= self.window)
+            .or_else(|| samples.front())
+            .map(|s| s.1)
+            .unwrap_or(latest);
+
+        latest.saturating_sub(baseline)
+    }
+
+    pub fn restart_loops(&self, now: Instant) -> Vec<RestartLoop> {
+        let mut loops: Vec<RestartLoop> = self.history.keys()
+            .map(|service| (service, self.restarts_in_window(service, now)))
+            .filter(|(_, restarts)| *restarts >= self.threshold)
+            .map(|(service, restarts)| RestartLoop {
+                service: service.clone(),
+                restarts,
+                window_secs: self.window.as_secs(),
+            })
+            .collect();
+        loops.sort_by_key(|l| std::cmp::Reverse(l.restarts));
+        loops
+    }
+
+    pub fn retain(&mut self, services: &HashSet<&str>) {
+        self.history.retain(|name, _| services.contains(name.as_str()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::services::{ServiceState, ServiceType};
+
+    fn service(name: &str, status: ServiceStatus, requires: &[&str], wants: &[&str]) -> ServiceInfo {
+        let list = |units: &[&str]| units.iter().map(|u| u.to_string()).collect();
+        ServiceInfo {
+            name: name.to_string(),
+            description: String::new(),
+            status,
+            state: ServiceState::Enabled,
+            enabled: true,
+            active_since: None,
+            memory_usage: 0,
+            cpu_usage: 0.0,
+            restart_count: 0,
+            main_pid: None,
+            unit_file_path: String::new(),
+            service_type: ServiceType::Simple,
+            dependencies: list(requires),
+            dependents: Vec::new(),
+            wants: list(wants),
+            after: list(requires),
+            binds_to: Vec::new(),
+            part_of: Vec::new(),
+            cgroup_path: None,
+            io_read_bytes: 0,
+            io_write_bytes: 0,
+            tasks: 0,
+            oom_kills: 0,
+            pressure: Default::default(),
+            result: String::new(),
+            exec_main_status: None,
+        }
+    }
+
+    #[test]
+    fn test_degraded_walk_and_tree() {
+        let services = vec![
+            service("postgresql", ServiceStatus::Failed, &[], &[]),
+            service("api", ServiceStatus::Active, &["postgresql.service"], &[]),
+            service("web", ServiceStatus::Active, &[], &["api.service"]),
+            service("metrics", ServiceStatus::Active, &[], &[]),
+        ];
+        let graph = ServiceDependencyGraph::build(&services);
+
+        assert_eq!(graph.failed_units(), vec!["postgresql.service".to_string()]);
+
+        let degraded = graph.degraded_by("postgresql.service");
+        assert_eq!(degraded.len(), 2);
+        assert_eq!(degraded[0].unit, "api.service");
+        assert!(degraded[0].hard);
+        assert_eq!(degraded[1].unit, "web.service");
+        assert_eq!(degraded[1].via, ServiceDependencyKind::Wants);
+        assert!(!degraded[1].hard);
+        assert_eq!(degraded[1].distance, 2);
+
+        let tree = graph.dependency_tree("web.service", 5);
+        let units: Vec<&str> = tree.iter().map(|e| e.unit.as_str()).collect();
+        assert_eq!(units, vec!["web.service", "api.service", "postgresql.service"]);
+        assert_eq!(tree[2].status, Some(ServiceStatus::Failed));
+        assert_eq!(graph.ordered_after("api.service"), vec!["postgresql.service".to_string()]);
+    }
+
+    #[test]
+    fn test_dotted_service_names() {
+        let services = vec![
+            service("dbus-org.freedesktop.resolve1", ServiceStatus::Failed, &[], &[]),
+            service("snap.lxd.daemon", ServiceStatus::Active, &["dbus-org.freedesktop.resolve1.service"], &[]),
+        ];
+        let graph = ServiceDependencyGraph::build(&services);
+
+        assert_eq!(ServiceDependencyGraph::unit_name(&services[1]), "snap.lxd.daemon.service");
+        assert_eq!(graph.failed_units(), vec!["dbus-org.freedesktop.resolve1.service".to_string()]);
+        assert_eq!(graph.status("snap.lxd.daemon.service"), Some(&ServiceStatus::Active));
+
+        let degraded = graph.degraded_by("dbus-org.freedesktop.resolve1.service");
+        assert_eq!(degraded.len(), 1);
+        assert_eq!(degraded[0].unit, "snap.lxd.daemon.service");
+    }
+
+    #[test]
+    fn test_restart_loop_detection() {
+        let mut tracker = RestartTracker::new(Duration::from_secs(600), 3);
+        let start = Instant::now();
+
+        tracker.record("flaky", 10, start);
+        tracker.record("flaky", 12, start + Duration::from_secs(60));
+        assert_eq!(tracker.restarts_in_window("flaky", start + Duration::from_secs(60)), 2);
+        assert!(tracker.restart_loops(start + Duration::from_secs(60)).is_empty());
+
+        tracker.record("flaky", 14, start + Duration::from_secs(120));
+        let loops = tracker.restart_loops(start + Duration::from_secs(120));
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].restarts, 4);
+
+        // Once the restarts age out of the window the loop clears
+        let later = start + Duration::from_secs(1000);
+        assert_eq!(tracker.restarts_in_window("flaky", later), 0);
+
+        // A counter reset starts a fresh history
+        tracker.record("flaky", 0, later);
+        assert_eq!(tracker.restarts_in_window("flaky", later), 0);
+    }
+}
@@ -1,9 +1,27 @@
 use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::process::Command;
 use std::str;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::cgroups::{CgroupReader, CpuUsageSampler, SliceNode};
+use super::dbus::is_error_reply;
+use super::journal::JournalFollower;
+use super::pressure::PressureMetrics;
+use super::service_graph::{RestartLoop, RestartTracker};
+use super::systemd::{merge_properties, SystemdClient, SystemdUnit, UnitEvent};
+
+/// How often the whole unit list is re-read over D-Bus. State changes
+/// arrive as PropertiesChanged signals in between; counters such as
+/// MemoryCurrent are not signalled and only move on a full refresh.
+const FULL_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+const UNSUBSCRIBED_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+const RESTART_LOOP_WINDOW: Duration = Duration::from_secs(600);
+const RESTART_LOOP_THRESHOLD: u32 = 3;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServiceInfo {
@@ -21,6 +39,38 @@ pub struct ServiceInfo {
     pub service_type: ServiceType,
     pub dependencies: Vec<String>,
     pub dependents: Vec<String>,
+    pub wants: Vec<String>,
+    pub after: Vec<String>,
+    pub binds_to: Vec<String>,
+    pub part_of: Vec<String>,
+    pub cgroup_path: Option<String>,
+    pub io_read_bytes: u64,
+    pub io_write_bytes: u64,
+    pub tasks: u32,
+    pub oom_kills: u64,
+    #[serde(default)]
+    pub pressure: PressureMetrics,
+    /// Result= of the last run, e.g. "success", "exit-code", "signal".
+    pub result: String,
+    /// ExecMainStatus=: the exit code, or the signal number when the
+    /// result is "signal" or "core-dump".
+    pub exec_main_status: Option<i32>,
+}
+
+impl ServiceInfo {
+    /// Describes why the last run failed, or None if it succeeded.
+    pub fn failure_reason(&self) -> Option<String> {
+        if self.result.is_empty() || self.result == "success" {
+            return None;
+        }
+        match self.exec_main_status {
+            Some(status) if self.result == "signal" || self.result == "core-dump" => {
+                Some(format!("{}, signal {}", self.result, status))
+            }
+            Some(status) if status != 0 => Some(format!("{}, status {}", self.result, status)),
+            _ => Some(self.result.clone()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -63,6 +113,8 @@ pub struct ServiceMetrics {
     pub disabled_services: usize,
     pub total_memory_usage: u64,
     pub system_service_manager: ServiceManager,
+    pub restart_loops: Vec<RestartLoop>,
+    pub slices: Vec<SliceNode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -74,10 +126,63 @@ pub enum ServiceManager {
     Unknown,
 }
 
+/// A start, stop or restart running on its own thread; systemd only
+/// reports the job result once the unit has finished changing state.
+pub struct ServiceJob {
+    pub action: ServiceAction,
+    pub service: String,
+    receiver: Receiver<Result<String>>,
+}
+
+impl ServiceJob {
+    /// The job result, or `None` while it is still running.
+    pub fn poll(&self) -> Option<Result<String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow!("job thread exited"))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl ServiceAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceAction::Start => "start",
+            ServiceAction::Stop => "stop",
+            ServiceAction::Restart => "restart",
+        }
+    }
+
+    fn job_method(&self) -> &'static str {
+        match self {
+            ServiceAction::Start => "StartUnit",
+            ServiceAction::Stop => "StopUnit",
+            ServiceAction::Restart => "RestartUnit",
+        }
+    }
+}
+
 pub struct ServiceMonitor {
     service_manager: ServiceManager,
     cached_services: HashMap<String, ServiceInfo>,
     last_update: Option<DateTime<Utc>>,
+    systemd: Option<SystemdClient>,
+    unit_events: Option<Receiver<UnitEvent>>,
+    units: HashMap<String, SystemdUnit>,
+    needs_full_refresh: bool,
+    last_full_refresh: Option<Instant>,
+    restart_tracker: RestartTracker,
+    cgroup_reader: CgroupReader,
+    service_cpu_sampler: CpuUsageSampler,
+    slice_cpu_sampler: CpuUsageSampler,
 }
 
 impl Default for ServiceStatus {
@@ -128,11 +233,27 @@ impl From<&str> for ServiceType {
 impl ServiceMonitor {
     pub fn new() -> Self {
         let service_manager = Self::detect_service_manager();
+
+        let systemd = if service_manager == ServiceManager::Systemd {
+            SystemdClient::connect().ok()
+        } else {
+            None
+        };
+        let unit_events = systemd.as_ref().and_then(|_| SystemdClient::subscribe().ok());
         
         Self {
             service_manager,
             cached_services: HashMap::new(),
             last_update: None,
+            systemd,
+            unit_events,
+            units: HashMap::new(),
+            needs_full_refresh: true,
+            last_full_refresh: None,
+            restart_tracker: RestartTracker::new(RESTART_LOOP_WINDOW, RESTART_LOOP_THRESHOLD),
+            cgroup_reader: CgroupReader::new(),
+            service_cpu_sampler: CpuUsageSampler::new(),
+            slice_cpu_sampler: CpuUsageSampler::new(),
         }
     }
 
@@ -161,7 +282,7 @@ impl ServiceMonitor {
     }
 
     pub fn get_service_metrics(&mut self) -> Result<ServiceMetrics> {
-        let services = match self.service_manager {
+        let mut services = match self.service_manager {
             ServiceManager::Systemd => self.get_systemd_services()?,
             ServiceManager::OpenRC => self.get_openrc_services()?,
             ServiceManager::InitD => self.get_initd_services()?,
@@ -169,6 +290,13 @@ impl ServiceMonitor {
             ServiceManager::Unknown => Vec::new(),
         };
 
+        let slices = if self.service_manager == ServiceManager::Systemd && self.cgroup_reader.is_unified() {
+            self.apply_cgroup_accounting(&mut services);
+            self.read_slices()
+        } else {
+            Vec::new()
+        };
+
         let active_services = services.iter()
             .filter(|s| s.status == ServiceStatus::Active)
             .count();
@@ -189,6 +317,14 @@ impl ServiceMonitor {
             .map(|s| s.memory_usage)
             .sum();
 
+        // Watch NRestarts deltas for restart loops
+        let now = Instant::now();
+        for service in &services {
+            self.restart_tracker.record(&service.name, service.restart_count, now);
+        }
+        self.restart_tracker.retain(&services.iter().map(|s| s.name.as_str()).collect());
+        let restart_loops = self.restart_tracker.restart_loops(now);
+
         // Update cache
         self.cached_services.clear();
         for service in &services {
@@ -204,11 +340,184 @@ impl ServiceMonitor {
             disabled_services,
             total_memory_usage,
             system_service_manager: self.service_manager.clone(),
+            restart_loops,
+            slices,
             services,
         })
     }
 
-    fn get_systemd_services(&self) -> Result<Vec<ServiceInfo>> {
+    /// Replaces the MainPID-only CPU figure with usage of the whole unit
+    /// cgroup, so forked workers and helper processes are counted too.
+    fn apply_cgroup_accounting(&mut self, services: &mut [ServiceInfo]) {
+        for service in services.iter_mut() {
+            let cgroup_path = service.cgroup_path.clone()
+                .unwrap_or_else(|| format!("/system.slice/{}.service", service.name));
+            let stats = match self.cgroup_reader.read_stats(&self.cgroup_reader.resolve(&cgroup_path)) {
+                Ok(stats) => stats,
+                Err(_) => continue,
+            };
+
+            service.cpu_usage = self.service_cpu_sampler.sample(&service.name, stats.cpu_usage_usec);
+            service.memory_usage = stats.memory_current;
+            service.io_read_bytes = stats.io_read_bytes;
+            service.io_write_bytes = stats.io_write_bytes;
+            service.tasks = stats.pids_current;
+            service.oom_kills = stats.oom_kills;
+            service.pressure = stats.pressure;
+            service.cgroup_path = Some(cgroup_path);
+        }
+
+        self.service_cpu_sampler.retain(|name| services.iter().any(|s| s.name == name));
+    }
+
+    fn read_slices(&mut self) -> Vec<SliceNode> {
+        let slices = self.cgroup_reader.read_slice_tree(&mut self.slice_cpu_sampler);
+
+        let paths: HashSet<&str> = slices.iter()
+            .flat_map(|slice| slice.flatten())
+            .map(|(_, node)| node.path.as_str())
+            .collect();
+        self.slice_cpu_sampler.retain(|path| paths.contains(path));
+
+        slices
+    }
+
+    fn get_systemd_services(&mut self) -> Result<Vec<ServiceInfo>> {
+        if self.systemd.is_some() {
+            match self.get_dbus_services() {
+                Ok(services) => return Ok(services),
+                Err(_) => {
+                    // The bus went away; fall back to systemctl from now on
+                    self.systemd = None;
+                    self.unit_events = None;
+                    self.units.clear();
+                }
+            }
+        }
+
+        self.get_systemctl_services()
+    }
+
+    fn get_dbus_services(&mut self) -> Result<Vec<ServiceInfo>> {
+        self.poll_unit_events()?;
+
+        let refresh_interval = if self.unit_events.is_some() {
+            FULL_REFRESH_INTERVAL
+        } else {
+            UNSUBSCRIBED_REFRESH_INTERVAL
+        };
+        let refresh_due = self.needs_full_refresh
+            || self.last_full_refresh.map(|t| t.elapsed() >= refresh_interval).unwrap_or(true);
+
+        if refresh_due {
+            let client = self.systemd.as_mut().ok_or_else(|| anyhow!("Not connected to systemd"))?;
+            self.units = client.list_service_units()?
+                .into_iter()
+                .map(|unit| (unit.path.clone(), unit))
+                .collect();
+            self.needs_full_refresh = false;
+            self.last_full_refresh = Some(Instant::now());
+        }
+
+        let mut services: Vec<ServiceInfo> = self.units.values()
+            .map(|unit| self.service_from_unit(unit))
+            .collect();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(services)
+    }
+
+    /// Applies queued unit signals to the cached unit properties.
+    fn poll_unit_events(&mut self) -> Result<()> {
+        let mut events = Vec::new();
+        if let Some(receiver) = &self.unit_events {
+            loop {
+                match receiver.try_recv() {
+                    Ok(event) => events.push(event),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        self.unit_events = None;
+                        self.needs_full_refresh = true;
+                        break;
+                    }
+                }
+            }
+        }
+
+        for event in events {
+            match event {
+                UnitEvent::PropertiesChanged { path, properties } => {
+                    if let Some(unit) = self.units.get_mut(&path) {
+                        merge_properties(&mut unit.properties, properties);
+                    }
+                }
+                UnitEvent::UnitNew { name, path } => {
+                    if !name.ends_with(".service") || self.units.contains_key(&path) {
+                        continue;
+                    }
+                    if let Some(client) = self.systemd.as_mut() {
+                        match client.get_unit_properties(&path) {
+                            Ok(properties) => {
+                                self.units.insert(path.clone(), SystemdUnit { name, path, properties });
+                            }
+                            // Short-lived units can be gone before GetAll answers
+                            Err(e) if is_error_reply(&e) => continue,
+                            Err(e) => return Err(e),
+                        }
+                    }
+                }
+                UnitEvent::UnitRemoved { path, .. } => {
+                    self.units.remove(&path);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn service_from_unit(&self, unit: &SystemdUnit) -> ServiceInfo {
+        let props = &unit.properties;
+        let text = |key: &str| props.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let number = |key: &str| props.get(key).and_then(|v| v.as_u64());
+        let list = |key: &str| props.get(key).map(|v| v.as_string_list()).unwrap_or_default();
+
+        let state = ServiceState::from(text("UnitFileState").as_str());
+        let main_pid = number("MainPID").filter(|pid| *pid != 0).map(|pid| pid as u32);
+        let active_since = number("ActiveEnterTimestamp")
+            .filter(|usec| *usec != 0)
+            .and_then(|usec| DateTime::from_timestamp_micros(usec as i64));
+
+        ServiceInfo {
+            name: unit.name.strip_suffix(".service").unwrap_or(&unit.name).to_string(),
+            description: text("Description"),
+            status: ServiceStatus::from(text("ActiveState").as_str()),
+            state: state.clone(),
+            enabled: matches!(state, ServiceState::Enabled),
+            active_since,
+            // MemoryCurrent is UINT64_MAX when memory accounting is off
+            memory_usage: number("MemoryCurrent").filter(|m| *m != u64::MAX).unwrap_or(0),
+            cpu_usage: main_pid.map(|pid| self.get_process_cpu_usage(pid).unwrap_or(0.0)).unwrap_or(0.0),
+            restart_count: number("NRestarts").unwrap_or(0) as u32,
+            main_pid,
+            unit_file_path: text("FragmentPath"),
+            service_type: ServiceType::from(props.get("Type").and_then(|v| v.as_str()).unwrap_or("simple")),
+            dependencies: list("Requires"),
+            dependents: list("WantedBy"),
+            wants: list("Wants"),
+            after: list("After"),
+            binds_to: list("BindsTo"),
+            part_of: list("PartOf"),
+            cgroup_path: Some(text("ControlGroup")).filter(|path| !path.is_empty()),
+            io_read_bytes: 0,
+            io_write_bytes: 0,
+            tasks: 0,
+            oom_kills: 0,
+            pressure: PressureMetrics::default(),
+            result: text("Result"),
+            exec_main_status: props.get("ExecMainStatus").and_then(|v| v.as_i64()).map(|status| status as i32),
+        }
+    }
+
+    fn get_systemctl_services(&self) -> Result<Vec<ServiceInfo>> {
         let mut services = Vec::new();
         
         // Get list of all services
@@ -253,7 +562,7 @@ impl ServiceMonitor {
         
         // Get service status
         let status_output = Command::new("systemctl")
-            .args(&["show", &service_unit, "--no-pager", "--property=Id,Description,ActiveState,UnitFileState,ActiveEnterTimestamp,MainPID,Type,MemoryCurrent,Requires,WantedBy,NRestarts"])
+            .args(["show", &service_unit, "--no-pager", "--property=Id,Description,ActiveState,UnitFileState,ActiveEnterTimestamp,MainPID,Type,MemoryCurrent,Requires,WantedBy,Wants,After,BindsTo,PartOf,NRestarts,ControlGroup,Result,ExecMainStatus"])
             .output()?;
 
         if !status_output.status.success() {
@@ -320,6 +629,12 @@ impl ServiceMonitor {
             .map(|deps| deps.split_whitespace().map(|s| s.to_string()).collect())
             .unwrap_or_default();
 
+        let unit_list = |key: &str| -> Vec<String> {
+            properties.get(key)
+                .map(|deps| deps.split_whitespace().map(|s| s.to_string()).collect())
+                .unwrap_or_default()
+        };
+
         // Get unit file path
         let unit_file_path = self.get_systemd_unit_file_path(&service_unit)?;
 
@@ -338,6 +653,18 @@ impl ServiceMonitor {
             service_type,
             dependencies,
             dependents,
+            wants: unit_list("Wants"),
+            after: unit_list("After"),
+            binds_to: unit_list("BindsTo"),
+            part_of: unit_list("PartOf"),
+            cgroup_path: properties.get("ControlGroup").filter(|path| !path.is_empty()).cloned(),
+            io_read_bytes: 0,
+            io_write_bytes: 0,
+            tasks: 0,
+            oom_kills: 0,
+            pressure: PressureMetrics::default(),
+            result: properties.get("Result").cloned().unwrap_or_default(),
+            exec_main_status: properties.get("ExecMainStatus").and_then(|status| status.parse().ok()),
         })
     }
 
@@ -420,6 +747,18 @@ impl ServiceMonitor {
                     service_type: ServiceType::Unknown,
                     dependencies: Vec::new(),
                     dependents: Vec::new(),
+                    wants: Vec::new(),
+                    after: Vec::new(),
+                    binds_to: Vec::new(),
+                    part_of: Vec::new(),
+                    cgroup_path: None,
+                    io_read_bytes: 0,
+                    io_write_bytes: 0,
+                    tasks: 0,
+                    oom_kills: 0,
+                    pressure: PressureMetrics::default(),
+                    result: String::new(),
+                    exec_main_status: None,
                 };
                 
                 services.push(service_info);
@@ -455,6 +794,18 @@ impl ServiceMonitor {
                         service_type: ServiceType::Unknown,
                         dependencies: Vec::new(),
                         dependents: Vec::new(),
+                        wants: Vec::new(),
+                        after: Vec::new(),
+                        binds_to: Vec::new(),
+                        part_of: Vec::new(),
+                        cgroup_path: None,
+                        io_read_bytes: 0,
+                        io_write_bytes: 0,
+                        tasks: 0,
+                        oom_kills: 0,
+                        pressure: PressureMetrics::default(),
+                        result: String::new(),
+                        exec_main_status: None,
                     };
                     
                     services.push(service_info);
@@ -487,6 +838,18 @@ impl ServiceMonitor {
                         service_type: ServiceType::Unknown,
                         dependencies: Vec::new(),
                         dependents: Vec::new(),
+                        wants: Vec::new(),
+                        after: Vec::new(),
+                        binds_to: Vec::new(),
+                        part_of: Vec::new(),
+                        cgroup_path: None,
+                        io_read_bytes: 0,
+                        io_write_bytes: 0,
+                        tasks: 0,
+                        oom_kills: 0,
+                        pressure: PressureMetrics::default(),
+                        result: String::new(),
+                        exec_main_status: None,
                     };
                     
                     services.push(service_info);
@@ -497,6 +860,15 @@ impl ServiceMonitor {
         Ok(services)
     }
 
+    /// Follows the journal of a systemd service, starting with its last
+    /// `lines` entries.
+    pub fn follow_journal(&self, service_name: &str, lines: usize) -> Result<JournalFollower> {
+        if self.service_manager != ServiceManager::Systemd {
+            return Err(anyhow!("The journal is only available with systemd"));
+        }
+        JournalFollower::follow_unit(&format!("{}.service", service_name), lines)
+    }
+
     pub fn get_service_manager(&self) -> &ServiceManager {
         &self.service_manager
     }
@@ -506,53 +878,60 @@ impl ServiceMonitor {
     }
 
     pub fn restart_service(&self, service_name: &str) -> Result<()> {
-        match self.service_manager {
-            ServiceManager::Systemd => {
-                let output = Command::new("systemctl")
-                    .args(&["restart", &format!("{}.service", service_name)])
-                    .output()?;
-                
-                if !output.status.success() {
-                    return Err(anyhow!("Failed to restart service: {}", service_name));
-                }
-            }
-            _ => return Err(anyhow!("Service restart not implemented for this service manager")),
-        }
-        
-        Ok(())
+        self.perform_action(service_name, ServiceAction::Restart).map(|_| ())
     }
 
     pub fn stop_service(&self, service_name: &str) -> Result<()> {
-        match self.service_manager {
-            ServiceManager::Systemd => {
-                let output = Command::new("systemctl")
-                    .args(&["stop", &format!("{}.service", service_name)])
-                    .output()?;
-                
-                if !output.status.success() {
-                    return Err(anyhow!("Failed to stop service: {}", service_name));
-                }
-            }
-            _ => return Err(anyhow!("Service stop not implemented for this service manager")),
-        }
-        
-        Ok(())
+        self.perform_action(service_name, ServiceAction::Stop).map(|_| ())
     }
 
     pub fn start_service(&self, service_name: &str) -> Result<()> {
+        self.perform_action(service_name, ServiceAction::Start).map(|_| ())
+    }
+
+    /// Runs an action on a service and returns the job result reported by
+    /// systemd. Anything other than "done" is turned into an error.
+    pub fn perform_action(&self, service_name: &str, action: ServiceAction) -> Result<String> {
         match self.service_manager {
-            ServiceManager::Systemd => {
-                let output = Command::new("systemctl")
-                    .args(&["start", &format!("{}.service", service_name)])
-                    .output()?;
-                
-                if !output.status.success() {
-                    return Err(anyhow!("Failed to start service: {}", service_name));
-                }
+            ServiceManager::Systemd => Self::run_systemd_action(self.systemd.is_some(), service_name, action),
+            _ => Err(anyhow!("Service {} not implemented for this service manager", action.as_str())),
+        }
+    }
+
+    /// Runs an action on a background thread; poll the job for its result.
+    pub fn start_action(&self, service_name: &str, action: ServiceAction) -> Result<ServiceJob> {
+        if self.service_manager != ServiceManager::Systemd {
+            return Err(anyhow!("Service {} not implemented for this service manager", action.as_str()));
+        }
+
+        let use_dbus = self.systemd.is_some();
+        let service = service_name.to_string();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = sender.send(Self::run_systemd_action(use_dbus, &service, action));
+        });
+
+        Ok(ServiceJob { action, service: service_name.to_string(), receiver })
+    }
+
+    fn run_systemd_action(use_dbus: bool, service_name: &str, action: ServiceAction) -> Result<String> {
+        let unit = format!("{}.service", service_name);
+
+        if use_dbus {
+            let result = SystemdClient::run_job(action.job_method(), &unit)?;
+            if result != "done" {
+                return Err(anyhow!("Job for {} finished with result '{}'", unit, result));
             }
-            _ => return Err(anyhow!("Service start not implemented for this service manager")),
+            return Ok(result);
         }
-        
-        Ok(())
+
+        let output = Command::new("systemctl")
+            .args([action.as_str(), &unit])
+            .output()?;
+
+        if !output.status.success() {
+            return Err(anyhow!("Failed to {} service: {}", action.as_str(), service_name));
+        }
+        Ok("done".to_string())
     }
 }
\ No newline at end of file
@@ -2,8 +2,24 @@ use anyhow::{Result, anyhow};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
 use std::process::Command;
 use std::str;
+use std::time::{Duration, Instant};
+
+use super::ssh::{BlocklistFormat, ProcessSnapshot, SshConnection, SshMonitor, SshThreat};
+use super::utmp::{
+    current_sessions, login_history, read_records, summarize_failed_logins, BootEvent, FailedLoginSource,
+    LoginRecord, UtmpFile, UtmpRecord,
+};
+
+const UTMP_PATHS: &[&str] = &["/run/utmp", "/var/run/utmp"];
+const WTMP_PATH: &str = "/var/log/wtmp";
+const BTMP_PATH: &str = "/var/log/btmp";
+const MAX_LOGIN_HISTORY: usize = 500;
+/// Linking SSH sessions to sshd walks all of /proc and the fds of each
+/// session's process tree, so it is redone at most this often.
+const SSH_LINK_INTERVAL: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserSession {
@@ -25,6 +41,9 @@ pub struct UserSession {
     pub shell: String,
     pub seat: Option<String>,
     pub service: Option<String>,
+    /// The session's login process: the logind leader or the utmp PID.
+    pub leader_pid: Option<u32>,
+    pub ssh: Option<SshConnection>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -57,6 +76,14 @@ pub struct SessionMetrics {
     pub total_cpu_usage: f64,
     pub login_attempts_failed: u32,
     pub session_manager: SessionManager,
+    /// Past and current logins from wtmp, newest first.
+    pub login_history: Vec<LoginRecord>,
+    /// Reboots and shutdowns from wtmp, newest first.
+    pub boot_events: Vec<BootEvent>,
+    /// Failed logins from btmp grouped by source, most attempts first.
+    pub failed_login_sources: Vec<FailedLoginSource>,
+    /// Sources flagged by the SSH brute-force detector.
+    pub ssh_threats: Vec<SshThreat>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -71,6 +98,12 @@ pub struct SessionMonitor {
     session_manager: SessionManager,
     cached_sessions: HashMap<String, UserSession>,
     last_update: Option<DateTime<Utc>>,
+    wtmp: UtmpFile,
+    btmp: UtmpFile,
+    ssh: SshMonitor,
+    /// SSH connection of each session leader as of `ssh_linked_at`.
+    ssh_links: HashMap<u32, Option<SshConnection>>,
+    ssh_linked_at: Option<Instant>,
 }
 
 impl Default for SessionType {
@@ -111,6 +144,11 @@ impl SessionMonitor {
             session_manager,
             cached_sessions: HashMap::new(),
             last_update: None,
+            wtmp: UtmpFile::new(WTMP_PATH),
+            btmp: UtmpFile::new(BTMP_PATH),
+            ssh: SshMonitor::new(),
+            ssh_links: HashMap::new(),
+            ssh_linked_at: None,
         }
     }
 
@@ -129,14 +167,37 @@ impl SessionMonitor {
         SessionManager::Traditional
     }
 
+    /// Re-reads the process tree and sockets behind each SSH session once
+    /// `SSH_LINK_INTERVAL` has passed.
+    fn link_ssh_sessions(&mut self, sessions: &[UserSession]) {
+        if self.ssh_linked_at.map(|t| t.elapsed() < SSH_LINK_INTERVAL).unwrap_or(false) {
+            return;
+        }
+        self.ssh_linked_at = Some(Instant::now());
+
+        let processes = ProcessSnapshot::read();
+        self.ssh_links = sessions.iter()
+            .filter(|s| s.session_type == SessionType::SSH)
+            .filter_map(|s| s.leader_pid.map(|pid| (pid, self.ssh.connection(&processes, pid, &s.user))))
+            .collect();
+    }
+
     pub fn get_session_metrics(&mut self) -> Result<SessionMetrics> {
-        let sessions = match self.session_manager {
+        let mut sessions = match self.session_manager {
             SessionManager::Systemd => self.get_systemd_sessions()?,
             SessionManager::ConsoleKit => self.get_consolekit_sessions()?,
             SessionManager::Traditional => self.get_traditional_sessions()?,
             SessionManager::Unknown => Vec::new(),
         };
 
+        if sessions.iter().any(|s| s.session_type == SessionType::SSH) {
+            self.link_ssh_sessions(&sessions);
+            for session in sessions.iter_mut().filter(|s| s.session_type == SessionType::SSH) {
+                session.ssh = session.leader_pid
+                    .and_then(|pid| self.ssh_links.get(&pid).cloned().flatten());
+            }
+        }
+
         let active_sessions = sessions.iter()
             .filter(|s| s.state == SessionState::Active)
             .count();
@@ -160,7 +221,12 @@ impl SessionMonitor {
             .map(|s| s.cpu_usage)
             .sum();
 
-        let login_attempts_failed = self.get_failed_login_attempts()?;
+        let history = self.wtmp.refresh().map(login_history).unwrap_or_default();
+        let failed_login_sources = self.btmp.refresh().map(summarize_failed_logins).ok();
+        let login_attempts_failed = match &failed_login_sources {
+            Some(sources) => sources.iter().map(|s| s.attempts).sum(),
+            None => self.get_failed_login_attempts()?,
+        };
 
         // Update cache
         self.cached_sessions.clear();
@@ -180,9 +246,25 @@ impl SessionMonitor {
             login_attempts_failed,
             session_manager: self.session_manager.clone(),
             sessions,
+            login_history: history.sessions.into_iter().take(MAX_LOGIN_HISTORY).collect(),
+            boot_events: history.boot_events,
+            failed_login_sources: failed_login_sources.unwrap_or_default(),
+            ssh_threats: self.ssh.threats(),
         })
     }
 
+    /// Reads new sshd auth messages into the brute-force detector and
+    /// returns the threats raised since the last poll.
+    pub fn poll_ssh_auth(&mut self) -> Vec<SshThreat> {
+        self.ssh.poll()
+    }
+
+    /// Writes the sources flagged by the brute-force detector as a firewall
+    /// blocklist and returns how many there were.
+    pub fn write_ssh_blocklist(&self, path: &Path, format: BlocklistFormat) -> Result<usize> {
+        self.ssh.write_blocklist(path, format)
+    }
+
     fn get_systemd_sessions(&self) -> Result<Vec<UserSession>> {
         let mut sessions = Vec::new();
 
@@ -221,7 +303,7 @@ impl SessionMonitor {
     fn get_systemd_session_details(&self, session_id: &str, uid: u32, user: &str) -> Result<UserSession> {
         // Get session properties
         let properties_output = Command::new("loginctl")
-            .args(&["show-session", session_id, "--no-pager", "--property=Type,State,TTY,Display,RemoteHost,Timestamp,IdleHint,IdleSinceHint,Seat,Service"])
+            .args(["show-session", session_id, "--no-pager", "--property=Type,State,TTY,Display,RemoteHost,Timestamp,IdleHint,IdleSinceHint,Seat,Service,Leader"])
             .output()?;
 
         if !properties_output.status.success() {
@@ -250,6 +332,14 @@ impl SessionMonitor {
         let remote_host = properties.get("RemoteHost").filter(|s| !s.is_empty()).cloned();
         let seat = properties.get("Seat").filter(|s| !s.is_empty()).cloned();
         let service = properties.get("Service").filter(|s| !s.is_empty()).cloned();
+        let leader_pid = properties.get("Leader").and_then(|pid| pid.parse().ok()).filter(|&pid| pid != 0);
+
+        // logind reports SSH logins as Type=tty
+        let session_type = if service.as_deref() == Some("sshd") {
+            SessionType::SSH
+        } else {
+            session_type
+        };
 
         // Parse login time
         let login_time = properties.get("Timestamp")
@@ -305,45 +395,84 @@ impl SessionMonitor {
             shell: user_info.shell,
             seat,
             service,
+            leader_pid,
+            ssh: None,
         })
     }
 
+    /// ConsoleKit keeps utmp up to date, which carries everything its
+    /// multi-line `ck-list-sessions` output would give us.
     fn get_consolekit_sessions(&self) -> Result<Vec<UserSession>> {
-        let mut sessions = Vec::new();
-
-        // Get sessions from ConsoleKit
-        let output = Command::new("ck-list-sessions")
-            .output()?;
-
-        if !output.status.success() {
-            return Ok(sessions);
-        }
+        self.get_traditional_sessions()
+    }
 
-        let stdout = str::from_utf8(&output.stdout)?;
-        
-        // Parse ConsoleKit output (simplified implementation)
-        for line in stdout.lines() {
-            if line.contains("Session") && line.contains(":") {
-                // This is a simplified parser - real implementation would be more complex
-                if let Some(session_info) = self.parse_consolekit_session(line)? {
-                    sessions.push(session_info);
-                }
-            }
+    fn get_traditional_sessions(&self) -> Result<Vec<UserSession>> {
+        if let Some(records) = UTMP_PATHS.iter().find_map(|path| read_records(Path::new(path)).ok()) {
+            return Ok(current_sessions(&records)
+                .into_iter()
+                .filter_map(|record| self.session_from_utmp(record).ok())
+                .collect());
         }
 
-        Ok(sessions)
+        self.get_who_sessions()
     }
 
-    fn parse_consolekit_session(&self, _line: &str) -> Result<Option<UserSession>> {
-        // Placeholder implementation for ConsoleKit parsing
-        // Real implementation would parse the ConsoleKit session format
-        Ok(None)
+    fn session_from_utmp(&self, record: &UtmpRecord) -> Result<UserSession> {
+        let user_info = self.get_user_info_by_name(&record.user)?;
+        let processes = self.get_user_processes(user_info.uid)?;
+        let (cpu_usage, memory_usage) = self.calculate_user_resource_usage(&processes)?;
+
+        // X display managers record the display (":0") as the host
+        let display = Some(record.host.clone()).filter(|host| host.starts_with(':'));
+        let remote_host = record.source().filter(|_| display.is_none());
+
+        let session_type = if display.is_some() {
+            SessionType::X11
+        } else if remote_host.is_some() {
+            SessionType::SSH
+        } else if record.line.starts_with("tty") {
+            SessionType::TTY
+        } else if record.line == "console" {
+            SessionType::Console
+        } else {
+            SessionType::Unknown
+        };
+
+        // Like `who -u`, idle time is how long ago the terminal was last read
+        let idle_time = std::fs::metadata(Path::new("/dev").join(&record.line))
+            .and_then(|m| m.accessed())
+            .ok()
+            .and_then(|accessed| accessed.elapsed().ok())
+            .map(|idle| idle.as_secs());
+
+        Ok(UserSession {
+            user: record.user.clone(),
+            session_id: format!("utmp-{}-{}", record.line, record.pid),
+            session_type,
+            state: SessionState::Active,
+            tty: Some(record.line.clone()),
+            display,
+            remote_host,
+            login_time: record.timestamp,
+            idle_time,
+            cpu_usage,
+            memory_usage,
+            processes,
+            uid: user_info.uid,
+            gid: user_info.gid,
+            home_dir: user_info.home_dir,
+            shell: user_info.shell,
+            seat: None,
+            service: None,
+            leader_pid: u32::try_from(record.pid).ok().filter(|&pid| pid != 0),
+            ssh: None,
+        })
     }
 
-    fn get_traditional_sessions(&self) -> Result<Vec<UserSession>> {
+    fn get_who_sessions(&self) -> Result<Vec<UserSession>> {
         let mut sessions = Vec::new();
 
-        // Use 'who' command for traditional session information
+        // Use 'who' command when utmp cannot be read
         let output = Command::new("who")
             .args(&["-H", "-u"])
             .output()?;
@@ -424,6 +553,8 @@ impl SessionMonitor {
             shell: user_info.shell,
             seat: None,
             service: None,
+            leader_pid: None,
+            ssh: None,
         })
     }
 
@@ -441,6 +572,14 @@ impl SessionMonitor {
     }
 
     fn get_user_info_by_name(&self, username: &str) -> Result<UserInfo> {
+        // Local accounts resolve without spawning getent
+        if let Ok(passwd) = std::fs::read_to_string("/etc/passwd") {
+            let prefix = format!("{}:", username);
+            if let Some(entry) = passwd.lines().find(|line| line.starts_with(&prefix)) {
+                return self.parse_passwd_entry(entry);
+            }
+        }
+
         let output = Command::new("getent")
             .args(&["passwd", username])
             .output()?;
@@ -540,6 +679,7 @@ impl SessionMonitor {
         Ok((total_cpu, total_memory))
     }
 
+    /// Fallback when btmp is not readable (it is root-only by default).
     fn get_failed_login_attempts(&self) -> Result<u32> {
         // Try to read failed login attempts from system logs
         let mut failed_attempts = 0u32;
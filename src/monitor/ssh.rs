@@ -0,0 +1,949 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use super::journal::parse_journal_json;
+use super::security::AlertSeverity;
+
+const AUTH_LOG_PATHS: &[&str] = &["/var/log/auth.log", "/var/log/secure"];
+/// OpenSSH 9.8 moved per-connection work into `sshd-session`.
+const SSHD_NAMES: &[&str] = &["sshd", "sshd-session"];
+/// How far back the first read of the auth log goes.
+const INITIAL_LOOKBACK_HOURS: i64 = 24;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_ACCEPTED_LOGINS: usize = 1000;
+
+/// Sliding windows the detector counts failures over.
+const MINUTE: chrono::Duration = chrono::Duration::minutes(1);
+const TEN_MINUTES: chrono::Duration = chrono::Duration::minutes(10);
+const HOUR: chrono::Duration = chrono::Duration::hours(1);
+
+/// An SSH login tied back to the sshd processes serving it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshConnection {
+    /// The per-connection sshd monitor (`sshd: user [priv]`).
+    pub sshd_pid: u32,
+    /// The monitor and everything below it, including the user's shell.
+    pub process_tree: Vec<u32>,
+    pub remote_addr: Option<IpAddr>,
+    pub remote_port: Option<u16>,
+    pub local_port: Option<u16>,
+    /// `publickey`, `password`, `keyboard-interactive/pam`, ... as logged
+    /// by sshd when the login was accepted.
+    pub auth_method: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SshAuthOutcome {
+    Accepted,
+    Failed,
+    /// `Invalid user`: the account does not exist.
+    InvalidUser,
+}
+
+/// One sshd authentication message from the journal or auth log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshAuthEvent {
+    pub timestamp: DateTime<Utc>,
+    pub outcome: SshAuthOutcome,
+    pub method: Option<String>,
+    pub user: String,
+    pub valid_user: bool,
+    pub source: IpAddr,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SshThreatKind {
+    /// Many failures from one source.
+    BruteForce,
+    /// Many existing accounts tried a few times each, as with leaked
+    /// username/password pairs.
+    CredentialStuffing,
+    /// Many non-existent accounts probed.
+    UserEnumeration,
+    /// A successful login from a source that just failed repeatedly.
+    CompromiseSuspected,
+}
+
+impl SshThreatKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SshThreatKind::BruteForce => "brute force",
+            SshThreatKind::CredentialStuffing => "credential stuffing",
+            SshThreatKind::UserEnumeration => "user enumeration",
+            SshThreatKind::CompromiseSuspected => "login after failures",
+        }
+    }
+
+    pub fn severity(&self) -> AlertSeverity {
+        match self {
+            SshThreatKind::BruteForce => AlertSeverity::High,
+            SshThreatKind::CredentialStuffing => AlertSeverity::High,
+            SshThreatKind::UserEnumeration => AlertSeverity::Medium,
+            SshThreatKind::CompromiseSuspected => AlertSeverity::Critical,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshThreat {
+    pub source: IpAddr,
+    pub kind: SshThreatKind,
+    pub failures_last_minute: u32,
+    pub failures_last_ten_minutes: u32,
+    pub failures_last_hour: u32,
+    /// Usernames tried from the source within the last hour.
+    pub users: Vec<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+impl SshThreat {
+    pub fn message(&self) -> String {
+        format!(
+            "SSH {} from {}: {} failures in 1m, {} in 10m, {} in 1h across {} users",
+            self.kind.label(),
+            self.source,
+            self.failures_last_minute,
+            self.failures_last_ten_minutes,
+            self.failures_last_hour,
+            self.users.len()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum BlocklistFormat {
+    Nftables,
+    Iptables,
+}
+
+impl BlocklistFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            BlocklistFormat::Nftables => "nft",
+            BlocklistFormat::Iptables => "sh",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Attempt {
+    time: DateTime<Utc>,
+    user: String,
+    valid_user: bool,
+}
+
+/// Tracks failed SSH logins per source over 1 minute, 10 minute and 1 hour
+/// windows and flags sources whose pattern matches a known attack.
+#[derive(Debug, Clone)]
+pub struct BruteForceDetector {
+    failures: HashMap<IpAddr, VecDeque<Attempt>>,
+    threats: HashMap<(IpAddr, SshThreatKind), SshThreat>,
+    /// Connections already counted through their `Invalid user` line, so
+    /// the `Failed ... for invalid user` that follows is not counted twice.
+    counted_invalid: HashSet<(IpAddr, u16)>,
+    pub failures_per_minute: u32,
+    pub failures_per_ten_minutes: u32,
+    pub failures_per_hour: u32,
+    pub distinct_users: usize,
+    pub failures_before_success: u32,
+}
+
+impl Default for BruteForceDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BruteForceDetector {
+    pub fn new() -> Self {
+        Self {
+            failures: HashMap::new(),
+            threats: HashMap::new(),
+            counted_invalid: HashSet::new(),
+            failures_per_minute: 10,
+            failures_per_ten_minutes: 30,
+            failures_per_hour: 100,
+            distinct_users: 5,
+            failures_before_success: 5,
+        }
+    }
+
+    /// Feeds one event and returns the threats it raised for the first time.
+    pub fn record(&mut self, event: &SshAuthEvent) -> Vec<SshThreat> {
+        self.prune(event.timestamp);
+
+        match event.outcome {
+            SshAuthOutcome::Accepted => {
+                let failures = self.count_since(event.source, event.timestamp - HOUR, |_| true);
+                if failures as u32 >= self.failures_before_success {
+                    return self.raise(event.source, SshThreatKind::CompromiseSuspected, event.timestamp).into_iter().collect();
+                }
+                return Vec::new();
+            }
+            SshAuthOutcome::InvalidUser => {
+                if let Some(port) = event.port {
+                    self.counted_invalid.insert((event.source, port));
+                }
+            }
+            SshAuthOutcome::Failed => {
+                if !event.valid_user {
+                    if let Some(port) = event.port {
+                        if self.counted_invalid.remove(&(event.source, port)) {
+                            return Vec::new();
+                        }
+                    }
+                }
+            }
+        }
+
+        self.failures.entry(event.source).or_default().push_back(Attempt {
+            time: event.timestamp,
+            user: event.user.clone(),
+            valid_user: event.valid_user,
+        });
+
+        let now = event.timestamp;
+        let mut raised = Vec::new();
+
+        let last_minute = self.count_since(event.source, now - MINUTE, |_| true) as u32;
+        let last_ten = self.count_since(event.source, now - TEN_MINUTES, |_| true) as u32;
+        let last_hour = self.count_since(event.source, now - HOUR, |_| true) as u32;
+        if last_minute >= self.failures_per_minute
+            || last_ten >= self.failures_per_ten_minutes
+            || last_hour >= self.failures_per_hour
+        {
+            raised.extend(self.raise(event.source, SshThreatKind::BruteForce, now));
+        }
+
+        let invalid_users = self.distinct_users_since(event.source, now - TEN_MINUTES, false);
+        if invalid_users >= self.distinct_users {
+            raised.extend(self.raise(event.source, SshThreatKind::UserEnumeration, now));
+        }
+
+        // Stuffing spreads a handful of guesses over many real accounts,
+        // where brute force hammers a few
+        let valid_users = self.distinct_users_since(event.source, now - TEN_MINUTES, true);
+        let valid_failures = self.count_since(event.source, now - TEN_MINUTES, |a| a.valid_user);
+        if valid_users >= self.distinct_users && valid_failures <= valid_users * 2 {
+            raised.extend(self.raise(event.source, SshThreatKind::CredentialStuffing, now));
+        }
+
+        raised
+    }
+
+    /// Sources currently flagged, most failures first.
+    pub fn threats(&self) -> Vec<SshThreat> {
+        let mut threats: Vec<SshThreat> = self.threats.values().cloned().collect();
+        threats.sort_by(|a, b| b.failures_last_hour.cmp(&a.failures_last_hour).then(a.source.cmp(&b.source)));
+        threats
+    }
+
+    /// Flagged sources, for a firewall blocklist.
+    pub fn blocklist(&self) -> Vec<IpAddr> {
+        let mut sources: Vec<IpAddr> = self.threats.keys().map(|(source, _)| *source).collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// Drops attempts older than the longest window and threats that have
+    /// been quiet for as long.
+    pub fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - HOUR;
+        self.failures.retain(|_, attempts| {
+            while attempts.front().map(|a| a.time < cutoff).unwrap_or(false) {
+                attempts.pop_front();
+            }
+            !attempts.is_empty()
+        });
+        self.threats.retain(|_, threat| threat.last_seen >= cutoff);
+        if self.failures.is_empty() {
+            self.counted_invalid.clear();
+        }
+    }
+
+    fn count_since(&self, source: IpAddr, since: DateTime<Utc>, filter: impl Fn(&Attempt) -> bool) -> usize {
+        self.failures.get(&source)
+            .map(|attempts| attempts.iter().filter(|a| a.time >= since && filter(a)).count())
+            .unwrap_or(0)
+    }
+
+    fn distinct_users_since(&self, source: IpAddr, since: DateTime<Utc>, valid_user: bool) -> usize {
+        self.failures.get(&source)
+            .map(|attempts| {
+                attempts.iter()
+                    .filter(|a| a.time >= since && a.valid_user == valid_user)
+                    .map(|a| a.user.as_str())
+                    .collect::<HashSet<_>>()
+                    .len()
+            })
+            .unwrap_or(0)
+    }
+
+    fn raise(&mut self, source: IpAddr, kind: SshThreatKind, now: DateTime<Utc>) -> Option<SshThreat> {
+        let mut users: Vec<String> = self.failures.get(&source)
+            .map(|attempts| attempts.iter().map(|a| a.user.clone()).collect::<HashSet<_>>().into_iter().collect())
+            .unwrap_or_default();
+        users.sort();
+
+        let failures_last_minute = self.count_since(source, now - MINUTE, |_| true) as u32;
+        let failures_last_ten_minutes = self.count_since(source, now - TEN_MINUTES, |_| true) as u32;
+        let failures_last_hour = self.count_since(source, now - HOUR, |_| true) as u32;
+
+        match self.threats.get_mut(&(source, kind)) {
+            Some(threat) => {
+                threat.failures_last_minute = failures_last_minute;
+                threat.failures_last_ten_minutes = failures_last_ten_minutes;
+                threat.failures_last_hour = failures_last_hour;
+                threat.users = users;
+                threat.last_seen = now;
+                None
+            }
+            None => {
+                let threat = SshThreat {
+                    source,
+                    kind,
+                    failures_last_minute,
+                    failures_last_ten_minutes,
+                    failures_last_hour,
+                    users,
+                    first_seen: now,
+                    last_seen: now,
+                };
+                self.threats.insert((source, kind), threat.clone());
+                Some(threat)
+            }
+        }
+    }
+}
+
+enum AuthLogSource {
+    Journal { cursor: Option<String> },
+    File { path: PathBuf, offset: u64 },
+}
+
+/// Incremental reader of sshd's authentication messages, from the journal
+/// or, without one, the syslog auth log.
+pub struct SshAuthLog {
+    source: Option<AuthLogSource>,
+}
+
+impl Default for SshAuthLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SshAuthLog {
+    pub fn new() -> Self {
+        let journal = Command::new("journalctl")
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+
+        let source = if journal {
+            Some(AuthLogSource::Journal { cursor: None })
+        } else {
+            AUTH_LOG_PATHS.iter()
+                .map(PathBuf::from)
+                .find(|path| path.exists())
+                .map(|path| AuthLogSource::File { path, offset: 0 })
+        };
+
+        Self { source }
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.source.is_some()
+    }
+
+    /// Returns the events logged since the previous call. The first call
+    /// returns the last 24 hours.
+    pub fn read_new(&mut self) -> Result<Vec<SshAuthEvent>> {
+        let since = Utc::now() - chrono::Duration::hours(INITIAL_LOOKBACK_HOURS);
+        match &mut self.source {
+            Some(AuthLogSource::Journal { cursor }) => {
+                let mut command = Command::new("journalctl");
+                command.args(["--no-pager", "--output=json"]);
+                for name in SSHD_NAMES {
+                    command.arg(format!("--identifier={}", name));
+                }
+                match cursor {
+                    Some(cursor) => command.arg(format!("--after-cursor={}", cursor)),
+                    None => command.arg(format!("--since=-{}h", INITIAL_LOOKBACK_HOURS)),
+                };
+
+                let output = command.stderr(Stdio::null()).output()
+                    .map_err(|e| anyhow!("Failed to run journalctl: {}", e))?;
+
+                let mut events = Vec::new();
+                for line in String::from_utf8_lossy(&output.stdout).lines() {
+                    let entry = match parse_journal_json(line) {
+                        Some(entry) => entry,
+                        None => continue,
+                    };
+                    if entry.cursor.is_some() {
+                        *cursor = entry.cursor.clone();
+                    }
+                    if let Some(event) = entry.timestamp.and_then(|time| parse_sshd_message(&entry.message, time)) {
+                        events.push(event);
+                    }
+                }
+                Ok(events)
+            }
+            Some(AuthLogSource::File { path, offset }) => {
+                let mut file = File::open(path)?;
+                let length = file.metadata()?.len();
+                // Rotated or truncated
+                if length < *offset {
+                    *offset = 0;
+                }
+                file.seek(SeekFrom::Start(*offset))?;
+                let mut content = Vec::new();
+                file.read_to_end(&mut content)?;
+
+                // Leave a partially written last line for the next read
+                let complete = content.iter().rposition(|&b| b == b'\n').map(|i| i + 1).unwrap_or(0);
+                *offset += complete as u64;
+
+                Ok(String::from_utf8_lossy(&content[..complete])
+                    .lines()
+                    .filter_map(parse_auth_log_line)
+                    .filter(|event| event.timestamp >= since)
+                    .collect())
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Reads sshd's auth messages, feeds the brute-force detector and links
+/// SSH sessions to their connections.
+pub struct SshMonitor {
+    auth_log: SshAuthLog,
+    detector: BruteForceDetector,
+    /// Recent successful logins, newest last, for the auth method of
+    /// current sessions.
+    accepted: VecDeque<SshAuthEvent>,
+    last_poll: Option<Instant>,
+    /// Set once the initial lookback has been read.
+    primed: bool,
+}
+
+impl Default for SshMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SshMonitor {
+    pub fn new() -> Self {
+        Self {
+            auth_log: SshAuthLog::new(),
+            detector: BruteForceDetector::new(),
+            accepted: VecDeque::new(),
+            last_poll: None,
+            primed: false,
+        }
+    }
+
+    /// Reads new auth messages at most every few seconds and returns the
+    /// threats raised by them. The initial lookback only primes the
+    /// detector: its threats are listed but not returned as new.
+    pub fn poll(&mut self) -> Vec<SshThreat> {
+        if self.last_poll.map(|t| t.elapsed() < POLL_INTERVAL).unwrap_or(false) {
+            return Vec::new();
+        }
+        self.last_poll = Some(Instant::now());
+
+        let (events, live) = match self.auth_log.read_new() {
+            Ok(events) => (events, std::mem::replace(&mut self.primed, true)),
+            Err(_) => (Vec::new(), self.primed),
+        };
+        let mut raised = Vec::new();
+        for event in events {
+            let threats = self.detector.record(&event);
+            if live {
+                raised.extend(threats);
+            }
+            if event.outcome == SshAuthOutcome::Accepted {
+                self.accepted.push_back(event);
+                if self.accepted.len() > MAX_ACCEPTED_LOGINS {
+                    self.accepted.pop_front();
+                }
+            }
+        }
+        self.detector.prune(Utc::now());
+        raised
+    }
+
+    pub fn threats(&self) -> Vec<SshThreat> {
+        self.detector.threats()
+    }
+
+    pub fn detector(&self) -> &BruteForceDetector {
+        &self.detector
+    }
+
+    pub fn is_auth_log_available(&self) -> bool {
+        self.auth_log.is_available()
+    }
+
+    /// Links the session whose login process is `pid` to its sshd process
+    /// tree, TCP connection and the method it authenticated with.
+    pub fn connection(&self, processes: &ProcessSnapshot, pid: u32, user: &str) -> Option<SshConnection> {
+        let sshd_pid = processes.sshd_monitor(pid)?;
+        let process_tree = processes.descendants(sshd_pid);
+
+        let (mut remote_addr, mut remote_port, mut local_port) = (None, None, None);
+        let inodes: HashSet<u64> = process_tree.iter().flat_map(|&pid| socket_inodes(pid)).collect();
+        if let Some(socket) = processes.sockets.iter().find(|socket| inodes.contains(&socket.inode)) {
+            remote_addr = Some(socket.remote.0);
+            remote_port = Some(socket.remote.1);
+            local_port = Some(socket.local_port);
+        } else if let Some((addr, port, local)) = process_tree.iter().find_map(|&pid| ssh_connection_env(pid)) {
+            // The sshd sockets are root's; the shell's environment is ours
+            remote_addr = Some(addr);
+            remote_port = Some(port);
+            local_port = Some(local);
+        }
+
+        let auth_method = self.accepted.iter().rev()
+            .find(|event| {
+                event.user == user
+                    && remote_addr.map(|addr| addr == event.source).unwrap_or(true)
+                    && remote_port.zip(event.port).map(|(a, b)| a == b).unwrap_or(true)
+            })
+            .and_then(|event| event.method.clone());
+
+        Some(SshConnection {
+            sshd_pid,
+            process_tree,
+            remote_addr,
+            remote_port,
+            local_port,
+            auth_method,
+        })
+    }
+
+    /// Writes a firewall blocklist of the flagged sources for the user to
+    /// review and apply; nothing is applied here.
+    pub fn write_blocklist(&self, path: &Path, format: BlocklistFormat) -> Result<usize> {
+        let sources = self.detector.blocklist();
+        std::fs::write(path, render_blocklist(&sources, format))?;
+        Ok(sources.len())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TcpSocket {
+    inode: u64,
+    local_port: u16,
+    remote: (IpAddr, u16),
+}
+
+/// Parent/child links and names of all processes plus the established TCP
+/// sockets, read once per refresh.
+pub struct ProcessSnapshot {
+    parents: HashMap<u32, u32>,
+    children: HashMap<u32, Vec<u32>>,
+    names: HashMap<u32, String>,
+    sockets: Vec<TcpSocket>,
+}
+
+impl ProcessSnapshot {
+    pub fn read() -> Self {
+        let mut parents = HashMap::new();
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        let mut names = HashMap::new();
+
+        if let Ok(entries) = std::fs::read_dir("/proc") {
+            for entry in entries.flatten() {
+                let pid = match entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) {
+                    Some(pid) => pid,
+                    None => continue,
+                };
+                let stat = match std::fs::read_to_string(entry.path().join("stat")) {
+                    Ok(stat) => stat,
+                    Err(_) => continue,
+                };
+                // comm is parenthesised and may itself contain spaces or ')'
+                let (open, close) = match (stat.find('('), stat.rfind(')')) {
+                    (Some(open), Some(close)) => (open, close),
+                    _ => continue,
+                };
+                let ppid = stat[close + 1..].split_whitespace().nth(1).and_then(|p| p.parse::<u32>().ok());
+                if let Some(ppid) = ppid {
+                    parents.insert(pid, ppid);
+                    children.entry(ppid).or_default().push(pid);
+                }
+                names.insert(pid, stat[open + 1..close].to_string());
+            }
+        }
+
+        let mut sockets = Vec::new();
+        for path in ["/proc/net/tcp", "/proc/net/tcp6"] {
+            if let Ok(content) = std::fs::read_to_string(path) {
+                sockets.extend(content.lines().skip(1).filter_map(parse_tcp_line));
+            }
+        }
+
+        Self { parents, children, names, sockets }
+    }
+
+    fn is_sshd(&self, pid: u32) -> bool {
+        self.names.get(&pid).map(|name| SSHD_NAMES.contains(&name.as_str())).unwrap_or(false)
+    }
+
+    /// The per-connection sshd process above `pid`: the highest sshd
+    /// ancestor still below the listening daemon.
+    fn sshd_monitor(&self, pid: u32) -> Option<u32> {
+        let mut current = pid;
+        while !self.is_sshd(current) {
+            current = *self.parents.get(&current).filter(|&&ppid| ppid != 0)?;
+        }
+        while let Some(&parent) = self.parents.get(&current) {
+            let grandparent = self.parents.get(&parent).copied().unwrap_or(0);
+            if self.is_sshd(parent) && self.is_sshd(grandparent) {
+                current = parent;
+            } else {
+                break;
+            }
+        }
+        Some(current)
+    }
+
+    fn descendants(&self, pid: u32) -> Vec<u32> {
+        let mut tree = vec![pid];
+        let mut index = 0;
+        while index < tree.len() {
+            if let Some(children) = self.children.get(&tree[index]) {
+                tree.extend(children);
+            }
+            index += 1;
+        }
+        tree
+    }
+}
+
+fn socket_inodes(pid: u32) -> Vec<u64> {
+    std::fs::read_dir(format!("/proc/{}/fd", pid))
+        .map(|entries| {
+            entries.flatten()
+                .filter_map(|entry| std::fs::read_link(entry.path()).ok())
+                .filter_map(|target| {
+                    let target = target.to_string_lossy().to_string();
+                    target.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `SSH_CONNECTION=client_ip client_port server_ip server_port`
+fn ssh_connection_env(pid: u32) -> Option<(IpAddr, u16, u16)> {
+    let environ = std::fs::read(format!("/proc/{}/environ", pid)).ok()?;
+    let value = environ.split(|&b| b == 0)
+        .find_map(|var| var.strip_prefix(b"SSH_CONNECTION="))?;
+    let value = String::from_utf8_lossy(value);
+    let fields: Vec<&str> = value.split_whitespace().collect();
+    if fields.len() != 4 {
+        return None;
+    }
+    Some((fields[0].parse().ok()?, fields[1].parse().ok()?, fields[3].parse().ok()?))
+}
+
+/// Parses an established connection from `/proc/net/tcp{,6}`.
+fn parse_tcp_line(line: &str) -> Option<TcpSocket> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    // 01 is TCP_ESTABLISHED
+    if fields.len() < 10 || fields[3] != "01" {
+        return None;
+    }
+    let (_, local_port) = parse_socket_address(fields[1])?;
+    let remote = parse_socket_address(fields[2])?;
+    Some(TcpSocket {
+        inode: fields[9].parse().ok()?,
+        local_port,
+        remote,
+    })
+}
+
+/// The kernel prints addresses as 32-bit words in host byte order.
+fn parse_socket_address(field: &str) -> Option<(IpAddr, u16)> {
+    let (address, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let word = |i: usize| -> Option<[u8; 4]> {
+        Some(u32::from_str_radix(address.get(i * 8..i * 8 + 8)?, 16).ok()?.to_ne_bytes())
+    };
+
+    let address = match address.len() {
+        8 => IpAddr::V4(Ipv4Addr::from(word(0)?)),
+        32 => {
+            let mut octets = [0u8; 16];
+            for i in 0..4 {
+                octets[i * 4..i * 4 + 4].copy_from_slice(&word(i)?);
+            }
+            let address = Ipv6Addr::from(octets);
+            address.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(address))
+        }
+        _ => return None,
+    };
+    Some((address, port))
+}
+
+/// Parses sshd's authentication messages:
+///
+/// - `Accepted publickey for alice from 10.0.0.5 port 51234 ssh2: ED25519 SHA256:...`
+/// - `Failed password for [invalid user ]bob from 203.0.113.7 port 40022 ssh2`
+/// - `Invalid user admin from 203.0.113.7 port 40022`
+pub fn parse_sshd_message(message: &str, timestamp: DateTime<Utc>) -> Option<SshAuthEvent> {
+    let (outcome, method, rest) = if let Some(rest) = message.strip_prefix("Accepted ") {
+        let (method, rest) = rest.split_once(" for ")?;
+        (SshAuthOutcome::Accepted, Some(method.to_string()), rest)
+    } else if let Some(rest) = message.strip_prefix("Failed ") {
+        let (method, rest) = rest.split_once(" for ")?;
+        (SshAuthOutcome::Failed, Some(method.to_string()), rest)
+    } else if let Some(rest) = message.strip_prefix("Invalid user ") {
+        (SshAuthOutcome::InvalidUser, None, rest)
+    } else {
+        return None;
+    };
+
+    let (valid_user, rest) = match rest.strip_prefix("invalid user ") {
+        Some(rest) => (false, rest),
+        None => (outcome != SshAuthOutcome::InvalidUser, rest),
+    };
+
+    // Usernames are attacker-controlled and may contain " from "
+    let (user, address) = rest.rsplit_once(" from ")?;
+    let mut fields = address.split_whitespace();
+    let source: IpAddr = fields.next()?.parse().ok()?;
+    let port = match (fields.next(), fields.next()) {
+        (Some("port"), Some(port)) => port.parse().ok(),
+        _ => None,
+    };
+
+    Some(SshAuthEvent {
+        timestamp,
+        outcome,
+        method,
+        user: user.to_string(),
+        valid_user,
+        source,
+        port,
+    })
+}
+
+/// Parses a syslog auth log line, with either an RFC 3339 timestamp or
+/// the traditional `Oct 18 10:00:00` one, which carries no year.
+fn parse_auth_log_line(line: &str) -> Option<SshAuthEvent> {
+    let (head, message) = line.split_once("]: ")?;
+    if !SSHD_NAMES.iter().any(|name| head.contains(&format!(" {}[", name))) {
+        return None;
+    }
+
+    let timestamp = match DateTime::parse_from_rfc3339(head.split_whitespace().next()?) {
+        Ok(timestamp) => timestamp.with_timezone(&Utc),
+        Err(_) => {
+            let stamp: Vec<&str> = head.split_whitespace().take(3).collect();
+            let now = Local::now();
+            let parse = |year: i32| {
+                NaiveDateTime::parse_from_str(&format!("{} {}", year, stamp.join(" ")), "%Y %b %e %H:%M:%S").ok()
+            };
+            let mut naive = parse(now.year())?;
+            // A December entry read in January
+            if naive > now.naive_local() + chrono::Duration::days(1) {
+                naive = parse(now.year() - 1)?;
+            }
+            Local.from_local_datetime(&naive).earliest()?.with_timezone(&Utc)
+        }
+    };
+
+    parse_sshd_message(message, timestamp)
+}
+
+pub fn render_blocklist(sources: &[IpAddr], format: BlocklistFormat) -> String {
+    let (v4, v6): (Vec<IpAddr>, Vec<IpAddr>) = sources.iter().partition(|source| source.is_ipv4());
+    let join = |addresses: &[IpAddr]| addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ");
+
+    let mut out = String::new();
+    match format {
+        BlocklistFormat::Nftables => {
+            out.push_str("#!/usr/sbin/nft -f\n");
+            out.push_str("# Sources flagged by seer's SSH brute-force detector. Apply with: nft -f <file>\n");
+            out.push_str("table inet seer_ssh_blocklist\n");
+            out.push_str("delete table inet seer_ssh_blocklist\n\n");
+            out.push_str("table inet seer_ssh_blocklist {\n");
+            out.push_str("    set blocked_v4 {\n        type ipv4_addr\n");
+            if !v4.is_empty() {
+                out.push_str(&format!("        elements = {{ {} }}\n", join(&v4)));
+            }
+            out.push_str("    }\n\n");
+            out.push_str("    set blocked_v6 {\n        type ipv6_addr\n");
+            if !v6.is_empty() {
+                out.push_str(&format!("        elements = {{ {} }}\n", join(&v6)));
+            }
+            out.push_str("    }\n\n");
+            out.push_str("    chain input {\n");
+            out.push_str("        type filter hook input priority filter - 10; policy accept;\n");
+            out.push_str("        ip saddr @blocked_v4 drop\n");
+            out.push_str("        ip6 saddr @blocked_v6 drop\n");
+            out.push_str("    }\n}\n");
+        }
+        BlocklistFormat::Iptables => {
+            out.push_str("#!/bin/sh\n");
+            out.push_str("# Sources flagged by seer's SSH brute-force detector. Review, then run as root.\n");
+            for source in &v4 {
+                out.push_str(&format!("iptables -I INPUT -s {} -j DROP\n", source));
+            }
+            for source in &v6 {
+                out.push_str(&format!("ip6tables -I INPUT -s {} -j DROP\n", source));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+    use std::fs;
+    use std::io::Write;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_parse_sshd_message() {
+        let event = parse_sshd_message("Accepted publickey for alice from 10.0.0.5 port 51234 ssh2: ED25519 SHA256:abc", at(0)).unwrap();
+        assert_eq!(event.outcome, SshAuthOutcome::Accepted);
+        assert_eq!(event.method.as_deref(), Some("publickey"));
+        assert_eq!(event.user, "alice");
+        assert_eq!(event.source, "10.0.0.5".parse::<IpAddr>().unwrap());
+        assert_eq!(event.port, Some(51234));
+
+        let event = parse_sshd_message("Failed password for invalid user admin from 2001:db8::7 port 40022 ssh2", at(0)).unwrap();
+        assert_eq!(event.outcome, SshAuthOutcome::Failed);
+        assert!(!event.valid_user);
+        assert_eq!(event.user, "admin");
+
+        let event = parse_sshd_message("Invalid user a from b from 203.0.113.7 port 1", at(0)).unwrap();
+        assert_eq!(event.outcome, SshAuthOutcome::InvalidUser);
+        assert_eq!(event.user, "a from b");
+
+        assert!(parse_sshd_message("Connection closed by 203.0.113.7 port 1 [preauth]", at(0)).is_none());
+
+        let event = parse_auth_log_line("2026-10-18T10:00:00.123456+00:00 host sshd[812]: Failed password for root from 203.0.113.7 port 40022 ssh2").unwrap();
+        assert_eq!(event.timestamp.timestamp(), 1_792_317_600);
+        assert!(event.valid_user);
+        assert!(parse_auth_log_line("2026-10-18T10:00:00+00:00 host sudo[1]: Failed password for root from 203.0.113.7 port 1").is_none());
+    }
+
+    #[test]
+    fn test_brute_force_detector() {
+        let source: IpAddr = "203.0.113.7".parse().unwrap();
+        let event = |seconds, outcome, user: &str, valid_user, port| SshAuthEvent {
+            timestamp: at(seconds),
+            outcome,
+            method: Some("password".to_string()),
+            user: user.to_string(),
+            valid_user,
+            source,
+            port: Some(port),
+        };
+
+        let mut detector = BruteForceDetector::new();
+        let mut raised = Vec::new();
+        for i in 0..10 {
+            raised.extend(detector.record(&event(i, SshAuthOutcome::Failed, "root", true, 40000)));
+        }
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].kind, SshThreatKind::BruteForce);
+        assert_eq!(raised[0].failures_last_minute, 10);
+
+        let raised = detector.record(&event(20, SshAuthOutcome::Accepted, "root", true, 40001));
+        assert_eq!(raised[0].kind, SshThreatKind::CompromiseSuspected);
+        assert_eq!(detector.blocklist(), vec![source]);
+
+        // "Invalid user" and the "Failed ... invalid user" after it are one attempt
+        let mut detector = BruteForceDetector::new();
+        let mut raised = Vec::new();
+        for (i, user) in ["admin", "test", "oracle", "guest", "ubuntu"].iter().enumerate() {
+            let port = 50000 + i as u16;
+            raised.extend(detector.record(&event(i as i64, SshAuthOutcome::InvalidUser, user, false, port)));
+            raised.extend(detector.record(&event(i as i64, SshAuthOutcome::Failed, user, false, port)));
+        }
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].kind, SshThreatKind::UserEnumeration);
+        assert_eq!(raised[0].failures_last_minute, 5);
+
+        let mut detector = BruteForceDetector::new();
+        let raised: Vec<SshThreat> = ["alice", "bob", "carol", "dave", "erin"].iter().enumerate()
+            .flat_map(|(i, user)| detector.record(&event(i as i64 * 30, SshAuthOutcome::Failed, user, true, 1)))
+            .collect();
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].kind, SshThreatKind::CredentialStuffing);
+
+        // Quiet for an hour: everything expires
+        detector.prune(at(4000));
+        assert!(detector.threats().is_empty());
+    }
+
+    #[test]
+    fn test_backlog_primes_detector_without_raising() {
+        let dir = TempDir::new("ssh_backlog");
+        let path = dir.join("auth.log");
+        let failures = |source: &str, time: DateTime<Utc>| -> String {
+            (0..10).map(|i| format!(
+                "{} host sshd[812]: Failed password for root from {} port {} ssh2\n",
+                (time + chrono::Duration::seconds(i)).to_rfc3339(), source, 40000 + i,
+            )).collect()
+        };
+        fs::write(&path, failures("203.0.113.7", Utc::now() - chrono::Duration::minutes(10))).unwrap();
+
+        let mut monitor = SshMonitor::new();
+        monitor.auth_log = SshAuthLog { source: Some(AuthLogSource::File { path: path.clone(), offset: 0 }) };
+        assert!(monitor.poll().is_empty());
+        assert_eq!(monitor.threats().len(), 1);
+
+        let mut log = fs::OpenOptions::new().append(true).open(&path).unwrap();
+        log.write_all(failures("198.51.100.9", Utc::now()).as_bytes()).unwrap();
+        monitor.last_poll = None;
+        let raised = monitor.poll();
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].source, "198.51.100.9".parse::<IpAddr>().unwrap());
+    }
+
+    #[test]
+    fn test_socket_address_and_blocklist() {
+        let (address, port) = parse_socket_address("0100007F:0016").unwrap();
+        assert_eq!(address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(port, 22);
+        let (address, _) = parse_socket_address("0000000000000000FFFF00000500000A:C822").unwrap();
+        assert_eq!(address, "10.0.0.5".parse::<IpAddr>().unwrap());
+
+        let sources: Vec<IpAddr> = vec!["203.0.113.7".parse().unwrap(), "2001:db8::7".parse().unwrap()];
+        let nft = render_blocklist(&sources, BlocklistFormat::Nftables);
+        assert!(nft.contains("elements = { 203.0.113.7 }"));
+        assert!(nft.contains("elements = { 2001:db8::7 }"));
+        let iptables = render_blocklist(&sources, BlocklistFormat::Iptables);
+        assert!(iptables.contains("iptables -I INPUT -s 203.0.113.7 -j DROP"));
+        assert!(iptables.contains("ip6tables -I INPUT -s 2001:db8::7 -j DROP"));
+    }
+}
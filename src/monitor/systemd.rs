@@ -0,0 +1,347 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::dbus::{Connection, Message, Value};
+
+const SYSTEMD_NAME: &str = "org.freedesktop.systemd1";
+const SYSTEMD_PATH: &str = "/org/freedesktop/systemd1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.systemd1.Manager";
+const UNIT_INTERFACE: &str = "org.freedesktop.systemd1.Unit";
+const SERVICE_INTERFACE: &str = "org.freedesktop.systemd1.Service";
+const TIMER_INTERFACE: &str = "org.freedesktop.systemd1.Timer";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+const JOB_TIMEOUT: Duration = Duration::from_secs(30);
+const BATCH_SIZE: usize = 64;
+
+/// Unit and service properties kept per unit; everything else returned
+/// by GetAll is dropped to keep the cache small.
+pub const UNIT_PROPERTIES: &[&str] = &[
+    "Id",
+    "Description",
+    "LoadState",
+    "ActiveState",
+    "SubState",
+    "UnitFileState",
+    "FragmentPath",
+    "ActiveEnterTimestamp",
+    "Requires",
+    "Wants",
+    "WantedBy",
+    "After",
+    "BindsTo",
+    "PartOf",
+    "Type",
+    "MainPID",
+    "MemoryCurrent",
+    "NRestarts",
+    "ControlGroup",
+    "Result",
+    "ExecMainStatus",
+    "Unit",
+    "TimersCalendar",
+    "TimersMonotonic",
+    "NextElapseUSecRealtime",
+    "NextElapseUSecMonotonic",
+    "LastTriggerUSec",
+    "Persistent",
+];
+
+#[derive(Debug, Clone)]
+pub struct SystemdUnit {
+    pub name: String,
+    pub path: String,
+    pub properties: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone)]
+pub enum UnitEvent {
+    PropertiesChanged { path: String, properties: HashMap<String, Value> },
+    UnitNew { name: String, path: String },
+    UnitRemoved { name: String, path: String },
+}
+
+/// Talks to PID 1 over the system bus instead of spawning systemctl.
+pub struct SystemdClient {
+    connection: Connection,
+}
+
+impl SystemdClient {
+    pub fn connect() -> Result<Self> {
+        let mut connection = Connection::system()?;
+        // Fail early when systemd is not on the bus (e.g. inside containers)
+        connection.call(manager_call("GetDefaultTarget", Vec::new()))?;
+        Ok(Self { connection })
+    }
+
+    /// Lists all loaded service units with their properties.
+    pub fn list_service_units(&mut self) -> Result<Vec<SystemdUnit>> {
+        self.list_units(".service", SERVICE_INTERFACE)
+    }
+
+    /// Lists all loaded timer units with their properties.
+    pub fn list_timer_units(&mut self) -> Result<Vec<SystemdUnit>> {
+        self.list_units(".timer", TIMER_INTERFACE)
+    }
+
+    /// Looks up a loaded unit by name, returning None if it is not loaded.
+    pub fn get_unit_by_name(&mut self, name: &str) -> Result<Option<SystemdUnit>> {
+        let path = match self.connection.call(manager_call("GetUnit", vec![Value::Str(name.to_string())])) {
+            Ok(reply) => match reply.first().and_then(|v| v.as_str()) {
+                Some(path) => path.to_string(),
+                None => return Ok(None),
+            },
+            Err(_) => return Ok(None),
+        };
+        // Units without a Service interface (targets, paths) have no extra properties
+        let properties = match self.get_unit_properties(&path) {
+            Ok(properties) => properties,
+            Err(_) => return Ok(None),
+        };
+        Ok(Some(SystemdUnit { name: name.to_string(), path, properties }))
+    }
+
+    /// Lists loaded units whose name ends in `suffix`, with the Unit and
+    /// type-specific properties. GetAll calls are pipelined in batches
+    /// rather than issued one round trip at a time.
+    fn list_units(&mut self, suffix: &str, interface: &str) -> Result<Vec<SystemdUnit>> {
+        let reply = self.connection.call(manager_call("ListUnits", Vec::new()))?;
+
+        let mut units: Vec<SystemdUnit> = reply.first()
+            .and_then(|v| v.as_array())
+            .unwrap_or(&[])
+            .iter()
+            .filter_map(|entry| {
+                let fields = entry.as_struct()?;
+                let name = fields.first()?.as_str()?;
+                let path = fields.get(6)?.as_str()?;
+                name.ends_with(suffix).then(|| SystemdUnit {
+                    name: name.to_string(),
+                    path: path.to_string(),
+                    properties: HashMap::new(),
+                })
+            })
+            .collect();
+
+        for batch in units.chunks_mut(BATCH_SIZE) {
+            let mut pending = Vec::with_capacity(batch.len() * 2);
+            for (index, unit) in batch.iter().enumerate() {
+                for interface in [UNIT_INTERFACE, interface] {
+                    let serial = self.connection.send(get_all_call(&unit.path, interface))?;
+                    pending.push((index, serial));
+                }
+            }
+
+            for (index, serial) in pending {
+                // A unit can vanish between ListUnits and GetAll
+                if let Ok(reply) = self.connection.wait_reply(serial, REPLY_TIMEOUT) {
+                    if let Some(properties) = reply.first() {
+                        merge_properties(&mut batch[index].properties, properties.to_property_map());
+                    }
+                }
+            }
+        }
+
+        Ok(units)
+    }
+
+    pub fn get_unit_properties(&mut self, path: &str) -> Result<HashMap<String, Value>> {
+        let interface = if path.ends_with("_2etimer") { TIMER_INTERFACE } else { SERVICE_INTERFACE };
+        let mut properties = HashMap::new();
+        for interface in [UNIT_INTERFACE, interface] {
+            let reply = self.connection.call(get_all_call(path, interface))?;
+            if let Some(values) = reply.first() {
+                merge_properties(&mut properties, values.to_property_map());
+            }
+        }
+        Ok(properties)
+    }
+
+    /// Subscribes to unit changes on a dedicated connection. The returned
+    /// channel disconnects when the bus connection is lost.
+    pub fn subscribe() -> Result<Receiver<UnitEvent>> {
+        let mut connection = Connection::system()?;
+        connection.add_match(&format!(
+            "type='signal',sender='{}',interface='{}',member='PropertiesChanged',path_namespace='{}/unit'",
+            SYSTEMD_NAME, PROPERTIES_INTERFACE, SYSTEMD_PATH
+        ))?;
+        connection.add_match(&format!(
+            "type='signal',sender='{}',interface='{}'",
+            SYSTEMD_NAME, MANAGER_INTERFACE
+        ))?;
+        connection.call(manager_call("Subscribe", Vec::new()))?;
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok(Some(signal)) = connection.next_signal(None) {
+                if let Some(event) = unit_event_from_signal(&signal) {
+                    if sender.send(event).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    /// Queues a start/stop/restart job and waits for systemd to report its
+    /// result ("done", "failed", "timeout", "dependency", ...).
+    pub fn run_job(method: &str, unit: &str) -> Result<String> {
+        let mut connection = Connection::system()?;
+        connection.add_match(&format!(
+            "type='signal',sender='{}',interface='{}',member='JobRemoved'",
+            SYSTEMD_NAME, MANAGER_INTERFACE
+        ))?;
+        connection.call(manager_call("Subscribe", Vec::new()))?;
+
+        let reply = connection.call(manager_call(method, vec![
+            Value::Str(unit.to_string()),
+            Value::Str("replace".to_string()),
+        ]))?;
+        let job = reply.first()
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow!("{} returned no job", method))?
+            .to_string();
+
+        let deadline = Instant::now() + JOB_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let signal = connection.next_signal(Some(remaining))?
+                .ok_or_else(|| anyhow!("Timed out waiting for job {} on {}", job, unit))?;
+
+            if !signal.is_signal(MANAGER_INTERFACE, "JobRemoved") {
+                continue;
+            }
+            // JobRemoved(u id, o job, s unit, s result)
+            if signal.body.get(1).and_then(|v| v.as_str()) == Some(job.as_str()) {
+                return Ok(signal.body.get(3).and_then(|v| v.as_str()).unwrap_or("unknown").to_string());
+            }
+        }
+    }
+
+    /// Sets `AllowedCPUs=` on a unit until the next reboot, as
+    /// `systemctl set-property --runtime` would.
+    pub fn set_allowed_cpus(unit: &str, cpus: &[usize]) -> Result<()> {
+        let mut connection = Connection::system()?;
+        let mask = cpu_mask_bytes(cpus).into_iter().map(Value::Byte).collect();
+        let property = Value::Struct(vec![
+            Value::Str("AllowedCPUs".to_string()),
+            Value::Variant(Box::new(Value::Array("y".to_string(), mask))),
+        ]);
+        connection.call(manager_call("SetUnitProperties", vec![
+            Value::Str(unit.to_string()),
+            Value::Bool(true),
+            Value::Array("(sv)".to_string(), vec![property]),
+        ]))?;
+        Ok(())
+    }
+}
+
+/// Encodes CPUs as the little-endian bitmask systemd expects for
+/// `AllowedCPUs=`: bit `n % 8` of byte `n / 8` stands for CPU `n`.
+pub fn cpu_mask_bytes(cpus: &[usize]) -> Vec<u8> {
+    let mut bytes = vec![0u8; cpus.iter().max().map_or(0, |&max| max / 8 + 1)];
+    for &cpu in cpus {
+        bytes[cpu / 8] |= 1 << (cpu % 8);
+    }
+    bytes
+}
+
+fn manager_call(member: &str, body: Vec<Value>) -> Message {
+    Message::method_call(SYSTEMD_NAME, SYSTEMD_PATH, MANAGER_INTERFACE, member, body)
+}
+
+fn get_all_call(path: &str, interface: &str) -> Message {
+    Message::method_call(SYSTEMD_NAME, path, PROPERTIES_INTERFACE, "GetAll", vec![Value::Str(interface.to_string())])
+}
+
+/// Copies the properties we track into `target`.
+pub fn merge_properties(target: &mut HashMap<String, Value>, properties: HashMap<String, Value>) {
+    for (key, value) in properties {
+        if UNIT_PROPERTIES.contains(&key.as_str()) {
+            target.insert(key, value);
+        }
+    }
+}
+
+fn unit_event_from_signal(signal: &Message) -> Option<UnitEvent> {
+    let name_and_path = || {
+        let name = signal.body.first()?.as_str()?.to_string();
+        let path = signal.body.get(1)?.as_str()?.to_string();
+        Some((name, path))
+    };
+
+    if signal.is_signal(PROPERTIES_INTERFACE, "PropertiesChanged") {
+        let interface = signal.body.first()?.as_str()?;
+        if interface != UNIT_INTERFACE && interface != SERVICE_INTERFACE {
+            return None;
+        }
+        return Some(UnitEvent::PropertiesChanged {
+            path: signal.path.clone()?,
+            properties: signal.body.get(1)?.to_property_map(),
+        });
+    }
+
+    if signal.is_signal(MANAGER_INTERFACE, "UnitNew") {
+        let (name, path) = name_and_path()?;
+        return Some(UnitEvent::UnitNew { name, path });
+    }
+
+    if signal.is_signal(MANAGER_INTERFACE, "UnitRemoved") {
+        let (name, path) = name_and_path()?;
+        return Some(UnitEvent::UnitRemoved { name, path });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::dbus::SIGNAL;
+
+    #[test]
+    fn test_cpu_mask_bytes() {
+        assert_eq!(cpu_mask_bytes(&[0, 1, 2, 3]), vec![0x0f]);
+        assert_eq!(cpu_mask_bytes(&[1, 9, 16]), vec![0x02, 0x02, 0x01]);
+        assert!(cpu_mask_bytes(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_properties_changed_event() {
+        let changed = Value::Array("{sv}".to_string(), vec![
+            Value::DictEntry(
+                Box::new(Value::Str("ActiveState".to_string())),
+                Box::new(Value::Variant(Box::new(Value::Str("failed".to_string())))),
+            ),
+            Value::DictEntry(
+                Box::new(Value::Str("InvocationID".to_string())),
+                Box::new(Value::Variant(Box::new(Value::Array("y".to_string(), Vec::new())))),
+            ),
+        ]);
+        let signal = Message {
+            message_type: SIGNAL,
+            path: Some("/org/freedesktop/systemd1/unit/nginx_2eservice".to_string()),
+            interface: Some(PROPERTIES_INTERFACE.to_string()),
+            member: Some("PropertiesChanged".to_string()),
+            body: vec![Value::Str(UNIT_INTERFACE.to_string()), changed, Value::Array("s".to_string(), Vec::new())],
+            ..Default::default()
+        };
+
+        match unit_event_from_signal(&signal) {
+            Some(UnitEvent::PropertiesChanged { path, properties }) => {
+                assert_eq!(path, "/org/freedesktop/systemd1/unit/nginx_2eservice");
+                let mut tracked = HashMap::new();
+                merge_properties(&mut tracked, properties);
+                assert_eq!(tracked.len(), 1);
+                assert_eq!(tracked["ActiveState"].as_str(), Some("failed"));
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Fixtures shared by the monitor unit tests.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A scratch directory standing in for `/proc`, `/sys` or a cgroup tree.
+/// It is removed on drop, so a failed assertion doesn't leave it behind.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Creates an empty directory; `name` keeps tests running in parallel
+    /// apart.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("seer_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
@@ -3,6 +3,8 @@ use std::fs;
 use std::path::Path;
 use serde::{Deserialize, Serialize};
 
+use super::fan_control::FanController;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThermalMetrics {
     pub cpu_temperatures: Vec<CpuTemperature>,
@@ -38,6 +40,7 @@ pub struct CoolingDevice {
 pub struct ThermalMonitor {
     thermal_zones_path: String,
     _cooling_devices_path: String,
+    fan_control: FanController,
 }
 
 impl ThermalMonitor {
@@ -45,9 +48,15 @@ impl ThermalMonitor {
         Self {
             thermal_zones_path: "/sys/class/thermal".to_string(),
             _cooling_devices_path: "/sys/class/thermal".to_string(),
+            fan_control: FanController::new(),
         }
     }
 
+    /// Cooling device and PWM control; see [`FanController`].
+    pub fn fan_control(&mut self) -> &mut FanController {
+        &mut self.fan_control
+    }
+
     pub fn get_thermal_metrics(&self) -> Result<ThermalMetrics> {
         let mut cpu_temperatures = Vec::new();
         let mut thermal_zones = Vec::new();
@@ -0,0 +1,536 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use super::affinity::parse_cpu_list;
+
+const DEFAULT_SYS_ROOT: &str = "/sys";
+const NODE_DIR: &str = "devices/system/node";
+
+/// Core kinds on hybrid CPUs such as Intel Alder Lake or Arm big.LITTLE.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoreType {
+    Performance,
+    Efficiency,
+}
+
+impl CoreType {
+    pub fn label(self) -> &'static str {
+        match self {
+            CoreType::Performance => "P",
+            CoreType::Efficiency => "E",
+        }
+    }
+}
+
+/// An online logical CPU from `/sys/devices/system/cpu/cpu<N>/topology`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogicalCpu {
+    pub id: usize,
+    pub package_id: usize,
+    pub die_id: usize,
+    pub core_id: usize,
+    /// SMT siblings on the same physical core, including this CPU.
+    pub thread_siblings: Vec<usize>,
+    pub numa_node: Option<usize>,
+    /// `None` unless the CPU mixes core types.
+    pub core_type: Option<CoreType>,
+    pub max_frequency_khz: Option<u64>,
+}
+
+/// A physical core and the logical CPUs it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicalCore {
+    pub package_id: usize,
+    pub core_id: usize,
+    pub core_type: Option<CoreType>,
+    pub cpus: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuCache {
+    pub level: u8,
+    /// `Data`, `Instruction` or `Unified`.
+    pub cache_type: String,
+    pub size: u64,
+    pub ways: Option<u32>,
+    pub line_size: Option<u32>,
+    pub shared_cpus: Vec<usize>,
+}
+
+impl CpuCache {
+    pub fn label(&self) -> String {
+        match self.cache_type.as_str() {
+            "Data" => format!("L{}d", self.level),
+            "Instruction" => format!("L{}i", self.level),
+            _ => format!("L{}", self.level),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpus: Vec<usize>,
+    /// Relative access cost to each node, indexed like `numa_nodes`.
+    pub distances: Vec<u32>,
+}
+
+/// CPU layout read from sysfs. Falls back to counts alone where sysfs is
+/// unavailable, in which case `cpus`, `caches` and `numa_nodes` are empty.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CpuTopology {
+    pub logical_cpus: usize,
+    /// Physical cores across all packages.
+    pub physical_cpus: usize,
+    pub threads_per_core: usize,
+    #[serde(default)]
+    pub packages: usize,
+    /// Online CPUs ordered by ID, matching the per-core usage order.
+    #[serde(default)]
+    pub cpus: Vec<LogicalCpu>,
+    /// Each cache instance once, e.g. one L3 per package.
+    #[serde(default)]
+    pub caches: Vec<CpuCache>,
+    #[serde(default)]
+    pub numa_nodes: Vec<NumaNode>,
+}
+
+impl CpuTopology {
+    pub fn read() -> Result<Self> {
+        Self::read_from(Path::new(DEFAULT_SYS_ROOT))
+    }
+
+    /// The kernel's online CPU list, e.g. "0-7". It changes on CPU hotplug
+    /// and when CPUs are taken offline, which is when to re-read.
+    pub fn read_online() -> Option<String> {
+        Self::read_online_from(Path::new(DEFAULT_SYS_ROOT))
+    }
+
+    pub fn read_online_from(sys_root: &Path) -> Option<String> {
+        fs::read_to_string(sys_root.join("devices/system/cpu/online")).ok().map(|list| list.trim().to_string())
+    }
+
+    pub fn read_from(sys_root: &Path) -> Result<Self> {
+        let cpu_root = sys_root.join("devices/system/cpu");
+        let mut ids: Vec<usize> = fs::read_dir(&cpu_root)?
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.strip_prefix("cpu")?.parse().ok())
+            // Offline CPUs have no topology directory
+            .filter(|id| cpu_root.join(format!("cpu{}/topology", id)).is_dir())
+            .collect();
+        ids.sort_unstable();
+        if ids.is_empty() {
+            return Err(anyhow!("no CPU topology under {}", cpu_root.display()));
+        }
+
+        let numa_nodes = read_numa_nodes(&sys_root.join(NODE_DIR));
+        let core_types = read_core_types(sys_root, &ids);
+        let mut cpus = Vec::with_capacity(ids.len());
+        let mut caches: Vec<CpuCache> = Vec::new();
+
+        for &id in &ids {
+            let cpu_dir = cpu_root.join(format!("cpu{}", id));
+            let topology = cpu_dir.join("topology");
+            let mut thread_siblings = read_list(&topology.join("thread_siblings_list"));
+            if thread_siblings.is_empty() {
+                thread_siblings.push(id);
+            }
+
+            cpus.push(LogicalCpu {
+                id,
+                package_id: read_number(&topology.join("physical_package_id")).unwrap_or(0),
+                die_id: read_number(&topology.join("die_id")).unwrap_or(0),
+                core_id: read_number(&topology.join("core_id")).unwrap_or(id),
+                thread_siblings,
+                numa_node: numa_nodes.iter().find(|node| node.cpus.contains(&id)).map(|node| node.id),
+                core_type: core_types.get(&id).copied(),
+                max_frequency_khz: read_number(&cpu_dir.join("cpufreq/cpuinfo_max_freq")),
+            });
+
+            for cache in read_caches(&cpu_dir.join("cache")) {
+                let seen = caches.iter().any(|known| {
+                    known.level == cache.level && known.cache_type == cache.cache_type && known.shared_cpus == cache.shared_cpus
+                });
+                if !seen {
+                    caches.push(cache);
+                }
+            }
+        }
+        caches.sort_by(|a, b| a.level.cmp(&b.level).then(a.cache_type.cmp(&b.cache_type)));
+
+        let mut topology = Self {
+            logical_cpus: cpus.len(),
+            cpus,
+            caches,
+            numa_nodes,
+            ..Self::default()
+        };
+        topology.physical_cpus = topology.cores().len();
+        topology.threads_per_core = topology.cpus.iter().map(|cpu| cpu.thread_siblings.len()).max().unwrap_or(1);
+        let mut packages: Vec<usize> = topology.cpus.iter().map(|cpu| cpu.package_id).collect();
+        packages.sort_unstable();
+        packages.dedup();
+        topology.packages = packages.len();
+        Ok(topology)
+    }
+
+    /// Physical cores ordered by package and lowest CPU ID. Cores are
+    /// keyed by their sibling list since `core_id` repeats across dies
+    /// and packages and is sparse on many machines.
+    pub fn cores(&self) -> Vec<PhysicalCore> {
+        let mut cores: Vec<PhysicalCore> = Vec::new();
+        for cpu in &self.cpus {
+            if cores.iter().any(|core| core.package_id == cpu.package_id && core.cpus.contains(&cpu.id)) {
+                continue;
+            }
+            let mut siblings: Vec<usize> = cpu.thread_siblings.iter()
+                .copied()
+                .filter(|sibling| self.position(*sibling).is_some())
+                .collect();
+            if !siblings.contains(&cpu.id) {
+                siblings.push(cpu.id);
+            }
+            siblings.sort_unstable();
+            cores.push(PhysicalCore {
+                package_id: cpu.package_id,
+                core_id: cpu.core_id,
+                core_type: cpu.core_type,
+                cpus: siblings,
+            });
+        }
+        cores.sort_by_key(|core| (core.package_id, core.cpus[0]));
+        cores
+    }
+
+    /// Index of a CPU in `cpus`, which is also its index in per-core usage.
+    pub fn position(&self, cpu: usize) -> Option<usize> {
+        self.cpus.binary_search_by_key(&cpu, |logical| logical.id).ok()
+    }
+
+    pub fn is_hybrid(&self) -> bool {
+        self.cpus.iter().any(|cpu| cpu.core_type.is_some())
+    }
+
+    pub fn core_count(&self, core_type: CoreType) -> usize {
+        self.cores().iter().filter(|core| core.core_type == Some(core_type)).count()
+    }
+
+    pub fn get_core_siblings(&self, cpu_index: usize) -> Vec<usize> {
+        if let Some(position) = self.position(cpu_index) {
+            return self.cpus[position].thread_siblings.clone();
+        }
+
+        // Without sysfs, assume siblings are numbered consecutively
+        let threads_per_core = self.threads_per_core.max(1);
+        let core_id = cpu_index / threads_per_core;
+        (0..threads_per_core)
+            .map(|i| core_id * threads_per_core + i)
+            .filter(|&sibling| sibling < self.logical_cpus)
+            .collect()
+    }
+}
+
+/// Intel hybrid parts expose separate PMUs listing their P- and E-cores.
+/// Elsewhere, differing `cpu_capacity` values mark big and little cores.
+fn read_core_types(sys_root: &Path, ids: &[usize]) -> HashMap<usize, CoreType> {
+    let mut types = HashMap::new();
+    let performance = read_list(&sys_root.join("devices/cpu_core/cpus"));
+    let efficiency = read_list(&sys_root.join("devices/cpu_atom/cpus"));
+    if !performance.is_empty() && !efficiency.is_empty() {
+        types.extend(performance.into_iter().map(|cpu| (cpu, CoreType::Performance)));
+        types.extend(efficiency.into_iter().map(|cpu| (cpu, CoreType::Efficiency)));
+        return types;
+    }
+
+    let capacities: Vec<(usize, u64)> = ids.iter()
+        .filter_map(|&id| {
+            let path = sys_root.join(format!("devices/system/cpu/cpu{}/cpu_capacity", id));
+            Some((id, read_number(&path)?))
+        })
+        .collect();
+    let highest = capacities.iter().map(|&(_, capacity)| capacity).max();
+    if let Some(highest) = highest {
+        if capacities.iter().any(|&(_, capacity)| capacity < highest) {
+            for (id, capacity) in capacities {
+                let core_type = if capacity == highest { CoreType::Performance } else { CoreType::Efficiency };
+                types.insert(id, core_type);
+            }
+        }
+    }
+    types
+}
+
+fn read_caches(cache_dir: &Path) -> Vec<CpuCache> {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("index"))
+        .filter_map(|entry| {
+            let dir = entry.path();
+            Some(CpuCache {
+                level: read_number(&dir.join("level"))?,
+                cache_type: fs::read_to_string(dir.join("type")).ok()?.trim().to_string(),
+                size: parse_cache_size(&fs::read_to_string(dir.join("size")).ok()?)?,
+                ways: read_number(&dir.join("ways_of_associativity")),
+                line_size: read_number(&dir.join("coherency_line_size")),
+                shared_cpus: read_list(&dir.join("shared_cpu_list")),
+            })
+        })
+        .collect()
+}
+
+fn read_numa_nodes(node_root: &Path) -> Vec<NumaNode> {
+    let Ok(entries) = fs::read_dir(node_root) else {
+        return Vec::new();
+    };
+
+    let mut nodes: Vec<NumaNode> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+            let dir = entry.path();
+            Some(NumaNode {
+                id,
+                cpus: read_list(&dir.join("cpulist")),
+                distances: fs::read_to_string(dir.join("distance"))
+                    .map(|content| content.split_whitespace().filter_map(|d| d.parse().ok()).collect())
+                    .unwrap_or_default(),
+            })
+        })
+        .collect();
+    nodes.sort_by_key(|node| node.id);
+    nodes
+}
+
+/// Parses sysfs cache sizes such as `32K` or `16384K`.
+pub fn parse_cache_size(content: &str) -> Option<u64> {
+    let content = content.trim();
+    let (digits, multiplier) = match content.chars().last()? {
+        'K' => (&content[..content.len() - 1], 1024),
+        'M' => (&content[..content.len() - 1], 1024 * 1024),
+        'G' => (&content[..content.len() - 1], 1024 * 1024 * 1024),
+        _ => (content, 1),
+    };
+    digits.parse::<u64>().ok().map(|value| value * multiplier)
+}
+
+/// Memory use and allocation counters of one NUMA node, as shown by
+/// `numastat -m` and `numastat`. Sizes are bytes, counters are pages.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NumaMemory {
+    pub node: usize,
+    pub total: u64,
+    pub free: u64,
+    pub used: u64,
+    pub file_pages: u64,
+    pub anon_pages: u64,
+    pub shmem: u64,
+    pub slab: u64,
+    /// Allocations satisfied on this node as intended.
+    pub numa_hit: u64,
+    /// Allocations meant for another node that landed here.
+    pub numa_miss: u64,
+    /// Allocations meant for this node that landed elsewhere.
+    pub numa_foreign: u64,
+    pub interleave_hit: u64,
+    pub local_node: u64,
+    pub other_node: u64,
+}
+
+impl NumaMemory {
+    pub fn read_all() -> Vec<Self> {
+        Self::read_all_from(&Path::new(DEFAULT_SYS_ROOT).join(NODE_DIR))
+    }
+
+    pub fn read_all_from(node_root: &Path) -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(node_root) else {
+            return Vec::new();
+        };
+
+        let mut nodes: Vec<Self> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let node = entry.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+                let meminfo = fs::read_to_string(entry.path().join("meminfo")).ok()?;
+                let numastat = fs::read_to_string(entry.path().join("numastat")).unwrap_or_default();
+                Some(Self::parse(node, &meminfo, &numastat))
+            })
+            .collect();
+        nodes.sort_by_key(|node| node.node);
+        nodes
+    }
+
+    /// Parses a node's `meminfo` (lines like `Node 0 MemFree: 1024 kB`)
+    /// and `numastat`.
+    pub fn parse(node: usize, meminfo: &str, numastat: &str) -> Self {
+        let memory: HashMap<&str, u64> = meminfo.lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace().skip(2);
+                let key = fields.next()?.trim_end_matches(':');
+                let value: u64 = fields.next()?.parse().ok()?;
+                let bytes = if fields.next() == Some("kB") { value * 1024 } else { value };
+                Some((key, bytes))
+            })
+            .collect();
+        let counters: HashMap<&str, u64> = numastat.lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(' ')?;
+                Some((key, value.trim().parse().ok()?))
+            })
+            .collect();
+        let memory_field = |name: &str| memory.get(name).copied().unwrap_or(0);
+        let counter = |name: &str| counters.get(name).copied().unwrap_or(0);
+
+        Self {
+            node,
+            total: memory_field("MemTotal"),
+            free: memory_field("MemFree"),
+            used: memory_field("MemUsed"),
+            file_pages: memory_field("FilePages"),
+            anon_pages: memory_field("AnonPages"),
+            shmem: memory_field("Shmem"),
+            slab: memory_field("Slab"),
+            numa_hit: counter("numa_hit"),
+            numa_miss: counter("numa_miss"),
+            numa_foreign: counter("numa_foreign"),
+            interleave_hit: counter("interleave_hit"),
+            local_node: counter("local_node"),
+            other_node: counter("other_node"),
+        }
+    }
+
+    /// Share of allocations by tasks on this node that were served locally.
+    pub fn local_ratio(&self) -> Option<f64> {
+        let total = self.local_node + self.other_node;
+        (total > 0).then(|| self.local_node as f64 / total as f64)
+    }
+}
+
+fn read_list(path: &Path) -> Vec<usize> {
+    fs::read_to_string(path).map(|content| parse_cpu_list(&content)).unwrap_or_default()
+}
+
+fn read_number<T: std::str::FromStr>(path: &Path) -> Option<T> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::test_support::TempDir;
+
+    /// Builds a two-package sysfs tree: package 0 has two SMT cores
+    /// (CPUs 0/2 and 1/3), package 1 has one core (CPU 4) on node 1.
+    fn fake_sysfs(name: &str) -> TempDir {
+        let root = TempDir::new(&format!("topology_{}", name));
+        let cpu_root = root.join("devices/system/cpu");
+        let layout = [(0, 0, 0, "0,2"), (1, 0, 1, "1,3"), (2, 0, 0, "0,2"), (3, 0, 1, "1,3"), (4, 1, 0, "4")];
+        for (id, package, core, siblings) in layout {
+            let topology = cpu_root.join(format!("cpu{}/topology", id));
+            fs::create_dir_all(&topology).unwrap();
+            fs::write(topology.join("physical_package_id"), format!("{}\n", package)).unwrap();
+            fs::write(topology.join("core_id"), format!("{}\n", core)).unwrap();
+            fs::write(topology.join("thread_siblings_list"), format!("{}\n", siblings)).unwrap();
+
+            let l1 = cpu_root.join(format!("cpu{}/cache/index0", id));
+            fs::create_dir_all(&l1).unwrap();
+            fs::write(l1.join("level"), "1\n").unwrap();
+            fs::write(l1.join("type"), "Data\n").unwrap();
+            fs::write(l1.join("size"), "48K\n").unwrap();
+            fs::write(l1.join("shared_cpu_list"), format!("{}\n", siblings)).unwrap();
+
+            let l3 = cpu_root.join(format!("cpu{}/cache/index3", id));
+            fs::create_dir_all(&l3).unwrap();
+            fs::write(l3.join("level"), "3\n").unwrap();
+            fs::write(l3.join("type"), "Unified\n").unwrap();
+            fs::write(l3.join("size"), "30720K\n").unwrap();
+            fs::write(l3.join("shared_cpu_list"), if package == 0 { "0-3\n" } else { "4\n" }).unwrap();
+        }
+        // An offline CPU has no topology directory
+        fs::create_dir_all(cpu_root.join("cpu5")).unwrap();
+
+        for (node, cpus, distance) in [(0, "0-3", "10 21"), (1, "4", "21 10")] {
+            let dir = root.join(format!("devices/system/node/node{}", node));
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("cpulist"), format!("{}\n", cpus)).unwrap();
+            fs::write(dir.join("distance"), format!("{}\n", distance)).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_reads_smt_siblings_caches_and_numa_nodes() {
+        let root = fake_sysfs("smt");
+        let topology = CpuTopology::read_from(&root).unwrap();
+
+        assert_eq!(topology.logical_cpus, 5);
+        assert_eq!(topology.physical_cpus, 3);
+        assert_eq!(topology.packages, 2);
+        assert_eq!(topology.threads_per_core, 2);
+        assert!(!topology.is_hybrid());
+
+        let cores: Vec<Vec<usize>> = topology.cores().into_iter().map(|core| core.cpus).collect();
+        assert_eq!(cores, vec![vec![0, 2], vec![1, 3], vec![4]]);
+        assert_eq!(topology.get_core_siblings(3), vec![1, 3]);
+
+        let labels: Vec<String> = topology.caches.iter().map(|cache| cache.label()).collect();
+        assert_eq!(labels, vec!["L1d", "L1d", "L1d", "L3", "L3"]);
+        assert_eq!(topology.caches[3].size, 30 * 1024 * 1024);
+
+        assert_eq!(topology.numa_nodes.len(), 2);
+        assert_eq!(topology.numa_nodes[1].distances, vec![21, 10]);
+        assert_eq!(topology.cpus[4].numa_node, Some(1));
+    }
+
+    #[test]
+    fn test_detects_hybrid_cores() {
+        let root = fake_sysfs("hybrid");
+        for (pmu, cpus) in [("cpu_core", "0-3"), ("cpu_atom", "4")] {
+            fs::create_dir_all(root.join("devices").join(pmu)).unwrap();
+            fs::write(root.join("devices").join(pmu).join("cpus"), format!("{}\n", cpus)).unwrap();
+        }
+
+        let topology = CpuTopology::read_from(&root).unwrap();
+        assert!(topology.is_hybrid());
+        assert_eq!(topology.core_count(CoreType::Performance), 2);
+        assert_eq!(topology.core_count(CoreType::Efficiency), 1);
+    }
+
+    #[test]
+    fn test_offlined_cpu_changes_online_list() {
+        let root = fake_sysfs("offline");
+        let cpu_root = root.join("devices/system/cpu");
+        fs::write(cpu_root.join("online"), "0-4\n").unwrap();
+        assert_eq!(CpuTopology::read_online_from(&root).as_deref(), Some("0-4"));
+
+        // Offlining removes the CPU's topology directory
+        fs::remove_dir_all(cpu_root.join("cpu4/topology")).unwrap();
+        fs::write(cpu_root.join("online"), "0-3\n").unwrap();
+        assert_eq!(CpuTopology::read_online_from(&root).as_deref(), Some("0-3"));
+
+        let topology = CpuTopology::read_from(&root).unwrap();
+        assert_eq!(topology.logical_cpus, 4);
+        assert_eq!(topology.packages, 1);
+    }
+
+    #[test]
+    fn test_parses_node_meminfo_and_numastat() {
+        let meminfo = "Node 1 MemTotal:       16384 kB\nNode 1 MemFree:         4096 kB\nNode 1 MemUsed:        12288 kB\n\
+                       Node 1 FilePages:       2048 kB\nNode 1 AnonPages:       8192 kB\nNode 1 HugePages_Total:     4\n";
+        let numastat = "numa_hit 9000\nnuma_miss 100\nnuma_foreign 50\ninterleave_hit 10\nlocal_node 8000\nother_node 2000\n";
+        let node = NumaMemory::parse(1, meminfo, numastat);
+
+        assert_eq!(node.total, 16384 * 1024);
+        assert_eq!(node.used, 12288 * 1024);
+        assert_eq!(node.anon_pages, 8192 * 1024);
+        assert_eq!(node.numa_miss, 100);
+        assert_eq!(node.local_ratio(), Some(0.8));
+        assert_eq!(parse_cache_size("1280K"), Some(1280 * 1024));
+    }
+}
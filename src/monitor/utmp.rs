@@ -0,0 +1,422 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Size of `struct utmp` in glibc on Linux, with the 32-bit `ut_tv`
+/// used by all architectures for file compatibility.
+pub const UTMP_RECORD_SIZE: usize = 384;
+
+const LINE_OFFSET: usize = 8;
+const ID_OFFSET: usize = 40;
+const USER_OFFSET: usize = 44;
+const HOST_OFFSET: usize = 76;
+const EXIT_OFFSET: usize = 332;
+const SESSION_OFFSET: usize = 336;
+const TV_OFFSET: usize = 340;
+const ADDR_OFFSET: usize = 348;
+
+const LINE_SIZE: usize = 32;
+const ID_SIZE: usize = 4;
+const USER_SIZE: usize = 32;
+const HOST_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UtmpType {
+    Empty,
+    RunLevel,
+    BootTime,
+    NewTime,
+    OldTime,
+    InitProcess,
+    LoginProcess,
+    UserProcess,
+    DeadProcess,
+    Accounting,
+    Unknown(i16),
+}
+
+impl From<i16> for UtmpType {
+    fn from(value: i16) -> Self {
+        match value {
+            0 => UtmpType::Empty,
+            1 => UtmpType::RunLevel,
+            2 => UtmpType::BootTime,
+            3 => UtmpType::NewTime,
+            4 => UtmpType::OldTime,
+            5 => UtmpType::InitProcess,
+            6 => UtmpType::LoginProcess,
+            7 => UtmpType::UserProcess,
+            8 => UtmpType::DeadProcess,
+            9 => UtmpType::Accounting,
+            other => UtmpType::Unknown(other),
+        }
+    }
+}
+
+/// One record from utmp, wtmp or btmp.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UtmpRecord {
+    pub record_type: UtmpType,
+    pub pid: i32,
+    pub line: String,
+    pub id: String,
+    pub user: String,
+    pub host: String,
+    pub exit_termination: i16,
+    pub exit_status: i16,
+    pub session: i32,
+    pub timestamp: DateTime<Utc>,
+    pub address: Option<IpAddr>,
+}
+
+impl UtmpRecord {
+    /// The remote address if recorded, otherwise the host field.
+    pub fn source(&self) -> Option<String> {
+        match self.address {
+            Some(address) => Some(address.to_string()),
+            None if !self.host.is_empty() => Some(self.host.clone()),
+            None => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SessionEnd {
+    LoggedOut,
+    StillLoggedIn,
+    /// The system rebooted without a shutdown record.
+    Crash,
+    /// The system was shut down while the user was logged in.
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoginRecord {
+    pub user: String,
+    pub tty: String,
+    pub host: Option<String>,
+    pub login_time: DateTime<Utc>,
+    pub logout_time: Option<DateTime<Utc>>,
+    pub end: SessionEnd,
+}
+
+impl LoginRecord {
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        self.logout_time.unwrap_or(now) - self.login_time
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BootEventKind {
+    Reboot,
+    Shutdown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BootEvent {
+    pub kind: BootEventKind,
+    pub time: DateTime<Utc>,
+    /// Kernel release, recorded in the host field.
+    pub kernel: String,
+}
+
+/// Login sessions and boot events reconstructed from wtmp, newest first,
+/// in the way `last` pairs records.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoginHistory {
+    pub sessions: Vec<LoginRecord>,
+    pub boot_events: Vec<BootEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedLoginSource {
+    pub source: String,
+    pub attempts: u32,
+    pub users: Vec<String>,
+    pub first_attempt: DateTime<Utc>,
+    pub last_attempt: DateTime<Utc>,
+}
+
+/// A utmp-format file that is only re-parsed when it changes on disk.
+/// wtmp in particular can hold many thousands of records.
+pub struct UtmpFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+    records: Vec<UtmpRecord>,
+}
+
+impl UtmpFile {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            modified: None,
+            len: 0,
+            records: Vec::new(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn refresh(&mut self) -> Result<&[UtmpRecord]> {
+        let metadata = fs::metadata(&self.path)?;
+        let modified = metadata.modified().ok();
+        if self.modified.is_none() || modified != self.modified || metadata.len() != self.len {
+            self.records = read_records(&self.path)?;
+            self.modified = modified;
+            self.len = metadata.len();
+        }
+        Ok(&self.records)
+    }
+}
+
+pub fn read_records(path: &Path) -> Result<Vec<UtmpRecord>> {
+    Ok(parse_records(&fs::read(path)?))
+}
+
+/// Parses consecutive records; a trailing partial record is ignored.
+pub fn parse_records(data: &[u8]) -> Vec<UtmpRecord> {
+    data.chunks_exact(UTMP_RECORD_SIZE)
+        .filter_map(parse_record)
+        .collect()
+}
+
+fn parse_record(raw: &[u8]) -> Option<UtmpRecord> {
+    let i16_at = |offset: usize| i16::from_ne_bytes([raw[offset], raw[offset + 1]]);
+    let i32_at = |offset: usize| i32::from_ne_bytes([raw[offset], raw[offset + 1], raw[offset + 2], raw[offset + 3]]);
+
+    let record_type = UtmpType::from(i16_at(0));
+    if record_type == UtmpType::Empty {
+        return None;
+    }
+
+    let seconds = i32_at(TV_OFFSET) as u32 as i64;
+    let micros = i32_at(TV_OFFSET + 4).clamp(0, 999_999) as u32;
+
+    Some(UtmpRecord {
+        record_type,
+        pid: i32_at(4),
+        line: c_string(&raw[LINE_OFFSET..LINE_OFFSET + LINE_SIZE]),
+        id: c_string(&raw[ID_OFFSET..ID_OFFSET + ID_SIZE]),
+        user: c_string(&raw[USER_OFFSET..USER_OFFSET + USER_SIZE]),
+        host: c_string(&raw[HOST_OFFSET..HOST_OFFSET + HOST_SIZE]),
+        exit_termination: i16_at(EXIT_OFFSET),
+        exit_status: i16_at(EXIT_OFFSET + 2),
+        session: i32_at(SESSION_OFFSET),
+        timestamp: DateTime::from_timestamp(seconds, micros * 1000)?,
+        address: parse_address(&raw[ADDR_OFFSET..ADDR_OFFSET + 16]),
+    })
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+/// `ut_addr_v6` holds an IPv4 address in its first word, or a full IPv6
+/// address, in network byte order.
+fn parse_address(bytes: &[u8]) -> Option<IpAddr> {
+    if bytes.iter().all(|b| *b == 0) {
+        return None;
+    }
+    if bytes[4..].iter().all(|b| *b == 0) {
+        return Some(IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])));
+    }
+    let mut octets = [0u8; 16];
+    octets.copy_from_slice(bytes);
+    Some(IpAddr::V6(Ipv6Addr::from(octets)))
+}
+
+/// Logged-in users from utmp, skipping records whose process is gone
+/// (left behind when a terminal emulator crashes).
+pub fn current_sessions(records: &[UtmpRecord]) -> Vec<&UtmpRecord> {
+    records.iter()
+        .filter(|r| r.record_type == UtmpType::UserProcess && !r.user.is_empty())
+        .filter(|r| r.pid <= 0 || Path::new("/proc").join(r.pid.to_string()).exists())
+        .collect()
+}
+
+/// Pairs logins with logouts by terminal line. A reboot without a
+/// preceding shutdown record ends open sessions as crashed.
+pub fn login_history(records: &[UtmpRecord]) -> LoginHistory {
+    let mut sessions: Vec<LoginRecord> = Vec::new();
+    let mut boot_events = Vec::new();
+    let mut open: HashMap<String, usize> = HashMap::new();
+
+    let close_all = |sessions: &mut Vec<LoginRecord>, open: &mut HashMap<String, usize>, time, end: SessionEnd| {
+        for (_, index) in open.drain() {
+            sessions[index].logout_time = Some(time);
+            sessions[index].end = end.clone();
+        }
+    };
+
+    for record in records {
+        match record.record_type {
+            UtmpType::UserProcess if !record.user.is_empty() => {
+                if let Some(index) = open.remove(&record.line) {
+                    sessions[index].logout_time = Some(record.timestamp);
+                    sessions[index].end = SessionEnd::LoggedOut;
+                }
+                open.insert(record.line.clone(), sessions.len());
+                sessions.push(LoginRecord {
+                    user: record.user.clone(),
+                    tty: record.line.clone(),
+                    host: record.source(),
+                    login_time: record.timestamp,
+                    logout_time: None,
+                    end: SessionEnd::StillLoggedIn,
+                });
+            }
+            UtmpType::DeadProcess => {
+                if let Some(index) = open.remove(&record.line) {
+                    sessions[index].logout_time = Some(record.timestamp);
+                    sessions[index].end = SessionEnd::LoggedOut;
+                }
+            }
+            UtmpType::RunLevel if record.user == "shutdown" => {
+                close_all(&mut sessions, &mut open, record.timestamp, SessionEnd::Down);
+                boot_events.push(BootEvent {
+                    kind: BootEventKind::Shutdown,
+                    time: record.timestamp,
+                    kernel: record.host.clone(),
+                });
+            }
+            UtmpType::BootTime => {
+                close_all(&mut sessions, &mut open, record.timestamp, SessionEnd::Crash);
+                boot_events.push(BootEvent {
+                    kind: BootEventKind::Reboot,
+                    time: record.timestamp,
+                    kernel: record.host.clone(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    sessions.reverse();
+    boot_events.reverse();
+    LoginHistory { sessions, boot_events }
+}
+
+/// Groups btmp records by source address, most attempts first. Local
+/// failures without a host are grouped under their terminal.
+pub fn summarize_failed_logins(records: &[UtmpRecord]) -> Vec<FailedLoginSource> {
+    let mut sources: HashMap<String, FailedLoginSource> = HashMap::new();
+
+    for record in records {
+        if !matches!(record.record_type, UtmpType::LoginProcess | UtmpType::UserProcess) {
+            continue;
+        }
+
+        let source = record.source().unwrap_or_else(|| format!("local ({})", record.line));
+        let entry = sources.entry(source.clone()).or_insert_with(|| FailedLoginSource {
+            source,
+            attempts: 0,
+            users: Vec::new(),
+            first_attempt: record.timestamp,
+            last_attempt: record.timestamp,
+        });
+
+        entry.attempts += 1;
+        if !record.user.is_empty() && !entry.users.contains(&record.user) {
+            entry.users.push(record.user.clone());
+        }
+        entry.first_attempt = entry.first_attempt.min(record.timestamp);
+        entry.last_attempt = entry.last_attempt.max(record.timestamp);
+    }
+
+    let mut sources: Vec<FailedLoginSource> = sources.into_values().collect();
+    sources.sort_by(|a, b| b.attempts.cmp(&a.attempts).then_with(|| b.last_attempt.cmp(&a.last_attempt)));
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(record_type: i16, pid: i32, line: &str, user: &str, host: &str, seconds: i32, address: [u8; 4]) -> Vec<u8> {
+        let mut raw = vec![0u8; UTMP_RECORD_SIZE];
+        raw[0..2].copy_from_slice(&record_type.to_ne_bytes());
+        raw[4..8].copy_from_slice(&pid.to_ne_bytes());
+        raw[LINE_OFFSET..LINE_OFFSET + line.len()].copy_from_slice(line.as_bytes());
+        raw[USER_OFFSET..USER_OFFSET + user.len()].copy_from_slice(user.as_bytes());
+        raw[HOST_OFFSET..HOST_OFFSET + host.len()].copy_from_slice(host.as_bytes());
+        raw[TV_OFFSET..TV_OFFSET + 4].copy_from_slice(&seconds.to_ne_bytes());
+        raw[ADDR_OFFSET..ADDR_OFFSET + 4].copy_from_slice(&address);
+        raw
+    }
+
+    #[test]
+    fn test_parse_records() {
+        let mut data = encode(7, 4242, "pts/0", "alice", "203.0.113.9", 1_700_000_000, [203, 0, 113, 9]);
+        data.extend(encode(0, 0, "", "", "", 0, [0; 4]));
+        data.extend(vec![0u8; 100]);
+
+        let records = parse_records(&data);
+        assert_eq!(records.len(), 1);
+        let record = &records[0];
+        assert_eq!(record.record_type, UtmpType::UserProcess);
+        assert_eq!(record.pid, 4242);
+        assert_eq!(record.line, "pts/0");
+        assert_eq!(record.user, "alice");
+        assert_eq!(record.timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(record.source().as_deref(), Some("203.0.113.9"));
+    }
+
+    #[test]
+    fn test_login_history() {
+        let mut data = Vec::new();
+        data.extend(encode(2, 0, "~", "reboot", "6.1.0-13-amd64", 1000, [0; 4]));
+        data.extend(encode(7, 10, "tty1", "root", "", 1100, [0; 4]));
+        data.extend(encode(7, 11, "pts/0", "alice", "10.0.0.5", 1200, [10, 0, 0, 5]));
+        data.extend(encode(8, 11, "pts/0", "", "", 1500, [0; 4]));
+        data.extend(encode(7, 12, "pts/1", "bob", "", 1600, [0; 4]));
+        data.extend(encode(1, 0, "~~", "shutdown", "6.1.0-13-amd64", 2000, [0; 4]));
+        data.extend(encode(2, 0, "~", "reboot", "6.1.0-14-amd64", 2100, [0; 4]));
+        data.extend(encode(7, 13, "pts/0", "alice", "10.0.0.5", 2200, [10, 0, 0, 5]));
+        data.extend(encode(2, 0, "~", "reboot", "6.1.0-14-amd64", 2500, [0; 4]));
+        data.extend(encode(7, 14, "tty1", "root", "", 2600, [0; 4]));
+
+        let history = login_history(&parse_records(&data));
+        let summary: Vec<(&str, &str, Option<i64>, SessionEnd)> = history.sessions.iter()
+            .map(|s| (s.user.as_str(), s.tty.as_str(), s.logout_time.map(|t| t.timestamp()), s.end.clone()))
+            .collect();
+        assert_eq!(summary, vec![
+            ("root", "tty1", None, SessionEnd::StillLoggedIn),
+            ("alice", "pts/0", Some(2500), SessionEnd::Crash),
+            ("bob", "pts/1", Some(2000), SessionEnd::Down),
+            ("alice", "pts/0", Some(1500), SessionEnd::LoggedOut),
+            ("root", "tty1", Some(2000), SessionEnd::Down),
+        ]);
+        assert_eq!(history.sessions[3].host.as_deref(), Some("10.0.0.5"));
+
+        let kinds: Vec<BootEventKind> = history.boot_events.iter().map(|e| e.kind.clone()).collect();
+        assert_eq!(kinds, vec![BootEventKind::Reboot, BootEventKind::Reboot, BootEventKind::Shutdown, BootEventKind::Reboot]);
+        assert_eq!(history.boot_events[0].kernel, "6.1.0-14-amd64");
+    }
+
+    #[test]
+    fn test_summarize_failed_logins() {
+        let mut data = Vec::new();
+        data.extend(encode(6, 0, "ssh:notty", "admin", "198.51.100.7", 100, [198, 51, 100, 7]));
+        data.extend(encode(6, 0, "ssh:notty", "oracle", "198.51.100.7", 160, [198, 51, 100, 7]));
+        data.extend(encode(6, 0, "ssh:notty", "admin", "198.51.100.7", 130, [198, 51, 100, 7]));
+        data.extend(encode(6, 0, "tty2", "root", "", 200, [0; 4]));
+
+        let sources = summarize_failed_logins(&parse_records(&data));
+        assert_eq!(sources.len(), 2);
+        assert_eq!(sources[0].source, "198.51.100.7");
+        assert_eq!(sources[0].attempts, 3);
+        assert_eq!(sources[0].users, vec!["admin", "oracle"]);
+        assert_eq!(sources[0].first_attempt.timestamp(), 100);
+        assert_eq!(sources[0].last_attempt.timestamp(), 160);
+        assert_eq!(sources[1].source, "local (tty2)");
+    }
+}
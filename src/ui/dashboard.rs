@@ -1,8 +1,8 @@
 use crate::ui::App;
-use crate::monitor::SystemMonitor;
+use crate::monitor::{CpuTopology, SystemMonitor};
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect, Alignment},
-    style::{Modifier, Style},
+    style::{Color, Modifier, Style},
     text::{Line, Span},
     widgets::{
         Block, Borders, Cell, Gauge, Paragraph, Row, Table, BorderType,
@@ -131,22 +131,26 @@ impl App {
                     height: area.height - 4,
                 };
 
-                let core_text: Vec<Line> = metrics.cpu.per_core_usage
-                    .iter()
-                    .enumerate()
-                    .take((core_area.height as usize).saturating_sub(1))
-                    .map(|(i, usage)| {
-                        let bar_length = (usage / 100.0 * 12.0) as usize;
-                        let bar = "▰".repeat(bar_length);
-                        let empty_bar = "▱".repeat(12 - bar_length);
-                        Line::from(vec![
-                            Span::styled(format!("Core {:2}: ", i), Style::default().fg(self.theme_colors.muted)),
-                            Span::styled(bar, Style::default().fg(color)),
-                            Span::styled(empty_bar, Style::default().fg(self.theme_colors.muted)),
-                            Span::styled(format!(" {:.1}%", usage), Style::default().fg(self.theme_colors.foreground)),
-                        ])
-                    })
-                    .collect();
+                let max_lines = (core_area.height as usize).saturating_sub(1);
+                let core_text: Vec<Line> = match metrics.cpu.topology.as_ref().filter(|t| !t.cpus.is_empty()) {
+                    Some(topology) => self.grouped_core_lines(topology, &metrics.cpu.per_core_usage, color, max_lines),
+                    None => metrics.cpu.per_core_usage
+                        .iter()
+                        .enumerate()
+                        .take(max_lines)
+                        .map(|(i, usage)| {
+                            let bar_length = (usage / 100.0 * 12.0) as usize;
+                            let bar = "▰".repeat(bar_length);
+                            let empty_bar = "▱".repeat(12 - bar_length);
+                            Line::from(vec![
+                                Span::styled(format!("Core {:2}: ", i), Style::default().fg(self.theme_colors.muted)),
+                                Span::styled(bar, Style::default().fg(color)),
+                                Span::styled(empty_bar, Style::default().fg(self.theme_colors.muted)),
+                                Span::styled(format!(" {:.1}%", usage), Style::default().fg(self.theme_colors.foreground)),
+                            ])
+                        })
+                        .collect(),
+                };
 
                 let paragraph = Paragraph::new(core_text);
                 f.render_widget(paragraph, core_area);
@@ -166,6 +170,46 @@ impl App {
         }
     }
 
+    /// One line per physical core with its SMT siblings side by side,
+    /// under a heading per socket on multi-socket machines.
+    fn grouped_core_lines(&self, topology: &CpuTopology, per_core_usage: &[f32], color: Color, max_lines: usize) -> Vec<Line<'static>> {
+        let mut lines = Vec::new();
+        let mut package = None;
+
+        for core in topology.cores() {
+            if topology.packages > 1 && package != Some(core.package_id) {
+                package = Some(core.package_id);
+                lines.push(Line::from(Span::styled(
+                    format!("Socket {}", core.package_id),
+                    Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD),
+                )));
+            }
+
+            let core_type = core.core_type.map_or(" ", |core_type| core_type.label());
+            let mut spans = vec![Span::styled(
+                format!("Core {:2}{}: ", core.core_id, core_type),
+                Style::default().fg(self.theme_colors.muted),
+            )];
+            for cpu in &core.cpus {
+                let usage = topology.position(*cpu)
+                    .and_then(|index| per_core_usage.get(index))
+                    .copied()
+                    .unwrap_or(0.0);
+                let bar_length = ((usage / 100.0 * 6.0) as usize).min(6);
+                spans.extend([
+                    Span::styled(format!("{:>3} ", cpu), Style::default().fg(self.theme_colors.muted)),
+                    Span::styled("▰".repeat(bar_length), Style::default().fg(color)),
+                    Span::styled("▱".repeat(6 - bar_length), Style::default().fg(self.theme_colors.muted)),
+                    Span::styled(format!(" {:>3.0}% ", usage), Style::default().fg(self.theme_colors.foreground)),
+                ]);
+            }
+            lines.push(Line::from(spans));
+        }
+
+        lines.truncate(max_lines);
+        lines
+    }
+
     fn render_memory_widget(&self, f: &mut Frame, area: Rect) {
         if let Some(metrics) = &self.system_metrics {
             let chunks = Layout::default()
@@ -244,7 +288,9 @@ impl App {
                 crate::ui::SortBy::Memory => "🧠 Memory",
                 crate::ui::SortBy::Pid => "🔢 PID",
                 crate::ui::SortBy::Name => "📛 Name",
+                crate::ui::SortBy::Pss => "🧠 PSS",
             };
+            let show_pss = self.sort_by == crate::ui::SortBy::Pss;
 
             let search_info = if self.search_mode {
                 format!(" | Search: '{}'", self.search_query)
@@ -276,9 +322,10 @@ impl App {
                 search_info
             );
 
-            let header_cells = ["🆔 PID", "📛 Name", "🔥 CPU%", "🧠 MEM%", "💾 Memory", "👤 User", "📊 Status"]
-                .iter()
-                .map(|h| Cell::from(*h).style(Style::default().add_modifier(Modifier::BOLD).fg(self.theme_colors.foreground)));
+            let memory_header = if show_pss { "💾 PSS" } else { "💾 Memory" };
+            let header_cells = ["🆔 PID", "📛 Name", "🔥 CPU%", "🧠 MEM%", memory_header, "👤 User", "📦 Container", "⚙️ Unit", "📊 Status"]
+                .into_iter()
+                .map(|h| Cell::from(h).style(Style::default().add_modifier(Modifier::BOLD).fg(self.theme_colors.foreground)));
             let header = Row::new(header_cells).style(Style::default().bg(self.theme_colors.secondary));
 
             // Calculate viewport: how many rows can fit in the table
@@ -347,8 +394,16 @@ impl App {
                         Cell::from(process.name.clone()),
                         Cell::from(format!("{:.1}", process.cpu_usage)).style(Style::default().fg(cpu_color)),
                         Cell::from(format!("{:.1}", process.memory_percentage)).style(Style::default().fg(memory_color)),
-                        Cell::from(SystemMonitor::format_bytes(process.memory_usage)),
+                        Cell::from(match process.memory_detail {
+                            Some(detail) if show_pss => SystemMonitor::format_bytes(detail.pss),
+                            _ => SystemMonitor::format_bytes(process.memory_usage),
+                        }),
                         Cell::from(process.user.clone()),
+                        Cell::from(process.container_id.as_deref()
+                            .map(crate::monitor::short_container_id)
+                            .unwrap_or("-")
+                            .to_string()),
+                        Cell::from(process.systemd_unit.clone().unwrap_or_else(|| "-".to_string())),
                         Cell::from(status_display),
                     ]).style(style)
                 })
@@ -368,6 +423,8 @@ impl App {
                     Constraint::Length(8),   // MEM%
                     Constraint::Length(10),  // Memory
                     Constraint::Length(10),  // User
+                    Constraint::Length(13),  // Container
+                    Constraint::Length(20),  // Unit
                     Constraint::Min(10),     // Status
                 ]);
 
@@ -2,18 +2,25 @@ pub mod dashboard;
 pub mod widgets;
 
 use crate::config::Config;
-use crate::monitor::{SystemMonitor, SystemMetrics, ExportFormat, Exporter, ProcessTreeBuilder, ProcessGroupBy, ProcessGroup, AffinityManager, AlertSeverity, LogAlertSeverity, LogLevel, FsEventSeverity, FsEventType};
+use crate::monitor::{BlocklistFormat, ContainerAction, ContainerActionJob, ContainerInfo, ContainerStatus, FanOutput, FanOutputKind, JournalEntry, JournalFollower, LogFollower, RestartLoop, ScheduledJobKind, ScheduledJobStatus, ServiceAction, ServiceJob, ServiceDependencyGraph, ServiceInfo, ServiceStatus, SshThreatKind};
+use crate::monitor::{SystemMonitor, SystemMetrics, ExportFormat, Exporter, ProcessTreeBuilder, ProcessGroupBy, ProcessGroup, AffinityManager, ProcessAffinity, ThreadStat, ThreadUsage, set_thread_nice, AlertSeverity, LogAlertSeverity, LogLevel, FsEventSeverity, FsEventType};
 use anyhow::Result;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect, Alignment},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Clear, Paragraph, BorderType, Row, Cell, Table},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, BorderType, Row, Cell, Table, Wrap},
     Frame,
 };
 
+use std::collections::VecDeque;
+
 pub use dashboard::*;
 pub use widgets::{AdvancedMonitoringView, AdvancedTab};
 
+const MAX_CONTAINER_LOG_LINES: usize = 500;
+const MAX_SERVICE_JOURNAL_LINES: usize = 500;
+
 #[derive(Debug, Clone)]
 pub enum AppView {
     Dashboard,
@@ -36,7 +43,9 @@ pub enum AppView {
     LogMonitorView,
     FileSystemMonitorView,
     ContainerView,
+    PodView,
     ServiceView,
+    ScheduledJobsView,
     SessionView,
     HardwareSensorView,
     DatabaseMonitorView,
@@ -76,6 +85,55 @@ pub enum SortBy {
     Memory,
     Pid,
     Name,
+    Pss,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThreadSortBy {
+    Cpu,
+    Tid,
+    Name,
+    State,
+    LastCpu,
+}
+
+impl ThreadSortBy {
+    pub fn next(self) -> Self {
+        match self {
+            ThreadSortBy::Cpu => ThreadSortBy::Tid,
+            ThreadSortBy::Tid => ThreadSortBy::Name,
+            ThreadSortBy::Name => ThreadSortBy::State,
+            ThreadSortBy::State => ThreadSortBy::LastCpu,
+            ThreadSortBy::LastCpu => ThreadSortBy::Cpu,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ThreadSortBy::Cpu => "CPU",
+            ThreadSortBy::Tid => "TID",
+            ThreadSortBy::Name => "name",
+            ThreadSortBy::State => "state",
+            ThreadSortBy::LastCpu => "last CPU",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AffinityTarget {
+    Process(u32),
+    Thread(u32),
+    Cgroup(String),
+}
+
+impl AffinityTarget {
+    pub fn label(&self) -> String {
+        match self {
+            AffinityTarget::Process(pid) => format!("process {}", pid),
+            AffinityTarget::Thread(tid) => format!("thread {}", tid),
+            AffinityTarget::Cgroup(path) => format!("cgroup {}", path),
+        }
+    }
 }
 
 pub struct App {
@@ -101,6 +159,38 @@ pub struct App {
     cached_group_by: Option<ProcessGroupBy>,
     pub selected_process_pid: Option<u32>,
     pub advanced_monitoring_view: AdvancedMonitoringView,
+    pub selected_container_index: usize,
+    pub pending_container_action: Option<(ContainerAction, ContainerInfo)>,
+    running_container_action: Option<ContainerActionJob>,
+    last_container_action: Option<String>,
+    pub pending_container_exec: Option<ContainerInfo>,
+    cached_containers: Vec<ContainerInfo>,
+    container_logs: Option<LogFollower>,
+    container_logs_name: String,
+    container_log_lines: VecDeque<String>,
+    pub selected_service_index: usize,
+    pub pending_service_action: Option<(ServiceAction, String)>,
+    cached_services: Vec<ServiceInfo>,
+    running_service_job: Option<ServiceJob>,
+    last_service_job: Option<String>,
+    pub show_service_dependencies: bool,
+    pub show_service_slices: bool,
+    service_journal: Option<JournalFollower>,
+    service_journal_name: String,
+    service_journal_lines: VecDeque<JournalEntry>,
+    pub selected_session_index: usize,
+    cached_session_users: Vec<String>,
+    pub show_all_login_history: bool,
+    pub selected_fan_output: usize,
+    cached_fan_outputs: Vec<FanOutput>,
+    pub thread_sort_by: ThreadSortBy,
+    pub selected_thread_tid: Option<u32>,
+    cached_threads: Vec<ThreadUsage>,
+    pub affinity_target: Option<AffinityTarget>,
+    pub affinity_cursor: usize,
+    affinity_draft: Vec<bool>,
+    affinity_columns: usize,
+    affinity_message: Option<String>,
 }
 
 impl App {
@@ -131,6 +221,38 @@ impl App {
             cached_group_by: None,
             selected_process_pid: None,
             advanced_monitoring_view: AdvancedMonitoringView::new(),
+            selected_container_index: 0,
+            pending_container_action: None,
+            running_container_action: None,
+            last_container_action: None,
+            pending_container_exec: None,
+            cached_containers: Vec::new(),
+            container_logs: None,
+            container_logs_name: String::new(),
+            container_log_lines: VecDeque::new(),
+            selected_service_index: 0,
+            pending_service_action: None,
+            cached_services: Vec::new(),
+            running_service_job: None,
+            last_service_job: None,
+            show_service_dependencies: false,
+            show_service_slices: false,
+            service_journal: None,
+            service_journal_name: String::new(),
+            service_journal_lines: VecDeque::new(),
+            selected_session_index: 0,
+            cached_session_users: Vec::new(),
+            show_all_login_history: false,
+            selected_fan_output: 0,
+            cached_fan_outputs: Vec::new(),
+            thread_sort_by: ThreadSortBy::Cpu,
+            selected_thread_tid: None,
+            cached_threads: Vec::new(),
+            affinity_target: None,
+            affinity_cursor: 0,
+            affinity_draft: Vec::new(),
+            affinity_columns: 1,
+            affinity_message: None,
         }
     }
 
@@ -150,7 +272,7 @@ impl App {
             AppView::ProcessList => self.render_process_list(f),
             AppView::ProcessTree => self.render_process_tree(f),
             AppView::ProcessGroups => self.render_process_groups(f),
-            AppView::ProcessDetails => self.render_process_details(f),
+            AppView::ProcessDetails => self.render_process_details(f, system_monitor),
             AppView::ProcessAffinity => self.render_process_affinity(f),
             AppView::NetworkView => self.render_network_view(f),
             AppView::DiskView => self.render_disk_view(f),
@@ -166,7 +288,9 @@ impl App {
             AppView::LogMonitorView => self.render_log_monitor_view(f, system_monitor),
             AppView::FileSystemMonitorView => self.render_filesystem_monitor_view(f, system_monitor),
             AppView::ContainerView => self.render_container_view(f, system_monitor),
+            AppView::PodView => self.render_pod_view(f, system_monitor),
             AppView::ServiceView => self.render_service_view(f, system_monitor),
+            AppView::ScheduledJobsView => self.render_scheduled_jobs_view(f, system_monitor),
             AppView::SessionView => self.render_session_view(f, system_monitor),
             AppView::HardwareSensorView => self.render_hardware_sensor_view(f, system_monitor),
             AppView::DatabaseMonitorView => self.render_database_monitor_view(f, system_monitor),
@@ -179,6 +303,14 @@ impl App {
             self.render_confirmation_dialog(f);
         }
 
+        if let Some((action, container)) = &self.pending_container_action {
+            self.render_container_action_dialog(f, *action, container);
+        }
+
+        if let Some((action, service)) = &self.pending_service_action {
+            self.render_service_action_dialog(f, *action, service);
+        }
+
         if let Some(error) = &self.error_message {
             self.render_error_dialog(f, error);
         }
@@ -243,6 +375,9 @@ impl App {
                     SortBy::Memory => self.cached_processes.sort_by(|a, b| b.memory_usage.cmp(&a.memory_usage)),
                     SortBy::Pid => self.cached_processes.sort_by(|a, b| a.pid.cmp(&b.pid)),
                     SortBy::Name => self.cached_processes.sort_by(|a, b| a.name.cmp(&b.name)),
+                    // Processes without smaps_rollup fall back to RSS so they still rank
+                    SortBy::Pss => self.cached_processes.sort_by_key(|p| std::cmp::Reverse(
+                        p.memory_detail.map(|detail| detail.pss).unwrap_or(p.memory_usage))),
                 }
                 
                 self.cached_sort_by = Some(self.sort_by.clone());
@@ -309,6 +444,11 @@ impl App {
         self.cached_sort_by = None;
     }
 
+    pub fn sort_by_pss(&mut self) {
+        self.sort_by = SortBy::Pss;
+        self.cached_sort_by = None;
+    }
+
     pub fn toggle_help(&mut self) {
         self.show_help = !self.show_help;
     }
@@ -352,7 +492,10 @@ impl App {
             ProcessGroupBy::User => ProcessGroupBy::Parent,
             ProcessGroupBy::Parent => ProcessGroupBy::Application,
             ProcessGroupBy::Application => ProcessGroupBy::Status,
-            ProcessGroupBy::Status => ProcessGroupBy::None,
+            ProcessGroupBy::Status => ProcessGroupBy::Container,
+            ProcessGroupBy::Container => ProcessGroupBy::Unit,
+            ProcessGroupBy::Unit => ProcessGroupBy::Pod,
+            ProcessGroupBy::Pod => ProcessGroupBy::None,
         };
         self.cached_group_by = None;
         self.selected_group_index = 0;
@@ -386,6 +529,8 @@ impl App {
     pub fn toggle_process_details(&mut self) {
         if let Some(pid) = self.get_selected_process_pid() {
             self.selected_process_pid = Some(pid);
+            self.selected_thread_tid = None;
+            self.cached_threads.clear();
             self.current_view = match self.current_view {
                 AppView::ProcessDetails => AppView::Dashboard,
                 _ => AppView::ProcessDetails,
@@ -393,6 +538,88 @@ impl App {
         }
     }
 
+    pub fn next_thread(&mut self) {
+        self.move_thread_selection(1);
+    }
+
+    pub fn previous_thread(&mut self) {
+        self.move_thread_selection(-1);
+    }
+
+    /// Threads are selected by TID so the selection follows a thread while
+    /// the CPU sort reorders the list.
+    fn move_thread_selection(&mut self, step: isize) {
+        let threads_len = self.cached_threads.len();
+        if threads_len == 0 {
+            return;
+        }
+
+        let index = self.selected_thread_index().unwrap_or(0) as isize;
+        let index = (index + step).rem_euclid(threads_len as isize) as usize;
+        self.selected_thread_tid = Some(self.cached_threads[index].thread.tid);
+    }
+
+    fn selected_thread_index(&self) -> Option<usize> {
+        self.selected_thread_tid
+            .and_then(|tid| self.cached_threads.iter().position(|usage| usage.thread.tid == tid))
+    }
+
+    fn get_selected_thread(&self) -> Option<&ThreadStat> {
+        self.selected_thread_index().map(|index| &self.cached_threads[index].thread)
+    }
+
+    pub fn cycle_thread_sort(&mut self) {
+        self.thread_sort_by = self.thread_sort_by.next();
+        self.sort_threads();
+    }
+
+    fn sort_threads(&mut self) {
+        match self.thread_sort_by {
+            ThreadSortBy::Cpu => self.cached_threads.sort_by(|a, b| {
+                b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap_or(std::cmp::Ordering::Equal)
+            }),
+            ThreadSortBy::Tid => self.cached_threads.sort_by_key(|usage| usage.thread.tid),
+            ThreadSortBy::Name => self.cached_threads.sort_by(|a, b| a.thread.name.cmp(&b.thread.name)),
+            ThreadSortBy::State => self.cached_threads.sort_by_key(|usage| usage.thread.state),
+            ThreadSortBy::LastCpu => self.cached_threads.sort_by_key(|usage| usage.thread.last_cpu),
+        }
+    }
+
+    /// Changes the selected thread's nice value by `delta`; lowering it
+    /// needs `CAP_SYS_NICE`.
+    pub fn adjust_thread_nice(&mut self, delta: i32) {
+        let Some(thread) = self.get_selected_thread() else {
+            self.set_error_message(Some("No thread selected".to_string()));
+            return;
+        };
+
+        let (tid, nice) = (thread.tid, (thread.nice as i32 + delta).clamp(-20, 19));
+        if let Err(e) = set_thread_nice(tid, nice) {
+            self.set_error_message(Some(format!("Failed to renice thread {}: {}", tid, e)));
+        }
+    }
+
+    /// Pins the selected thread to the CPU it last ran on, or lets a pinned
+    /// thread run on every CPU again.
+    pub fn toggle_thread_pinning(&mut self) {
+        let Some(thread) = self.get_selected_thread() else {
+            self.set_error_message(Some("No thread selected".to_string()));
+            return;
+        };
+
+        let cpu_count = AffinityManager::cpu_count();
+        let affinity = if thread.is_pinned() {
+            ProcessAffinity::new(thread.tid, cpu_count)
+        } else {
+            ProcessAffinity::from_cpus(thread.tid, &[thread.last_cpu as usize], cpu_count)
+        };
+
+        let tid = thread.tid;
+        if let Err(e) = AffinityManager::set_thread_affinity(tid, &affinity) {
+            self.set_error_message(Some(format!("Failed to set affinity of thread {}: {}", tid, e)));
+        }
+    }
+
     pub fn toggle_process_affinity(&mut self) {
         if let Some(pid) = self.get_selected_process_pid() {
             self.selected_process_pid = Some(pid);
@@ -400,6 +627,107 @@ impl App {
                 AppView::ProcessAffinity => AppView::Dashboard,
                 _ => AppView::ProcessAffinity,
             };
+            if matches!(self.current_view, AppView::ProcessAffinity) {
+                self.open_affinity_picker(AffinityTarget::Process(pid));
+            }
+        }
+    }
+
+    /// Opens the CPU picker for the thread selected in Process Details.
+    pub fn edit_thread_affinity(&mut self) {
+        match self.get_selected_thread() {
+            Some(thread) => {
+                let tid = thread.tid;
+                self.current_view = AppView::ProcessAffinity;
+                self.open_affinity_picker(AffinityTarget::Thread(tid));
+            }
+            None => self.set_error_message(Some("No thread selected".to_string())),
+        }
+    }
+
+    fn open_affinity_picker(&mut self, target: AffinityTarget) {
+        self.affinity_message = None;
+        let cpu_count = AffinityManager::cpu_count();
+        let current = match &target {
+            AffinityTarget::Process(id) | AffinityTarget::Thread(id) => {
+                AffinityManager::get_process_affinity(*id).map(|affinity| affinity.allowed_cpus)
+            }
+            AffinityTarget::Cgroup(path) => crate::monitor::CgroupReader::new()
+                .read_cpuset(path)
+                .ok_or_else(|| anyhow::anyhow!("cpuset controller is not enabled for {}", path)),
+        };
+
+        self.affinity_draft = match current {
+            Ok(cpus) => (0..cpu_count).map(|cpu| cpus.contains(&cpu)).collect(),
+            Err(e) => {
+                self.affinity_message = Some(format!("Could not read current CPUs: {}", e));
+                vec![true; cpu_count]
+            }
+        };
+        self.affinity_cursor = self.affinity_cursor.min(cpu_count.saturating_sub(1));
+        self.affinity_target = Some(target);
+    }
+
+    /// Switches the picker between the process and its cgroup.
+    pub fn cycle_affinity_target(&mut self) {
+        let Some(pid) = self.selected_process_pid else { return };
+        let cgroup = self.system_metrics.as_ref()
+            .and_then(|m| m.processes.iter().find(|p| p.pid == pid))
+            .and_then(|p| p.cgroup_path.clone());
+
+        let target = match (&self.affinity_target, cgroup) {
+            (Some(AffinityTarget::Process(_)), Some(cgroup)) => AffinityTarget::Cgroup(cgroup),
+            _ => AffinityTarget::Process(pid),
+        };
+        self.open_affinity_picker(target);
+    }
+
+    /// Moves the picker cursor; vertical moves step by a whole grid row.
+    pub fn move_affinity_cursor(&mut self, step: isize, vertical: bool) {
+        let cpu_count = self.affinity_draft.len();
+        if cpu_count == 0 {
+            return;
+        }
+
+        let step = if vertical { step * self.affinity_columns.max(1) as isize } else { step };
+        let cursor = self.affinity_cursor as isize + step;
+        if (0..cpu_count as isize).contains(&cursor) {
+            self.affinity_cursor = cursor as usize;
+        }
+    }
+
+    pub fn toggle_affinity_cpu(&mut self) {
+        if let Some(enabled) = self.affinity_draft.get_mut(self.affinity_cursor) {
+            *enabled = !*enabled;
+        }
+    }
+
+    pub fn select_all_affinity_cpus(&mut self) {
+        let all_enabled = self.affinity_draft.iter().all(|&enabled| enabled);
+        // A second press leaves only the CPU under the cursor
+        for (cpu, enabled) in self.affinity_draft.iter_mut().enumerate() {
+            *enabled = !all_enabled || cpu == self.affinity_cursor;
+        }
+    }
+
+    pub fn apply_affinity(&mut self) {
+        let Some(target) = self.affinity_target.clone() else { return };
+        let cpus: Vec<usize> = self.affinity_draft.iter()
+            .enumerate()
+            .filter(|(_, &enabled)| enabled)
+            .map(|(cpu, _)| cpu)
+            .collect();
+        let affinity = ProcessAffinity::from_cpus(0, &cpus, self.affinity_draft.len());
+
+        let result = match &target {
+            AffinityTarget::Process(pid) => AffinityManager::set_process_affinity(*pid, &affinity),
+            AffinityTarget::Thread(tid) => AffinityManager::set_thread_affinity(*tid, &affinity),
+            AffinityTarget::Cgroup(path) => AffinityManager::set_cgroup_cpus(path, &cpus),
+        };
+        match result {
+            Ok(()) => self.affinity_message = Some(format!("{} now runs on CPUs {}",
+                target.label(), crate::monitor::format_cpu_list(&cpus))),
+            Err(e) => self.set_error_message(Some(format!("Failed to set CPUs of {}: {}", target.label(), e))),
         }
     }
 
@@ -506,6 +834,199 @@ impl App {
             AppView::ContainerView => AppView::Dashboard,
             _ => AppView::ContainerView,
         };
+        self.container_logs = None;
+    }
+
+    pub fn next_service(&mut self) {
+        let services_len = self.cached_services.len();
+        if services_len > 0 {
+            self.selected_service_index = (self.selected_service_index + 1) % services_len;
+        }
+    }
+
+    pub fn previous_service(&mut self) {
+        let services_len = self.cached_services.len();
+        if services_len > 0 {
+            self.selected_service_index = if self.selected_service_index == 0 {
+                services_len - 1
+            } else {
+                self.selected_service_index - 1
+            };
+        }
+    }
+
+    /// Queues an action on the selected service; it runs once confirmed.
+    pub fn request_service_action(&mut self, action: ServiceAction) {
+        match self.cached_services.get(self.selected_service_index) {
+            Some(service) => self.pending_service_action = Some((action, service.name.clone())),
+            None => self.set_error_message(Some("No service selected".to_string())),
+        }
+    }
+
+    pub fn confirm_service_action(&mut self, system_monitor: &mut SystemMonitor) {
+        if let Some((action, service)) = self.pending_service_action.take() {
+            if let Some(job) = &self.running_service_job {
+                self.set_error_message(Some(format!("Still waiting for {} {}", job.action.as_str(), job.service)));
+                return;
+            }
+            match system_monitor.service_monitor.start_action(&service, action) {
+                Ok(job) => self.running_service_job = Some(job),
+                Err(e) => self.set_error_message(Some(format!("Failed to {} {}: {}", action.as_str(), service, e))),
+            }
+        }
+    }
+
+    /// Picks up the result of a finished service job.
+    fn poll_service_job(&mut self) {
+        let Some(job) = &self.running_service_job else { return };
+        let Some(result) = job.poll() else { return };
+        let (action, service) = (job.action.as_str(), job.service.clone());
+        self.running_service_job = None;
+        match result {
+            Ok(result) => self.last_service_job = Some(format!("{} {}: {}", action, service, result)),
+            Err(e) => {
+                self.last_service_job = Some(format!("{} {}: failed", action, service));
+                self.set_error_message(Some(format!("Failed to {} {}: {}", action, service, e)));
+            }
+        }
+    }
+
+    pub fn toggle_service_dependencies(&mut self) {
+        self.show_service_dependencies = !self.show_service_dependencies;
+        self.show_service_slices = false;
+        self.service_journal = None;
+    }
+
+    pub fn toggle_service_slices(&mut self) {
+        self.show_service_slices = !self.show_service_slices;
+        self.show_service_dependencies = false;
+        self.service_journal = None;
+    }
+
+    pub fn toggle_service_journal(&mut self, system_monitor: &mut SystemMonitor) {
+        if self.service_journal.take().is_some() {
+            return;
+        }
+
+        if let Some(service) = self.cached_services.get(self.selected_service_index).cloned() {
+            match system_monitor.service_monitor.follow_journal(&service.name, MAX_SERVICE_JOURNAL_LINES) {
+                Ok(follower) => {
+                    self.service_journal = Some(follower);
+                    self.service_journal_name = service.name;
+                    self.service_journal_lines.clear();
+                    self.show_service_dependencies = false;
+                    self.show_service_slices = false;
+                }
+                Err(e) => self.set_error_message(Some(format!("Failed to follow journal: {}", e))),
+            }
+        }
+    }
+
+    pub fn cancel_service_action(&mut self) {
+        self.pending_service_action = None;
+    }
+
+    pub fn toggle_scheduled_jobs_view(&mut self) {
+        self.current_view = match self.current_view {
+            AppView::ScheduledJobsView => AppView::Dashboard,
+            _ => AppView::ScheduledJobsView,
+        };
+    }
+
+    pub fn toggle_pod_view(&mut self) {
+        self.current_view = match self.current_view {
+            AppView::PodView => AppView::Dashboard,
+            _ => AppView::PodView,
+        };
+    }
+
+    pub fn next_container(&mut self) {
+        let containers_len = self.cached_containers.len();
+        if containers_len > 0 {
+            self.selected_container_index = (self.selected_container_index + 1) % containers_len;
+        }
+    }
+
+    pub fn previous_container(&mut self) {
+        let containers_len = self.cached_containers.len();
+        if containers_len > 0 {
+            self.selected_container_index = if self.selected_container_index == 0 {
+                containers_len - 1
+            } else {
+                self.selected_container_index - 1
+            };
+        }
+    }
+
+    fn get_selected_container(&self) -> Option<ContainerInfo> {
+        self.cached_containers.get(self.selected_container_index).cloned()
+    }
+
+    /// Queues an action on the selected container; it runs once confirmed.
+    pub fn request_container_action(&mut self, action: ContainerAction) {
+        match self.get_selected_container() {
+            Some(container) => {
+                let action = match (action, &container.status) {
+                    (ContainerAction::Pause, ContainerStatus::Paused) => ContainerAction::Unpause,
+                    _ => action,
+                };
+                self.pending_container_action = Some((action, container));
+            }
+            None => self.set_error_message(Some("No container selected".to_string())),
+        }
+    }
+
+    pub fn confirm_container_action(&mut self, system_monitor: &mut SystemMonitor) {
+        if let Some((action, container)) = self.pending_container_action.take() {
+            if let Some(job) = &self.running_container_action {
+                self.set_error_message(Some(format!("Still waiting for {} {}", job.action.as_str(), job.container_name)));
+                return;
+            }
+            match system_monitor.container_monitor.start_action(&container, action) {
+                Ok(job) => self.running_container_action = Some(job),
+                Err(e) => self.set_error_message(Some(format!("Failed to {} {}: {}", action.as_str(), container.name, e))),
+            }
+        }
+    }
+
+    /// Picks up the result of a finished container action.
+    fn poll_container_action(&mut self) {
+        let Some(job) = &self.running_container_action else { return };
+        let Some(result) = job.poll() else { return };
+        let (action, name) = (job.action.as_str(), job.container_name.clone());
+        self.running_container_action = None;
+        match result {
+            Ok(()) => self.last_container_action = Some(format!("{} {}: done", action, name)),
+            Err(e) => {
+                self.last_container_action = Some(format!("{} {}: failed", action, name));
+                self.set_error_message(Some(format!("Failed to {} {}: {}", action, name, e)));
+            }
+        }
+    }
+
+    pub fn cancel_container_action(&mut self) {
+        self.pending_container_action = None;
+    }
+
+    pub fn toggle_container_logs(&mut self, system_monitor: &mut SystemMonitor) {
+        if self.container_logs.take().is_some() {
+            return;
+        }
+
+        if let Some(container) = self.get_selected_container() {
+            match system_monitor.container_monitor.follow_logs(&container, MAX_CONTAINER_LOG_LINES) {
+                Ok(follower) => {
+                    self.container_logs = Some(follower);
+                    self.container_logs_name = container.name;
+                    self.container_log_lines.clear();
+                }
+                Err(e) => self.set_error_message(Some(format!("Failed to follow logs: {}", e))),
+            }
+        }
+    }
+
+    pub fn request_container_exec(&mut self) {
+        self.pending_container_exec = self.get_selected_container();
     }
 
     pub fn toggle_service_view(&mut self) {
@@ -513,6 +1034,7 @@ impl App {
             AppView::ServiceView => AppView::Dashboard,
             _ => AppView::ServiceView,
         };
+        self.service_journal = None;
     }
 
     pub fn toggle_session_view(&mut self) {
@@ -522,6 +1044,95 @@ impl App {
         };
     }
 
+    pub fn next_session(&mut self) {
+        let sessions_len = self.cached_session_users.len();
+        if sessions_len > 0 {
+            self.selected_session_index = (self.selected_session_index + 1) % sessions_len;
+        }
+    }
+
+    pub fn previous_session(&mut self) {
+        let sessions_len = self.cached_session_users.len();
+        if sessions_len > 0 {
+            self.selected_session_index = if self.selected_session_index == 0 {
+                sessions_len - 1
+            } else {
+                self.selected_session_index - 1
+            };
+        }
+    }
+
+    pub fn toggle_all_login_history(&mut self) {
+        self.show_all_login_history = !self.show_all_login_history;
+    }
+
+    /// Writes the sources flagged by the SSH brute-force detector to a
+    /// blocklist file in the working directory; applying it is up to the user.
+    pub fn write_ssh_blocklist(&mut self, format: BlocklistFormat, system_monitor: &mut SystemMonitor) {
+        let filename = format!(
+            "seer_ssh_blocklist_{}.{}",
+            chrono::Utc::now().format("%Y%m%d_%H%M%S"),
+            format.extension()
+        );
+        match system_monitor.session_monitor.write_ssh_blocklist(std::path::Path::new(&filename), format) {
+            Ok(count) => self.export_message = Some(format!("Wrote {} blocked SSH sources to {}", count, filename)),
+            Err(e) => self.set_error_message(Some(format!("Failed to write SSH blocklist: {}", e))),
+        }
+    }
+
+    pub fn next_fan_output(&mut self) {
+        let outputs_len = self.cached_fan_outputs.len();
+        if outputs_len > 0 {
+            self.selected_fan_output = (self.selected_fan_output + 1) % outputs_len;
+        }
+    }
+
+    pub fn previous_fan_output(&mut self) {
+        let outputs_len = self.cached_fan_outputs.len();
+        if outputs_len > 0 {
+            self.selected_fan_output = if self.selected_fan_output == 0 {
+                outputs_len - 1
+            } else {
+                self.selected_fan_output - 1
+            };
+        }
+    }
+
+    pub fn toggle_fan_control(&mut self, system_monitor: &mut SystemMonitor) {
+        let fan_control = system_monitor.thermal_monitor.fan_control();
+        match fan_control.toggle() {
+            Ok(()) if fan_control.is_active() => {
+                self.export_message = Some("Fan control on; outputs return to automatic when seer exits".to_string());
+            }
+            Ok(()) => self.export_message = Some("Fan control off; outputs restored".to_string()),
+            Err(e) => self.set_error_message(Some(format!("Fan control: {}", e))),
+        }
+    }
+
+    /// Steps the selected output by one cooling state or 16/255 PWM.
+    pub fn adjust_fan_output(&mut self, system_monitor: &mut SystemMonitor, direction: i32) {
+        let output = match self.cached_fan_outputs.get(self.selected_fan_output) {
+            Some(output) => output,
+            None => return,
+        };
+        let step = match output.kind {
+            FanOutputKind::CoolingDevice => 1,
+            FanOutputKind::Pwm => 16,
+        };
+        let value = (output.value as i32 + direction * step).clamp(0, output.max as i32) as u32;
+        if let Err(e) = system_monitor.thermal_monitor.fan_control().set_output(&output.id, value) {
+            self.set_error_message(Some(format!("Fan control: {}", e)));
+        }
+    }
+
+    pub fn set_fan_output_automatic(&mut self, system_monitor: &mut SystemMonitor) {
+        if let Some(output) = self.cached_fan_outputs.get(self.selected_fan_output) {
+            if let Err(e) = system_monitor.thermal_monitor.fan_control().set_automatic(&output.id) {
+                self.set_error_message(Some(format!("Fan control: {}", e)));
+            }
+        }
+    }
+
     pub fn toggle_hardware_sensor_view(&mut self) {
         self.current_view = match self.current_view {
             AppView::HardwareSensorView => AppView::Dashboard,
@@ -837,16 +1448,53 @@ impl App {
             "  L            Toggle log monitoring view",
             "  F            Toggle filesystem monitoring view",
             "  C            Toggle container monitoring view",
+            "  K            Toggle Kubernetes pod view",
             "  V            Toggle service monitoring view",
+            "  Z            Toggle scheduled jobs (timers and cron)",
             "  X            Toggle user session monitoring view",
             "  W            Toggle hardware sensor monitoring view",
             "",
+            "Container View (C):",
+            "  ↑/↓          Select container",
+            "  s/o/b        Start / stop / restart (bounce) selected container",
+            "  u            Pause or unpause selected container",
+            "  k / Del      Kill / remove selected container",
+            "  l            Toggle live log pane (follow mode)",
+            "  x            Open a shell in the selected container",
+            "",
+            "Session View (X):",
+            "  ↑/↓          Select session",
+            "  a            Toggle login history of all users",
+            "  b / i        Write nftables / iptables blocklist of flagged SSH sources",
+            "",
+            "Service View (V):",
+            "  ↑/↓          Select service",
+            "  s/o/b        Start / stop / restart (bounce) selected service",
+            "  d            Toggle dependency tree of selected service",
+            "  c            Toggle cgroup slice tree",
+            "  l            Follow journal of selected service",
+            "",
+            "Process Details (D):",
+            "  ↑/↓          Select thread",
+            "  s            Cycle thread sort (CPU / TID / name / state / last CPU)",
+            "  +/-          Raise / lower nice of selected thread",
+            "  a            Pin selected thread to its last CPU, or unpin it",
+            "  c            Pick CPUs for selected thread",
+            "",
+            "CPU Affinity (A):",
+            "  ←/→/↑/↓      Move between CPUs",
+            "  Space        Toggle CPU",
+            "  a            Select all CPUs, or only the one under the cursor",
+            "  Enter        Apply to the process (all threads), thread or cgroup",
+            "  g            Switch between process and its cgroup/service",
+            "",
             "Process Management:",
             "  ↑/↓          Navigate process list/groups",
             "  c            Sort by CPU usage",
             "  m            Sort by Memory usage",
             "  1            Sort by PID",
             "  2            Sort by Name",
+            "  3            Sort by PSS (proportional memory)",
             "  g            Cycle process grouping mode",
             "  k            Kill selected process",
             "  z            Toggle zombie highlighting",
@@ -913,6 +1561,127 @@ impl App {
         f.render_widget(paragraph, popup_area);
     }
 
+    fn render_cpu_topology(&self, f: &mut Frame, area: Rect, topology: Option<&crate::monitor::CpuTopology>) {
+        use crate::monitor::CoreType;
+        let format_bytes = crate::monitor::SystemMonitor::format_bytes;
+
+        let lines = match topology.filter(|t| !t.cpus.is_empty()) {
+            Some(topology) => {
+                let mut lines = vec![format!("🧩 {} socket(s), {} cores, {} threads ({} per core)",
+                    topology.packages, topology.physical_cpus, topology.logical_cpus, topology.threads_per_core)];
+
+                if topology.is_hybrid() {
+                    let max_frequency = |core_type: CoreType| topology.cpus.iter()
+                        .filter(|cpu| cpu.core_type == Some(core_type))
+                        .filter_map(|cpu| cpu.max_frequency_khz)
+                        .max()
+                        .map(|khz| format!(" up to {:.1} GHz", khz as f64 / 1_000_000.0))
+                        .unwrap_or_default();
+                    lines.push(format!("⚡ Hybrid: {} P-cores{}, {} E-cores{}",
+                        topology.core_count(CoreType::Performance), max_frequency(CoreType::Performance),
+                        topology.core_count(CoreType::Efficiency), max_frequency(CoreType::Efficiency)));
+                }
+
+                lines.push(String::new());
+                let mut cache_groups: Vec<(String, u64, Option<u32>, usize, usize)> = Vec::new();
+                for cache in &topology.caches {
+                    let label = cache.label();
+                    match cache_groups.iter_mut().find(|group| group.0 == label && group.1 == cache.size) {
+                        Some(group) => group.3 += 1,
+                        None => cache_groups.push((label, cache.size, cache.ways, 1, cache.shared_cpus.len())),
+                    }
+                }
+                for (label, size, ways, count, shared) in cache_groups {
+                    lines.push(format!("🗄️  {:<4} {} × {}{}, shared by {} CPU(s)", label, format_bytes(size), count,
+                        ways.map(|ways| format!(", {}-way", ways)).unwrap_or_default(), shared));
+                }
+
+                if !topology.numa_nodes.is_empty() {
+                    lines.push(String::new());
+                    for node in &topology.numa_nodes {
+                        lines.push(format!("🧭 Node {}: CPUs {}  distances {}", node.id,
+                            crate::monitor::format_cpu_list(&node.cpus),
+                            node.distances.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(" ")));
+                    }
+                }
+                lines
+            }
+            None => vec!["CPU topology is unavailable (no /sys/devices/system/cpu)".to_string()],
+        };
+
+        let widget = Paragraph::new(lines.join("\n"))
+            .block(Block::default()
+                .title("CPU Topology")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground))
+            .wrap(Wrap { trim: false });
+        f.render_widget(widget, area);
+    }
+
+    fn render_numa_memory(&self, f: &mut Frame, area: Rect, metrics: &SystemMetrics) {
+        let format_bytes = crate::monitor::SystemMonitor::format_bytes;
+        let node_cpus = |node: usize| metrics.cpu.topology.as_ref()
+            .and_then(|topology| topology.numa_nodes.iter().find(|n| n.id == node))
+            .map(|n| crate::monitor::format_cpu_list(&n.cpus))
+            .unwrap_or_default();
+
+        let rows: Vec<Row> = metrics.memory.numa.iter().map(|node| {
+            let used_percentage = if node.total > 0 { node.used as f64 / node.total as f64 * 100.0 } else { 0.0 };
+            let local = node.local_ratio();
+            let local_color = match local {
+                Some(ratio) if ratio < 0.8 => self.theme_colors.warning,
+                _ => self.theme_colors.foreground,
+            };
+            Row::new(vec![
+                Cell::from(node.node.to_string()),
+                Cell::from(node_cpus(node.node)),
+                Cell::from(format_bytes(node.total)),
+                Cell::from(format!("{} ({:.0}%)", format_bytes(node.used), used_percentage)),
+                Cell::from(format_bytes(node.file_pages)),
+                Cell::from(format_bytes(node.anon_pages)),
+                Cell::from(node.numa_hit.to_string()),
+                Cell::from(node.numa_miss.to_string()).style(Style::default().fg(if node.numa_miss > 0 {
+                    self.theme_colors.warning
+                } else {
+                    self.theme_colors.foreground
+                })),
+                Cell::from(node.numa_foreign.to_string()),
+                Cell::from(local.map_or("-".to_string(), |ratio| format!("{:.1}%", ratio * 100.0)))
+                    .style(Style::default().fg(local_color)),
+            ])
+        }).collect();
+
+        let title = if metrics.memory.numa.is_empty() {
+            "NUMA Memory (no NUMA information)".to_string()
+        } else {
+            format!("NUMA Memory ({} node(s), hit/miss/foreign in pages)", metrics.memory.numa.len())
+        };
+        let table = Table::new(rows)
+            .header(Row::new(vec!["Node", "CPUs", "Total", "Used", "File", "Anon", "Hit", "Miss", "Foreign", "Local"])
+                .style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
+            .widths(&[
+                Constraint::Length(4),
+                Constraint::Length(9),
+                Constraint::Length(9),
+                Constraint::Length(15),
+                Constraint::Length(9),
+                Constraint::Length(9),
+                Constraint::Length(11),
+                Constraint::Length(8),
+                Constraint::Length(8),
+                Constraint::Length(6),
+            ])
+            .block(Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+        f.render_widget(table, area);
+    }
+
     fn render_system_info(&self, f: &mut Frame) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -937,10 +1706,14 @@ impl App {
         if let Some(metrics) = &self.system_metrics {
             let system_info = crate::monitor::SystemMonitor::new().get_system_info();
             
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(15), Constraint::Min(8)])
+                .split(chunks[1]);
             let info_chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(chunks[1]);
+                .split(rows[0]);
 
             // Left column - System details
             let system_details = vec![
@@ -972,6 +1745,9 @@ impl App {
                        crate::monitor::SystemMonitor::format_bytes(metrics.memory.used_ram),
                        metrics.memory.ram_percentage),
                 format!("📉 Available RAM: {}", crate::monitor::SystemMonitor::format_bytes(metrics.memory.available_ram)),
+                format!("⏳ Memory Pressure: {}", metrics.pressure.memory
+                       .map(|pressure| pressure.summary())
+                       .unwrap_or_else(|| "unavailable".to_string())),
                 String::new(),
                 format!("🔄 Total Swap: {}", crate::monitor::SystemMonitor::format_bytes(metrics.memory.total_swap)),
                 format!("📊 Used Swap: {} ({:.1}%)", 
@@ -992,6 +1768,13 @@ impl App {
                     .style(Style::default().fg(self.theme_colors.border)))
                 .style(Style::default().fg(self.theme_colors.foreground));
             f.render_widget(memory_widget, info_chunks[1]);
+
+            let topology_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .split(rows[1]);
+            self.render_cpu_topology(f, topology_chunks[0], metrics.cpu.topology.as_ref());
+            self.render_numa_memory(f, topology_chunks[1], metrics);
         }
 
         // Footer
@@ -1440,6 +2223,9 @@ impl App {
             ProcessGroupBy::Parent => "By Parent",
             ProcessGroupBy::Application => "By Application",
             ProcessGroupBy::Status => "By Status",
+            ProcessGroupBy::Container => "By Container",
+            ProcessGroupBy::Unit => "By Systemd Unit",
+            ProcessGroupBy::Pod => "By Pod",
         };
 
         let header_text = format!("📁 Process Groups - {}", group_type_name);
@@ -1515,14 +2301,14 @@ impl App {
         f.render_widget(footer, chunks[2]);
     }
 
-    fn render_process_details(&self, f: &mut Frame) {
+    fn render_process_details(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
         if let Some(pid) = self.selected_process_pid {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
                     Constraint::Length(3),   // Header
-                    Constraint::Min(15),     // Process details
-                    Constraint::Length(8),   // Resource limits section
+                    Constraint::Length(18),  // Process details
+                    Constraint::Min(8),      // Thread list
                     Constraint::Length(3),   // Footer
                 ])
                 .split(f.size());
@@ -1556,7 +2342,21 @@ impl App {
                     format!("💾 Memory: {} ({:.1}%)", 
                            crate::monitor::SystemMonitor::format_bytes(process.memory_usage),
                            process.memory_percentage),
+                    format!("🧩 PSS / USS: {}", process.memory_detail
+                        .map(|detail| format!("{} / {} (swap {})",
+                            crate::monitor::SystemMonitor::format_bytes(detail.pss),
+                            crate::monitor::SystemMonitor::format_bytes(detail.uss()),
+                            crate::monitor::SystemMonitor::format_bytes(detail.swap)))
+                        .unwrap_or_else(|| "N/A".to_string())),
                     format!("🧵 Threads: {}", process.threads_count),
+                    format!("📦 Container: {}", process.container_id.as_deref()
+                        .map(crate::monitor::short_container_id)
+                        .unwrap_or("N/A")),
+                    format!("⚙️  Unit: {}", process.systemd_unit.as_deref().unwrap_or("N/A")),
+                    format!("🗂️  Cgroup: {}", process.cgroup_path.as_deref().unwrap_or("N/A")),
+                    format!("☸️  Pod: {}", process.pod.as_ref()
+                        .map(|pod| format!("{} ({})", pod.qualified_name(), pod.container_name))
+                        .unwrap_or_else(|| "N/A".to_string())),
                     String::new(),
                     format!("📁 Working Dir: {}", 
                            process.working_directory.as_deref().unwrap_or("N/A")),
@@ -1574,7 +2374,7 @@ impl App {
                 f.render_widget(basic_widget, details_chunks[0]);
 
                 // Right column - Process tree info
-                let tree_info = vec![
+                let mut tree_info = vec![
                     format!("🌳 Parent PID: {}", 
                            process.parent_pid.map_or("None".to_string(), |p| p.to_string())),
                     format!("⏱️  Start Time: {}", 
@@ -1592,6 +2392,40 @@ impl App {
                            process.group_name.as_deref().unwrap_or("N/A")),
                     format!("🧟 Is Zombie: {}", if process.is_zombie { "Yes" } else { "No" }),
                 ];
+                if let Some(detail) = process.memory_detail {
+                    let format_bytes = crate::monitor::SystemMonitor::format_bytes;
+                    tree_info.extend([
+                        String::new(),
+                        format!("🧠 Anonymous: {}  File-backed: {}",
+                               format_bytes(detail.anonymous), format_bytes(detail.file_backed())),
+                        format!("🤝 Shared clean/dirty: {} / {}",
+                               format_bytes(detail.shared_clean), format_bytes(detail.shared_dirty)),
+                        format!("🔒 Private clean/dirty: {} / {}",
+                               format_bytes(detail.private_clean), format_bytes(detail.private_dirty)),
+                        format!("🔄 Swap: {} (PSS {})",
+                               format_bytes(detail.swap), format_bytes(detail.swap_pss)),
+                    ]);
+                }
+                if let Some(profile) = system_monitor.profiler.get_profile(pid) {
+                    let stats = &profile.statistics;
+                    tree_info.extend([
+                        String::new(),
+                        format!("🔀 Context switches: {:.0}/s (avg {:.0}/s, {:.0}% involuntary)",
+                               stats.context_switch_rate, stats.avg_context_switch_rate,
+                               stats.involuntary_switch_ratio * 100.0),
+                        format!("⏳ Run-queue latency: {:.2} ms per timeslice", stats.run_queue_latency_ms),
+                    ]);
+                    for anomaly in profile.get_anomalies() {
+                        match anomaly.anomaly_type {
+                            crate::monitor::AnomalyType::ContextSwitchStorm => tree_info.push(format!(
+                                "⚠️  Context switch storm: {:.0}/s (expected < {:.0}/s)",
+                                anomaly.value, anomaly.expected_range.1)),
+                            crate::monitor::AnomalyType::RunQueueLatency => tree_info.push(format!(
+                                "⚠️  Waiting for CPU: {:.1} ms per timeslice", anomaly.value)),
+                            _ => {}
+                        }
+                    }
+                }
 
                 let tree_widget = Paragraph::new(tree_info.join("\n"))
                     .block(Block::default()
@@ -1603,20 +2437,16 @@ impl App {
                     .wrap(ratatui::widgets::Wrap { trim: true });
                 f.render_widget(tree_widget, details_chunks[1]);
 
-                // Resource limits section
-                let limits_text = "Resource limits information would be displayed here.\n\nPress 'L' to view detailed resource limits and usage.";
-                let limits_widget = Paragraph::new(limits_text)
-                    .block(Block::default()
-                        .title("Resource Information")
-                        .borders(Borders::ALL)
-                        .border_type(BorderType::Rounded)
-                        .style(Style::default().fg(self.theme_colors.border)))
-                    .style(Style::default().fg(self.theme_colors.muted));
-                f.render_widget(limits_widget, chunks[2]);
+                self.cached_threads = system_monitor.thread_sampler.sample(pid).to_vec();
+                self.sort_threads();
+                if self.selected_thread_index().is_none() {
+                    self.selected_thread_tid = self.cached_threads.first().map(|usage| usage.thread.tid);
+                }
+                self.render_thread_list(f, chunks[2]);
             }
 
             // Footer
-            let footer = Paragraph::new("'D' return to dashboard | 'A' view affinity | 'L' view limits")
+            let footer = Paragraph::new("'D' return to dashboard | ↑/↓ select thread | 's' sort | '+'/'-' nice | 'a' pin thread | 'c' thread CPUs | 'A' view affinity")
                 .style(Style::default().fg(self.theme_colors.warning))
                 .alignment(Alignment::Center)
                 .block(Block::default()
@@ -1627,13 +2457,87 @@ impl App {
         }
     }
 
-    fn render_process_affinity(&self, f: &mut Frame) {
+    fn render_thread_list(&self, f: &mut Frame, area: Rect) {
+        let selected_index = self.selected_thread_index().unwrap_or(0);
+        let max_rows = area.height.saturating_sub(3) as usize;
+        let scroll_offset = if selected_index < max_rows {
+            0
+        } else {
+            selected_index - max_rows + 1
+        };
+
+        let rows: Vec<Row> = self.cached_threads.iter()
+            .skip(scroll_offset)
+            .take(max_rows)
+            .map(|usage| {
+                let thread = &usage.thread;
+                let cpu_color = if usage.cpu_usage > 80.0 {
+                    self.theme_colors.error
+                } else if usage.cpu_usage > 30.0 {
+                    self.theme_colors.warning
+                } else {
+                    self.theme_colors.foreground
+                };
+                let row_style = if self.selected_thread_tid == Some(thread.tid) {
+                    Style::default().bg(self.theme_colors.selection).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                let priority = if thread.rt_priority > 0 {
+                    format!("{} {}", thread.policy_name(), thread.rt_priority)
+                } else {
+                    format!("{} {}", thread.policy_name(), thread.priority)
+                };
+
+                Row::new(vec![
+                    Cell::from(thread.tid.to_string()),
+                    Cell::from(thread.name.clone()),
+                    Cell::from(thread.state.to_string()),
+                    Cell::from(format!("{:.1}%", usage.cpu_usage)).style(Style::default().fg(cpu_color)),
+                    Cell::from(thread.last_cpu.to_string()),
+                    Cell::from(thread.wchan.clone().unwrap_or_else(|| "-".to_string())),
+                    Cell::from(priority),
+                    Cell::from(thread.nice.to_string()),
+                    Cell::from(thread.cpus_allowed.clone()).style(Style::default().fg(if thread.is_pinned() {
+                        self.theme_colors.accent
+                    } else {
+                        self.theme_colors.foreground
+                    })),
+                ]).style(row_style)
+            })
+            .collect();
+
+        let table = Table::new(rows)
+            .header(Row::new(vec!["TID", "Name", "S", "CPU", "Last", "Wchan", "Policy/Prio", "Nice", "CPUs"])
+                .style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
+            .widths(&[
+                Constraint::Length(8),
+                Constraint::Length(16),
+                Constraint::Length(2),
+                Constraint::Length(7),
+                Constraint::Length(5),
+                Constraint::Min(16),
+                Constraint::Length(12),
+                Constraint::Length(5),
+                Constraint::Length(10),
+            ])
+            .block(Block::default()
+                .title(format!("Threads ({}) - sorted by {}", self.cached_threads.len(), self.thread_sort_by.label()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+        f.render_widget(table, area);
+    }
+
+    fn render_process_affinity(&mut self, f: &mut Frame) {
         if let Some(pid) = self.selected_process_pid {
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
                     Constraint::Length(3),   // Header
-                    Constraint::Min(10),     // Affinity info
+                    Constraint::Length(6),   // Target and current mask
+                    Constraint::Min(6),      // CPU picker
                     Constraint::Length(3),   // Footer
                 ])
                 .split(f.size());
@@ -1648,34 +2552,111 @@ impl App {
                     .style(Style::default().fg(self.theme_colors.border)));
             f.render_widget(header, chunks[0]);
 
-            // Affinity information
-            let affinity_text = if AffinityManager::is_taskset_available() {
-                "CPU affinity information and controls would be displayed here.\n\nThis feature requires 'taskset' command to be available."
-            } else {
-                "CPU affinity management is not available.\n\nThis feature requires the 'taskset' command to be installed.\nOn Ubuntu/Debian: sudo apt install util-linux\nOn RHEL/CentOS: sudo yum install util-linux"
+            let process = self.system_metrics.as_ref()
+                .and_then(|m| m.processes.iter().find(|p| p.pid == pid));
+            let target = self.affinity_target.clone().unwrap_or(AffinityTarget::Process(pid));
+            let current = match &target {
+                AffinityTarget::Process(id) | AffinityTarget::Thread(id) => AffinityManager::get_process_affinity(*id)
+                    .map(|affinity| crate::monitor::format_cpu_list(&affinity.allowed_cpus))
+                    .unwrap_or_else(|e| format!("unknown ({})", e)),
+                AffinityTarget::Cgroup(path) => crate::monitor::CgroupReader::new()
+                    .read_cpuset(path)
+                    .map(|cpus| crate::monitor::format_cpu_list(&cpus))
+                    .unwrap_or_else(|| "all (cpuset controller not enabled)".to_string()),
             };
+            let selected: Vec<usize> = self.affinity_draft.iter()
+                .enumerate()
+                .filter(|(_, &enabled)| enabled)
+                .map(|(cpu, _)| cpu)
+                .collect();
 
-            let affinity_widget = Paragraph::new(affinity_text)
+            let mut info = vec![
+                format!("🎯 Target: {} ({})", target.label(), process.map_or("exited", |p| p.name.as_str())),
+                format!("✅ Current CPUs: {}", current),
+                format!("✏️  Selected: {} ({} of {})",
+                       if selected.is_empty() { "none".to_string() } else { crate::monitor::format_cpu_list(&selected) },
+                       selected.len(), self.affinity_draft.len()),
+            ];
+            if let Some(message) = &self.affinity_message {
+                info.push(message.clone());
+            }
+            let info_widget = Paragraph::new(info.join("\n"))
                 .block(Block::default()
                     .title("CPU Affinity")
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
                     .style(Style::default().fg(self.theme_colors.border)))
                 .style(Style::default().fg(self.theme_colors.foreground));
-            f.render_widget(affinity_widget, chunks[1]);
+            f.render_widget(info_widget, chunks[1]);
+
+            self.render_affinity_picker(f, chunks[2]);
 
             // Footer
-            let footer = Paragraph::new("'A' return to dashboard")
+            let footer = Paragraph::new("←/→/↑/↓ move | Space toggle | 'a' all/only | Enter apply | 'g' process/cgroup | 'A' return to dashboard")
                 .style(Style::default().fg(self.theme_colors.warning))
                 .alignment(Alignment::Center)
                 .block(Block::default()
                     .borders(Borders::ALL)
                     .border_type(BorderType::Rounded)
                     .style(Style::default().fg(self.theme_colors.border)));
-            f.render_widget(footer, chunks[2]);
+            f.render_widget(footer, chunks[3]);
         }
     }
 
+    fn render_affinity_picker(&mut self, f: &mut Frame, area: Rect) {
+        const CELL_WIDTH: u16 = 16;
+        let block = Block::default()
+            .title("CPUs")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default().fg(self.theme_colors.border));
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+
+        self.affinity_columns = (inner.width / CELL_WIDTH).max(1) as usize;
+        let per_core_usage = self.system_metrics.as_ref()
+            .map(|m| m.cpu.per_core_usage.clone())
+            .unwrap_or_default();
+        // Per-core usage only covers online CPUs; the topology maps IDs to it
+        let topology = self.system_metrics.as_ref().and_then(|m| m.cpu.topology.clone());
+
+        let cursor_row = self.affinity_cursor / self.affinity_columns;
+        let visible_rows = inner.height.max(1) as usize;
+        let first_row = cursor_row.saturating_sub(visible_rows - 1);
+
+        let lines: Vec<Line> = self.affinity_draft
+            .chunks(self.affinity_columns)
+            .enumerate()
+            .skip(first_row)
+            .take(visible_rows)
+            .map(|(row, cpus)| {
+                let spans: Vec<Span> = cpus.iter().enumerate().map(|(column, &enabled)| {
+                    let cpu = row * self.affinity_columns + column;
+                    let index = match &topology {
+                        Some(topology) if !topology.cpus.is_empty() => topology.position(cpu),
+                        _ => Some(cpu),
+                    };
+                    let usage = index.and_then(|index| per_core_usage.get(index))
+                        .map(|usage| format!("{:>3.0}%", usage))
+                        .unwrap_or_else(|| " off".to_string());
+                    let mut style = Style::default().fg(if enabled {
+                        self.theme_colors.success
+                    } else {
+                        self.theme_colors.muted
+                    });
+                    if cpu == self.affinity_cursor {
+                        style = style.bg(self.theme_colors.selection).add_modifier(Modifier::BOLD);
+                    }
+                    let text = format!("[{}] CPU{:<3} {}", if enabled { "x" } else { " " }, cpu, usage);
+                    Span::styled(format!("{:<width$}", text, width = CELL_WIDTH as usize), style)
+                }).collect();
+                Line::from(spans)
+            })
+            .collect();
+
+        f.render_widget(Paragraph::new(lines), inner);
+    }
+
     fn render_performance_view(&self, f: &mut Frame) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -1738,7 +2719,7 @@ impl App {
         f.render_widget(footer, chunks[2]);
     }
 
-    fn render_thermal_view(&self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
+    fn render_thermal_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
@@ -1848,10 +2829,15 @@ impl App {
                 .style(Style::default().fg(self.theme_colors.border)))
             .style(Style::default().fg(self.theme_colors.foreground))
             .wrap(ratatui::widgets::Wrap { trim: true });
-        f.render_widget(thermal_widget, chunks[1]);
+        let content_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(chunks[1]);
+        f.render_widget(thermal_widget, content_chunks[0]);
+        self.render_fan_control(f, content_chunks[1], system_monitor);
 
         // Footer
-        let footer = Paragraph::new("Press any key to return to dashboard")
+        let footer = Paragraph::new("'R' return | '↑/↓' select | 'c' control mode | '+/-' adjust | 'a' automatic")
             .style(Style::default().fg(self.theme_colors.warning))
             .alignment(Alignment::Center)
             .block(Block::default()
@@ -1861,6 +2847,71 @@ impl App {
         f.render_widget(footer, chunks[2]);
     }
 
+    fn render_fan_control(&mut self, f: &mut Frame, area: Rect, system_monitor: &mut SystemMonitor) {
+        let fan_control = system_monitor.thermal_monitor.fan_control();
+        let active = fan_control.is_active();
+        let last_error = fan_control.last_error().map(|e| e.to_string());
+        self.cached_fan_outputs = fan_control.outputs();
+        if self.selected_fan_output >= self.cached_fan_outputs.len() {
+            self.selected_fan_output = self.cached_fan_outputs.len().saturating_sub(1);
+        }
+
+        let rows: Vec<Row> = self.cached_fan_outputs.iter().enumerate()
+            .map(|(index, output)| {
+                let (value, mode) = match output.kind {
+                    FanOutputKind::CoolingDevice => (format!("{}/{}", output.value, output.max), String::new()),
+                    FanOutputKind::Pwm => (
+                        format!("{:.0}%", output.value as f64 * 100.0 / output.max.max(1) as f64),
+                        output.rpm.map(|rpm| format!("{} RPM ", rpm)).unwrap_or_default(),
+                    ),
+                };
+                let mode = match (&output.curve, output.automatic) {
+                    (Some(sensor), _) if active => format!("{}curve {}", mode, sensor),
+                    (_, true) => format!("{}auto", mode),
+                    (_, false) => format!("{}manual", mode),
+                };
+                let style = if index == self.selected_fan_output {
+                    Style::default().bg(self.theme_colors.selection).add_modifier(Modifier::BOLD)
+                } else if output.automatic {
+                    Style::default().fg(self.theme_colors.foreground)
+                } else {
+                    Style::default().fg(self.theme_colors.warning)
+                };
+                Row::new(vec![
+                    Cell::from(output.id.clone()),
+                    Cell::from(output.label.clone()),
+                    Cell::from(value),
+                    Cell::from(mode),
+                ]).style(style)
+            })
+            .collect();
+
+        let mut title = if active {
+            "Fan Control [ACTIVE]".to_string()
+        } else {
+            "Fan Control [read-only]".to_string()
+        };
+        if let Some(error) = last_error {
+            title = format!("{} - {}", title, error);
+        }
+        let table = Table::new(rows)
+            .widths(&[
+                Constraint::Percentage(40),
+                Constraint::Percentage(20),
+                Constraint::Percentage(12),
+                Constraint::Percentage(28),
+            ])
+            .header(Row::new(vec!["Output", "Device", "Level", "Mode"])
+                .style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
+            .block(Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(if active { self.theme_colors.warning } else { self.theme_colors.border })))
+            .style(Style::default().fg(self.theme_colors.foreground));
+        f.render_widget(table, area);
+    }
+
     fn render_dependency_view(&self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -2073,10 +3124,23 @@ impl App {
 
                 leak_content.push(format!("{} {} (PID: {})", severity_icon, alert.process_name, alert.pid));
                 leak_content.push(format!("   • Type: {}", alert_type));
-                leak_content.push(format!("   • Growth Rate: {:.2} MB/min", alert.growth_rate));
+                leak_content.push(format!("   • Growth Rate: {:.2} MB/min ({:.0}% confidence)", alert.growth_rate, alert.confidence * 100.0));
                 leak_content.push(format!("   • Current Memory: {}", 
                     crate::monitor::SystemMonitor::format_bytes(alert.current_memory)));
+                if let Some(exhaustion) = &alert.exhaustion {
+                    let eta = if exhaustion.minutes < 120.0 {
+                        format!("{:.0} min", exhaustion.minutes)
+                    } else {
+                        format!("{:.1} h", exhaustion.minutes / 60.0)
+                    };
+                    leak_content.push(format!("   • Exhausts {} ({} free) in ~{}", exhaustion.limit,
+                        crate::monitor::SystemMonitor::format_bytes(exhaustion.headroom), eta));
+                }
                 leak_content.push(format!("   • Suggestion: {}", alert.suggested_action));
+                if let Some(killed_at) = alert.oom_killed_at {
+                    leak_content.push(format!("   • 💀 OOM killed at {}",
+                        killed_at.with_timezone(&chrono::Local).format("%H:%M:%S")));
+                }
                 leak_content.push("".to_string());
             }
             
@@ -2096,15 +3160,18 @@ impl App {
                 metrics.memory.ram_percentage));
             leak_content.push(format!("   • Available: {}", 
                 crate::monitor::SystemMonitor::format_bytes(metrics.memory.available_ram)));
+            if let Some(pressure) = &metrics.pressure.memory {
+                leak_content.push(format!("   • Pressure (avg10): {}", pressure.summary()));
+            }
             leak_content.push("".to_string());
         }
 
         leak_content.extend(vec![
             "🔧 Detection Algorithms Active:".to_string(),
-            "   • Steady growth pattern detection".to_string(),
-            "   • Rapid memory increase monitoring".to_string(),
-            "   • Memory spike identification".to_string(),
-            "   • Suspicious allocation patterns".to_string(),
+            "   • Theil–Sen and least-squares trends over 6 hours".to_string(),
+            "   • Plateau detection for warming caches".to_string(),
+            "   • Rising floors across garbage collections".to_string(),
+            "   • Rapid memory increase and spike identification".to_string(),
         ]);
 
         let leak_widget = Paragraph::new(leak_content.join("\n"))
@@ -2115,7 +3182,17 @@ impl App {
                 .style(Style::default().fg(self.theme_colors.border)))
             .style(Style::default().fg(self.theme_colors.foreground))
             .wrap(ratatui::widgets::Wrap { trim: true });
-        f.render_widget(leak_widget, chunks[1]);
+        let body_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(10), Constraint::Length(10)])
+            .split(chunks[1]);
+        let top_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+            .split(body_chunks[0]);
+        f.render_widget(leak_widget, top_chunks[0]);
+        self.render_memory_composition(f, top_chunks[1]);
+        self.render_oom_timeline(f, body_chunks[1], &system_monitor.oom_monitor);
 
         // Footer
         let footer = Paragraph::new("Press any key to return to dashboard")
@@ -2128,6 +3205,127 @@ impl App {
         f.render_widget(footer, chunks[2]);
     }
 
+    /// OOM kills and memcg limit hits, newest first, with the victim, its
+    /// cgroup and what else was using memory at the refresh before.
+    fn render_oom_timeline(&self, f: &mut Frame, area: Rect, oom_monitor: &crate::monitor::OomMonitor) {
+        let format_bytes = crate::monitor::SystemMonitor::format_bytes;
+        let events = oom_monitor.events();
+        let mut lines = Vec::new();
+
+        if events.is_empty() {
+            let note = if oom_monitor.is_kernel_log_available() {
+                "No OOM events since boot"
+            } else {
+                "No OOM events seen in cgroup counters (kernel log needs root for victim details)"
+            };
+            lines.push(Line::from(Span::styled(note, Style::default().fg(self.theme_colors.muted))));
+        }
+
+        for event in events.iter().rev() {
+            let color = match event.kind {
+                crate::monitor::OomEventKind::Kill => self.theme_colors.error,
+                crate::monitor::OomEventKind::LimitReached => self.theme_colors.warning,
+            };
+            let mut spans = vec![
+                Span::raw(format!("{} ", event.timestamp.with_timezone(&chrono::Local).format("%m-%d %H:%M:%S"))),
+                Span::styled(format!("{:<14}", event.kind.label()), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                Span::raw(event.message()),
+            ];
+            if let Some(rss) = event.victim_rss {
+                spans.push(Span::raw(format!(", rss {}", format_bytes(rss))));
+            }
+            if let Some(memcg) = &event.oom_memcg {
+                if event.cgroup.as_ref() != Some(memcg) {
+                    spans.push(Span::raw(format!(", limit of {}", memcg)));
+                }
+            }
+            if let Some(warning) = &event.leak_warning {
+                spans.push(Span::styled(
+                    format!("  ⚠ leak alert since {} ({:.1} MB/min)",
+                        warning.detected_at.with_timezone(&chrono::Local).format("%H:%M:%S"), warning.growth_rate),
+                    Style::default().fg(self.theme_colors.warning)));
+            }
+            lines.push(Line::from(spans));
+
+            if !event.top_consumers.is_empty() {
+                let consumers: Vec<String> = event.top_consumers.iter()
+                    .map(|consumer| format!("{} ({}) {}", consumer.name, consumer.pid, format_bytes(consumer.memory)))
+                    .collect();
+                lines.push(Line::from(Span::styled(
+                    format!("    top: {}", consumers.join(", ")),
+                    Style::default().fg(self.theme_colors.muted))));
+            }
+        }
+
+        let widget = Paragraph::new(lines)
+            .block(Block::default()
+                .title(format!("OOM Events ({})", events.len()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+        f.render_widget(widget, area);
+    }
+
+    /// Where physical memory went according to `/proc/meminfo`, and the
+    /// processes with the largest proportional share of it.
+    fn render_memory_composition(&self, f: &mut Frame, area: Rect) {
+        let format_bytes = crate::monitor::SystemMonitor::format_bytes;
+        let mut lines = Vec::new();
+
+        match self.system_metrics.as_ref().and_then(|metrics| metrics.memory.breakdown.map(|breakdown| (metrics, breakdown))) {
+            Some((metrics, breakdown)) => {
+                let total = breakdown.total.max(1);
+                for (label, bytes) in breakdown.composition() {
+                    let percent = bytes as f64 * 100.0 / total as f64;
+                    let bar = "█".repeat((percent / 5.0).round() as usize);
+                    lines.push(Line::from(vec![
+                        Span::raw(format!("{:<21}{:>10} {:>5.1}% ", label, format_bytes(bytes), percent)),
+                        Span::styled(bar, Style::default().fg(self.theme_colors.accent)),
+                    ]));
+                }
+
+                lines.push(Line::from(""));
+                lines.push(Line::from(format!("Dirty: {}  Writeback: {}",
+                    format_bytes(breakdown.dirty), format_bytes(breakdown.writeback))));
+                lines.push(Line::from(format!("Mapped: {}  Swap cached: {}",
+                    format_bytes(breakdown.mapped), format_bytes(breakdown.swap_cached))));
+                if breakdown.hugepages_total > 0 {
+                    lines.push(Line::from(format!("Huge pages: {} of {} free ({} each)",
+                        breakdown.hugepages_free, breakdown.hugepages_total, format_bytes(breakdown.hugepage_size))));
+                }
+
+                let mut by_pss: Vec<_> = metrics.processes.iter()
+                    .filter_map(|process| process.memory_detail.map(|detail| (process, detail)))
+                    .collect();
+                by_pss.sort_by_key(|(_, detail)| std::cmp::Reverse(detail.pss));
+                if !by_pss.is_empty() {
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        format!("{:<16}{:>10}{:>10}{:>10}", "Top by PSS", "PSS", "USS", "Swap"),
+                        Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD))));
+                    for (process, detail) in by_pss.iter().take(8) {
+                        lines.push(Line::from(format!("{:<16}{:>10}{:>10}{:>10}",
+                            process.name.chars().take(15).collect::<String>(),
+                            format_bytes(detail.pss), format_bytes(detail.uss()), format_bytes(detail.swap))));
+                    }
+                }
+            }
+            None => lines.push(Line::from(Span::styled(
+                "/proc/meminfo is not available",
+                Style::default().fg(self.theme_colors.muted)))),
+        }
+
+        let widget = Paragraph::new(lines)
+            .block(Block::default()
+                .title("Memory Composition")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+        f.render_widget(widget, area);
+    }
+
     fn render_io_analysis_view(&self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -2187,6 +3385,7 @@ impl App {
                     crate::monitor::BottleneckType::IOStorm => "I/O Storm",
                     crate::monitor::BottleneckType::SlowDisk => "Slow Disk Response",
                     crate::monitor::BottleneckType::IOContentionDetected => "I/O Contention",
+                    crate::monitor::BottleneckType::IOPressure => "I/O Pressure",
                 };
 
                 io_content.push(format!("{} {}", severity_icon, alert_type));
@@ -2289,14 +3488,15 @@ impl App {
                 "❌ No GPU support detected".to_string(),
                 "".to_string(),
                 "🔧 To enable GPU monitoring:".to_string(),
-                "   • Install nvidia-smi for NVIDIA GPUs".to_string(),
+                "   • Install the NVIDIA driver (NVML or nvidia-smi)".to_string(),
                 "   • Install ROCm tools for AMD GPUs".to_string(),
                 "   • Ensure proper drivers are installed".to_string(),
                 "".to_string(),
                 "📊 Supported Vendors:".to_string(),
-                "   • NVIDIA (requires nvidia-smi)".to_string(),
+                "   • NVIDIA (NVML, or nvidia-smi)".to_string(),
                 "   • AMD (requires ROCm tools)".to_string(),
                 "   • Intel (basic sysfs support)".to_string(),
+                "   • Per-process engine usage from DRM fdinfo (any driver)".to_string(),
             ]);
         } else {
             let supported_vendors = system_monitor.gpu_monitor.get_supported_vendors();
@@ -2348,7 +3548,32 @@ impl App {
                     if let Some(power) = gpu.power_usage {
                         gpu_content.push(format!("   • Power: {:.1}W", power));
                     }
-                    
+
+                    if let (Some(graphics), Some(memory)) = (gpu.clock_graphics, gpu.clock_memory) {
+                        gpu_content.push(format!("   • Clocks: {} MHz core, {} MHz memory", graphics, memory));
+                    }
+
+                    if let Some(nvidia) = &gpu.nvidia {
+                        if !nvidia.throttle_reasons.is_empty() {
+                            gpu_content.push(format!("   • Throttled: {}", nvidia.throttle_reasons.join(", ")));
+                        }
+                        if let (Some(corrected), Some(uncorrected)) = (nvidia.ecc_corrected, nvidia.ecc_uncorrected) {
+                            let ecc_icon = if uncorrected > 0 { "🔴" } else if corrected > 0 { "🟡" } else { "🟢" };
+                            gpu_content.push(format!("   • ECC errors: {}{} corrected, {} uncorrected", ecc_icon, corrected, uncorrected));
+                        }
+                        if let (Some(tx), Some(rx)) = (nvidia.pcie_tx_kbps, nvidia.pcie_rx_kbps) {
+                            gpu_content.push(format!("   • PCIe: {}/s TX, {}/s RX",
+                                SystemMonitor::format_bytes(tx as u64 * 1024),
+                                SystemMonitor::format_bytes(rx as u64 * 1024)));
+                        }
+                        for instance in &nvidia.mig_instances {
+                            gpu_content.push(format!("   • MIG {} (GI {}, CI {}): {} MB / {} MB, {} process(es)",
+                                instance.name, instance.gpu_instance_id, instance.compute_instance_id,
+                                instance.memory_used / (1024 * 1024), instance.memory_total / (1024 * 1024),
+                                instance.pids.len()));
+                        }
+                    }
+
                     if !gpu.processes.is_empty() {
                         gpu_content.push(format!("   • Active Processes: {}", gpu.processes.len()));
                     }
@@ -2361,7 +3586,8 @@ impl App {
                 }
 
                 // Show GPU process summary
-                let gpu_processes = system_monitor.gpu_monitor.get_gpu_processes();
+                let mut gpu_processes = system_monitor.gpu_monitor.get_gpu_processes();
+                gpu_processes.sort_by(|a, b| b.gpu_utilization.unwrap_or(0.0).total_cmp(&a.gpu_utilization.unwrap_or(0.0)));
                 if !gpu_processes.is_empty() {
                     gpu_content.push("🔧 GPU Processes:".to_string());
                     for process in gpu_processes.iter().take(5) {
@@ -2372,8 +3598,17 @@ impl App {
                             crate::monitor::GPUProcessType::Mixed => "Mixed",
                             crate::monitor::GPUProcessType::Unknown => "Unknown",
                         };
-                        gpu_content.push(format!("   • {} (PID: {}): {} MB ({})", 
-                            process.process_name, process.pid, memory_mb, process_type));
+                        let engines = process.engine_utilization.iter()
+                            .filter(|(_, percent)| *percent >= 0.5)
+                            .map(|(engine, percent)| format!("{} {:.0}%", engine, percent))
+                            .collect::<Vec<_>>();
+                        if engines.is_empty() {
+                            gpu_content.push(format!("   • {} (PID: {}): {} MB ({})", 
+                                process.process_name, process.pid, memory_mb, process_type));
+                        } else {
+                            gpu_content.push(format!("   • {} (PID: {}): {} MB ({}) {}", 
+                                process.process_name, process.pid, memory_mb, process_type, engines.join(" ")));
+                        }
                     }
                     if gpu_processes.len() > 5 {
                         gpu_content.push(format!("   ... and {} more processes", gpu_processes.len() - 5));
@@ -2663,12 +3898,15 @@ impl App {
     }
 
     fn render_container_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
+        self.poll_container_action();
+
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),  // Header
                 Constraint::Length(6),  // Stats
                 Constraint::Min(10),    // Container list
+                Constraint::Length(8),  // Events
                 Constraint::Length(3),  // Footer
             ])
             .split(f.size());
@@ -2726,11 +3964,34 @@ impl App {
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
             f.render_widget(memory_usage, stats_chunks[4]);
 
+            self.cached_containers = metrics.containers.clone();
+            if self.selected_container_index >= metrics.containers.len() {
+                self.selected_container_index = metrics.containers.len().saturating_sub(1);
+            }
+
+            let list_area = if self.container_logs.is_some() {
+                let split = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .split(chunks[2]);
+                self.render_container_logs(f, split[1]);
+                split[0]
+            } else {
+                chunks[2]
+            };
+
             // Container list
-            let headers = Row::new(vec!["Name", "Image", "Status", "CPU%", "Memory", "Network I/O"]);
+            let headers = Row::new(vec!["Name", "Image", "Status", "Health", "CPU%", "Memory", "Network I/O", "PIDs", "Restarts", "OOM", "Stall"]);
             let mut rows = Vec::new();
 
-            for container in &metrics.containers {
+            let max_rows = list_area.height.saturating_sub(3) as usize;
+            let scroll_offset = if self.selected_container_index < max_rows {
+                0
+            } else {
+                self.selected_container_index - max_rows + 1
+            };
+
+            for (index, container) in metrics.containers.iter().enumerate().skip(scroll_offset).take(max_rows) {
                 let status_color = match container.status {
                     crate::monitor::ContainerStatus::Running => self.theme_colors.success,
                     crate::monitor::ContainerStatus::Exited => self.theme_colors.muted,
@@ -2738,64 +3999,227 @@ impl App {
                     _ => self.theme_colors.foreground,
                 };
 
+                let health_color = match container.health.as_deref() {
+                    Some("healthy") => self.theme_colors.success,
+                    Some("unhealthy") => self.theme_colors.error,
+                    Some("starting") => self.theme_colors.warning,
+                    _ => self.theme_colors.muted,
+                };
+
+                let status_text = match container.exit_code {
+                    Some(code) if container.status != crate::monitor::ContainerStatus::Running => {
+                        format!("{:?} ({})", container.status, code)
+                    }
+                    _ => format!("{:?}", container.status),
+                };
+
+                let oom_color = if container.oom_kills > 0 {
+                    self.theme_colors.error
+                } else {
+                    self.theme_colors.foreground
+                };
+
                 let network_io = format!("↓{} ↑{}", 
                     crate::monitor::SystemMonitor::format_bytes(container.network_rx),
                     crate::monitor::SystemMonitor::format_bytes(container.network_tx));
 
+                let row_style = if index == self.selected_container_index {
+                    Style::default().bg(self.theme_colors.selection).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+
                 rows.push(Row::new(vec![
                     Cell::from(container.name.clone()),
                     Cell::from(container.image.clone()),
-                    Cell::from(format!("{:?}", container.status)).style(Style::default().fg(status_color)),
+                    Cell::from(status_text).style(Style::default().fg(status_color)),
+                    Cell::from(container.health.clone().unwrap_or_else(|| "-".to_string())).style(Style::default().fg(health_color)),
                     Cell::from(format!("{:.1}%", container.cpu_usage)),
                     Cell::from(crate::monitor::SystemMonitor::format_bytes(container.memory_usage)),
                     Cell::from(network_io),
-                ]));
+                    Cell::from(container.pids.to_string()),
+                    Cell::from(container.restart_count.to_string()),
+                    Cell::from(container.oom_kills.to_string()).style(Style::default().fg(oom_color)),
+                    self.pressure_cell(&container.pressure),
+                ]).style(row_style));
             }
 
             let table = Table::new(rows)
             .widths(&[
-                Constraint::Percentage(20),
-                Constraint::Percentage(25),
-                Constraint::Percentage(15),
-                Constraint::Percentage(10),
-                Constraint::Percentage(15),
-                Constraint::Percentage(15),
+                Constraint::Percentage(14),
+                Constraint::Percentage(14),
+                Constraint::Percentage(11),
+                Constraint::Percentage(8),
+                Constraint::Percentage(7),
+                Constraint::Percentage(9),
+                Constraint::Percentage(13),
+                Constraint::Percentage(5),
+                Constraint::Percentage(6),
+                Constraint::Percentage(4),
+                Constraint::Percentage(9),
             ])
             .header(headers.style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
             .block(Block::default()
-                .title("Containers")
+                .title(match (&self.running_container_action, &self.last_container_action) {
+                    (Some(job), _) => format!("Containers - {} {}: running...", job.action.as_str(), job.container_name),
+                    (None, Some(last)) => format!("Containers - last action: {}", last),
+                    (None, None) => "Containers".to_string(),
+                })
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .style(Style::default().fg(self.theme_colors.border)))
             .style(Style::default().fg(self.theme_colors.foreground));
 
-            f.render_widget(table, chunks[2]);
+            f.render_widget(table, list_area);
+
+            // Live engine events, newest first
+            let event_rows: Vec<Row> = metrics.recent_events.iter().rev()
+                .map(|event| {
+                    let action_color = match event.action.as_str() {
+                        "oom" | "kill" => self.theme_colors.error,
+                        "die" | "stop" => self.theme_colors.warning,
+                        "start" | "unpause" => self.theme_colors.success,
+                        _ => self.theme_colors.foreground,
+                    };
+                    let exit_code = event.exit_code
+                        .map(|code| format!("exit {}", code))
+                        .unwrap_or_default();
+
+                    Row::new(vec![
+                        Cell::from(event.timestamp.with_timezone(&chrono::Local).format("%H:%M:%S").to_string()),
+                        Cell::from(event.container_name.clone()),
+                        Cell::from(event.action.clone()).style(Style::default().fg(action_color)),
+                        Cell::from(exit_code),
+                    ])
+                })
+                .collect();
+
+            let events_title = if metrics.alerts.is_empty() {
+                "Events".to_string()
+            } else {
+                format!("Events ({} alerts)", metrics.alerts.len())
+            };
+
+            let events_table = Table::new(event_rows)
+            .widths(&[
+                Constraint::Percentage(15),
+                Constraint::Percentage(35),
+                Constraint::Percentage(30),
+                Constraint::Percentage(20),
+            ])
+            .block(Block::default()
+                .title(events_title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+
+            f.render_widget(events_table, chunks[3]);
         }
 
         // Footer
-        let footer = Paragraph::new("Press 'C' to return to dashboard | '↑/↓' to navigate")
+        let footer = Paragraph::new("'C' dashboard | '↑/↓' select | s:start o:stop b:restart u:pause/unpause k:kill Del:remove | l:logs x:shell")
             .style(Style::default().fg(self.theme_colors.warning))
             .alignment(Alignment::Center)
             .block(Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .style(Style::default().fg(self.theme_colors.border)));
-        f.render_widget(footer, chunks[3]);
+        f.render_widget(footer, chunks[4]);
     }
 
-    fn render_service_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
+    fn render_container_logs(&mut self, f: &mut Frame, area: Rect) {
+        if let Some(follower) = &self.container_logs {
+            for line in follower.drain() {
+                self.container_log_lines.push_back(line);
+                if self.container_log_lines.len() > MAX_CONTAINER_LOG_LINES {
+                    self.container_log_lines.pop_front();
+                }
+            }
+        }
+
+        let visible = area.height.saturating_sub(2) as usize;
+        let text = self.container_log_lines.iter()
+            .skip(self.container_log_lines.len().saturating_sub(visible))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let logs = Paragraph::new(text)
+            .style(Style::default().fg(self.theme_colors.foreground))
+            .block(Block::default()
+                .title(format!("Logs: {} (following, 'l' to close)", self.container_logs_name))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(logs, area);
+    }
+
+    fn render_container_action_dialog(&self, f: &mut Frame, action: ContainerAction, container: &ContainerInfo) {
+        let size = f.size();
+        let popup_area = centered_rect(50, 20, size);
+
+        f.render_widget(Clear, popup_area);
+
+        let block = Block::default()
+            .title("⚠️  Container Action")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default()
+                .bg(self.theme_colors.background)
+                .fg(self.theme_colors.warning)
+                .add_modifier(Modifier::BOLD));
+
+        let text = format!("{} container {} ({})?\n\nPress 'y' to confirm, any other key to cancel",
+            capitalize(action.as_str()), container.name, container.id.chars().take(12).collect::<String>());
+
+        let paragraph = Paragraph::new(text)
+            .block(block)
+            .style(Style::default().fg(self.theme_colors.foreground))
+            .alignment(Alignment::Center);
+
+        f.render_widget(paragraph, popup_area);
+    }
+
+    fn render_service_action_dialog(&self, f: &mut Frame, action: ServiceAction, service: &str) {
+        let size = f.size();
+        let popup_area = centered_rect(50, 20, size);
+
+        f.render_widget(Clear, popup_area);
+
+        let block = Block::default()
+            .title("⚠️  Service Action")
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .style(Style::default()
+                .bg(self.theme_colors.background)
+                .fg(self.theme_colors.warning)
+                .add_modifier(Modifier::BOLD));
+
+        let text = format!("{} {}.service?\n\nPress 'y' to confirm, any other key to cancel",
+            capitalize(action.as_str()), service);
+
+        let paragraph = Paragraph::new(text)
+            .block(block)
+            .style(Style::default().fg(self.theme_colors.foreground))
+            .alignment(Alignment::Center);
+
+        f.render_widget(paragraph, popup_area);
+    }
+
+    fn render_pod_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Length(3),  // Header
                 Constraint::Length(6),  // Stats
-                Constraint::Min(10),    // Service list
+                Constraint::Min(10),    // Pod list
                 Constraint::Length(3),  // Footer
             ])
             .split(f.size());
 
         // Header
-        let header = Paragraph::new("⚙️ Service Monitor")
+        let header = Paragraph::new("☸️ Kubernetes Pods")
             .style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD))
             .alignment(Alignment::Center)
             .block(Block::default()
@@ -2804,38 +4228,153 @@ impl App {
                 .style(Style::default().fg(self.theme_colors.border)));
         f.render_widget(header, chunks[0]);
 
-        // Stats and Service list
-        if let Ok(metrics) = system_monitor.service_monitor.get_service_metrics() {
+        let processes = self.system_metrics.as_ref()
+            .map(|m| m.processes.as_slice())
+            .unwrap_or(&[]);
+        let metrics = system_monitor.kubernetes_monitor.get_pod_metrics(processes);
+
+        if !system_monitor.kubernetes_monitor.is_kubernetes_node() {
+            let message = Paragraph::new("No kubepods cgroup found - this host does not appear to be a Kubernetes node")
+                .style(Style::default().fg(self.theme_colors.muted))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+            f.render_widget(message, chunks[2]);
+        } else {
             let stats_chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(25),
                 ])
                 .split(chunks[1]);
 
-            let total_services = Paragraph::new(format!("Total\n{}", metrics.total_services))
+            let total_pods = Paragraph::new(format!("Pods\n{}", metrics.total_pods))
                 .style(Style::default().fg(self.theme_colors.info))
                 .alignment(Alignment::Center)
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
-            f.render_widget(total_services, stats_chunks[0]);
+            f.render_widget(total_pods, stats_chunks[0]);
 
-            let active_services = Paragraph::new(format!("Active\n{}", metrics.active_services))
-                .style(Style::default().fg(self.theme_colors.success))
+            let cpu_usage = Paragraph::new(format!("CPU\n{:.1}%", metrics.total_cpu_usage))
+                .style(Style::default().fg(self.theme_colors.warning))
                 .alignment(Alignment::Center)
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
-            f.render_widget(active_services, stats_chunks[1]);
+            f.render_widget(cpu_usage, stats_chunks[1]);
 
-            let failed_services = Paragraph::new(format!("Failed\n{}", metrics.failed_services))
-                .style(Style::default().fg(self.theme_colors.error))
+            let memory_usage = Paragraph::new(format!("Memory\n{}", crate::monitor::SystemMonitor::format_bytes(metrics.total_memory_usage)))
+                .style(Style::default().fg(self.theme_colors.primary))
                 .alignment(Alignment::Center)
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
-            f.render_widget(failed_services, stats_chunks[2]);
+            f.render_widget(memory_usage, stats_chunks[2]);
 
-            let enabled_services = Paragraph::new(format!("Enabled\n{}", metrics.enabled_services))
+            let runtime = Paragraph::new(format!("CRI Socket\n{}", metrics.cri_socket.as_deref().unwrap_or("unavailable")))
+                .style(Style::default().fg(self.theme_colors.accent))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+            f.render_widget(runtime, stats_chunks[3]);
+
+            let headers = Row::new(vec!["Namespace", "Pod", "QoS", "Containers", "Procs", "CPU%", "Memory"]);
+            let rows: Vec<Row> = metrics.pods.iter().map(|pod| {
+                Row::new(vec![
+                    Cell::from(pod.namespace.clone()),
+                    Cell::from(pod.name.clone()),
+                    Cell::from(pod.qos_class.clone()),
+                    Cell::from(pod.containers.join(", ")),
+                    Cell::from(pod.process_count.to_string()),
+                    Cell::from(format!("{:.1}%", pod.cpu_usage)),
+                    Cell::from(crate::monitor::SystemMonitor::format_bytes(pod.memory_usage)),
+                ])
+            }).collect();
+
+            let table = Table::new(rows)
+            .widths(&[
+                Constraint::Percentage(15),
+                Constraint::Percentage(25),
+                Constraint::Percentage(10),
+                Constraint::Percentage(20),
+                Constraint::Percentage(8),
+                Constraint::Percentage(8),
+                Constraint::Percentage(12),
+            ])
+            .header(headers.style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
+            .block(Block::default()
+                .title("Pods")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+
+            f.render_widget(table, chunks[2]);
+        }
+
+        // Footer
+        let footer = Paragraph::new("Press 'K' to return to dashboard | 'G' then 'g' to group processes by pod")
+            .style(Style::default().fg(self.theme_colors.warning))
+            .alignment(Alignment::Center)
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(footer, chunks[3]);
+    }
+
+    fn render_service_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
+        self.poll_service_job();
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),  // Header
+                Constraint::Length(6),  // Stats
+                Constraint::Min(10),    // Service list
+                Constraint::Length(8),  // Failures and restart loops
+                Constraint::Length(3),  // Footer
+            ])
+            .split(f.size());
+
+        // Header
+        let header = Paragraph::new("⚙️ Service Monitor")
+            .style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center)
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(header, chunks[0]);
+
+        // Stats and Service list
+        if let Ok(metrics) = system_monitor.service_monitor.get_service_metrics() {
+            let stats_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                ])
+                .split(chunks[1]);
+
+            let total_services = Paragraph::new(format!("Total\n{}", metrics.total_services))
+                .style(Style::default().fg(self.theme_colors.info))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+            f.render_widget(total_services, stats_chunks[0]);
+
+            let active_services = Paragraph::new(format!("Active\n{}", metrics.active_services))
+                .style(Style::default().fg(self.theme_colors.success))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+            f.render_widget(active_services, stats_chunks[1]);
+
+            let failed_services = Paragraph::new(format!("Failed\n{}", metrics.failed_services))
+                .style(Style::default().fg(self.theme_colors.error))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+            f.render_widget(failed_services, stats_chunks[2]);
+
+            let enabled_services = Paragraph::new(format!("Enabled\n{}", metrics.enabled_services))
                 .style(Style::default().fg(self.theme_colors.accent))
                 .alignment(Alignment::Center)
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
@@ -2847,17 +4386,61 @@ impl App {
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
             f.render_widget(memory_usage, stats_chunks[4]);
 
+            self.cached_services = metrics.services.clone();
+            if self.selected_service_index >= metrics.services.len() {
+                self.selected_service_index = metrics.services.len().saturating_sub(1);
+            }
+
+            let graph = ServiceDependencyGraph::build(&metrics.services);
+
+            let list_area = if self.service_journal.is_some() {
+                let split = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+                    .split(chunks[2]);
+                let service = metrics.services.iter().find(|s| s.name == self.service_journal_name);
+                self.render_service_journal(f, split[1], service);
+                split[0]
+            } else if self.show_service_dependencies {
+                let split = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .split(chunks[2]);
+                if let Some(service) = metrics.services.get(self.selected_service_index) {
+                    self.render_service_dependencies(f, split[1], &graph, &ServiceDependencyGraph::unit_name(service));
+                }
+                split[0]
+            } else if self.show_service_slices {
+                let split = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                    .split(chunks[2]);
+                self.render_service_slices(f, split[1], &metrics.slices);
+                split[0]
+            } else {
+                chunks[2]
+            };
+
+            self.render_service_failures(f, chunks[3], &graph, &metrics.services, &metrics.restart_loops);
+
             // Service list
-            let headers = Row::new(vec!["Service", "Status", "State", "CPU%", "Memory", "Restarts"]);
+            let headers = Row::new(vec!["Service", "Status", "State", "CPU%", "Memory", "IO R/W", "Tasks", "OOM", "Restarts", "Stall"]);
             let mut rows = Vec::new();
 
-            for service in &metrics.services {
-                let status_color = match service.status {
-                    crate::monitor::ServiceStatus::Active => self.theme_colors.success,
-                    crate::monitor::ServiceStatus::Inactive => self.theme_colors.muted,
-                    crate::monitor::ServiceStatus::Failed => self.theme_colors.error,
-                    crate::monitor::ServiceStatus::Activating => self.theme_colors.warning,
-                    _ => self.theme_colors.foreground,
+            let max_rows = list_area.height.saturating_sub(3) as usize;
+            let scroll_offset = if self.selected_service_index < max_rows {
+                0
+            } else {
+                self.selected_service_index - max_rows + 1
+            };
+
+            for (index, service) in metrics.services.iter().enumerate().skip(scroll_offset).take(max_rows) {
+                let status_color = self.service_status_color(Some(&service.status));
+
+                let restart_color = if metrics.restart_loops.iter().any(|l| l.service == service.name) {
+                    self.theme_colors.error
+                } else {
+                    self.theme_colors.foreground
                 };
 
                 let state_color = match service.state {
@@ -2867,45 +4450,407 @@ impl App {
                     _ => self.theme_colors.foreground,
                 };
 
+                let row_style = if index == self.selected_service_index {
+                    Style::default().bg(self.theme_colors.selection).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+
                 rows.push(Row::new(vec![
                     Cell::from(service.name.clone()),
                     Cell::from(format!("{:?}", service.status)).style(Style::default().fg(status_color)),
                     Cell::from(format!("{:?}", service.state)).style(Style::default().fg(state_color)),
                     Cell::from(format!("{:.1}%", service.cpu_usage)),
                     Cell::from(crate::monitor::SystemMonitor::format_bytes(service.memory_usage)),
-                    Cell::from(service.restart_count.to_string()),
-                ]));
+                    Cell::from(format!(
+                        "{}/{}",
+                        crate::monitor::SystemMonitor::format_bytes(service.io_read_bytes),
+                        crate::monitor::SystemMonitor::format_bytes(service.io_write_bytes)
+                    )),
+                    Cell::from(service.tasks.to_string()),
+                    Cell::from(service.oom_kills.to_string()).style(Style::default().fg(if service.oom_kills > 0 {
+                        self.theme_colors.error
+                    } else {
+                        self.theme_colors.foreground
+                    })),
+                    Cell::from(service.restart_count.to_string()).style(Style::default().fg(restart_color)),
+                    self.pressure_cell(&service.pressure),
+                ]).style(row_style));
             }
 
             let table = Table::new(rows)
             .widths(&[
-                Constraint::Percentage(25),
-                Constraint::Percentage(15),
-                Constraint::Percentage(15),
-                Constraint::Percentage(10),
-                Constraint::Percentage(15),
+                Constraint::Percentage(18),
+                Constraint::Percentage(9),
+                Constraint::Percentage(9),
+                Constraint::Percentage(7),
                 Constraint::Percentage(10),
+                Constraint::Percentage(16),
+                Constraint::Percentage(6),
+                Constraint::Percentage(5),
+                Constraint::Percentage(8),
+                Constraint::Percentage(12),
             ])
             .header(headers.style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
             .block(Block::default()
-                .title("Services")
+                .title(match (&self.running_service_job, &self.last_service_job) {
+                    (Some(job), _) => format!("Services - {} {}: pending...", job.action.as_str(), job.service),
+                    (None, Some(job)) => format!("Services - last job: {}", job),
+                    (None, None) => "Services".to_string(),
+                })
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .style(Style::default().fg(self.theme_colors.border)))
             .style(Style::default().fg(self.theme_colors.foreground));
 
-            f.render_widget(table, chunks[2]);
+            f.render_widget(table, list_area);
         }
 
         // Footer
-        let footer = Paragraph::new("Press 'V' to return to dashboard | '↑/↓' to navigate")
+        let footer = Paragraph::new("Press 'V' to return to dashboard | '↑/↓' select | 's' start | 'o' stop | 'b' restart | 'd' dependencies | 'c' slices | 'l' journal")
             .style(Style::default().fg(self.theme_colors.warning))
             .alignment(Alignment::Center)
             .block(Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .style(Style::default().fg(self.theme_colors.border)));
-        f.render_widget(footer, chunks[3]);
+        f.render_widget(footer, chunks[4]);
+    }
+
+    fn service_status_color(&self, status: Option<&ServiceStatus>) -> Color {
+        match status {
+            Some(ServiceStatus::Active) => self.theme_colors.success,
+            Some(ServiceStatus::Inactive) => self.theme_colors.muted,
+            Some(ServiceStatus::Failed) => self.theme_colors.error,
+            Some(ServiceStatus::Activating) | Some(ServiceStatus::Deactivating) => self.theme_colors.warning,
+            _ => self.theme_colors.foreground,
+        }
+    }
+
+    /// The most contended resource of a cgroup by PSI `some` avg10.
+    fn pressure_cell(&self, pressure: &crate::monitor::PressureMetrics) -> Cell<'static> {
+        match pressure.worst() {
+            Some((resource, avg10)) => {
+                let color = if avg10 >= 40.0 {
+                    self.theme_colors.error
+                } else if avg10 >= 10.0 {
+                    self.theme_colors.warning
+                } else {
+                    self.theme_colors.foreground
+                };
+                Cell::from(format!("{} {:.0}%", resource.label(), avg10)).style(Style::default().fg(color))
+            }
+            None => Cell::from("-"),
+        }
+    }
+
+    /// Renders the `systemctl list-dependencies` tree of a unit, coloured
+    /// by the health of each service in it.
+    fn render_service_dependencies(&self, f: &mut Frame, area: Rect, graph: &ServiceDependencyGraph, unit: &str) {
+        let mut lines: Vec<Line> = graph.dependency_tree(unit, 4)
+            .into_iter()
+            .map(|entry| {
+                let marker = match entry.status {
+                    Some(ServiceStatus::Failed) => "✗",
+                    Some(_) => "●",
+                    None => "○",
+                };
+                let mut text = format!("{}{} {}", "  ".repeat(entry.depth), marker, entry.unit);
+                if let Some(kind) = entry.kind {
+                    text.push_str(&format!(" ({})", kind.as_str()));
+                }
+                if entry.repeated {
+                    text.push_str(" …");
+                }
+                Line::from(Span::styled(text, Style::default().fg(self.service_status_color(entry.status.as_ref()))))
+            })
+            .collect();
+
+        let after = graph.ordered_after(unit);
+        if !after.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                format!("After: {}", after.join(", ")),
+                Style::default().fg(self.theme_colors.muted),
+            )));
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title(format!("Dependencies of {}", unit))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+    }
+
+    fn render_service_journal(&mut self, f: &mut Frame, area: Rect, service: Option<&ServiceInfo>) {
+        if let Some(follower) = &self.service_journal {
+            for entry in follower.drain() {
+                self.service_journal_lines.push_back(entry);
+                if self.service_journal_lines.len() > MAX_SERVICE_JOURNAL_LINES {
+                    self.service_journal_lines.pop_front();
+                }
+            }
+        }
+
+        let visible = area.height.saturating_sub(2) as usize;
+        let lines: Vec<Line> = self.service_journal_lines.iter()
+            .skip(self.service_journal_lines.len().saturating_sub(visible))
+            .map(|entry| {
+                let color = if entry.is_error() {
+                    self.theme_colors.error
+                } else if entry.is_warning() {
+                    self.theme_colors.warning
+                } else {
+                    self.theme_colors.foreground
+                };
+                Line::from(Span::styled(entry.format_line(), Style::default().fg(color)))
+            })
+            .collect();
+
+        let reason = service
+            .and_then(|s| s.failure_reason())
+            .map(|reason| format!(" [{}]", reason))
+            .unwrap_or_default();
+
+        let journal = Paragraph::new(lines)
+            .block(Block::default()
+                .title(format!("Journal: {}.service{} (following, 'l' to close)", self.service_journal_name, reason))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(journal, area);
+    }
+
+    /// Shows the cgroup slice hierarchy with per-slice and per-unit usage.
+    fn render_service_slices(&self, f: &mut Frame, area: Rect, slices: &[crate::monitor::SliceNode]) {
+        let lines: Vec<Line> = if slices.is_empty() {
+            vec![Line::from(Span::styled(
+                "No cgroup v2 slices found",
+                Style::default().fg(self.theme_colors.muted),
+            ))]
+        } else {
+            slices.iter()
+                .flat_map(|slice| slice.flatten())
+                .map(|(depth, node)| {
+                    let color = if node.is_slice() {
+                        self.theme_colors.primary
+                    } else {
+                        self.theme_colors.foreground
+                    };
+                    Line::from(vec![
+                        Span::styled(format!("{}{}", "  ".repeat(depth), node.name), Style::default().fg(color)),
+                        Span::styled(
+                            format!(
+                                "  {:.1}% {} {} tasks",
+                                node.cpu_usage,
+                                crate::monitor::SystemMonitor::format_bytes(node.stats.memory_current),
+                                node.stats.pids_current
+                            ),
+                            Style::default().fg(self.theme_colors.muted),
+                        ),
+                    ])
+                })
+                .collect()
+        };
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title("Slices")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(paragraph, area);
+    }
+
+    /// Lists failed units with everything degraded because of them, and
+    /// services whose NRestarts keeps climbing.
+    fn render_service_failures(&self, f: &mut Frame, area: Rect, graph: &ServiceDependencyGraph, services: &[ServiceInfo], restart_loops: &[RestartLoop]) {
+        let mut lines = Vec::new();
+
+        for failed in graph.failed_units() {
+            let reason = services.iter()
+                .find(|s| ServiceDependencyGraph::unit_name(s) == failed)
+                .and_then(|s| s.failure_reason())
+                .map(|reason| format!(" ({})", reason))
+                .unwrap_or_default();
+            let degraded = graph.degraded_by(&failed);
+            let affected = if degraded.is_empty() {
+                "nothing depends on it".to_string()
+            } else {
+                degraded.iter()
+                    .map(|d| format!("{} [{}{}]", d.unit, d.via.as_str(), if d.hard { "" } else { ", soft" }))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            lines.push(Line::from(vec![
+                Span::styled(format!("✗ {}{}", failed, reason), Style::default().fg(self.theme_colors.error).add_modifier(Modifier::BOLD)),
+                Span::styled(format!(" → degrades {}", affected), Style::default().fg(self.theme_colors.warning)),
+            ]));
+        }
+
+        for restart_loop in restart_loops {
+            lines.push(Line::from(Span::styled(
+                format!("↻ {}.service restarted {} times in the last {} min",
+                    restart_loop.service, restart_loop.restarts, restart_loop.window_secs / 60),
+                Style::default().fg(self.theme_colors.error),
+            )));
+        }
+
+        if lines.is_empty() {
+            lines.push(Line::from(Span::styled("No failed units or restart loops", Style::default().fg(self.theme_colors.success))));
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title("Failures & Restart Loops")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, area);
+    }
+
+    fn render_scheduled_jobs_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),  // Header
+                Constraint::Length(6),  // Stats
+                Constraint::Min(10),    // Job list
+                Constraint::Length(8),  // Flagged jobs
+                Constraint::Length(3),  // Footer
+            ])
+            .split(f.size());
+
+        let header = Paragraph::new("⏰ Scheduled Jobs")
+            .style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD))
+            .alignment(Alignment::Center)
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(header, chunks[0]);
+
+        if let Ok(jobs) = system_monitor.scheduled_job_monitor.get_scheduled_jobs() {
+            let timers = jobs.iter().filter(|j| j.kind == ScheduledJobKind::SystemdTimer).count();
+            let cron = jobs.len() - timers;
+            let failed = jobs.iter().filter(|j| j.status == ScheduledJobStatus::Failed).count();
+            let missed = jobs.iter().filter(|j| j.status == ScheduledJobStatus::Missed).count();
+
+            let stats_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(25),
+                ])
+                .split(chunks[1]);
+
+            let stats = [
+                (format!("Timers\n{}", timers), self.theme_colors.info),
+                (format!("Cron Jobs\n{}", cron), self.theme_colors.accent),
+                (format!("Failed\n{}", failed), self.theme_colors.error),
+                (format!("Missed\n{}", missed), self.theme_colors.warning),
+            ];
+            for (index, (text, color)) in stats.into_iter().enumerate() {
+                let paragraph = Paragraph::new(text)
+                    .style(Style::default().fg(color))
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+                f.render_widget(paragraph, stats_chunks[index]);
+            }
+
+            let format_time = |time: Option<chrono::DateTime<chrono::Utc>>| {
+                time.map(|t| t.with_timezone(&chrono::Local).format("%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| "-".to_string())
+            };
+
+            let headers = Row::new(vec!["Job", "Type", "User", "Schedule", "Last Run", "Next Run", "Result", "Status"]);
+            let rows: Vec<Row> = jobs.iter().map(|job| {
+                let status_color = match job.status {
+                    ScheduledJobStatus::Ok => self.theme_colors.success,
+                    ScheduledJobStatus::Missed => self.theme_colors.warning,
+                    ScheduledJobStatus::Failed => self.theme_colors.error,
+                    ScheduledJobStatus::Unknown => self.theme_colors.muted,
+                };
+                Row::new(vec![
+                    Cell::from(job.name.clone()),
+                    Cell::from(match job.kind {
+                        ScheduledJobKind::SystemdTimer => "timer",
+                        ScheduledJobKind::Cron => "cron",
+                    }),
+                    Cell::from(job.user.clone()),
+                    Cell::from(job.schedule.clone()),
+                    Cell::from(format_time(job.last_run)),
+                    Cell::from(format_time(job.next_run)),
+                    Cell::from(job.last_result.clone().unwrap_or_else(|| "-".to_string())),
+                    Cell::from(format!("{:?}", job.status)).style(Style::default().fg(status_color)),
+                ])
+            }).collect();
+
+            let table = Table::new(rows)
+            .widths(&[
+                Constraint::Percentage(20),
+                Constraint::Percentage(6),
+                Constraint::Percentage(8),
+                Constraint::Percentage(20),
+                Constraint::Percentage(11),
+                Constraint::Percentage(11),
+                Constraint::Percentage(14),
+                Constraint::Percentage(10),
+            ])
+            .header(headers.style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
+            .block(Block::default()
+                .title("Timers & Cron Jobs")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+            f.render_widget(table, chunks[2]);
+
+            let mut lines: Vec<Line> = jobs.iter()
+                .filter(|job| matches!(job.status, ScheduledJobStatus::Failed | ScheduledJobStatus::Missed))
+                .map(|job| {
+                    let color = if job.status == ScheduledJobStatus::Failed {
+                        self.theme_colors.error
+                    } else {
+                        self.theme_colors.warning
+                    };
+                    Line::from(vec![
+                        Span::styled(format!("{} ", job.name), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                        Span::styled(
+                            format!("{} ({})", job.reason.clone().unwrap_or_default(), job.source),
+                            Style::default().fg(color),
+                        ),
+                    ])
+                })
+                .collect();
+            if lines.is_empty() {
+                lines.push(Line::from(Span::styled("No missed or failed jobs", Style::default().fg(self.theme_colors.success))));
+            }
+
+            let flagged = Paragraph::new(lines)
+                .block(Block::default()
+                    .title("Missed & Failed")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .style(Style::default().fg(self.theme_colors.border)))
+                .wrap(Wrap { trim: false });
+            f.render_widget(flagged, chunks[3]);
+        }
+
+        let footer = Paragraph::new("Press 'Z' to return to dashboard")
+            .style(Style::default().fg(self.theme_colors.warning))
+            .alignment(Alignment::Center)
+            .block(Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(footer, chunks[4]);
     }
 
     fn render_session_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
@@ -2914,7 +4859,8 @@ impl App {
             .constraints([
                 Constraint::Length(3),  // Header
                 Constraint::Length(6),  // Stats
-                Constraint::Min(10),    // Session list
+                Constraint::Min(8),     // Session list
+                Constraint::Length(12), // Login history and failed logins
                 Constraint::Length(3),  // Footer
             ])
             .split(f.size());
@@ -2934,11 +4880,12 @@ impl App {
             let stats_chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(20),
+                    Constraint::Percentage(17),
+                    Constraint::Percentage(17),
+                    Constraint::Percentage(17),
+                    Constraint::Percentage(17),
+                    Constraint::Percentage(16),
+                    Constraint::Percentage(16),
                 ])
                 .split(chunks[1]);
 
@@ -2972,11 +4919,26 @@ impl App {
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
             f.render_widget(memory_usage, stats_chunks[4]);
 
+            let failed_logins = Paragraph::new(format!("Failed Logins\n{}", metrics.login_attempts_failed))
+                .style(Style::default().fg(if metrics.login_attempts_failed > 0 {
+                    self.theme_colors.error
+                } else {
+                    self.theme_colors.success
+                }))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
+            f.render_widget(failed_logins, stats_chunks[5]);
+
+            self.cached_session_users = metrics.sessions.iter().map(|s| s.user.clone()).collect();
+            if self.selected_session_index >= metrics.sessions.len() {
+                self.selected_session_index = metrics.sessions.len().saturating_sub(1);
+            }
+
             // Session list
-            let headers = Row::new(vec!["User", "Type", "State", "TTY", "Login Time", "Idle"]);
+            let headers = Row::new(vec!["User", "Type", "State", "TTY", "From", "SSH", "Login Time", "Idle"]);
             let mut rows = Vec::new();
 
-            for session in &metrics.sessions {
+            for (index, session) in metrics.sessions.iter().enumerate() {
                 let state_color = match session.state {
                     crate::monitor::SessionState::Active => self.theme_colors.success,
                     crate::monitor::SessionState::Online => self.theme_colors.info,
@@ -3002,24 +4964,47 @@ impl App {
                     "-".to_string()
                 };
 
+                let row_style = if index == self.selected_session_index {
+                    Style::default().bg(self.theme_colors.selection).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+
+                let from = match session.ssh.as_ref().and_then(|ssh| ssh.remote_addr.zip(ssh.remote_port)) {
+                    Some((addr, port)) => std::net::SocketAddr::new(addr, port).to_string(),
+                    None => session.remote_host.clone().or_else(|| session.display.clone()).unwrap_or_else(|| "-".to_string()),
+                };
+                let ssh = session.ssh.as_ref()
+                    .map(|ssh| format!(
+                        "sshd {} ({} procs) {}",
+                        ssh.sshd_pid,
+                        ssh.process_tree.len(),
+                        ssh.auth_method.as_deref().unwrap_or("")
+                    ))
+                    .unwrap_or_else(|| "-".to_string());
+
                 rows.push(Row::new(vec![
                     Cell::from(session.user.clone()),
                     Cell::from(format!("{:?}", session.session_type)).style(Style::default().fg(type_color)),
                     Cell::from(format!("{:?}", session.state)).style(Style::default().fg(state_color)),
                     Cell::from(session.tty.as_deref().unwrap_or("-")),
-                    Cell::from(session.login_time.format("%H:%M").to_string()),
+                    Cell::from(from),
+                    Cell::from(ssh),
+                    Cell::from(session.login_time.with_timezone(&chrono::Local).format("%m-%d %H:%M").to_string()),
                     Cell::from(idle_time),
-                ]));
+                ]).style(row_style));
             }
 
             let table = Table::new(rows)
             .widths(&[
-                Constraint::Percentage(15),
-                Constraint::Percentage(15),
-                Constraint::Percentage(15),
-                Constraint::Percentage(15),
-                Constraint::Percentage(20),
+                Constraint::Percentage(11),
+                Constraint::Percentage(7),
+                Constraint::Percentage(8),
+                Constraint::Percentage(8),
                 Constraint::Percentage(20),
+                Constraint::Percentage(24),
+                Constraint::Percentage(13),
+                Constraint::Percentage(9),
             ])
             .header(headers.style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
             .block(Block::default()
@@ -3030,17 +5015,150 @@ impl App {
             .style(Style::default().fg(self.theme_colors.foreground));
 
             f.render_widget(table, chunks[2]);
+
+            let audit_chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                .split(chunks[3]);
+            let selected_user = if self.show_all_login_history {
+                None
+            } else {
+                metrics.sessions.get(self.selected_session_index).map(|s| s.user.as_str())
+            };
+            self.render_login_history(f, audit_chunks[0], &metrics, selected_user);
+            self.render_login_audit(f, audit_chunks[1], &metrics);
         }
 
         // Footer
-        let footer = Paragraph::new("Press 'X' to return to dashboard | '↑/↓' to navigate")
+        let footer = Paragraph::new("Press 'X' to return to dashboard | '↑/↓' select session | 'a' history of all users | 'b'/'i' write SSH blocklist")
             .style(Style::default().fg(self.theme_colors.warning))
             .alignment(Alignment::Center)
             .block(Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
                 .style(Style::default().fg(self.theme_colors.border)));
-        f.render_widget(footer, chunks[3]);
+        f.render_widget(footer, chunks[4]);
+    }
+
+    /// Login/logout timeline from wtmp, interleaved with reboots and
+    /// shutdowns, for one user or everyone.
+    fn render_login_history(&self, f: &mut Frame, area: Rect, metrics: &crate::monitor::SessionMetrics, user: Option<&str>) {
+        use crate::monitor::{BootEventKind, SessionEnd};
+
+        let now = chrono::Utc::now();
+        let format_time = |time: chrono::DateTime<chrono::Utc>| time.with_timezone(&chrono::Local).format("%m-%d %H:%M").to_string();
+
+        let mut entries: Vec<(chrono::DateTime<chrono::Utc>, Line)> = metrics.login_history.iter()
+            .filter(|record| user.map(|u| record.user == u).unwrap_or(true))
+            .map(|record| {
+                let (end, color) = match record.end {
+                    SessionEnd::StillLoggedIn => ("still logged in".to_string(), self.theme_colors.success),
+                    SessionEnd::LoggedOut => (record.logout_time.map(format_time).unwrap_or_default(), self.theme_colors.foreground),
+                    SessionEnd::Down => ("down".to_string(), self.theme_colors.muted),
+                    SessionEnd::Crash => ("crash".to_string(), self.theme_colors.warning),
+                };
+                let duration = record.duration(now);
+                let text = format!(
+                    "{:<10} {:<10} {:<18} {} - {} ({}:{:02})",
+                    record.user,
+                    record.tty,
+                    record.host.as_deref().unwrap_or(""),
+                    format_time(record.login_time),
+                    end,
+                    duration.num_hours(),
+                    duration.num_minutes() % 60,
+                );
+                (record.login_time, Line::from(Span::styled(text, Style::default().fg(color))))
+            })
+            .collect();
+
+        entries.extend(metrics.boot_events.iter().map(|event| {
+            let (label, color) = match event.kind {
+                BootEventKind::Reboot => ("reboot", self.theme_colors.info),
+                BootEventKind::Shutdown => ("shutdown", self.theme_colors.accent),
+            };
+            let text = format!("{:<10} {:<10} {:<18} {}", label, "system", event.kernel, format_time(event.time));
+            (event.time, Line::from(Span::styled(text, Style::default().fg(color))))
+        }));
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.0));
+        let mut lines: Vec<Line> = entries.into_iter().map(|(_, line)| line).collect();
+        if lines.is_empty() {
+            lines.push(Line::from(Span::styled("No wtmp records available", Style::default().fg(self.theme_colors.muted))));
+        }
+
+        let title = match user {
+            Some(user) => format!("Login History: {}", user),
+            None => "Login History: all users".to_string(),
+        };
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(paragraph, area);
+    }
+
+    fn render_login_audit(&self, f: &mut Frame, area: Rect, metrics: &crate::monitor::SessionMetrics) {
+        let mut lines: Vec<Line> = metrics.ssh_threats.iter()
+            .map(|threat| {
+                let color = match threat.kind {
+                    SshThreatKind::UserEnumeration => self.theme_colors.warning,
+                    _ => self.theme_colors.error,
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:<18}", threat.source), Style::default().fg(color).add_modifier(Modifier::BOLD)),
+                    Span::styled(
+                        format!(
+                            " {} {}/{}/{} (1m/10m/1h)",
+                            threat.kind.label(),
+                            threat.failures_last_minute,
+                            threat.failures_last_ten_minutes,
+                            threat.failures_last_hour
+                        ),
+                        Style::default().fg(color),
+                    ),
+                ])
+            })
+            .collect();
+
+        lines.extend(metrics.failed_login_sources.iter()
+            .map(|source| {
+                Line::from(vec![
+                    Span::styled(format!("{:<18}", source.source), Style::default().fg(self.theme_colors.error)),
+                    Span::styled(
+                        format!(
+                            " {:>5}x  last {}  users: {}",
+                            source.attempts,
+                            source.last_attempt.with_timezone(&chrono::Local).format("%m-%d %H:%M"),
+                            source.users.join(", ")
+                        ),
+                        Style::default().fg(self.theme_colors.foreground),
+                    ),
+                ])
+            })
+        );
+        if lines.is_empty() {
+            let message = if metrics.login_attempts_failed > 0 {
+                format!("{} failures in auth logs (btmp not readable)", metrics.login_attempts_failed)
+            } else {
+                "No failed logins recorded".to_string()
+            };
+            lines.push(Line::from(Span::styled(message, Style::default().fg(self.theme_colors.muted))));
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(Block::default()
+                .title(if metrics.ssh_threats.is_empty() {
+                    "Failed Logins by Source".to_string()
+                } else {
+                    format!("Failed Logins by Source - {} flagged ('b' nftables / 'i' iptables blocklist)", metrics.ssh_threats.len())
+                })
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)));
+        f.render_widget(paragraph, area);
     }
 
     fn render_hardware_sensor_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
@@ -3095,9 +5213,16 @@ impl App {
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
             f.render_widget(fan_sensors, stats_chunks[2]);
 
-            let critical_alerts = Paragraph::new(format!("Alerts\n{}", metrics.critical_alerts.len()))
+            // Logged events stay in the SEL; only count the last day's
+            let day_ago = chrono::Utc::now() - chrono::Duration::hours(24);
+            let recent_events = metrics.events.iter()
+                .filter(|e| matches!(e.severity, crate::monitor::SensorStatus::Warning | crate::monitor::SensorStatus::Critical))
+                .filter(|e| e.timestamp.map(|t| t > day_ago).unwrap_or(false))
+                .count();
+            let alert_count = metrics.critical_alerts.len() + recent_events;
+            let critical_alerts = Paragraph::new(format!("Alerts\n{}", alert_count))
                 .style(Style::default().fg(
-                    if metrics.critical_alerts.is_empty() { self.theme_colors.success } else { self.theme_colors.error }
+                    if alert_count == 0 { self.theme_colors.success } else { self.theme_colors.error }
                 ))
                 .alignment(Alignment::Center)
                 .block(Block::default().borders(Borders::ALL).border_type(BorderType::Rounded));
@@ -3171,7 +5296,16 @@ impl App {
                 .style(Style::default().fg(self.theme_colors.border)))
             .style(Style::default().fg(self.theme_colors.foreground));
 
-            f.render_widget(table, chunks[2]);
+            if metrics.events.is_empty() {
+                f.render_widget(table, chunks[2]);
+            } else {
+                let list_chunks = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(6), Constraint::Length(8)])
+                    .split(chunks[2]);
+                f.render_widget(table, list_chunks[0]);
+                self.render_hardware_events(f, list_chunks[1], &metrics.events);
+            }
         }
 
         // Footer
@@ -3185,6 +5319,44 @@ impl App {
         f.render_widget(footer, chunks[3]);
     }
 
+    fn render_hardware_events(&self, f: &mut Frame, area: Rect, events: &[crate::monitor::HardwareEvent]) {
+        let rows: Vec<Row> = events.iter()
+            .take(area.height.saturating_sub(3) as usize)
+            .map(|event| {
+                let color = match event.severity {
+                    crate::monitor::SensorStatus::Critical => self.theme_colors.error,
+                    crate::monitor::SensorStatus::Warning => self.theme_colors.warning,
+                    crate::monitor::SensorStatus::Normal => self.theme_colors.success,
+                    _ => self.theme_colors.foreground,
+                };
+                let time = event.timestamp
+                    .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_else(|| "pre-init".to_string());
+                Row::new(vec![
+                    Cell::from(time),
+                    Cell::from(event.sensor.clone()),
+                    Cell::from(event.message.clone()).style(Style::default().fg(color)),
+                ])
+            })
+            .collect();
+
+        let table = Table::new(rows)
+            .widths(&[
+                Constraint::Length(20),
+                Constraint::Percentage(30),
+                Constraint::Percentage(50),
+            ])
+            .header(Row::new(vec!["Time", "Sensor", "Event"])
+                .style(Style::default().fg(self.theme_colors.primary).add_modifier(Modifier::BOLD)))
+            .block(Block::default()
+                .title(format!("Hardware Events ({})", events.len()))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .style(Style::default().fg(self.theme_colors.border)))
+            .style(Style::default().fg(self.theme_colors.foreground));
+        f.render_widget(table, area);
+    }
+
     // Advanced Monitoring Domain Render Methods
     fn render_database_monitor_view(&mut self, f: &mut Frame, system_monitor: &mut SystemMonitor) {
         self.advanced_monitoring_view.current_tab = AdvancedTab::Database;
@@ -3225,4 +5397,12 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
             Constraint::Percentage((100 - percent_x) / 2),
         ])
         .split(popup_layout[1])[1]
-}
\ No newline at end of file
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
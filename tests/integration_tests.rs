@@ -56,6 +56,7 @@ fn test_config_creation_with_defaults() {
         export: None,
         threshold_cpu: 80.0,
         threshold_memory: 80.0,
+        fan_watchdog: false,
     };
     
     let config = Config::new(cli).expect("Failed to create config");